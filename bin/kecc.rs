@@ -6,12 +6,16 @@ use std::os::unix::io::{FromRawFd, IntoRawFd};
 use std::path::Path;
 use std::process::{Command, Stdio};
 
-use lang_c::ast::TranslationUnit;
+use lang_c::driver;
 use tempfile::tempdir;
 
 use kecc::{
-    ir, ok_or_exit, write, Asmgen, Deadcode, Gvn, IrParse, IrVisualizer, Irgen, Mem2reg, Optimize,
-    Parse, SimplifyCfg, Translate, O1,
+    ir, ok_or_exit, pipeline_with, write, Asmgen, BranchFold, Canonicalize, ConstFold, ConstProp,
+    CopyProp, DeadFunctionElimination, DeadGlobalElimination, DeadStoreElimination, Deadcode,
+    DivByConst, Gvn, IndVarSimplify, Inline, InstCombine, IrParse, IrVisualizer, Irgen, Licm,
+    LoopUnroll, LoopUnrollInner, Mem2reg, Narrowing, OptLevel, Optimize, ParseSource, PassManager,
+    PhiElimination, Pre, ProfileGuidedUnroll, PruneBlockArgs, Reassociate, Sccp, SimplifyCfg, Sroa,
+    StoreForwarding, SwitchLowering, Translate, UnreachableBlockElimination,
 };
 
 #[derive(Debug, Parser)]
@@ -37,34 +41,221 @@ struct KeccCli {
     #[clap(long)]
     irprint: bool,
 
+    /// Links the IR of the C or IR file with that of the input file, e.g., to execute a program of
+    /// several files
+    #[clap(long, value_name = "FILE")]
+    link: Vec<String>,
+
     /// Executes the input file
     #[clap(long)]
     irrun: bool,
 
+    /// Records the nondeterministic inputs of the execution into the file
+    #[clap(
+        long,
+        value_name = "FILE",
+        requires = "irrun",
+        conflicts_with = "replay"
+    )]
+    record: Option<String>,
+
+    /// Replays the nondeterministic inputs recorded in the file
+    #[clap(long, value_name = "FILE", requires = "irrun")]
+    replay: Option<String>,
+
+    /// Limits the memory of the execution to the number of bytes
+    #[clap(long = "memory-limit", value_name = "BYTES", requires = "irrun")]
+    memory_limit: Option<usize>,
+
+    /// Reports the executed instructions per function and block
+    #[clap(long, requires = "irrun")]
+    profile: bool,
+
+    /// Writes the execution counts of each block to the file, to be read by `--profile-use`
+    #[clap(long = "profile-output", value_name = "FILE", requires = "irrun")]
+    profile_output: Option<String>,
+
+    /// Reports heap memory that is never freed
+    #[clap(long = "leak-check", requires = "irrun")]
+    leak_check: bool,
+
+    /// Fails as soon as the execution computes with an undefined value
+    #[clap(long = "undef-check", requires = "irrun")]
+    undef_check: bool,
+
+    /// Fails as soon as a `nsw`, `nuw` or `exact` flag of an instruction does not hold
+    #[clap(long = "flag-check", requires = "irrun")]
+    flag_check: bool,
+
     /// Visualizes IR
     #[clap(long, value_name = "FILE")]
     irviz: Option<String>,
 
-    /// Optimizes IR
+    /// Optimizes IR, as `--opt-level 1` does
     #[clap(short = 'O', long)]
     optimize: bool,
 
+    /// Optimizes IR with the passes of the level, which is 0, 1 or 2
+    #[clap(long = "opt-level", value_name = "LEVEL", conflicts_with = "optimize")]
+    opt_level: Option<OptLevel>,
+
     /// Performs simplify-cfg
     #[clap(long = "simplify-cfg")]
     simplify_cfg: bool,
 
+    /// Removes blocks unreachable from the entry block
+    #[clap(long = "remove-unreachable")]
+    remove_unreachable: bool,
+
+    /// Splits local structs and arrays accessed at constant offsets into scalars
+    #[clap(long)]
+    sroa: bool,
+
     /// Performs mem2reg
     #[clap(long)]
     mem2reg: bool,
 
+    /// Folds instructions on constants
+    #[clap(long = "const-fold")]
+    const_fold: bool,
+
+    /// Propagates constants through registers and jump arguments
+    #[clap(long = "const-prop")]
+    const_prop: bool,
+
+    /// Performs sparse conditional constant propagation, resolving constant branches
+    #[clap(long)]
+    sccp: bool,
+
+    /// Replaces branches on constants with the jumps they take
+    #[clap(long = "branch-fold")]
+    branch_fold: bool,
+
+    /// Simplifies instructions by algebraic identities
+    #[clap(long)]
+    instcombine: bool,
+
+    /// Reassociates chains of associative operations into a canonical order
+    #[clap(long)]
+    reassociate: bool,
+
+    /// Replaces divisions and remainders by constants with shifts and multiplications
+    #[clap(long = "div-by-const")]
+    div_by_const: bool,
+
+    /// Narrows integer computations that are only truncated
+    #[clap(long)]
+    narrow: bool,
+
+    /// Lowers switches into jump tables, comparison trees or comparison chains
+    #[clap(long = "lower-switch")]
+    lower_switch: bool,
+
+    /// Replaces copies with their sources
+    #[clap(long = "copy-prop")]
+    copy_prop: bool,
+
+    /// Removes phinodes receiving the same value from every jump
+    #[clap(long = "prune-block-args")]
+    prune_block_args: bool,
+
     /// Performs deadcode elimination
     #[clap(long)]
     deadcode: bool,
 
+    /// Inlines calls to functions defined in the input
+    #[clap(long)]
+    inline: bool,
+
+    /// Sets the largest size of a function inlined at a call site, less the bonuses of the call
+    #[clap(long = "inline-threshold", value_name = "SIZE")]
+    inline_threshold: Option<usize>,
+
+    /// Sets the bonus of each constant argument of a call site
+    #[clap(long = "inline-bonus", value_name = "SIZE")]
+    inline_bonus: Option<usize>,
+
+    /// Inlines the function at every call site that is not recursive
+    #[clap(long = "inline-always", value_name = "NAME")]
+    inline_always: Vec<String>,
+
+    /// Never inlines the function
+    #[clap(long = "inline-never", value_name = "NAME")]
+    inline_never: Vec<String>,
+
+    /// Sets how much the whole program may grow by inlining, in percent of its size
+    #[clap(long = "inline-growth", value_name = "PERCENT")]
+    inline_growth: Option<usize>,
+
+    /// Removes functions unreachable from `main`
+    #[clap(long = "dead-function")]
+    dead_function: bool,
+
+    /// Removes global variables that are never read
+    #[clap(long = "dead-global")]
+    dead_global: bool,
+
+    /// Forwards stored values to the loads reading them back
+    #[clap(long = "store-forward")]
+    store_forward: bool,
+
+    /// Removes stores whose values are never loaded
+    #[clap(long)]
+    dse: bool,
+
     /// Performs gvn
     #[clap(long)]
     gvn: bool,
 
+    /// Computes partially redundant computations on the paths missing them
+    #[clap(long)]
+    pre: bool,
+
+    /// Hoists loop-invariant code out of loops
+    #[clap(long)]
+    licm: bool,
+
+    /// Strength-reduces induction variables and simplifies loop exit conditions
+    #[clap(long)]
+    indvars: bool,
+
+    /// Unrolls innermost loops, fully if they have few iterations
+    #[clap(long = "loop-unroll")]
+    loop_unroll: bool,
+
+    /// Sets the number of copies of the body of a partially unrolled loop
+    #[clap(
+        long = "unroll-factor",
+        value_name = "FACTOR",
+        requires = "loop_unroll"
+    )]
+    unroll_factor: Option<usize>,
+
+    /// Unrolls the loops found hot by the profile in the file, written by `--profile-output` for
+    /// the same input, before the other optimizations, and inlines the calls it finds hot
+    #[clap(long = "profile-use", value_name = "FILE")]
+    profile_use: Option<String>,
+
+    /// Renumbers blocks and instructions into a canonical order
+    #[clap(long)]
+    canonicalize: bool,
+
+    /// Lowers phinodes into copies through memory
+    #[clap(long = "phi-elim")]
+    phi_elim: bool,
+
+    /// Prints the changes each optimization makes to the IR to stderr
+    #[clap(long = "print-changes")]
+    print_changes: bool,
+
+    /// Prints each function an optimization changes to stderr after the optimization
+    #[clap(long = "print-after-all")]
+    print_after_all: bool,
+
+    /// Prints statistics of the output IR to stderr
+    #[clap(long)]
+    irstats: bool,
+
     /// Prints the output IR
     #[clap(long)]
     iroutput: bool,
@@ -92,7 +283,7 @@ fn main() {
     let ext = input.extension();
     if ext == Some(OsStr::new("c")) {
         // 将 input.c 文件的字符串解析为 c 语言的 ast
-        let input = ok_or_exit!(Parse::default().translate(&input), 1);
+        let input = ok_or_exit!(ParseSource.translate(&input), 1);
         compile_c(&input, &mut output, &matches);
     } else if ext == Some(OsStr::new("ir")) {
         // 将 input.ir 文件的字符串进行解析
@@ -105,14 +296,14 @@ fn main() {
 
 // 将 input 文件里的 c 代码进行编译并输出到 output 文件
 // match 是命令行参数的结构体
-fn compile_c(input: &TranslationUnit, output: &mut dyn ::std::io::Write, matches: &KeccCli) {
+fn compile_c(input: &driver::Parse, output: &mut dyn ::std::io::Write, matches: &KeccCli) {
     if matches.parse {
         return;
     }
 
     // 输出源代码转化为 ast 后再转化为 c 语言时的代码
     if matches.print {
-        write(input, output).unwrap();
+        write(&input.unit, output).unwrap();
         return;
     }
 
@@ -142,29 +333,167 @@ fn compile_ir(
         return;
     }
 
+    if !matches.link.is_empty() {
+        let units = matches
+            .link
+            .iter()
+            .map(|path| translate_to_ir(Path::new(path)));
+        *input = ok_or_exit!(ir::link(std::iter::once(input.clone()).chain(units)), 1);
+    }
+
     if matches.irprint {
         write(input, output).unwrap();
         return;
     }
 
-    if matches.optimize {
-        O1::default().optimize(input);
+    let mut inline = Inline::default();
+    if let Some(threshold) = matches.inline_threshold {
+        inline.threshold = threshold;
+    }
+    if let Some(bonus) = matches.inline_bonus {
+        inline.constant_arg_bonus = bonus;
+    }
+    if let Some(growth) = matches.inline_growth {
+        inline.growth = growth;
+    }
+    inline.always.extend(matches.inline_always.iter().cloned());
+    inline.never.extend(matches.inline_never.iter().cloned());
+
+    let mut passes = PassManager::new();
+    if let Some(path) = &matches.profile_use {
+        let text = ok_or_exit!(::std::fs::read_to_string(path), 1);
+        let profile = ok_or_exit!(ir::Profile::parse(&text), 1);
+        inline.profile = profile.clone();
+        let _ = passes.add("profile-unroll", ProfileGuidedUnroll::new(profile));
+    }
+    if let Some(level) = matches
+        .opt_level
+        .or(matches.optimize.then_some(OptLevel::O1))
+    {
+        let _ = passes.append(pipeline_with(level, inline));
     } else {
         if matches.simplify_cfg {
-            SimplifyCfg::default().optimize(input);
+            let _ = passes.add("simplify-cfg", SimplifyCfg::default());
+        }
+
+        if matches.remove_unreachable {
+            let _ = passes.add("remove-unreachable", UnreachableBlockElimination::default());
+        }
+
+        if matches.sroa {
+            let _ = passes.add("sroa", Sroa::default());
         }
 
         if matches.mem2reg {
-            Mem2reg::default().optimize(input);
+            let _ = passes.add("mem2reg", Mem2reg::default());
+        }
+
+        if matches.const_fold {
+            let _ = passes.add("const-fold", ConstFold::default());
+        }
+
+        if matches.const_prop {
+            let _ = passes.add("const-prop", ConstProp::default());
+        }
+
+        if matches.sccp {
+            let _ = passes.add("sccp", Sccp::default());
+        }
+
+        if matches.branch_fold {
+            let _ = passes.add("branch-fold", BranchFold::default());
+        }
+
+        if matches.instcombine {
+            let _ = passes.add("instcombine", InstCombine::default());
+        }
+
+        if matches.reassociate {
+            let _ = passes.add("reassociate", Reassociate::default());
+        }
+
+        if matches.div_by_const {
+            let _ = passes.add("div-by-const", DivByConst::default());
+        }
+
+        if matches.narrow {
+            let _ = passes.add("narrow", Narrowing::default());
+        }
+
+        if matches.lower_switch {
+            let _ = passes.add("lower-switch", SwitchLowering::default());
+        }
+
+        if matches.copy_prop {
+            let _ = passes.add("copy-prop", CopyProp::default());
+        }
+
+        if matches.prune_block_args {
+            let _ = passes.add("prune-block-args", PruneBlockArgs::default());
         }
 
         if matches.deadcode {
-            Deadcode::default().optimize(input);
+            let _ = passes.add("deadcode", Deadcode::default());
+        }
+
+        if matches.inline {
+            let _ = passes.add("inline", inline);
+        }
+
+        if matches.dead_function {
+            let _ = passes.add("dead-function", DeadFunctionElimination::default());
+        }
+
+        if matches.dead_global {
+            let _ = passes.add("dead-global", DeadGlobalElimination::default());
+        }
+
+        if matches.store_forward {
+            let _ = passes.add("store-forward", StoreForwarding::default());
+        }
+
+        if matches.dse {
+            let _ = passes.add("dse", DeadStoreElimination::default());
         }
 
         if matches.gvn {
-            Gvn::default().optimize(input);
+            let _ = passes.add("gvn", Gvn::default());
+        }
+
+        if matches.pre {
+            let _ = passes.add("pre", Pre::default());
+        }
+
+        if matches.licm {
+            let _ = passes.add("licm", Licm::default());
+        }
+
+        if matches.indvars {
+            let _ = passes.add("indvars", IndVarSimplify::default());
         }
+
+        if matches.loop_unroll {
+            let mut inner = LoopUnrollInner::default();
+            if let Some(factor) = matches.unroll_factor {
+                inner.factor = factor;
+            }
+            let _ = passes.add("loop-unroll", LoopUnroll::new(inner));
+        }
+
+        if matches.canonicalize {
+            let _ = passes.add("canonicalize", Canonicalize::default());
+        }
+
+        if matches.phi_elim {
+            let _ = passes.add("phi-elim", PhiElimination::default());
+        }
+    }
+
+    if !passes.is_empty() {
+        let _ = passes
+            .print_changes(matches.print_changes)
+            .print_after_all(matches.print_after_all)
+            .optimize(input);
     }
 
     if let Some(path) = &matches.irviz {
@@ -207,13 +536,44 @@ fn compile_ir(
         temp_dir.close().expect("temp dir deletion failed");
     }
 
+    if matches.irstats {
+        eprint!("{}", ir::stats(input));
+    }
+
     if matches.iroutput {
         write(input, output).unwrap();
         return;
     }
 
     if matches.irrun {
-        let result = ir::interp(input, Vec::new()).unwrap();
+        let mut interpreter = ir::Interpreter::default();
+        if let Some(path) = &matches.record {
+            interpreter.record(path);
+        }
+        if let Some(path) = &matches.replay {
+            interpreter.replay(path);
+        }
+        if let Some(bytes) = matches.memory_limit {
+            interpreter.memory_limit(bytes);
+        }
+        interpreter.leak_check(matches.leak_check);
+        interpreter.undef_check(matches.undef_check);
+        interpreter.flag_check(matches.flag_check);
+        interpreter.profiling(matches.profile || matches.profile_output.is_some());
+
+        let result = interpreter.run(input, Vec::new()).unwrap();
+        for leak in interpreter.leaks() {
+            eprintln!("[leak] {leak}");
+        }
+        if matches.profile {
+            interpreter
+                .profile()
+                .write_report(input, &mut ::std::io::stderr())
+                .unwrap();
+        }
+        if let Some(path) = &matches.profile_output {
+            ok_or_exit!(::std::fs::write(path, interpreter.profile().to_string()), 1);
+        }
         let (value, width, is_signed) = result.get_int().expect("non-integer value occurs");
         assert_eq!(width, 32);
         assert!(is_signed);
@@ -227,3 +587,16 @@ fn compile_ir(
     let asm = ok_or_exit!(Asmgen::default().translate(input), 1);
     write(&asm, output).unwrap();
 }
+
+/// Translates the C or IR file at `path` to IR, exiting on failure.
+fn translate_to_ir(path: &Path) -> ir::TranslationUnit {
+    let ext = path.extension();
+    if ext == Some(OsStr::new("c")) {
+        let ast = ok_or_exit!(ParseSource.translate(&path), 1);
+        ok_or_exit!(Irgen::default().translate(&ast), 1)
+    } else if ext == Some(OsStr::new("ir")) {
+        ok_or_exit!(IrParse::default().translate(&path), 1)
+    } else {
+        panic!("Unsupported file extension: {ext:?}");
+    }
+}
@@ -0,0 +1,25 @@
+struct point {
+    int x;
+    int y;
+};
+
+int a[4] = {1, 2, 3, 4};
+int *pa = &a[2];
+int *p = a + 1;
+int *q = 3 + a - 1;
+long n = sizeof(long) * 2;
+long m = sizeof a / sizeof(int);
+struct point pts[2] = {{5, 6}, {7, 8}};
+int *py = &pts[1].y;
+int *ps[2] = {a, &a[3]};
+
+int f() {
+    return 10;
+}
+
+int (*fp)() = f;
+
+int main() {
+    return *pa == 3 && *p == 2 && p == q - 1 && n == 16 && m == 4 && *py == 8 && *ps[1] == 4 &&
+           fp() == 10;
+}
@@ -0,0 +1,19 @@
+int main() {
+    unsigned int u = 1;
+    int i = -1;
+    char c = -1;
+    unsigned char uc = 255;
+    long l = -1;
+    short s = 30000;
+    double d = 1;
+
+    int t1 = i < u;
+    int t2 = l < u;
+    int t3 = c == -1;
+    int t4 = uc + 1 == 256;
+    int t5 = s + s == 60000;
+    int t6 = i / 2 * d == 0;
+    int t7 = sizeof(c + c) == sizeof(int);
+
+    return t1 == 0 && t2 == 1 && t3 && t4 && t5 && t6 && t7;
+}
@@ -0,0 +1,18 @@
+int sum(int *a, int n) {
+    int s = 0;
+    for (int i = 0; i < n; i++) {
+        s += a[i];
+    }
+    return s;
+}
+
+int main() {
+    int a[5] = {1, 2, 3, 4, 5};
+    int *p = a;
+    int *q = &a[4];
+
+    p[1] = 20;
+    2[a] = 30;
+
+    return sum(a, 5) == 60 && *(a + 3) == 4 && *(q - 4) == 1;
+}
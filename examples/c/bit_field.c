@@ -0,0 +1,19 @@
+struct flags {
+    unsigned int a : 1;
+    unsigned int b : 3;
+    int c : 4;
+    unsigned int : 0;
+    unsigned int d : 8;
+};
+
+int main() {
+    struct flags f;
+
+    f.a = 1;
+    f.b = 9;
+    f.c = -3;
+    f.d = 300;
+    f.b += 2;
+
+    return f.a == 1 && f.b == 3 && f.c == -3 && f.d == 44 && sizeof(f) == 8;
+}
@@ -0,0 +1,19 @@
+long widen(long x);
+char narrow(char c);
+double half(double d);
+
+int main() {
+    return widen(2147483647) == 2147483648L && narrow(300) == 44 && half(3) == 1.5;
+}
+
+long widen(long x) {
+    return x + 1;
+}
+
+char narrow(char c) {
+    return c;
+}
+
+double half(double d) {
+    return d / 2;
+}
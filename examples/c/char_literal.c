@@ -0,0 +1,13 @@
+int main()
+{
+    char a = 'a';
+    char newline = '\n';
+    char quote = '\'';
+    char hex = '\x41';
+    char octal = '\101';
+    char nul = '\0';
+    char high = '\xff';
+
+    return (a == 97) + (newline == 10) * 2 + (quote == 39) * 4 + (hex == 'A') * 8 +
+           (octal == hex) * 16 + (nul == 0) * 32 + (high == -1) * 64;
+}
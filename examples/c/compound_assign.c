@@ -0,0 +1,23 @@
+int main() {
+    int a = 10;
+    unsigned char c = 250;
+    int arr[3] = {1, 2, 3};
+    int *p = arr;
+    int i = 0;
+
+    a += 5;
+    a -= 3;
+    a *= 4;
+    a /= 6;
+    a %= 5;
+    a <<= 3;
+    a >>= 1;
+    a |= 1;
+    a &= 13;
+    a ^= 6;
+    c += 10;
+    p += 2;
+    arr[i++] += 4;
+
+    return a == 11 && c == 4 && *p == 3 && arr[0] == 5 && i == 1;
+}
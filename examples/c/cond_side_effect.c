@@ -0,0 +1,12 @@
+int main() {
+    int a = 0;
+    int b = 0;
+    int zero = 0;
+    int i = 1;
+
+    int x = i ? a++ : b++;
+    int y = zero ? 10 / zero : 7;
+    int z = i > 0 ? (a += 2, a) : -1;
+
+    return x == 0 && y == 7 && z == 3 && a == 3 && b == 0;
+}
@@ -0,0 +1,9 @@
+int main()
+{
+    int c = 0;
+    int x = 2147483647;
+    volatile int v = 3;
+    int y = c ? x + 1 : 3;
+    int z = c ? v : 4;
+    return y + z;
+}
@@ -0,0 +1,21 @@
+int main() {
+    int i = 0;
+    int sum = 0;
+
+    do {
+        i++;
+        if (i == 3) {
+            continue;
+        }
+        if (i > 6) {
+            break;
+        }
+        sum += i;
+    } while (i < 10);
+
+    do {
+        sum += 100;
+    } while (0);
+
+    return sum == 118;
+}
@@ -0,0 +1,12 @@
+int x = 3 * 4 + 1;
+long y = (1l << 40) / 3 - -5;
+unsigned int z = ~0u >> 4;
+int w = 7 > 3 ? 10 % 4 : 2;
+int b = !0 && (1 || 0);
+int a[3] = {1 + 1, 2 * 3, -(4 - 8)};
+char s[4] = "abc";
+
+int main() {
+    return x == 13 && y == 366503875930l && z == 268435455u && w == 2 && b == 1 &&
+           a[0] + a[1] + a[2] == 12 && s[1] == 98 && s[3] == 0;
+}
@@ -0,0 +1,19 @@
+struct pair {
+    int a;
+    char b;
+};
+
+struct pair g = {3, 4};
+int garr[4] = {1, 2};
+struct pair gpairs[2] = {{1, 2}, {3}};
+
+int main() {
+    int arr[5] = {5, 6, 7};
+    struct pair p = {10, 20};
+    int grid[2][3] = {{1, 2, 3}, {4}};
+    struct pair pairs[2] = {{7, 8}};
+
+    return g.a + g.b + garr[1] + garr[3] + gpairs[1].a + gpairs[1].b + arr[2] + arr[4] + p.b +
+               grid[1][0] + grid[1][2] + pairs[0].b + pairs[1].a ==
+           51;
+}
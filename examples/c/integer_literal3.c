@@ -0,0 +1,12 @@
+int main() {
+    int t1 = 0x10 == 16;
+    int t2 = 010 == 8;
+    int t3 = sizeof(1L) == 8;
+    int t4 = sizeof(2147483648) == 8;
+    int t5 = sizeof(0x80000000) == 4;
+    int t6 = 0xFFFFFFFFu > 0;
+    int t7 = -1 < 1U == 0;
+    int t8 = 10ull / 3 == 3;
+
+    return t1 && t2 && t3 && t4 && t5 && t6 && t7 && t8;
+}
@@ -0,0 +1,33 @@
+struct node {
+    int value;
+    struct node *next;
+};
+
+struct tree;
+
+struct forest {
+    struct tree *first;
+};
+
+struct tree {
+    int value;
+    struct forest children;
+};
+
+int sum(struct node *head) {
+    int s = 0;
+    for (; head; head = head->next) {
+        s += head->value;
+    }
+    return s;
+}
+
+int main() {
+    struct node c = {3, 0};
+    struct node b = {2, &c};
+    struct node a = {1, &b};
+    struct tree leaf = {5, {0}};
+    struct tree root = {4, {&leaf}};
+
+    return sum(&a) == 6 && root.children.first->value == 5 && a.next->next->next == 0;
+}
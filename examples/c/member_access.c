@@ -0,0 +1,24 @@
+struct point {
+    int x;
+    int y;
+};
+
+struct rect {
+    struct point min;
+    struct point max;
+    char tag;
+};
+
+int main() {
+    struct rect r;
+    struct rect *p = &r;
+    struct point *q = &r.max;
+
+    r.min.x = 1;
+    p->min.y = 2;
+    q->x = 5;
+    (*q).y = 7;
+    p->tag = 'r';
+
+    return (r.max.x - p->min.x) * (p->max.y - r.min.y) == 20 && r.tag == 'r';
+}
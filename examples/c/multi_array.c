@@ -0,0 +1,23 @@
+int trace(int m[3][3]) {
+    return m[0][0] + m[1][1] + m[2][2];
+}
+
+int main() {
+    int m[3][3];
+    int cube[2][2][2];
+    int sum = 0;
+
+    for (int i = 0; i < 3; i++) {
+        for (int j = 0; j < 3; j++) {
+            m[i][j] = i * 3 + j;
+        }
+    }
+    for (int i = 0; i < 8; i++) {
+        cube[i / 4][i / 2 % 2][i % 2] = i;
+    }
+    for (int i = 0; i < 2; i++) {
+        sum += cube[i][1][1];
+    }
+
+    return trace(m) == 12 && sum == 10 && sizeof(m[1]) == 12 && *(m[2] + 1) == 7;
+}
@@ -0,0 +1,13 @@
+int count = 0;
+
+int tick(int value) {
+    count++;
+    return value;
+}
+
+int main() {
+    int a = tick(0) && tick(1) && tick(1);
+    int b = tick(1) || tick(0) || tick(0);
+    int c = tick(1) && tick(0) || tick(1) && tick(2);
+    return a == 0 && b == 1 && c == 1 && count == 6;
+}
@@ -0,0 +1,17 @@
+int length(char *s) {
+    int n = 0;
+    while (s[n]) {
+        n++;
+    }
+    return n;
+}
+
+int main() {
+    char *a = "hello";
+    char *b = "hello";
+    char c[4] = "hi\n";
+    char d[8] = "abc";
+
+    return length(a) == 5 && a[1] == 'e' && a == b && sizeof(c) == 4 && c[2] == 10 &&
+           length(d) == 3 && d[7] == 0 && length("x" "yz") == 3;
+}
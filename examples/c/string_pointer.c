@@ -0,0 +1,8 @@
+char *str = "hi";
+char *strs[3] = {"ab", "hi", "hi" + 1};
+char buf[3] = "ab";
+
+int main() {
+    return str[1] == 'i' && strs[0][1] == 'b' && strs[1] == str && *strs[2] == 'i' &&
+           buf[0] == 'a';
+}
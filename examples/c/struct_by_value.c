@@ -0,0 +1,28 @@
+struct vec {
+    int x;
+    int y;
+    long z;
+};
+
+struct vec make(int x, int y) {
+    struct vec v;
+    v.x = x;
+    v.y = y;
+    v.z = x * y;
+    return v;
+}
+
+struct vec add(struct vec a, struct vec b) {
+    a.x += b.x;
+    a.y += b.y;
+    a.z += b.z;
+    return a;
+}
+
+int main() {
+    struct vec a = make(1, 2);
+    struct vec b = make(3, 4);
+    struct vec c = add(a, b);
+
+    return a.x == 1 && c.x == 4 && c.y == 6 && c.z == 14 && add(c, a).z == 16;
+}
@@ -0,0 +1,42 @@
+int classify(int x) {
+    int r = 0;
+    switch (x) {
+        case 0: {
+            r += 1;
+        }
+        case 1: {
+            r += 10;
+            break;
+        }
+        default: {
+            r += 100;
+        }
+        case 3: {
+            r += 1000;
+        }
+    }
+    return r;
+}
+
+int group(int x) {
+    int r = 0;
+    switch (x) {
+        case 0:
+            r += 1;
+        case 1:
+        default:
+        case 2:
+            r += 10;
+            break;
+        case 3:
+        case 4:
+            return 20;
+    }
+    return r;
+}
+
+int main() {
+    return classify(0) == 11 && classify(1) == 10 && classify(3) == 1000 &&
+           classify(7) == 1100 && group(0) == 11 && group(1) == 10 && group(2) == 10 &&
+           group(3) == 20 && group(4) == 20 && group(9) == 10;
+}
@@ -0,0 +1,22 @@
+union value {
+    int i;
+    char c;
+    unsigned char bytes[4];
+};
+
+struct tagged {
+    int tag;
+    union value v;
+};
+
+int main() {
+    union value v;
+    struct tagged t;
+
+    v.i = 0x01020304;
+    t.tag = 1;
+    t.v.i = 7;
+
+    return sizeof(v) == 4 && v.bytes[0] == 4 && v.c == 4 && t.v.c == 7 &&
+           sizeof(struct tagged) == 8;
+}
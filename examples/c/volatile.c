@@ -0,0 +1,18 @@
+volatile int counter = 0;
+
+void bump(volatile int *p) {
+    *p = *p + 1;
+}
+
+int main() {
+    volatile int x = 1;
+    int y;
+
+    x = 2;
+    x = 3;
+    y = x + x;
+    bump(&counter);
+    bump(&counter);
+
+    return y == 6 && counter == 2;
+}
@@ -0,0 +1,13 @@
+static int counter = 1;
+int shared;
+extern int elsewhere;
+int external(int x);
+
+static int bump(int x) {
+  counter += x;
+  return counter;
+}
+
+int main() {
+  return bump(2) + bump(3);
+}
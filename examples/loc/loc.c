@@ -0,0 +1,10 @@
+int g = 1;
+
+int add_uninit(int a) {
+  int x;
+  return x + a;
+}
+
+int main() {
+  return add_uninit(g);
+}
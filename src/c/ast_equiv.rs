@@ -495,7 +495,13 @@ impl IsEquiv for Enumerator {
 
 impl IsEquiv for TypeQualifier {
     fn is_equiv(&self, other: &Self) -> bool {
-        matches!((self, other), (Self::Const, Self::Const))
+        matches!(
+            (self, other),
+            (Self::Const, Self::Const)
+                | (Self::Restrict, Self::Restrict)
+                | (Self::Volatile, Self::Volatile)
+                | (Self::Atomic, Self::Atomic)
+        )
     }
 }
 
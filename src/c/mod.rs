@@ -3,4 +3,4 @@ mod parse;
 mod write_c;
 
 pub use ast_equiv::assert_ast_equiv;
-pub use parse::Parse;
+pub use parse::{Parse, ParseSource};
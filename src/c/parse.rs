@@ -33,6 +33,24 @@ impl<P: AsRef<Path>> Translate<P> for Parse {
     }
 }
 
+/// Parses a C file like [`Parse`], but keeps the preprocessed source along with the AST, from
+/// which [`Irgen`](crate::Irgen) finds where the IR is in the source.
+#[derive(Default, Clone, Copy, Debug)]
+pub struct ParseSource;
+
+impl<P: AsRef<Path>> Translate<P> for ParseSource {
+    type Target = lang_c::driver::Parse;
+    type Error = Error;
+
+    fn translate(&mut self, source: &P) -> Result<Self::Target, Self::Error> {
+        let config = Config::default();
+        let ast = parse(&config, source).map_err(Error::ParseError)?;
+
+        ast.unit.assert_supported();
+        Ok(ast)
+    }
+}
+
 impl<T: AssertSupported> AssertSupported for Node<T> {
     fn assert_supported(&self) {
         self.node.assert_supported();
@@ -95,7 +113,8 @@ impl AssertSupported for FunctionDefinition {
     fn assert_supported(&self) {
         self.specifiers.assert_supported();
         self.declarator.assert_supported();
-        assert!(self.declarations.is_empty());
+        // The declarations of the parameters of a K&R-style definition are rejected by irgen.
+        self.declarations.assert_supported();
         self.statement.assert_supported();
     }
 }
@@ -115,7 +134,7 @@ impl AssertSupported for DeclarationSpecifier {
 
 impl AssertSupported for StorageClassSpecifier {
     fn assert_supported(&self) {
-        assert_eq!(*self, Self::Typedef)
+        assert!(matches!(self, Self::Typedef | Self::Static | Self::Extern))
     }
 }
 
@@ -132,13 +151,11 @@ impl AssertSupported for TypeSpecifier {
             Self::Signed => (),
             Self::Unsigned => (),
             Self::Bool => (),
-            Self::Complex => panic!("TypeSpecifier::Complex"),
-            Self::Atomic(_) => panic!("TypeSpecifier::Atomic"),
             Self::Struct(struct_type) => struct_type.assert_supported(),
-            Self::Enum(_) => panic!("TypeSpecifier::Enum"),
             Self::TypedefName(_) => (),
-            Self::TypeOf(_) => panic!("TypeSpecifier::TypeOf"),
-            Self::TS18661Float(_) => panic!("TypeSpecifier::TS18661Float"),
+            // Rejected by irgen as unsupported.
+            Self::Complex | Self::Atomic(_) | Self::Enum(_) => (),
+            Self::TypeOf(_) | Self::TS18661Float(_) => (),
         }
     }
 }
@@ -169,15 +186,14 @@ impl AssertSupported for StructField {
 impl AssertSupported for StructDeclarator {
     fn assert_supported(&self) {
         self.declarator.assert_supported();
-        assert!(self.bit_width.is_none());
+        self.bit_width.assert_supported();
     }
 }
 
 impl AssertSupported for StructKind {
     fn assert_supported(&self) {
         match self {
-            Self::Struct => (),
-            Self::Union => panic!("StructKind::Union"),
+            Self::Struct | Self::Union => (),
         }
     }
 }
@@ -228,8 +244,8 @@ impl AssertSupported for DerivedDeclarator {
             Self::Pointer(pointer_qualifiers) => pointer_qualifiers.assert_supported(),
             Self::Array(array_decl) => array_decl.assert_supported(),
             Self::Function(func_decl) => func_decl.assert_supported(),
-            // Support when K&R function has no parameter
-            Self::KRFunction(kr_func_decl) => assert!(kr_func_decl.is_empty()),
+            // Rejected by irgen as unsupported unless the function has no parameter.
+            Self::KRFunction(_) => (),
             Self::Block(_) => panic!("DerivedDeclarator::Block"),
         }
     }
@@ -258,7 +274,9 @@ impl AssertSupported for ArrayDeclarator {
 impl AssertSupported for TypeQualifier {
     fn assert_supported(&self) {
         match self {
-            Self::Const => (),
+            Self::Const | Self::Volatile => (),
+            // Rejected by irgen as unsupported.
+            Self::Atomic => (),
             _ => panic!("TypeQualifier::_"),
         }
     }
@@ -351,7 +369,10 @@ impl AssertSupported for ForInitializer {
 impl AssertSupported for Statement {
     fn assert_supported(&self) {
         match self {
-            Self::Labeled(_) => panic!("Statement::Labeled"),
+            Self::Labeled(stmt) => {
+                stmt.node.label.assert_supported();
+                stmt.node.statement.assert_supported();
+            }
             Self::Compound(items) => items.assert_supported(),
             Self::Expression(expr) => expr.assert_supported(),
             Self::If(stmt) => {
@@ -374,7 +395,8 @@ impl AssertSupported for Statement {
                 stmt.node.step.assert_supported();
                 stmt.node.statement.assert_supported();
             }
-            Self::Goto(_) => panic!("Statement::Goto"),
+            // Rejected by irgen as unsupported.
+            Self::Goto(_) => (),
             Self::Continue | Self::Break => (),
             Self::Return(expr) => expr.assert_supported(),
             Self::Asm(_) => panic!("Statement::Asm"),
@@ -402,59 +424,10 @@ impl AssertSupported for SwitchStatement {
                 )
             };
 
-            let stmt_in_label = if let Statement::Labeled(label_stmt) = stmt {
-                label_stmt.node.label.assert_supported();
-                &label_stmt.node.statement.node
-            } else {
-                panic!(
-                    "`BlockItem::Statement` in the `Statement::Compound` of the `switch` \
-                     is unsupported except `Statement::Labeled`"
-                )
-            };
-
-            let items = if let Statement::Compound(items) = stmt_in_label {
-                items
-            } else {
-                panic!("`Statement` in the `label` is unsupported except `Statement::Compound`")
-            };
-
-            // Split last and all the rest of the elements of the `Compound` items
-            let (last, items) = items
-                .split_last()
-                .unwrap_or_else(|| panic!("`Statement::Compound` has no item"));
-
-            for item in items {
-                match &item.node {
-                    BlockItem::Declaration(decl) => decl.assert_supported(),
-                    BlockItem::StaticAssert(_) => panic!("BlockItem::StaticAssert"),
-                    BlockItem::Statement(stmt) => {
-                        assert_ne!(
-                            &stmt.node,
-                            &Statement::Break,
-                            "`BlockItem::Statement` in the `Statement::Compound` of the \
-                             `label` should not be `Statement::Break` except the last one"
-                        );
-                        stmt.assert_supported();
-                    }
-                }
-            }
-
-            // The last element of the `items` must be `Statement::Break`
-            let stmt = if let BlockItem::Statement(stmt) = &last.node {
-                &stmt.node
-            } else {
-                panic!(
-                    "`BlockItem` in the `Statement::Compound` of the `label` \
-                     is unsupported except `BlockItem::Statement`"
-                )
-            };
-
-            assert_eq!(
-                stmt,
-                &Statement::Break,
-                "the last `BlockItem` in the `Statement::Compound` \
-                 of the `label` must be `Statement::Break`"
-            );
+            // A label may be stacked on another, e.g., `case 2: case 3: return 20;`, and the
+            // statements after a label fall through to the next ones, so they need not be in a
+            // block of their own or end with `break`.
+            stmt.assert_supported();
         }
     }
 }
@@ -464,7 +437,7 @@ impl AssertSupported for Expression {
         match self {
             Self::Identifier(_) => (),
             Self::Constant(constant) => constant.assert_supported(),
-            Self::StringLiteral(_) => panic!("Expression::StringLiteral"),
+            Self::StringLiteral(_) => (),
             Self::GenericSelection(_) => panic!("Expression::GenericSelection"),
             Self::Member(member) => member.assert_supported(),
             Self::Call(call) => call.assert_supported(),
@@ -487,10 +460,10 @@ impl AssertSupported for Expression {
 impl AssertSupported for Label {
     fn assert_supported(&self) {
         match self {
-            Self::Identifier(_) => panic!("Label::Identifier"),
             Self::Case(_) => (),
-            Self::CaseRange(_) => panic!("Label::CaseRange"),
             Self::Default => (),
+            // Rejected by irgen as unsupported.
+            Self::Identifier(_) | Self::CaseRange(_) => (),
         }
     }
 }
@@ -628,21 +601,111 @@ pub fn is_valid_global_variable_declaration(decl: &Declaration) -> bool {
     })
 }
 
+/// Whether `initializer` of a global variable is constant. The expressions must be constant
+/// expressions or address constants, and a string literal may also initialize a `char` array.
 #[inline]
 pub fn is_valid_global_variable_initializer(initializer: &Initializer) -> bool {
     match initializer {
-        Initializer::Expression(expr) => match &expr.node {
-            Expression::Constant(_) => true,
-            Expression::UnaryOperator(unary) => {
-                matches!(
-                    &unary.node.operator.node,
-                    UnaryOperator::Minus | UnaryOperator::Plus
-                ) && matches!(&unary.node.operand.node, Expression::Constant(_))
-            }
-            _ => false,
-        },
+        Initializer::Expression(expr) => {
+            is_constant_expression(&expr.node) || is_address_constant(&expr.node)
+        }
         Initializer::List(items) => items
             .iter()
             .all(|item| is_valid_global_variable_initializer(&item.node.initializer.node)),
     }
 }
+
+/// Whether `expr` is built only of the constants and operators that
+/// [`crate::ir::const_eval::expression`] evaluates, and of `sizeof` and `_Alignof`, which irgen
+/// evaluates.
+fn is_constant_expression(expr: &Expression) -> bool {
+    match expr {
+        Expression::Constant(_)
+        | Expression::SizeOfTy(_)
+        | Expression::SizeOfVal(_)
+        | Expression::AlignOf(_) => true,
+        Expression::UnaryOperator(unary) => {
+            matches!(
+                &unary.node.operator.node,
+                UnaryOperator::Plus
+                    | UnaryOperator::Minus
+                    | UnaryOperator::Complement
+                    | UnaryOperator::Negate
+            ) && is_constant_expression(&unary.node.operand.node)
+        }
+        Expression::BinaryOperator(binary) => {
+            !matches!(
+                &binary.node.operator.node,
+                BinaryOperator::Index
+                    | BinaryOperator::Assign
+                    | BinaryOperator::AssignMultiply
+                    | BinaryOperator::AssignDivide
+                    | BinaryOperator::AssignModulo
+                    | BinaryOperator::AssignPlus
+                    | BinaryOperator::AssignMinus
+                    | BinaryOperator::AssignShiftLeft
+                    | BinaryOperator::AssignShiftRight
+                    | BinaryOperator::AssignBitwiseAnd
+                    | BinaryOperator::AssignBitwiseXor
+                    | BinaryOperator::AssignBitwiseOr
+            ) && is_constant_expression(&binary.node.lhs.node)
+                && is_constant_expression(&binary.node.rhs.node)
+        }
+        Expression::Conditional(conditional) => {
+            is_constant_expression(&conditional.node.condition.node)
+                && is_constant_expression(&conditional.node.then_expression.node)
+                && is_constant_expression(&conditional.node.else_expression.node)
+        }
+        _ => false,
+    }
+}
+
+/// Whether `expr` may be an address constant, i.e., a pointer to a global object or function
+/// offset by a constant, e.g., `&a[2]`, `a + 1` or `"hi"`. Whether the names are of global
+/// objects or functions is left to irgen.
+fn is_address_constant(expr: &Expression) -> bool {
+    match expr {
+        // An array or a function decays to a pointer.
+        Expression::Identifier(_) | Expression::StringLiteral(_) => true,
+        Expression::UnaryOperator(unary) => {
+            unary.node.operator.node == UnaryOperator::Address
+                && is_lvalue_constant(&unary.node.operand.node)
+        }
+        Expression::BinaryOperator(binary) => {
+            let (lhs, rhs) = (&binary.node.lhs.node, &binary.node.rhs.node);
+            match &binary.node.operator.node {
+                BinaryOperator::Plus => {
+                    (is_address_constant(lhs) && is_constant_expression(rhs))
+                        || (is_constant_expression(lhs) && is_address_constant(rhs))
+                }
+                BinaryOperator::Minus => is_address_constant(lhs) && is_constant_expression(rhs),
+                _ => false,
+            }
+        }
+        _ => false,
+    }
+}
+
+/// Whether `expr` may designate a global object or function at a constant offset, e.g., `a[2]` or
+/// `s.x`, whose address is an address constant.
+fn is_lvalue_constant(expr: &Expression) -> bool {
+    match expr {
+        Expression::Identifier(_) | Expression::StringLiteral(_) => true,
+        Expression::BinaryOperator(binary)
+            if binary.node.operator.node == BinaryOperator::Index =>
+        {
+            let (lhs, rhs) = (&binary.node.lhs.node, &binary.node.rhs.node);
+            (is_address_constant(lhs) && is_constant_expression(rhs))
+                || (is_constant_expression(lhs) && is_address_constant(rhs))
+        }
+        Expression::Member(member) => match member.node.operator.node {
+            MemberOperator::Direct => is_lvalue_constant(&member.node.expression.node),
+            MemberOperator::Indirect => is_address_constant(&member.node.expression.node),
+        },
+        Expression::UnaryOperator(unary) => {
+            unary.node.operator.node == UnaryOperator::Indirection
+                && is_address_constant(&unary.node.operand.node)
+        }
+        _ => false,
+    }
+}
@@ -41,18 +41,727 @@ impl<T: WriteString> WriteString for Option<T> {
 }
 
 impl WriteLine for TranslationUnit {
-    fn write_line(&self, _indent: usize, _write: &mut dyn Write) -> Result<()> {
-        // todo!("homework 1")
+    fn write_line(&self, indent: usize, write: &mut dyn Write) -> Result<()> {
         for ex_decl in &self.0 {
-            ex_decl.write_line(_indent, _write)?;
-            writeln!(_write)?;
+            ex_decl.write_line(indent, write)?;
+            writeln!(write)?;
         }
         Ok(())
     }
 }
 
+impl WriteLine for ExternalDeclaration {
+    fn write_line(&self, indent: usize, write: &mut dyn Write) -> Result<()> {
+        match self {
+            Self::Declaration(decl) => decl.write_line(indent, write),
+            Self::StaticAssert(_) => panic!("ExternalDeclaration::StaticAssert"),
+            Self::FunctionDefinition(fdef) => fdef.write_line(indent, write),
+        }
+    }
+}
+
+impl WriteLine for Declaration {
+    fn write_line(&self, indent: usize, write: &mut dyn Write) -> Result<()> {
+        write_indent(indent, write)?;
+        writeln!(write, "{};", self.write_string())
+    }
+}
+
+impl WriteLine for FunctionDefinition {
+    fn write_line(&self, indent: usize, write: &mut dyn Write) -> Result<()> {
+        write_indent(indent, write)?;
+        write!(
+            write,
+            "{} {}",
+            join(&self.specifiers, " "),
+            self.declarator.write_string()
+        )?;
+
+        // The parameters of a K&R-style definition are declared between the declarator and the
+        // body.
+        if !self.declarations.is_empty() {
+            writeln!(write)?;
+            for decl in &self.declarations {
+                decl.write_line(indent, write)?;
+            }
+            return self.statement.write_line(indent, write);
+        }
+        if write_body(&self.statement.node, indent, write)? {
+            writeln!(write)?;
+        }
+        Ok(())
+    }
+}
+
+impl WriteLine for BlockItem {
+    fn write_line(&self, indent: usize, write: &mut dyn Write) -> Result<()> {
+        match self {
+            Self::Declaration(decl) => decl.write_line(indent, write),
+            Self::StaticAssert(_) => panic!("BlockItem::StaticAssert"),
+            Self::Statement(stmt) => stmt.write_line(indent, write),
+        }
+    }
+}
+
+impl WriteLine for Statement {
+    fn write_line(&self, indent: usize, write: &mut dyn Write) -> Result<()> {
+        write_indent(indent, write)?;
+        let braced = match self {
+            Self::Labeled(stmt) => {
+                let label = match &stmt.node.label.node {
+                    Label::Identifier(identifier) => identifier.write_string(),
+                    Label::Case(expr) => format!("case {}", expr.write_string()),
+                    Label::CaseRange(range) => format!(
+                        "case {} ... {}",
+                        range.node.low.write_string(),
+                        range.node.high.write_string()
+                    ),
+                    Label::Default => "default".to_string(),
+                };
+                write!(write, "{label}:")?;
+                write_body(&stmt.node.statement.node, indent, write)?
+            }
+            Self::Compound(items) => {
+                writeln!(write, "{{")?;
+                for item in items {
+                    item.write_line(indent + 1, write)?;
+                }
+                write_indent(indent, write)?;
+                write!(write, "}}")?;
+                true
+            }
+            Self::Expression(expr) => {
+                writeln!(write, "{};", expr.write_string())?;
+                false
+            }
+            Self::If(stmt) => return write_if(&stmt.node, indent, write),
+            Self::Switch(stmt) => {
+                write!(write, "switch ({})", stmt.node.expression.write_string())?;
+                write_body(&stmt.node.statement.node, indent, write)?
+            }
+            Self::While(stmt) => {
+                write!(write, "while ({})", stmt.node.expression.write_string())?;
+                write_body(&stmt.node.statement.node, indent, write)?
+            }
+            Self::DoWhile(stmt) => {
+                write!(write, "do")?;
+                if write_body(&stmt.node.statement.node, indent, write)? {
+                    write!(write, " ")?;
+                } else {
+                    write_indent(indent, write)?;
+                }
+                writeln!(write, "while ({});", stmt.node.expression.write_string())?;
+                false
+            }
+            Self::For(stmt) => {
+                let initializer = match &stmt.node.initializer.node {
+                    ForInitializer::Empty => "".to_string(),
+                    ForInitializer::Expression(expr) => expr.write_string(),
+                    ForInitializer::Declaration(decl) => decl.write_string(),
+                    ForInitializer::StaticAssert(_) => panic!("ForInitializer::StaticAssert"),
+                };
+                write!(
+                    write,
+                    "for ({initializer}; {}; {})",
+                    stmt.node.condition.write_string(),
+                    stmt.node.step.write_string()
+                )?;
+                write_body(&stmt.node.statement.node, indent, write)?
+            }
+            Self::Goto(identifier) => {
+                writeln!(write, "goto {};", identifier.write_string())?;
+                false
+            }
+            Self::Continue => {
+                writeln!(write, "continue;")?;
+                false
+            }
+            Self::Break => {
+                writeln!(write, "break;")?;
+                false
+            }
+            Self::Return(expr) => {
+                if let Some(expr) = expr {
+                    writeln!(write, "return {};", expr.write_string())?;
+                } else {
+                    writeln!(write, "return;")?;
+                }
+                false
+            }
+            Self::Asm(_) => panic!("Statement::Asm"),
+        };
+        if braced {
+            writeln!(write)?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes `stmt`, the body of a statement whose head is written up to the end of the line,
+/// returning whether it is a compound statement. A compound statement is opened on the line of
+/// the head and its closing brace is left without a newline, so that the head may continue after
+/// it, e.g., with `else` or `while`. Any other statement is written on lines of its own.
+fn write_body(stmt: &Statement, indent: usize, write: &mut dyn Write) -> Result<bool> {
+    if let Statement::Compound(items) = stmt {
+        writeln!(write, " {{")?;
+        for item in items {
+            item.write_line(indent + 1, write)?;
+        }
+        write_indent(indent, write)?;
+        write!(write, "}}")?;
+        Ok(true)
+    } else {
+        writeln!(write)?;
+        stmt.write_line(indent + 1, write)?;
+        Ok(false)
+    }
+}
+
+/// Writes the `if` statement `stmt` from the current column, with `else if` chains kept at the
+/// indentation of the first `if`.
+fn write_if(stmt: &IfStatement, indent: usize, write: &mut dyn Write) -> Result<()> {
+    write!(write, "if ({})", stmt.condition.write_string())?;
+    let braced = write_body(&stmt.then_statement.node, indent, write)?;
+    let Some(else_statement) = &stmt.else_statement else {
+        if braced {
+            writeln!(write)?;
+        }
+        return Ok(());
+    };
+
+    if braced {
+        write!(write, " else")?;
+    } else {
+        write_indent(indent, write)?;
+        write!(write, "else")?;
+    }
+    if let Statement::If(stmt) = &else_statement.node {
+        write!(write, " ")?;
+        return write_if(&stmt.node, indent, write);
+    }
+    if write_body(&else_statement.node, indent, write)? {
+        writeln!(write)?;
+    }
+    Ok(())
+}
+
+/// Writes each of `items` separated by `separator`.
+fn join<T: WriteString>(items: &[T], separator: &str) -> String {
+    items
+        .iter()
+        .map(WriteString::write_string)
+        .filter(|item| !item.is_empty())
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+impl WriteString for Declaration {
+    fn write_string(&self) -> String {
+        let specifiers = join(&self.specifiers, " ");
+        if self.declarators.is_empty() {
+            return specifiers;
+        }
+        format!("{specifiers} {}", join(&self.declarators, ", "))
+    }
+}
+
+impl WriteString for DeclarationSpecifier {
+    fn write_string(&self) -> String {
+        match self {
+            Self::StorageClass(storage_class) => storage_class.write_string(),
+            Self::TypeSpecifier(type_specifier) => type_specifier.write_string(),
+            Self::TypeQualifier(type_qualifier) => type_qualifier.write_string(),
+            Self::Function(function) => match function.node {
+                FunctionSpecifier::Inline => "inline".to_string(),
+                FunctionSpecifier::Noreturn => "_Noreturn".to_string(),
+            },
+            Self::Alignment(alignment) => match &alignment.node {
+                AlignmentSpecifier::Type(type_name) => {
+                    format!("_Alignas({})", type_name.write_string())
+                }
+                AlignmentSpecifier::Constant(expr) => format!("_Alignas({})", expr.write_string()),
+            },
+            Self::Extension(_) => panic!("DeclarationSpecifier::Extension"),
+        }
+    }
+}
+
+impl WriteString for StorageClassSpecifier {
+    fn write_string(&self) -> String {
+        match self {
+            Self::Typedef => "typedef",
+            Self::Extern => "extern",
+            Self::Static => "static",
+            Self::ThreadLocal => "_Thread_local",
+            Self::Auto => "auto",
+            Self::Register => "register",
+        }
+        .to_string()
+    }
+}
+
+impl WriteString for TypeSpecifier {
+    fn write_string(&self) -> String {
+        match self {
+            Self::Void => "void".to_string(),
+            Self::Char => "char".to_string(),
+            Self::Short => "short".to_string(),
+            Self::Int => "int".to_string(),
+            Self::Long => "long".to_string(),
+            Self::Float => "float".to_string(),
+            Self::Double => "double".to_string(),
+            Self::Signed => "signed".to_string(),
+            Self::Unsigned => "unsigned".to_string(),
+            Self::Bool => "_Bool".to_string(),
+            Self::Complex => "_Complex".to_string(),
+            Self::Atomic(type_name) => format!("_Atomic({})", type_name.write_string()),
+            Self::Struct(struct_type) => struct_type.write_string(),
+            Self::Enum(enum_type) => enum_type.write_string(),
+            Self::TypedefName(identifier) => identifier.write_string(),
+            Self::TypeOf(type_of) => match &type_of.node {
+                TypeOf::Expression(expr) => format!("typeof({})", expr.write_string()),
+                TypeOf::Type(type_name) => format!("typeof({})", type_name.write_string()),
+            },
+            Self::TS18661Float(_) => panic!("TypeSpecifier::TS18661Float"),
+        }
+    }
+}
+
+impl WriteString for TypeQualifier {
+    fn write_string(&self) -> String {
+        match self {
+            Self::Const => "const",
+            Self::Restrict => "restrict",
+            Self::Volatile => "volatile",
+            Self::Nonnull => "_Nonnull",
+            Self::NullUnspecified => "_Null_unspecified",
+            Self::Nullable => "_Nullable",
+            Self::Atomic => "_Atomic",
+        }
+        .to_string()
+    }
+}
+
+impl WriteString for StructType {
+    fn write_string(&self) -> String {
+        let kind = match self.kind.node {
+            StructKind::Struct => "struct",
+            StructKind::Union => "union",
+        };
+        let mut string = kind.to_string();
+        if let Some(identifier) = &self.identifier {
+            string.push_str(&format!(" {}", identifier.write_string()));
+        }
+        if let Some(declarations) = &self.declarations {
+            string.push_str(" {");
+            for declaration in declarations {
+                string.push_str(&format!(" {};", declaration.write_string()));
+            }
+            string.push_str(" }");
+        }
+        string
+    }
+}
+
+impl WriteString for StructDeclaration {
+    fn write_string(&self) -> String {
+        match self {
+            Self::Field(field) => field.write_string(),
+            Self::StaticAssert(_) => panic!("StructDeclaration::StaticAssert"),
+        }
+    }
+}
+
+impl WriteString for StructField {
+    fn write_string(&self) -> String {
+        let specifiers = join(&self.specifiers, " ");
+        if self.declarators.is_empty() {
+            return specifiers;
+        }
+        format!("{specifiers} {}", join(&self.declarators, ", "))
+    }
+}
+
+impl WriteString for StructDeclarator {
+    fn write_string(&self) -> String {
+        let declarator = self.declarator.write_string();
+        match &self.bit_width {
+            Some(bit_width) if declarator.is_empty() => format!(": {}", bit_width.write_string()),
+            Some(bit_width) => format!("{declarator} : {}", bit_width.write_string()),
+            None => declarator,
+        }
+    }
+}
+
+impl WriteString for EnumType {
+    fn write_string(&self) -> String {
+        let mut string = "enum".to_string();
+        if let Some(identifier) = &self.identifier {
+            string.push_str(&format!(" {}", identifier.write_string()));
+        }
+        if !self.enumerators.is_empty() {
+            string.push_str(&format!(" {{ {} }}", join(&self.enumerators, ", ")));
+        }
+        string
+    }
+}
+
+impl WriteString for Enumerator {
+    fn write_string(&self) -> String {
+        let identifier = self.identifier.write_string();
+        if let Some(expr) = &self.expression {
+            format!("{identifier} = {}", without_comma(expr))
+        } else {
+            identifier
+        }
+    }
+}
+
+impl WriteString for SpecifierQualifier {
+    fn write_string(&self) -> String {
+        match self {
+            Self::TypeSpecifier(type_specifier) => type_specifier.write_string(),
+            Self::TypeQualifier(type_qualifier) => type_qualifier.write_string(),
+            Self::Extension(_) => panic!("SpecifierQualifier::Extension"),
+        }
+    }
+}
+
+impl WriteString for InitDeclarator {
+    fn write_string(&self) -> String {
+        let declarator = self.declarator.write_string();
+        if let Some(initializer) = &self.initializer {
+            format!("{declarator} = {}", initializer.write_string())
+        } else {
+            declarator
+        }
+    }
+}
+
+impl WriteString for Declarator {
+    /// Writes the pointers first, followed by the declarator they apply to and then its array and
+    /// function suffixes, in the order the parser reads them into `derived`.
+    fn write_string(&self) -> String {
+        let mut string = String::new();
+        for derived in &self.derived {
+            if let DerivedDeclarator::Pointer(qualifiers) = &derived.node {
+                string.push('*');
+                for qualifier in qualifiers {
+                    string.push_str(&format!("{} ", qualifier.write_string()));
+                }
+            }
+        }
+
+        match &self.kind.node {
+            DeclaratorKind::Abstract => {}
+            DeclaratorKind::Identifier(identifier) => string.push_str(&identifier.write_string()),
+            DeclaratorKind::Declarator(declarator) => {
+                string.push_str(&format!("({})", declarator.write_string()))
+            }
+        }
+
+        for derived in &self.derived {
+            match &derived.node {
+                DerivedDeclarator::Pointer(_) => {}
+                DerivedDeclarator::Array(array) => {
+                    let qualifiers = join(&array.node.qualifiers, " ");
+                    let size = match &array.node.size {
+                        ArraySize::Unknown => "".to_string(),
+                        ArraySize::VariableUnknown => "*".to_string(),
+                        ArraySize::VariableExpression(expr) => without_comma(expr),
+                        ArraySize::StaticExpression(expr) => {
+                            format!("static {}", without_comma(expr))
+                        }
+                    };
+                    let sep = if qualifiers.is_empty() || size.is_empty() {
+                        ""
+                    } else {
+                        " "
+                    };
+                    string.push_str(&format!("[{qualifiers}{sep}{size}]"));
+                }
+                DerivedDeclarator::Function(function) => {
+                    let mut params = join(&function.node.parameters, ", ");
+                    if function.node.ellipsis == Ellipsis::Some {
+                        params.push_str(", ...");
+                    }
+                    string.push_str(&format!("({params})"));
+                }
+                DerivedDeclarator::KRFunction(identifiers) => {
+                    string.push_str(&format!("({})", join(identifiers, ", ")));
+                }
+                DerivedDeclarator::Block(_) => panic!("DerivedDeclarator::Block"),
+            }
+        }
+        string
+    }
+}
+
+impl WriteString for PointerQualifier {
+    fn write_string(&self) -> String {
+        match self {
+            Self::TypeQualifier(type_qualifier) => type_qualifier.write_string(),
+            Self::Extension(_) => panic!("PointerQualifier::Extension"),
+        }
+    }
+}
+
+impl WriteString for ParameterDeclaration {
+    fn write_string(&self) -> String {
+        let specifiers = join(&self.specifiers, " ");
+        let declarator = self.declarator.write_string();
+        if declarator.is_empty() {
+            specifiers
+        } else {
+            format!("{specifiers} {declarator}")
+        }
+    }
+}
+
+impl WriteString for TypeName {
+    fn write_string(&self) -> String {
+        let specifiers = join(&self.specifiers, " ");
+        let declarator = self.declarator.write_string();
+        if declarator.is_empty() {
+            specifiers
+        } else {
+            format!("{specifiers} {declarator}")
+        }
+    }
+}
+
 impl WriteString for Initializer {
     fn write_string(&self) -> String {
-        todo!()
+        match self {
+            Self::Expression(expr) => without_comma(expr),
+            Self::List(items) => format!("{{{}}}", join(items, ", ")),
+        }
+    }
+}
+
+impl WriteString for InitializerListItem {
+    fn write_string(&self) -> String {
+        let initializer = self.initializer.write_string();
+        if self.designation.is_empty() {
+            return initializer;
+        }
+        format!("{} = {initializer}", join(&self.designation, ""))
+    }
+}
+
+impl WriteString for Designator {
+    fn write_string(&self) -> String {
+        match self {
+            Self::Index(expr) => format!("[{}]", expr.write_string()),
+            Self::Member(identifier) => format!(".{}", identifier.write_string()),
+            Self::Range(range) => format!(
+                "[{} ... {}]",
+                range.node.from.write_string(),
+                range.node.to.write_string()
+            ),
+        }
+    }
+}
+
+impl WriteString for Identifier {
+    fn write_string(&self) -> String {
+        self.name.clone()
+    }
+}
+
+impl WriteString for Expression {
+    /// Writes the expression with its operands parenthesized as [`operand`] does, so that the
+    /// output does not depend on the precedences of the operators. The
+    /// operand of a prefix operator is always parenthesized, e.g., `-(1)`, as the IR prints the
+    /// initializers of global variables.
+    fn write_string(&self) -> String {
+        match self {
+            Self::Identifier(identifier) => identifier.write_string(),
+            Self::Constant(constant) => constant.write_string(),
+            Self::StringLiteral(string_literal) => string_literal.node.join(" "),
+            Self::GenericSelection(_) => panic!("Expression::GenericSelection"),
+            Self::Member(member) => {
+                let operator = match member.node.operator.node {
+                    MemberOperator::Direct => ".",
+                    MemberOperator::Indirect => "->",
+                };
+                format!(
+                    "{}{operator}{}",
+                    operand(&member.node.expression),
+                    member.node.identifier.write_string()
+                )
+            }
+            Self::Call(call) => {
+                let arguments = call
+                    .node
+                    .arguments
+                    .iter()
+                    .map(without_comma)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!("{}({arguments})", operand(&call.node.callee))
+            }
+            Self::CompoundLiteral(_) => panic!("Expression::CompoundLiteral"),
+            Self::SizeOfTy(size_of) => format!("sizeof({})", size_of.node.0.write_string()),
+            Self::SizeOfVal(size_of) => format!("sizeof({})", size_of.node.0.write_string()),
+            Self::AlignOf(align_of) => format!("_Alignof({})", align_of.node.0.write_string()),
+            Self::UnaryOperator(unary) => {
+                let inner = unary.node.operand.write_string();
+                match unary.node.operator.node {
+                    UnaryOperator::PostIncrement => format!("{}++", operand(&unary.node.operand)),
+                    UnaryOperator::PostDecrement => format!("{}--", operand(&unary.node.operand)),
+                    UnaryOperator::PreIncrement => format!("++({inner})"),
+                    UnaryOperator::PreDecrement => format!("--({inner})"),
+                    UnaryOperator::Address => format!("&({inner})"),
+                    UnaryOperator::Indirection => format!("*({inner})"),
+                    UnaryOperator::Plus => format!("+({inner})"),
+                    UnaryOperator::Minus => format!("-({inner})"),
+                    UnaryOperator::Complement => format!("~({inner})"),
+                    UnaryOperator::Negate => format!("!({inner})"),
+                }
+            }
+            Self::Cast(cast) => format!(
+                "({}){}",
+                cast.node.type_name.write_string(),
+                operand(&cast.node.expression)
+            ),
+            Self::BinaryOperator(binary) => {
+                let lhs = operand(&binary.node.lhs);
+                let rhs = &binary.node.rhs;
+                if binary.node.operator.node == BinaryOperator::Index {
+                    return format!("{lhs}[{}]", rhs.write_string());
+                }
+                format!(
+                    "{lhs} {} {}",
+                    binary_operator(&binary.node.operator.node),
+                    operand(rhs)
+                )
+            }
+            Self::Conditional(conditional) => format!(
+                "{} ? {} : {}",
+                operand(&conditional.node.condition),
+                operand(&conditional.node.then_expression),
+                operand(&conditional.node.else_expression)
+            ),
+            Self::Comma(exprs) => exprs
+                .iter()
+                .map(without_comma)
+                .collect::<Vec<_>>()
+                .join(", "),
+            Self::OffsetOf(_) => panic!("Expression::OffsetOf"),
+            Self::VaArg(_) => panic!("Expression::VaArg"),
+            Self::Statement(_) => panic!("Expression::Statement"),
+        }
+    }
+}
+
+/// Writes `expr` as the operand of an operator, parenthesized unless it is an identifier, a
+/// literal, or a member access, call or subscript, which bind tighter than any operator.
+fn operand(expr: &Node<Expression>) -> String {
+    let string = expr.write_string();
+    match &expr.node {
+        Expression::Identifier(_)
+        | Expression::Constant(_)
+        | Expression::StringLiteral(_)
+        | Expression::Member(_)
+        | Expression::Call(_) => string,
+        Expression::BinaryOperator(binary)
+            if binary.node.operator.node == BinaryOperator::Index =>
+        {
+            string
+        }
+        _ => format!("({string})"),
+    }
+}
+
+/// Writes `expr` where a comma would separate it from what follows, e.g., as an argument of a
+/// call, parenthesized if it is a comma expression.
+fn without_comma(expr: &Node<Expression>) -> String {
+    let string = expr.write_string();
+    if matches!(expr.node, Expression::Comma(_)) {
+        format!("({string})")
+    } else {
+        string
+    }
+}
+
+fn binary_operator(op: &BinaryOperator) -> &'static str {
+    match op {
+        BinaryOperator::Index => unreachable!("subscripts are written with brackets"),
+        BinaryOperator::Multiply => "*",
+        BinaryOperator::Divide => "/",
+        BinaryOperator::Modulo => "%",
+        BinaryOperator::Plus => "+",
+        BinaryOperator::Minus => "-",
+        BinaryOperator::ShiftLeft => "<<",
+        BinaryOperator::ShiftRight => ">>",
+        BinaryOperator::Less => "<",
+        BinaryOperator::Greater => ">",
+        BinaryOperator::LessOrEqual => "<=",
+        BinaryOperator::GreaterOrEqual => ">=",
+        BinaryOperator::Equals => "==",
+        BinaryOperator::NotEquals => "!=",
+        BinaryOperator::BitwiseAnd => "&",
+        BinaryOperator::BitwiseXor => "^",
+        BinaryOperator::BitwiseOr => "|",
+        BinaryOperator::LogicalAnd => "&&",
+        BinaryOperator::LogicalOr => "||",
+        BinaryOperator::Assign => "=",
+        BinaryOperator::AssignMultiply => "*=",
+        BinaryOperator::AssignDivide => "/=",
+        BinaryOperator::AssignModulo => "%=",
+        BinaryOperator::AssignPlus => "+=",
+        BinaryOperator::AssignMinus => "-=",
+        BinaryOperator::AssignShiftLeft => "<<=",
+        BinaryOperator::AssignShiftRight => ">>=",
+        BinaryOperator::AssignBitwiseAnd => "&=",
+        BinaryOperator::AssignBitwiseXor => "^=",
+        BinaryOperator::AssignBitwiseOr => "|=",
+    }
+}
+
+impl WriteString for Constant {
+    fn write_string(&self) -> String {
+        match self {
+            Self::Integer(integer) => integer.write_string(),
+            Self::Float(float) => float.write_string(),
+            Self::Character(character) => character.clone(),
+        }
+    }
+}
+
+impl WriteString for Integer {
+    fn write_string(&self) -> String {
+        let base = match self.base {
+            IntegerBase::Decimal => "",
+            IntegerBase::Octal => "0",
+            IntegerBase::Hexadecimal => "0x",
+            IntegerBase::Binary => "0b",
+        };
+        let unsigned = if self.suffix.unsigned { "u" } else { "" };
+        let size = match self.suffix.size {
+            IntegerSize::Int => "",
+            IntegerSize::Long => "l",
+            IntegerSize::LongLong => "ll",
+        };
+        let imaginary = if self.suffix.imaginary { "i" } else { "" };
+        format!("{base}{}{unsigned}{size}{imaginary}", self.number)
+    }
+}
+
+impl WriteString for Float {
+    fn write_string(&self) -> String {
+        let base = match self.base {
+            FloatBase::Decimal => "",
+            FloatBase::Hexadecimal => "0x",
+        };
+        let format = match self.suffix.format {
+            FloatFormat::Float => "f",
+            FloatFormat::Double => "",
+            FloatFormat::LongDouble => "l",
+            FloatFormat::TS18661Format(_) => panic!("FloatFormat::TS18661Format"),
+        };
+        let imaginary = if self.suffix.imaginary { "i" } else { "" };
+        format!("{base}{}{format}{imaginary}", self.number)
     }
 }
@@ -0,0 +1,221 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::ir::analysis::Cfg;
+use crate::ir::*;
+use crate::*;
+
+/// The memory object a pointer is derived from.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum MemoryBase {
+    /// The local allocation `aid`.
+    Local(usize),
+    /// The global variable or function of the name.
+    Global(String),
+    /// Unknown, e.g., a pointer given as an argument or loaded from memory.
+    Unknown,
+}
+
+/// Where a pointer points to: a byte offset into a memory object.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MemoryLocation {
+    pub base: MemoryBase,
+    /// The byte offset from the beginning of `base`, if known.
+    pub offset: Option<isize>,
+}
+
+impl MemoryLocation {
+    fn unknown() -> Self {
+        Self {
+            base: MemoryBase::Unknown,
+            offset: None,
+        }
+    }
+}
+
+/// A may-alias analysis of the pointers of a function.
+///
+/// Pointers are traced back through `getelementptr` and typecasts to the local allocation or
+/// global they are derived from. Pointers derived from different objects never alias, and
+/// pointers derived from the same object alias only if the accessed bytes may overlap.
+///
+/// A local allocation escapes if a pointer derived from it is used other than as the address of
+/// loads and stores, e.g., stored in memory or passed to a call. Only pointers derived from
+/// escaped allocations may alias unknown pointers or be accessed by callees.
+#[derive(Debug, Clone)]
+pub struct AliasAnalysis<'a> {
+    structs: &'a HashMap<String, Option<Dtype>>,
+    locations: HashMap<RegisterId, MemoryLocation>,
+    escaped: HashSet<usize>,
+}
+
+impl<'a> AliasAnalysis<'a> {
+    pub fn new(
+        definition: &FunctionDefinition,
+        structs: &'a HashMap<String, Option<Dtype>>,
+    ) -> Self {
+        let mut analysis = Self {
+            structs,
+            locations: HashMap::new(),
+            escaped: HashSet::new(),
+        };
+
+        // Definitions dominate their uses, so they come first in reverse post-order.
+        let cfg = Cfg::new(definition);
+        let mut order = cfg.reverse_post_order().to_vec();
+        order.extend(cfg.blocks().filter(|bid| !cfg.is_reachable(*bid)));
+        for bid in order {
+            let block = some_or!(definition.blocks.get(&bid), continue);
+            for (iid, instr) in block.instructions.iter().enumerate() {
+                let location = match &**instr {
+                    Instruction::GetElementPtr { ptr, offset, .. } => {
+                        let mut location = analysis.location(ptr);
+                        let offset = offset.get_constant().and_then(Self::constant_offset);
+                        location.offset = location.offset.zip(offset).map(|(l, r)| l + r);
+                        location
+                    }
+                    Instruction::TypeCast {
+                        value,
+                        target_dtype: Dtype::Pointer { .. },
+                    } if value.dtype().get_pointer_inner().is_some() => analysis.location(value),
+                    _ => continue,
+                };
+                let _unused = analysis
+                    .locations
+                    .insert(RegisterId::temp(bid, iid), location);
+            }
+        }
+
+        for block in definition.blocks.values() {
+            for instr in &block.instructions {
+                let escaping = match &**instr {
+                    Instruction::Load { .. } => Vec::new(),
+                    Instruction::Store { value, .. } => vec![value],
+                    Instruction::GetElementPtr { offset, .. } => vec![offset],
+                    Instruction::TypeCast {
+                        value,
+                        target_dtype: Dtype::Pointer { .. },
+                    } if value.dtype().get_pointer_inner().is_some() => Vec::new(),
+                    _ => instr.operands(),
+                };
+                for operand in escaping {
+                    if let MemoryBase::Local(aid) = analysis.location(operand).base {
+                        let _unused = analysis.escaped.insert(aid);
+                    }
+                }
+            }
+            for operand in block.exit.operands() {
+                if let MemoryBase::Local(aid) = analysis.location(operand).base {
+                    let _unused = analysis.escaped.insert(aid);
+                }
+            }
+        }
+
+        analysis
+    }
+
+    fn constant_offset(constant: &Constant) -> Option<isize> {
+        let (value, width, is_signed) = constant.get_int()?;
+        let value = if is_signed {
+            sign_extension(value, width as u128) as i128
+        } else {
+            value as i128
+        };
+        isize::try_from(value).ok()
+    }
+
+    /// Where `ptr` points to.
+    pub fn location(&self, ptr: &Operand) -> MemoryLocation {
+        match ptr {
+            Operand::Constant(Constant::GlobalVariable { name, .. }) => MemoryLocation {
+                base: MemoryBase::Global(name.clone()),
+                offset: Some(0),
+            },
+            Operand::Register {
+                rid: RegisterId::Local { aid },
+                ..
+            } => MemoryLocation {
+                base: MemoryBase::Local(*aid),
+                offset: Some(0),
+            },
+            Operand::Register { rid, .. } => self
+                .locations
+                .get(rid)
+                .cloned()
+                .unwrap_or_else(MemoryLocation::unknown),
+            Operand::Constant(_) => MemoryLocation::unknown(),
+        }
+    }
+
+    /// Whether a pointer derived from the local allocation `aid` may be used other than as the
+    /// address of loads and stores.
+    pub fn is_escaped(&self, aid: usize) -> bool {
+        self.escaped.contains(&aid)
+    }
+
+    /// Whether `location` may be accessed through unknown pointers or by callees.
+    pub fn is_exposed(&self, location: &MemoryLocation) -> bool {
+        match location.base {
+            MemoryBase::Local(aid) => self.is_escaped(aid),
+            MemoryBase::Global(_) | MemoryBase::Unknown => true,
+        }
+    }
+
+    /// Whether accessing the pointees of `lhs` and `rhs` may access the same bytes.
+    pub fn may_alias(&self, lhs: &Operand, rhs: &Operand) -> bool {
+        let lhs_location = self.location(lhs);
+        let rhs_location = self.location(rhs);
+
+        match (&lhs_location.base, &rhs_location.base) {
+            (MemoryBase::Unknown, MemoryBase::Unknown) => true,
+            (MemoryBase::Unknown, _) => self.is_exposed(&rhs_location),
+            (_, MemoryBase::Unknown) => self.is_exposed(&lhs_location),
+            (lhs_base, rhs_base) if lhs_base != rhs_base => false,
+            _ => {
+                let (lhs_offset, rhs_offset) =
+                    some_or!(lhs_location.offset.zip(rhs_location.offset), return true);
+                let (lhs_size, rhs_size) = some_or!(
+                    self.access_size(lhs).zip(self.access_size(rhs)),
+                    return true
+                );
+                lhs_offset < rhs_offset + rhs_size as isize
+                    && rhs_offset < lhs_offset + lhs_size as isize
+            }
+        }
+    }
+
+    fn access_size(&self, ptr: &Operand) -> Option<usize> {
+        let (size, _) = ptr
+            .dtype()
+            .get_pointer_inner()?
+            .size_align_of(self.structs)
+            .ok()?;
+        Some(size)
+    }
+
+    /// Whether `lhs` and `rhs` may conflict, i.e., may access the same memory with at least one of
+    /// them writing to it. Callees are assumed to read and write all exposed memory, and volatile
+    /// accesses are assumed to conflict with each other.
+    pub fn may_conflict(&self, lhs: &Instruction, rhs: &Instruction) -> bool {
+        if lhs.is_volatile() && rhs.is_volatile() {
+            return true;
+        }
+
+        match (lhs, rhs) {
+            (Instruction::Load { ptr: lhs, .. }, Instruction::Store { ptr: rhs, .. })
+            | (Instruction::Store { ptr: lhs, .. }, Instruction::Load { ptr: rhs, .. })
+            | (Instruction::Store { ptr: lhs, .. }, Instruction::Store { ptr: rhs, .. }) => {
+                self.may_alias(lhs, rhs)
+            }
+            (
+                Instruction::Call { .. },
+                Instruction::Load { ptr, .. } | Instruction::Store { ptr, .. },
+            )
+            | (
+                Instruction::Load { ptr, .. } | Instruction::Store { ptr, .. },
+                Instruction::Call { .. },
+            ) => self.is_exposed(&self.location(ptr)),
+            (Instruction::Call { .. }, Instruction::Call { .. }) => true,
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::ir::*;
+
+/// The control-flow graph of a function.
+///
+/// A block's successors are the distinct blocks its exit may jump to, in the order they appear in
+/// the exit. A block's predecessors are the distinct blocks that may jump to it, including
+/// unreachable ones.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cfg {
+    entry: BlockId,
+    successors: BTreeMap<BlockId, Vec<BlockId>>,
+    predecessors: BTreeMap<BlockId, Vec<BlockId>>,
+    /// Blocks reachable from the entry block, in reverse post-order.
+    reverse_post_order: Vec<BlockId>,
+}
+
+impl Cfg {
+    pub fn new(definition: &FunctionDefinition) -> Self {
+        let mut successors = BTreeMap::new();
+        let mut predecessors = definition
+            .blocks
+            .keys()
+            .map(|bid| (*bid, Vec::new()))
+            .collect::<BTreeMap<_, Vec<_>>>();
+
+        for (bid, block) in &definition.blocks {
+            let mut succs = Vec::new();
+            for arg in block.exit.jump_args() {
+                if !succs.contains(&arg.bid) {
+                    succs.push(arg.bid);
+                }
+            }
+            for succ in &succs {
+                predecessors.entry(*succ).or_default().push(*bid);
+            }
+            let _unused = successors.insert(*bid, succs);
+        }
+
+        let mut cfg = Self {
+            entry: definition.bid_init,
+            successors,
+            predecessors,
+            reverse_post_order: Vec::new(),
+        };
+        cfg.reverse_post_order = cfg.compute_reverse_post_order();
+        cfg
+    }
+
+    fn compute_reverse_post_order(&self) -> Vec<BlockId> {
+        let mut post_order = Vec::new();
+        let mut visited = BTreeSet::new();
+        let mut stack = vec![(self.entry, 0)];
+        let _unused = visited.insert(self.entry);
+
+        while let Some((bid, index)) = stack.last_mut() {
+            if let Some(succ) = self.successors(*bid).get(*index) {
+                *index += 1;
+                if visited.insert(*succ) {
+                    stack.push((*succ, 0));
+                }
+            } else {
+                post_order.push(*bid);
+                let _unused = stack.pop();
+            }
+        }
+
+        post_order.reverse();
+        post_order
+    }
+
+    /// The entry block.
+    pub fn entry(&self) -> BlockId {
+        self.entry
+    }
+
+    /// All blocks, including unreachable ones, in the order of their ids.
+    pub fn blocks(&self) -> impl Iterator<Item = BlockId> + '_ {
+        self.successors.keys().copied()
+    }
+
+    /// The blocks `bid` may jump to. Jumps to nonexistent blocks are included as they are.
+    pub fn successors(&self, bid: BlockId) -> &[BlockId] {
+        self.successors.get(&bid).map_or(&[], Vec::as_slice)
+    }
+
+    /// The blocks that may jump to `bid`.
+    pub fn predecessors(&self, bid: BlockId) -> &[BlockId] {
+        self.predecessors.get(&bid).map_or(&[], Vec::as_slice)
+    }
+
+    /// The blocks reachable from the entry block in reverse post-order, so that every block comes
+    /// before its successors except along back edges. The entry block comes first.
+    pub fn reverse_post_order(&self) -> &[BlockId] {
+        &self.reverse_post_order
+    }
+
+    /// The blocks reachable from the entry block in post-order.
+    pub fn post_order(&self) -> impl Iterator<Item = BlockId> + '_ {
+        self.reverse_post_order.iter().rev().copied()
+    }
+
+    /// Whether `bid` is reachable from the entry block.
+    pub fn is_reachable(&self, bid: BlockId) -> bool {
+        self.reverse_post_order.contains(&bid)
+    }
+
+    /// The blocks reachable from `from`, including `from` itself.
+    pub fn reachable_from(&self, from: BlockId) -> BTreeSet<BlockId> {
+        let mut reachable = BTreeSet::new();
+        let mut worklist = vec![from];
+        while let Some(bid) = worklist.pop() {
+            if reachable.insert(bid) {
+                worklist.extend(self.successors(bid));
+            }
+        }
+        reachable
+    }
+}
@@ -0,0 +1,198 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use crate::ir::visit::walk_function_mut;
+use crate::ir::*;
+
+/// Where a register is used: an instruction or the exit of a block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Use {
+    Instruction { bid: BlockId, iid: usize },
+    Exit { bid: BlockId },
+}
+
+impl Use {
+    /// The block of the use.
+    pub fn bid(&self) -> BlockId {
+        match self {
+            Self::Instruction { bid, .. } | Self::Exit { bid } => *bid,
+        }
+    }
+}
+
+/// The definitions and uses of the registers of a function.
+///
+/// Where a register is defined follows from its id: `%l0` is defined by an allocation, `%b0:p0` by a
+/// phinode and `%b0:i0` by an instruction. The index records which of them exist and, for each
+/// register, the instructions and exits using it. Unreachable blocks are indexed as well.
+///
+/// Rewriting a function with [`DefUse::rewrite`], [`DefUse::rewrite_at`] or
+/// [`DefUse::replace_all_uses`] keeps the uses up to date. Inserting or removing allocations,
+/// phinodes, instructions or blocks changes the registers and requires a new index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DefUse {
+    defs: HashSet<RegisterId>,
+    uses: HashMap<RegisterId, BTreeSet<Use>>,
+}
+
+impl DefUse {
+    pub fn new(definition: &FunctionDefinition) -> Self {
+        let mut def_use = Self {
+            defs: (0..definition.allocations.len())
+                .map(RegisterId::local)
+                .collect(),
+            uses: HashMap::new(),
+        };
+
+        for (bid, block) in &definition.blocks {
+            def_use
+                .defs
+                .extend((0..block.phinodes.len()).map(|aid| RegisterId::arg(*bid, aid)));
+            for (iid, instr) in block.instructions.iter().enumerate() {
+                let _unused = def_use.defs.insert(RegisterId::temp(*bid, iid));
+                def_use.add_uses(Use::Instruction { bid: *bid, iid }, instr.operands());
+            }
+            def_use.add_uses(Use::Exit { bid: *bid }, block.exit.operands());
+        }
+
+        def_use
+    }
+
+    /// Whether `rid` is defined by an allocation, a phinode or an instruction of the function.
+    pub fn is_defined(&self, rid: RegisterId) -> bool {
+        self.defs.contains(&rid)
+    }
+
+    /// The instructions and exits using `rid`, in the order of their blocks.
+    pub fn uses(&self, rid: RegisterId) -> &BTreeSet<Use> {
+        static EMPTY: BTreeSet<Use> = BTreeSet::new();
+        self.uses.get(&rid).unwrap_or(&EMPTY)
+    }
+
+    /// Whether `rid` is used by any instruction or exit.
+    pub fn is_used(&self, rid: RegisterId) -> bool {
+        !self.uses(rid).is_empty()
+    }
+
+    /// Runs `rewriter` over `definition`, updating the uses of each instruction and exit it
+    /// rewrites.
+    ///
+    /// The rewriter is run on each instruction and exit separately, so its `rewrite_function` and
+    /// `rewrite_block` methods are not called.
+    pub fn rewrite<R>(&mut self, definition: &mut FunctionDefinition, rewriter: &mut R)
+    where
+        R: IrRewriter + ?Sized,
+    {
+        walk_function_mut(
+            &mut Tracked {
+                def_use: self,
+                inner: rewriter,
+            },
+            definition,
+        );
+    }
+
+    /// Runs `rewriter` over the instruction or exit at `at` only, updating its uses.
+    pub fn rewrite_at<R>(&mut self, definition: &mut FunctionDefinition, at: Use, rewriter: &mut R)
+    where
+        R: IrRewriter + ?Sized,
+    {
+        let mut tracked = Tracked {
+            def_use: self,
+            inner: rewriter,
+        };
+        let block = definition
+            .blocks
+            .get_mut(&at.bid())
+            .expect("used block must exist");
+        match at {
+            Use::Instruction { bid, iid } => tracked
+                .rewrite_instruction(RegisterId::temp(bid, iid), &mut block.instructions[iid]),
+            Use::Exit { bid } => tracked.rewrite_block_exit(bid, &mut block.exit),
+        }
+    }
+
+    /// Replaces every use of `rid` in `definition` with `operand`, visiting only the instructions
+    /// and exits using it.
+    pub fn replace_all_uses(
+        &mut self,
+        definition: &mut FunctionDefinition,
+        rid: RegisterId,
+        operand: &Operand,
+    ) {
+        let uses = self.uses(rid).clone();
+        let mut replace = Replace { rid, operand };
+        for at in uses {
+            self.rewrite_at(definition, at, &mut replace);
+        }
+    }
+
+    fn add_uses(&mut self, at: Use, operands: Vec<&Operand>) {
+        for operand in operands {
+            if let Some((rid, _)) = operand.get_register() {
+                let _unused = self.uses.entry(*rid).or_default().insert(at);
+            }
+        }
+    }
+
+    fn remove_uses(&mut self, at: Use, operands: Vec<&Operand>) {
+        for operand in operands {
+            if let Some((rid, _)) = operand.get_register() {
+                if let Some(uses) = self.uses.get_mut(rid) {
+                    let _unused = uses.remove(&at);
+                    if uses.is_empty() {
+                        let _unused = self.uses.remove(rid);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Runs the inner rewriter on each instruction and exit, reindexing their uses.
+#[derive(Debug)]
+struct Tracked<'a, R: ?Sized> {
+    def_use: &'a mut DefUse,
+    inner: &'a mut R,
+}
+
+impl<R: IrRewriter + ?Sized> IrRewriter for Tracked<'_, R> {
+    fn rewrite_allocation(&mut self, aid: usize, allocation: &mut Named<Dtype>) {
+        self.inner.rewrite_allocation(aid, allocation);
+    }
+
+    fn rewrite_phinode(&mut self, rid: RegisterId, phinode: &mut Named<Dtype>) {
+        self.inner.rewrite_phinode(rid, phinode);
+    }
+
+    fn rewrite_instruction(&mut self, rid: RegisterId, instruction: &mut Named<Instruction>) {
+        let at = match rid {
+            RegisterId::Temp { bid, iid } => Use::Instruction { bid, iid },
+            _ => panic!("instruction must define a temporary register"),
+        };
+        self.def_use.remove_uses(at, instruction.operands());
+        self.inner.rewrite_instruction(rid, instruction);
+        self.def_use.add_uses(at, instruction.operands());
+    }
+
+    fn rewrite_block_exit(&mut self, bid: BlockId, exit: &mut BlockExit) {
+        let at = Use::Exit { bid };
+        self.def_use.remove_uses(at, exit.operands());
+        self.inner.rewrite_block_exit(bid, exit);
+        self.def_use.add_uses(at, exit.operands());
+    }
+}
+
+/// Replaces the uses of a register with an operand.
+#[derive(Debug)]
+struct Replace<'a> {
+    rid: RegisterId,
+    operand: &'a Operand,
+}
+
+impl IrRewriter for Replace<'_> {
+    fn rewrite_operand(&mut self, operand: &mut Operand) {
+        if operand.get_register().map(|(rid, _)| *rid) == Some(self.rid) {
+            *operand = self.operand.clone();
+        }
+    }
+}
@@ -0,0 +1,407 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::ir::analysis::Cfg;
+use crate::ir::*;
+use crate::*;
+
+/// The dominator tree of the blocks reachable from the entry block.
+///
+/// A block `a` dominates a block `b` if every path from the entry block to `b` passes through `a`.
+/// Every block dominates itself. Unreachable blocks are not in the tree, and neither dominate nor
+/// are dominated by any block.
+///
+/// Computed by the algorithm of Cooper, Harvey, and Kennedy, "A Simple, Fast Dominance Algorithm".
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dominators {
+    entry: BlockId,
+    /// The immediate dominator of each reachable block other than the entry block.
+    idoms: BTreeMap<BlockId, BlockId>,
+    /// The children of each reachable block in the tree, in reverse post-order.
+    children: BTreeMap<BlockId, Vec<BlockId>>,
+    /// The pre-order and post-order numbers of each reachable block in the tree.
+    numbers: BTreeMap<BlockId, (usize, usize)>,
+}
+
+impl Dominators {
+    pub fn new(cfg: &Cfg) -> Self {
+        let order = cfg.reverse_post_order();
+        let index = order
+            .iter()
+            .enumerate()
+            .map(|(i, bid)| (*bid, i))
+            .collect::<BTreeMap<_, _>>();
+        let preds = order
+            .iter()
+            .map(|bid| {
+                cfg.predecessors(*bid)
+                    .iter()
+                    .filter_map(|pred| index.get(pred).copied())
+                    .collect()
+            })
+            .collect::<Vec<_>>();
+        let idoms = immediate_dominators(&preds);
+
+        let mut dominators = Self {
+            entry: cfg.entry(),
+            idoms: BTreeMap::new(),
+            children: order.iter().map(|bid| (*bid, Vec::new())).collect(),
+            numbers: BTreeMap::new(),
+        };
+        for (i, bid) in order.iter().enumerate().skip(1) {
+            let idom = order[idoms[i]];
+            let _unused = dominators.idoms.insert(*bid, idom);
+            dominators
+                .children
+                .get_mut(&idom)
+                .expect("idom must be reachable")
+                .push(*bid);
+        }
+        dominators.numbers = number(dominators.entry, |bid| dominators.children(bid).to_vec());
+        dominators
+    }
+
+    /// The root of the tree, i.e., the entry block.
+    pub fn entry(&self) -> BlockId {
+        self.entry
+    }
+
+    /// Whether `bid` is reachable from the entry block, i.e., is in the tree.
+    pub fn is_reachable(&self, bid: BlockId) -> bool {
+        self.numbers.contains_key(&bid)
+    }
+
+    /// The immediate dominator of `bid`, which is its parent in the tree. The entry block and
+    /// unreachable blocks have none.
+    pub fn idom(&self, bid: BlockId) -> Option<BlockId> {
+        self.idoms.get(&bid).copied()
+    }
+
+    /// The blocks `bid` immediately dominates, which are its children in the tree.
+    pub fn children(&self, bid: BlockId) -> &[BlockId] {
+        self.children.get(&bid).map_or(&[], Vec::as_slice)
+    }
+
+    /// Whether `lhs` dominates `rhs`.
+    pub fn dominates(&self, lhs: BlockId, rhs: BlockId) -> bool {
+        match (self.numbers.get(&lhs), self.numbers.get(&rhs)) {
+            (Some((lhs_pre, lhs_post)), Some((rhs_pre, rhs_post))) => {
+                lhs_pre <= rhs_pre && rhs_post <= lhs_post
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether `lhs` dominates `rhs` and they are different blocks.
+    pub fn strictly_dominates(&self, lhs: BlockId, rhs: BlockId) -> bool {
+        lhs != rhs && self.dominates(lhs, rhs)
+    }
+
+    /// The dominators of `bid` from `bid` itself up to the entry block. Empty if `bid` is
+    /// unreachable.
+    pub fn dominators(&self, bid: BlockId) -> impl Iterator<Item = BlockId> + '_ {
+        let start = self.is_reachable(bid).then_some(bid);
+        std::iter::successors(start, |bid| self.idom(*bid))
+    }
+
+    /// The blocks of the tree in pre-order, so that every block comes after its dominators.
+    pub fn pre_order(&self) -> Vec<BlockId> {
+        let mut pre_order = self.numbers.iter().collect::<Vec<_>>();
+        pre_order.sort_by_key(|(_, (pre, _))| *pre);
+        pre_order.into_iter().map(|(bid, _)| *bid).collect()
+    }
+}
+
+/// The post-dominator tree of the blocks reachable from the entry block that reach an exit of the
+/// function, i.e., a block returning or ending in `unreachable`.
+///
+/// A block `a` post-dominates a block `b` if every path from `b` to an exit passes through `a`.
+/// Every block post-dominates itself. The root of the tree is a virtual exit succeeding all exits,
+/// so that a function with several exits still has a tree. Blocks not in the tree, e.g., those in
+/// an infinite loop, neither post-dominate nor are post-dominated by any block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PostDominators {
+    /// The immediate post-dominator of each block in the tree, where `None` is the virtual exit.
+    ipdoms: BTreeMap<BlockId, Option<BlockId>>,
+    /// The children of the virtual exit and of each block in the tree.
+    children: BTreeMap<Option<BlockId>, Vec<BlockId>>,
+    /// The pre-order and post-order numbers of the virtual exit and of each block in the tree.
+    numbers: BTreeMap<Option<BlockId>, (usize, usize)>,
+}
+
+impl PostDominators {
+    pub fn new(cfg: &Cfg) -> Self {
+        let exits = cfg
+            .reverse_post_order()
+            .iter()
+            .filter(|bid| cfg.successors(**bid).is_empty())
+            .copied()
+            .collect::<Vec<_>>();
+        let reverse_succs = |node: Option<BlockId>| -> Vec<Option<BlockId>> {
+            match node {
+                None => exits.iter().copied().map(Some).collect(),
+                Some(bid) => cfg
+                    .predecessors(bid)
+                    .iter()
+                    .filter(|pred| cfg.is_reachable(**pred))
+                    .copied()
+                    .map(Some)
+                    .collect(),
+            }
+        };
+
+        // The nodes of the reversed CFG in reverse post-order, starting from the virtual exit.
+        let mut order = Vec::new();
+        let mut visited = BTreeSet::from([None]);
+        let mut stack = vec![(None, reverse_succs(None), 0)];
+        while let Some((node, succs, index)) = stack.last_mut() {
+            if let Some(succ) = succs.get(*index) {
+                let succ = *succ;
+                *index += 1;
+                if visited.insert(succ) {
+                    stack.push((succ, reverse_succs(succ), 0));
+                }
+            } else {
+                order.push(*node);
+                let _unused = stack.pop();
+            }
+        }
+        order.reverse();
+
+        let index = order
+            .iter()
+            .enumerate()
+            .map(|(i, node)| (*node, i))
+            .collect::<BTreeMap<_, _>>();
+        let preds = order
+            .iter()
+            .map(|node| match node {
+                None => Vec::new(),
+                Some(bid) if exits.contains(bid) => vec![0],
+                Some(bid) => cfg
+                    .successors(*bid)
+                    .iter()
+                    .filter_map(|succ| index.get(&Some(*succ)).copied())
+                    .collect(),
+            })
+            .collect::<Vec<_>>();
+        let ipdoms = immediate_dominators(&preds);
+
+        let mut post_dominators = Self {
+            ipdoms: BTreeMap::new(),
+            children: order.iter().map(|node| (*node, Vec::new())).collect(),
+            numbers: BTreeMap::new(),
+        };
+        for (i, node) in order.iter().enumerate().skip(1) {
+            let bid = node.expect("only the root is the virtual exit");
+            let ipdom = order[ipdoms[i]];
+            let _unused = post_dominators.ipdoms.insert(bid, ipdom);
+            post_dominators
+                .children
+                .get_mut(&ipdom)
+                .expect("ipdom must be in the tree")
+                .push(bid);
+        }
+        post_dominators.numbers = number(None, |node| {
+            post_dominators
+                .children
+                .get(&node)
+                .map_or(Vec::new(), |children| {
+                    children.iter().copied().map(Some).collect()
+                })
+        });
+        post_dominators
+    }
+
+    /// Whether `bid` is in the tree, i.e., is reachable from the entry block and reaches an exit.
+    pub fn reaches_exit(&self, bid: BlockId) -> bool {
+        self.ipdoms.contains_key(&bid)
+    }
+
+    /// The immediate post-dominator of `bid`, which is its parent in the tree. Exits, blocks
+    /// whose paths to exits share no block, and blocks not in the tree have none.
+    pub fn ipdom(&self, bid: BlockId) -> Option<BlockId> {
+        self.ipdoms.get(&bid).copied().flatten()
+    }
+
+    /// The blocks `bid` immediately post-dominates, which are its children in the tree.
+    pub fn children(&self, bid: BlockId) -> &[BlockId] {
+        self.children.get(&Some(bid)).map_or(&[], Vec::as_slice)
+    }
+
+    /// The blocks immediately post-dominated by the virtual exit, which are the roots of the tree
+    /// among the blocks.
+    pub fn roots(&self) -> &[BlockId] {
+        self.children.get(&None).map_or(&[], Vec::as_slice)
+    }
+
+    /// Whether `lhs` post-dominates `rhs`.
+    pub fn post_dominates(&self, lhs: BlockId, rhs: BlockId) -> bool {
+        if !self.reaches_exit(lhs) || !self.reaches_exit(rhs) {
+            return false;
+        }
+        let (lhs_pre, lhs_post) = self.numbers[&Some(lhs)];
+        let (rhs_pre, rhs_post) = self.numbers[&Some(rhs)];
+        lhs_pre <= rhs_pre && rhs_post <= lhs_post
+    }
+
+    /// Whether `lhs` post-dominates `rhs` and they are different blocks.
+    pub fn strictly_post_dominates(&self, lhs: BlockId, rhs: BlockId) -> bool {
+        lhs != rhs && self.post_dominates(lhs, rhs)
+    }
+
+    /// The post-dominators of `bid` from `bid` itself up to the root of its tree. Empty if `bid`
+    /// is not in the tree.
+    pub fn post_dominators(&self, bid: BlockId) -> impl Iterator<Item = BlockId> + '_ {
+        let start = self.reaches_exit(bid).then_some(bid);
+        std::iter::successors(start, |bid| self.ipdom(*bid))
+    }
+}
+
+/// Computes the immediate dominator of each node of a graph by the algorithm of Cooper, Harvey,
+/// and Kennedy. The nodes are numbered in reverse post-order from the root `0`, and `preds[i]` are
+/// the predecessors of the node `i`. Every node must be reachable from the root, whose immediate
+/// dominator is itself.
+fn immediate_dominators(preds: &[Vec<usize>]) -> Vec<usize> {
+    let mut idoms = vec![None; preds.len()];
+    idoms[0] = Some(0);
+
+    let intersect = |idoms: &[Option<usize>], mut lhs: usize, mut rhs: usize| {
+        while lhs != rhs {
+            while lhs > rhs {
+                lhs = idoms[lhs].expect("processed block must have its idom");
+            }
+            while rhs > lhs {
+                rhs = idoms[rhs].expect("processed block must have its idom");
+            }
+        }
+        lhs
+    };
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (i, preds) in preds.iter().enumerate().skip(1) {
+            let new_idom = preds
+                .iter()
+                .copied()
+                .filter(|pred| idoms[*pred].is_some())
+                .reduce(|idom, pred| intersect(&idoms, idom, pred));
+            if new_idom != idoms[i] {
+                idoms[i] = new_idom;
+                changed = true;
+            }
+        }
+    }
+
+    idoms
+        .into_iter()
+        .map(|idom| idom.expect("reachable block must have its idom"))
+        .collect()
+}
+
+/// Numbers the nodes of the tree rooted at `root` in pre-order and post-order, so that a node is
+/// an ancestor of another if its pre-order number is not greater and its post-order number is not
+/// less.
+fn number<N, F>(root: N, children: F) -> BTreeMap<N, (usize, usize)>
+where
+    N: Ord + Copy,
+    F: Fn(N) -> Vec<N>,
+{
+    let mut numbers = BTreeMap::new();
+    let mut pre = 0;
+    let mut post = 0;
+    let mut stack = vec![(root, children(root), 0)];
+    let _unused = numbers.insert(root, (pre, 0));
+    pre += 1;
+
+    while let Some((node, node_children, index)) = stack.last_mut() {
+        if let Some(child) = node_children.get(*index) {
+            let child = *child;
+            *index += 1;
+            let _unused = numbers.insert(child, (pre, 0));
+            pre += 1;
+            stack.push((child, children(child), 0));
+        } else {
+            numbers
+                .get_mut(node)
+                .expect("visited node must be numbered")
+                .1 = post;
+            post += 1;
+            let _unused = stack.pop();
+        }
+    }
+
+    numbers
+}
+
+/// The dominance frontier of each block reachable from the entry block.
+///
+/// The dominance frontier of a block `a` is the set of blocks `b` such that `a` dominates a
+/// predecessor of `b` but does not strictly dominate `b`. They are where the definitions in `a`
+/// meet other definitions, and thus where phinodes are placed when constructing SSA.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DominanceFrontiers {
+    frontiers: BTreeMap<BlockId, BTreeSet<BlockId>>,
+}
+
+impl DominanceFrontiers {
+    pub fn new(cfg: &Cfg, dominators: &Dominators) -> Self {
+        let mut frontiers = cfg
+            .reverse_post_order()
+            .iter()
+            .map(|bid| (*bid, BTreeSet::new()))
+            .collect::<BTreeMap<_, _>>();
+
+        for bid in cfg.reverse_post_order() {
+            let preds = cfg
+                .predecessors(*bid)
+                .iter()
+                .filter(|pred| dominators.is_reachable(**pred))
+                .collect::<Vec<_>>();
+            // The entry block is a join point if it has any predecessor, since it is also entered
+            // when the function is called.
+            if preds.len() < 2 && (*bid != cfg.entry() || preds.is_empty()) {
+                continue;
+            }
+
+            let idom = dominators.idom(*bid);
+            for pred in preds {
+                let mut runner = *pred;
+                while Some(runner) != idom {
+                    let _unused = frontiers
+                        .get_mut(&runner)
+                        .expect("reachable block must have its frontier")
+                        .insert(*bid);
+                    runner = some_or!(dominators.idom(runner), break);
+                }
+            }
+        }
+
+        Self { frontiers }
+    }
+
+    /// The dominance frontier of `bid`. Empty if `bid` is unreachable.
+    pub fn frontier(&self, bid: BlockId) -> &BTreeSet<BlockId> {
+        static EMPTY: BTreeSet<BlockId> = BTreeSet::new();
+        self.frontiers.get(&bid).unwrap_or(&EMPTY)
+    }
+
+    /// The iterated dominance frontier of `bids`, i.e., the least set containing the frontiers of
+    /// `bids` and of its own blocks. For a variable defined in `bids`, these are the blocks that
+    /// need a phinode for it.
+    pub fn iterated_frontier<I>(&self, bids: I) -> BTreeSet<BlockId>
+    where
+        I: IntoIterator<Item = BlockId>,
+    {
+        let mut result = BTreeSet::new();
+        let mut worklist = bids.into_iter().collect::<Vec<_>>();
+        while let Some(bid) = worklist.pop() {
+            for frontier in self.frontier(bid) {
+                if result.insert(*frontier) {
+                    worklist.push(*frontier);
+                }
+            }
+        }
+        result
+    }
+}
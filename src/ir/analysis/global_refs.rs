@@ -0,0 +1,161 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use lang_c::ast;
+
+use crate::ir::visit::walk_instruction;
+use crate::ir::*;
+
+/// How a function refers to a global name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum GlobalUse {
+    /// The name is the callee of a call.
+    Call,
+    /// The name is the address of a non-volatile store, so that its value is overwritten but not
+    /// observed.
+    Store,
+    /// The name is used otherwise, e.g., loaded from, passed or stored elsewhere, so that its
+    /// address may escape.
+    Other,
+}
+
+/// The references between the global names of a translation unit.
+///
+/// Function definitions refer to names through their operands, and variables through the
+/// addresses in their initializers (see [`address_initializer`]). The names a unit refers to
+/// without declaring them are recorded as well.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GlobalRefs {
+    /// The names each defined function or initialized variable refers to, and how.
+    refs: BTreeMap<String, BTreeSet<(String, GlobalUse)>>,
+}
+
+impl GlobalRefs {
+    pub fn new(unit: &TranslationUnit) -> Self {
+        let refs = unit
+            .decls
+            .iter()
+            .filter_map(|(name, decl)| {
+                let mut collector = Collector::default();
+                match decl {
+                    Declaration::Variable { initializer, .. } => {
+                        collector.add_initializer(initializer.as_ref()?)
+                    }
+                    Declaration::Function { definition, .. } => {
+                        collector.visit_function(definition.as_ref()?)
+                    }
+                }
+                Some((name.clone(), collector.refs))
+            })
+            .collect();
+        Self { refs }
+    }
+
+    /// The names the function `name` refers to, and how. Each name appears once for each way it
+    /// is used.
+    pub fn refs(&self, name: &str) -> impl Iterator<Item = (&str, GlobalUse)> {
+        self.refs
+            .get(name)
+            .into_iter()
+            .flatten()
+            .map(|(name, kind)| (name.as_str(), *kind))
+    }
+
+    /// Whether any function refers to `name` in a way satisfying `pred`.
+    pub fn is_used(&self, name: &str, mut pred: impl FnMut(GlobalUse) -> bool) -> bool {
+        self.refs
+            .values()
+            .flatten()
+            .any(|(used, kind)| used == name && pred(*kind))
+    }
+
+    /// The names from which the whole program is entered, i.e., `main` if `unit` defines it. A
+    /// unit without `main` is a part of a program whose other parts may refer to any of its
+    /// names with external linkage, which are entered from instead.
+    pub fn roots(unit: &TranslationUnit) -> BTreeSet<String> {
+        if unit
+            .decls
+            .get("main")
+            .is_some_and(Declaration::is_definition)
+        {
+            return BTreeSet::from(["main".to_string()]);
+        }
+        unit.decls
+            .iter()
+            .filter(|(_, decl)| decl.linkage() == Linkage::External)
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    /// The names reachable from `roots` through references, including the roots themselves.
+    pub fn reachable(&self, roots: &BTreeSet<String>) -> BTreeSet<String> {
+        let mut reachable = roots.clone();
+        let mut worklist = roots.iter().cloned().collect::<Vec<_>>();
+        while let Some(name) = worklist.pop() {
+            for (used, _) in self.refs(&name) {
+                if reachable.insert(used.to_string()) {
+                    worklist.push(used.to_string());
+                }
+            }
+        }
+        reachable
+    }
+}
+
+/// Collects the global names a function refers to.
+#[derive(Default, Debug)]
+struct Collector {
+    refs: BTreeSet<(String, GlobalUse)>,
+}
+
+impl Collector {
+    fn add(&mut self, operand: &Operand, kind: GlobalUse) {
+        if let Some(name) = operand
+            .get_constant()
+            .and_then(Constant::get_global_variable_name)
+        {
+            let _unused = self.refs.insert((name, kind));
+        }
+    }
+
+    /// Adds the names whose addresses `initializer` takes.
+    fn add_initializer(&mut self, initializer: &ast::Initializer) {
+        match initializer {
+            ast::Initializer::Expression(expr) => {
+                if let Some((name, _)) = get_address_initializer(&expr.node) {
+                    let _unused = self.refs.insert((name.to_string(), GlobalUse::Other));
+                }
+            }
+            ast::Initializer::List(items) => {
+                for item in items {
+                    self.add_initializer(&item.node.initializer.node);
+                }
+            }
+        }
+    }
+}
+
+impl IrVisitor for Collector {
+    fn visit_instruction(&mut self, rid: RegisterId, instruction: &Named<Instruction>) {
+        match &**instruction {
+            Instruction::Call { callee, args, .. } => {
+                self.add(callee, GlobalUse::Call);
+                for arg in args {
+                    self.add(arg, GlobalUse::Other);
+                }
+            }
+            Instruction::Store {
+                ptr,
+                value,
+                volatile: false,
+            } => {
+                self.add(ptr, GlobalUse::Store);
+                self.add(value, GlobalUse::Other);
+            }
+            _ => walk_instruction(self, rid, instruction),
+        }
+    }
+
+    fn visit_operand(&mut self, operand: &Operand) {
+        self.add(operand, GlobalUse::Other);
+    }
+}
@@ -0,0 +1,131 @@
+use std::collections::{BTreeMap, HashSet};
+
+use crate::ir::analysis::Cfg;
+use crate::ir::*;
+
+/// The registers live at each point of a function.
+///
+/// A register is live at a point if its value may be used afterwards. Only registers holding
+/// values are tracked: block arguments, including function arguments, and instruction results.
+/// Registers of local allocations always hold the same address and are not tracked.
+///
+/// Block arguments are defined at the beginning of their block, and the arguments of a jump are
+/// used by the exit that jumps. Hence the phinodes of a block are never live at its beginning.
+///
+/// Unreachable blocks are analyzed as well.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Liveness {
+    live_in: BTreeMap<BlockId, HashSet<RegisterId>>,
+    live_out: BTreeMap<BlockId, HashSet<RegisterId>>,
+    /// The registers live right before each instruction of each block, followed by those live
+    /// right before its exit.
+    live_before: BTreeMap<BlockId, Vec<HashSet<RegisterId>>>,
+}
+
+impl Liveness {
+    pub fn new(definition: &FunctionDefinition, cfg: &Cfg) -> Self {
+        let mut liveness = Self {
+            live_in: BTreeMap::new(),
+            live_out: BTreeMap::new(),
+            live_before: BTreeMap::new(),
+        };
+
+        // Visiting blocks in post-order propagates liveness backward along forward edges in a
+        // single round, so that only back edges require more rounds. Unreachable blocks follow.
+        let mut order = cfg.post_order().collect::<Vec<_>>();
+        order.extend(cfg.blocks().filter(|bid| !cfg.is_reachable(*bid)));
+        let order = order
+            .into_iter()
+            .filter(|bid| definition.blocks.contains_key(bid))
+            .collect::<Vec<_>>();
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for bid in &order {
+                let live_out = cfg
+                    .successors(*bid)
+                    .iter()
+                    .filter_map(|succ| liveness.live_in.get(succ))
+                    .flatten()
+                    .copied()
+                    .collect::<HashSet<_>>();
+                let (live_in, live_before) =
+                    Self::transfer(*bid, &definition.blocks[bid], live_out.clone());
+
+                if liveness.live_in.get(bid) != Some(&live_in) {
+                    changed = true;
+                }
+                let _unused = liveness.live_in.insert(*bid, live_in);
+                let _unused = liveness.live_out.insert(*bid, live_out);
+                let _unused = liveness.live_before.insert(*bid, live_before);
+            }
+        }
+
+        liveness
+    }
+
+    /// Computes the registers live at the beginning of `block` and right before each of its
+    /// instructions and exit, given those live at its end.
+    fn transfer(
+        bid: BlockId,
+        block: &Block,
+        mut live: HashSet<RegisterId>,
+    ) -> (HashSet<RegisterId>, Vec<HashSet<RegisterId>>) {
+        let mut live_before = vec![HashSet::new(); block.instructions.len() + 1];
+
+        Self::gen(&mut live, block.exit.operands());
+        live_before[block.instructions.len()].clone_from(&live);
+
+        for (iid, instr) in block.instructions.iter().enumerate().rev() {
+            let _unused = live.remove(&RegisterId::temp(bid, iid));
+            Self::gen(&mut live, instr.operands());
+            live_before[iid].clone_from(&live);
+        }
+
+        // The phinodes are defined at the beginning of the block.
+        for aid in 0..block.phinodes.len() {
+            let _unused = live.remove(&RegisterId::arg(bid, aid));
+        }
+
+        (live, live_before)
+    }
+
+    fn gen(live: &mut HashSet<RegisterId>, operands: Vec<&Operand>) {
+        for operand in operands {
+            if let Some((rid, _)) = operand.get_register() {
+                if !matches!(rid, RegisterId::Local { .. }) {
+                    let _unused = live.insert(*rid);
+                }
+            }
+        }
+    }
+
+    /// The registers live at the beginning of `bid`.
+    pub fn live_in(&self, bid: BlockId) -> &HashSet<RegisterId> {
+        &self.live_in[&bid]
+    }
+
+    /// The registers live at the end of `bid`, i.e., those live at the beginning of its
+    /// successors.
+    pub fn live_out(&self, bid: BlockId) -> &HashSet<RegisterId> {
+        &self.live_out[&bid]
+    }
+
+    /// The registers live right before the instruction `iid` of `bid`. If `iid` is the number of
+    /// instructions of the block, the registers live right before its exit.
+    pub fn live_before(&self, bid: BlockId, iid: usize) -> &HashSet<RegisterId> {
+        &self.live_before[&bid][iid]
+    }
+
+    /// The registers live right after the instruction `iid` of `bid`.
+    pub fn live_after(&self, bid: BlockId, iid: usize) -> &HashSet<RegisterId> {
+        &self.live_before[&bid][iid + 1]
+    }
+
+    /// Whether the result of the instruction `iid` of `bid` is used afterwards.
+    pub fn is_used(&self, bid: BlockId, iid: usize) -> bool {
+        self.live_after(bid, iid)
+            .contains(&RegisterId::temp(bid, iid))
+    }
+}
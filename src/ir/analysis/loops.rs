@@ -0,0 +1,237 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::ir::analysis::{Cfg, Dominators};
+use crate::ir::*;
+
+/// A natural loop, identified by its header.
+///
+/// The body of a natural loop consists of its header and the blocks that reach the source of a
+/// back edge to the header without passing through the header. The header dominates every block
+/// of the body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Loop {
+    header: BlockId,
+    latches: Vec<BlockId>,
+    blocks: BTreeSet<BlockId>,
+    parent: Option<BlockId>,
+    children: Vec<BlockId>,
+    depth: usize,
+}
+
+impl Loop {
+    /// The header, which is the only block of the loop entered from outside of it.
+    pub fn header(&self) -> BlockId {
+        self.header
+    }
+
+    /// The sources of the back edges to the header, in reverse post-order.
+    pub fn latches(&self) -> &[BlockId] {
+        &self.latches
+    }
+
+    /// The blocks of the loop, including the header and the blocks of nested loops.
+    pub fn blocks(&self) -> &BTreeSet<BlockId> {
+        &self.blocks
+    }
+
+    /// Whether `bid` is in the loop.
+    pub fn contains(&self, bid: BlockId) -> bool {
+        self.blocks.contains(&bid)
+    }
+
+    /// The header of the innermost loop enclosing this one, if any.
+    pub fn parent(&self) -> Option<BlockId> {
+        self.parent
+    }
+
+    /// The headers of the loops immediately nested in this one, in reverse post-order.
+    pub fn children(&self) -> &[BlockId] {
+        &self.children
+    }
+
+    /// The number of loops enclosing this one, including itself. Outermost loops have depth 1.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// The blocks outside of the loop that the loop may jump to.
+    pub fn exits(&self, cfg: &Cfg) -> BTreeSet<BlockId> {
+        self.blocks
+            .iter()
+            .flat_map(|bid| cfg.successors(*bid))
+            .filter(|succ| !self.contains(**succ))
+            .copied()
+            .collect()
+    }
+
+    /// The preheader of the loop, i.e., the only predecessor of the header outside of the loop if
+    /// it jumps only to the header. Code hoisted out of the loop is placed there. The entry block
+    /// never has a preheader, since it is also entered when the function is called.
+    pub fn preheader(&self, cfg: &Cfg) -> Option<BlockId> {
+        if self.header == cfg.entry() {
+            return None;
+        }
+
+        let mut outside = cfg
+            .predecessors(self.header)
+            .iter()
+            .filter(|pred| cfg.is_reachable(**pred) && !self.contains(**pred));
+        let preheader = *outside.next()?;
+        if outside.next().is_some() || cfg.successors(preheader) != [self.header] {
+            return None;
+        }
+        Some(preheader)
+    }
+}
+
+/// The natural loops of the blocks reachable from the entry block, and how they nest.
+///
+/// An edge is a back edge if its target dominates its source. Back edges to the same header form
+/// a single loop. Two loops with different headers are either disjoint or one is nested in the
+/// other. Cycles entered at more than one block, which irreducible control flow creates, have no
+/// back edge and are not loops.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoopInfo {
+    /// The loops by their headers.
+    loops: BTreeMap<BlockId, Loop>,
+    /// The headers of the outermost loops, in reverse post-order.
+    roots: Vec<BlockId>,
+    /// The header of the innermost loop containing each block in a loop.
+    innermost: BTreeMap<BlockId, BlockId>,
+}
+
+impl LoopInfo {
+    pub fn new(cfg: &Cfg, dominators: &Dominators) -> Self {
+        let mut loops = BTreeMap::new();
+        // Enclosing loops have headers dominating, and thus preceding, those of nested loops.
+        let mut headers = Vec::new();
+
+        for header in cfg.reverse_post_order() {
+            let latches = cfg
+                .reverse_post_order()
+                .iter()
+                .filter(|pred| {
+                    cfg.predecessors(*header).contains(*pred)
+                        && dominators.dominates(*header, **pred)
+                })
+                .copied()
+                .collect::<Vec<_>>();
+            if latches.is_empty() {
+                continue;
+            }
+
+            let mut blocks = BTreeSet::from([*header]);
+            let mut worklist = latches.clone();
+            while let Some(bid) = worklist.pop() {
+                if blocks.insert(bid) {
+                    worklist.extend(
+                        cfg.predecessors(bid)
+                            .iter()
+                            .filter(|pred| dominators.is_reachable(**pred)),
+                    );
+                }
+            }
+
+            headers.push(*header);
+            let _unused = loops.insert(
+                *header,
+                Loop {
+                    header: *header,
+                    latches,
+                    blocks,
+                    parent: None,
+                    children: Vec::new(),
+                    depth: 1,
+                },
+            );
+        }
+
+        let mut roots = Vec::new();
+        let mut innermost = BTreeMap::new();
+        for (i, header) in headers.iter().enumerate() {
+            // The innermost enclosing loop is the last one containing the header, since it has
+            // been visited already and enclosing loops precede it.
+            let parent = headers[..i]
+                .iter()
+                .rev()
+                .find(|other| loops[*other].contains(*header))
+                .copied();
+            let depth = parent.map_or(1, |parent| loops[&parent].depth + 1);
+
+            let lp = loops.get_mut(header).expect("header must have its loop");
+            lp.parent = parent;
+            lp.depth = depth;
+            for bid in &lp.blocks {
+                let _unused = innermost.insert(*bid, *header);
+            }
+
+            match parent {
+                Some(parent) => loops
+                    .get_mut(&parent)
+                    .expect("parent must have its loop")
+                    .children
+                    .push(*header),
+                None => roots.push(*header),
+            }
+        }
+
+        Self {
+            loops,
+            roots,
+            innermost,
+        }
+    }
+
+    /// The loop whose header is `header`, if any.
+    pub fn get(&self, header: BlockId) -> Option<&Loop> {
+        self.loops.get(&header)
+    }
+
+    /// Whether `bid` is the header of a loop.
+    pub fn is_header(&self, bid: BlockId) -> bool {
+        self.loops.contains_key(&bid)
+    }
+
+    /// Whether the edge from `from` to `to` is a back edge.
+    pub fn is_back_edge(&self, from: BlockId, to: BlockId) -> bool {
+        self.get(to).is_some_and(|lp| lp.latches.contains(&from))
+    }
+
+    /// The innermost loop containing `bid`, if any.
+    pub fn loop_of(&self, bid: BlockId) -> Option<&Loop> {
+        self.innermost.get(&bid).map(|header| &self.loops[header])
+    }
+
+    /// The number of loops containing `bid`.
+    pub fn depth(&self, bid: BlockId) -> usize {
+        self.loop_of(bid).map_or(0, Loop::depth)
+    }
+
+    /// The headers of the outermost loops, in reverse post-order.
+    pub fn roots(&self) -> &[BlockId] {
+        &self.roots
+    }
+
+    /// All loops, each after the loops nested in it, so that inner loops are visited first.
+    pub fn post_order(&self) -> Vec<&Loop> {
+        let mut post_order = Vec::new();
+        let mut stack = self
+            .roots
+            .iter()
+            .rev()
+            .map(|header| (&self.loops[header], 0))
+            .collect::<Vec<_>>();
+
+        while let Some((lp, index)) = stack.last_mut() {
+            if let Some(child) = lp.children.get(*index) {
+                *index += 1;
+                stack.push((&self.loops[child], 0));
+            } else {
+                post_order.push(*lp);
+                let _unused = stack.pop();
+            }
+        }
+
+        post_order
+    }
+}
@@ -0,0 +1,17 @@
+//! Analyses of the IR shared by the verifier and optimizations.
+
+mod alias;
+mod cfg;
+mod def_use;
+mod dominators;
+mod global_refs;
+mod liveness;
+mod loops;
+
+pub use alias::{AliasAnalysis, MemoryBase, MemoryLocation};
+pub use cfg::Cfg;
+pub use def_use::{DefUse, Use};
+pub use dominators::{DominanceFrontiers, Dominators, PostDominators};
+pub use global_refs::{GlobalRefs, GlobalUse};
+pub use liveness::Liveness;
+pub use loops::{Loop, LoopInfo};
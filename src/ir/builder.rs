@@ -0,0 +1,237 @@
+use std::collections::BTreeMap;
+
+use crate::ir::*;
+use crate::*;
+
+/// A block under construction.
+#[derive(Debug, Clone)]
+struct BlockBuilder {
+    phinodes: Vec<Named<Dtype>>,
+    instructions: Vec<Named<Instruction>>,
+    exit: Option<BlockExit>,
+}
+
+/// Incrementally constructs a `FunctionDefinition`, keeping track of block ids, instruction ids
+/// and allocation ids so that the caller does not have to.
+///
+/// The entry block is created along with the builder and its phinodes are the parameters of the
+/// signature. Every block must be terminated before the definition is finished.
+///
+/// # Example
+///
+/// ```
+/// use kecc::ir::*;
+/// use lang_c::ast;
+///
+/// let signature = FunctionSignature::new(Dtype::function(Dtype::INT, vec![Dtype::INT]));
+/// let mut builder = FunctionBuilder::new(signature);
+///
+/// let entry = builder.entry();
+/// let exit = builder.append_block();
+/// let sum = builder.push_instr(
+///     entry,
+///     Instruction::BinOp {
+///         op: ast::BinaryOperator::Plus,
+///         lhs: builder.param(0),
+///         rhs: Operand::constant(Constant::int(1, Dtype::INT)),
+///         dtype: Dtype::INT,
+///         flags: BinOpFlags::default(),
+///     },
+/// );
+/// builder.terminate(entry, BlockExit::Jump { arg: JumpArg::new(exit, Vec::new()) });
+/// builder.terminate(exit, BlockExit::Return { value: sum });
+///
+/// let definition = builder.finish();
+/// assert_eq!(definition.blocks.len(), 2);
+/// ```
+#[derive(Debug, Clone)]
+pub struct FunctionBuilder {
+    signature: FunctionSignature,
+    allocations: Vec<Named<Dtype>>,
+    blocks: BTreeMap<BlockId, BlockBuilder>,
+    bid_init: BlockId,
+    bid_counter: usize,
+}
+
+impl FunctionBuilder {
+    /// Creates a builder for a function of `signature` with the entry block `b0`.
+    pub fn new(signature: FunctionSignature) -> Self {
+        let bid_init = BlockId(0);
+        let entry = BlockBuilder {
+            phinodes: signature
+                .params
+                .iter()
+                .map(|param| Named::new(None, param.clone()))
+                .collect(),
+            instructions: Vec::new(),
+            exit: None,
+        };
+
+        Self {
+            signature,
+            allocations: Vec::new(),
+            blocks: [(bid_init, entry)].into_iter().collect(),
+            bid_init,
+            bid_counter: bid_init.0 + 1,
+        }
+    }
+
+    pub fn signature(&self) -> &FunctionSignature {
+        &self.signature
+    }
+
+    /// The entry block.
+    pub fn entry(&self) -> BlockId {
+        self.bid_init
+    }
+
+    /// The `index`-th parameter of the function.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the function has no such parameter.
+    pub fn param(&self, index: usize) -> Operand {
+        self.phinode(self.bid_init, index)
+    }
+
+    /// Names the `index`-th parameter of the function, e.g., after the C parameter it stands for.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the function has no such parameter.
+    pub fn name_param(&mut self, index: usize, name: Option<String>) {
+        let phinode = self
+            .block_mut(self.bid_init)
+            .phinodes
+            .get_mut(index)
+            .unwrap_or_else(|| panic!("the function has no parameter {index}"));
+        phinode.name = name;
+    }
+
+    /// Appends a new empty block and returns its id.
+    pub fn append_block(&mut self) -> BlockId {
+        let bid = BlockId(self.bid_counter);
+        self.bid_counter += 1;
+        let _unused = self.blocks.insert(
+            bid,
+            BlockBuilder {
+                phinodes: Vec::new(),
+                instructions: Vec::new(),
+                exit: None,
+            },
+        );
+        bid
+    }
+
+    /// Adds the local allocation `alloc` and returns the pointer to it.
+    pub fn push_alloc(&mut self, alloc: Named<Dtype>) -> Operand {
+        let dtype = Dtype::pointer(alloc.deref().clone());
+        self.allocations.push(alloc);
+        Operand::register(RegisterId::local(self.allocations.len() - 1), dtype)
+    }
+
+    /// Adds a phinode to the block `bid` and returns the register it defines.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bid` is not a block of the builder.
+    pub fn push_phinode(&mut self, bid: BlockId, phinode: Named<Dtype>) -> Operand {
+        let phinodes = &mut self.block_mut(bid).phinodes;
+        phinodes.push(phinode);
+        let aid = phinodes.len() - 1;
+        self.phinode(bid, aid)
+    }
+
+    /// Appends `instr` to the block `bid` and returns the register holding its result.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bid` is not a block of the builder or is already terminated.
+    pub fn push_instr(&mut self, bid: BlockId, instr: Instruction) -> Operand {
+        self.push_named_instr(bid, Named::new(None, instr))
+    }
+
+    /// Appends a named `instr` to the block `bid` and returns the register holding its result.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bid` is not a block of the builder or is already terminated.
+    pub fn push_named_instr(&mut self, bid: BlockId, instr: Named<Instruction>) -> Operand {
+        let dtype = instr.dtype();
+        let block = self.block_mut(bid);
+        assert!(
+            block.exit.is_none(),
+            "the block `{bid}` is already terminated"
+        );
+        block.instructions.push(instr);
+        Operand::register(RegisterId::temp(bid, block.instructions.len() - 1), dtype)
+    }
+
+    /// Terminates the block `bid` with `exit`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bid` is not a block of the builder or is already terminated.
+    pub fn terminate(&mut self, bid: BlockId, exit: BlockExit) {
+        let block = self.block_mut(bid);
+        assert!(
+            block.exit.is_none(),
+            "the block `{bid}` is already terminated"
+        );
+        block.exit = Some(exit);
+    }
+
+    /// Returns `true` if the block `bid` is terminated.
+    pub fn is_terminated(&self, bid: BlockId) -> bool {
+        self.blocks
+            .get(&bid)
+            .is_some_and(|block| block.exit.is_some())
+    }
+
+    /// Finishes the construction of the function definition.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a block is not terminated.
+    pub fn finish(self) -> FunctionDefinition {
+        let blocks = self
+            .blocks
+            .into_iter()
+            .map(|(bid, block)| {
+                let exit = block
+                    .exit
+                    .unwrap_or_else(|| panic!("the block `{bid}` is not terminated"));
+                let block = Block {
+                    phinodes: block.phinodes,
+                    instructions: block.instructions,
+                    exit,
+                };
+                (bid, block)
+            })
+            .collect();
+
+        FunctionDefinition {
+            allocations: self.allocations,
+            blocks,
+            bid_init: self.bid_init,
+        }
+    }
+
+    fn phinode(&self, bid: BlockId, aid: usize) -> Operand {
+        let block = some_or!(
+            self.blocks.get(&bid),
+            panic!("the block `{bid}` does not exist")
+        );
+        let dtype = block
+            .phinodes
+            .get(aid)
+            .unwrap_or_else(|| panic!("the block `{bid}` has no phinode {aid}"));
+        Operand::register(RegisterId::arg(bid, aid), dtype.deref().clone())
+    }
+
+    fn block_mut(&mut self, bid: BlockId) -> &mut BlockBuilder {
+        self.blocks
+            .get_mut(&bid)
+            .unwrap_or_else(|| panic!("the block `{bid}` does not exist"))
+    }
+}
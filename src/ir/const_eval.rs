@@ -0,0 +1,560 @@
+//! Evaluation of constant expressions.
+//!
+//! [`binary`], [`unary`] and [`typecast`] compute the IR operations of the same name on constants,
+//! following the semantics of the interpreter, so that a pass folding an instruction gets the value
+//! the instruction would have at run time. Unlike the interpreter, they return an error instead of
+//! panicking on undefined operations such as division by zero.
+//!
+//! [`expression`] evaluates a C constant expression, e.g., the initializer of a global variable,
+//! following the C rules for the types of the operands and results.
+//!
+//! # Example
+//!
+//! ```
+//! use kecc::ir::*;
+//! use lang_c::ast;
+//!
+//! let lhs = Constant::int(3, Dtype::INT);
+//! let rhs = Constant::int(4, Dtype::INT);
+//! let product = const_eval::binary(&ast::BinaryOperator::Multiply, &lhs, &rhs).unwrap();
+//! assert_eq!(product, Constant::int(12, Dtype::INT));
+//!
+//! let truncated = const_eval::typecast(&Constant::int(300, Dtype::INT), &Dtype::CHAR).unwrap();
+//! assert_eq!(truncated, Constant::int(44, Dtype::CHAR));
+//! ```
+
+use core::cmp::Ordering;
+use core::convert::TryFrom;
+
+use lang_c::ast;
+use thiserror::Error;
+
+use crate::ir::*;
+
+#[derive(Debug, PartialEq, Eq, Clone, Error)]
+pub enum ConstEvalError {
+    #[error("invalid operands to `{op:?}`: `{lhs}` and `{rhs}`")]
+    InvalidBinaryOperands {
+        op: ast::BinaryOperator,
+        lhs: Dtype,
+        rhs: Dtype,
+    },
+    #[error("invalid operand to `{op:?}`: `{operand}`")]
+    InvalidUnaryOperand {
+        op: ast::UnaryOperator,
+        operand: Dtype,
+    },
+    #[error("cannot cast `{from}` to `{to}`")]
+    InvalidTypecast { from: Dtype, to: Dtype },
+    #[error("division by zero")]
+    DivisionByZero,
+    #[error("shift amount is negative or not less than the width of the operand")]
+    InvalidShift,
+    #[error("expression is not a compile-time constant")]
+    NotConstant,
+}
+
+/// Computes `lhs op rhs` as the `BinOp` instruction does.
+///
+/// The operands must have the same dtype, except for the shift amount, which may be any integer.
+/// Comparisons result in a boolean, and the other operations in the dtype of `lhs`.
+pub fn binary(
+    op: &ast::BinaryOperator,
+    lhs: &Constant,
+    rhs: &Constant,
+) -> Result<Constant, ConstEvalError> {
+    let invalid = || ConstEvalError::InvalidBinaryOperands {
+        op: op.clone(),
+        lhs: lhs.dtype(),
+        rhs: rhs.dtype(),
+    };
+    let is_shift = matches!(
+        op,
+        ast::BinaryOperator::ShiftLeft | ast::BinaryOperator::ShiftRight
+    );
+    let is_comparison = matches!(
+        op,
+        ast::BinaryOperator::Equals
+            | ast::BinaryOperator::NotEquals
+            | ast::BinaryOperator::Less
+            | ast::BinaryOperator::Greater
+            | ast::BinaryOperator::LessOrEqual
+            | ast::BinaryOperator::GreaterOrEqual
+    );
+
+    let dtypes_match = if is_shift {
+        lhs.dtype().get_int_width().is_some() && rhs.dtype().get_int_width().is_some()
+    } else {
+        same_scalar_dtype(&lhs.dtype(), &rhs.dtype())
+    };
+    if !dtypes_match {
+        return Err(invalid());
+    }
+
+    if lhs.is_undef() || rhs.is_undef() {
+        let dtype = if is_comparison {
+            Dtype::BOOL
+        } else {
+            lhs.dtype()
+        };
+        return Ok(Constant::undef(dtype));
+    }
+
+    match (lhs, rhs) {
+        (
+            Constant::Int {
+                value: lhs,
+                width,
+                is_signed,
+            },
+            Constant::Int {
+                value: rhs,
+                is_signed: rhs_signed,
+                ..
+            },
+        ) => {
+            if is_shift && ((*rhs_signed && (*rhs as i128) < 0) || *rhs >= *width as u128) {
+                return Err(ConstEvalError::InvalidShift);
+            }
+            binary_int(op, *lhs, *rhs, *width, *is_signed).ok_or_else(invalid)?
+        }
+        (Constant::Float { value: lhs, width }, Constant::Float { value: rhs, .. }) => {
+            binary_float(op, lhs.into_inner(), rhs.into_inner(), *width).ok_or_else(invalid)?
+        }
+        _ => Err(invalid()),
+    }
+}
+
+/// Computes `op operand` as the `UnaryOp` instruction does.
+///
+/// Negation results in a boolean, and the other operations in the dtype of `operand`.
+pub fn unary(op: &ast::UnaryOperator, operand: &Constant) -> Result<Constant, ConstEvalError> {
+    let invalid = || ConstEvalError::InvalidUnaryOperand {
+        op: op.clone(),
+        operand: operand.dtype(),
+    };
+
+    match (op, operand) {
+        (ast::UnaryOperator::Negate, Constant::Undef { dtype }) if is_scalar(dtype) => {
+            Ok(Constant::undef(Dtype::BOOL))
+        }
+        (ast::UnaryOperator::Plus | ast::UnaryOperator::Minus, Constant::Undef { dtype })
+            if is_scalar(dtype) =>
+        {
+            Ok(operand.clone())
+        }
+        (ast::UnaryOperator::Complement, Constant::Undef { dtype })
+            if dtype.get_int_width().is_some() =>
+        {
+            Ok(operand.clone())
+        }
+        (
+            _,
+            Constant::Int {
+                value,
+                width,
+                is_signed,
+            },
+        ) => {
+            let result = match op {
+                ast::UnaryOperator::Plus => *value,
+                ast::UnaryOperator::Minus => value.wrapping_neg(),
+                ast::UnaryOperator::Complement => !value,
+                ast::UnaryOperator::Negate => {
+                    return Ok(Constant::int((*value == 0).into(), Dtype::BOOL))
+                }
+                _ => return Err(invalid()),
+            };
+            Ok(int(result, *width, *is_signed))
+        }
+        (_, Constant::Float { value, width }) => {
+            let value = value.into_inner();
+            let result = match op {
+                ast::UnaryOperator::Plus => value,
+                ast::UnaryOperator::Minus => -value,
+                ast::UnaryOperator::Negate => {
+                    return Ok(Constant::int((value == 0.0).into(), Dtype::BOOL))
+                }
+                _ => return Err(invalid()),
+            };
+            Ok(float(result, *width))
+        }
+        _ => Err(invalid()),
+    }
+}
+
+/// Converts `constant` to `dtype` as the `TypeCast` instruction does.
+///
+/// Only integers and floating point numbers can be converted into each other. A conversion to a
+/// boolean results in whether the value is nonzero.
+pub fn typecast(constant: &Constant, dtype: &Dtype) -> Result<Constant, ConstEvalError> {
+    let invalid = || ConstEvalError::InvalidTypecast {
+        from: constant.dtype(),
+        to: dtype.clone(),
+    };
+
+    match (constant, dtype) {
+        (Constant::Undef { dtype: from }, _) if is_scalar(from) && is_scalar(dtype) => {
+            Ok(Constant::undef(dtype.clone()))
+        }
+        (Constant::Int { value, .. }, Dtype::Int { .. }) => Ok(int_to_int(*value, dtype)),
+        (Constant::Float { value, .. }, Dtype::Int { width: 1, .. }) => Ok(Constant::int(
+            (value.into_inner() != 0.0).into(),
+            dtype.clone(),
+        )),
+        (
+            Constant::Float { value, .. },
+            Dtype::Int {
+                width, is_signed, ..
+            },
+        ) => {
+            // Converting an out-of-range value is undefined in C, and saturates here.
+            let value = if *is_signed {
+                value.into_inner() as i128 as u128
+            } else {
+                value.into_inner() as u128
+            };
+            Ok(int(value, *width, *is_signed))
+        }
+        (
+            Constant::Int {
+                value, is_signed, ..
+            },
+            Dtype::Float { width, .. },
+        ) => {
+            let value = if *is_signed {
+                *value as i128 as f64
+            } else {
+                *value as f64
+            };
+            Ok(float(value, *width))
+        }
+        (Constant::Float { value, .. }, Dtype::Float { width, .. }) => {
+            Ok(float(value.into_inner(), *width))
+        }
+        _ => Err(invalid()),
+    }
+}
+
+/// Evaluates the C constant expression `expr`.
+///
+/// The operands are converted as in C: integers narrower than `int` are promoted to `int`, the
+/// operands of a binary operator are converted to their common type, and comparisons and logical
+/// operators result in an `int`. Identifiers, casts and `sizeof`, whose values depend on the
+/// declarations of the translation unit, are not constants for this function; see
+/// [`expression_with`].
+pub fn expression(expr: &ast::Expression) -> Result<Constant, ConstEvalError> {
+    expression_with(expr, &mut |_| Err(ConstEvalError::NotConstant))
+}
+
+/// Evaluates the C constant expression `expr` as [`expression`] does, where `leaf` evaluates the
+/// subexpressions other than constants and operators, e.g., `sizeof(long)` from the declarations
+/// known to the caller.
+pub fn expression_with<E: From<ConstEvalError>>(
+    expr: &ast::Expression,
+    leaf: &mut dyn FnMut(&ast::Expression) -> Result<Constant, E>,
+) -> Result<Constant, E> {
+    match expr {
+        ast::Expression::Constant(constant) => {
+            Ok(Constant::try_from(&constant.node).map_err(|_| ConstEvalError::NotConstant)?)
+        }
+        ast::Expression::UnaryOperator(unary) => {
+            let operator = &unary.node.operator.node;
+            let operand = expression_with(&unary.node.operand.node, leaf)?;
+            match operator {
+                ast::UnaryOperator::Negate => {
+                    let result = self::unary(operator, &operand)?;
+                    Ok(typecast(&result, &Dtype::INT)?)
+                }
+                ast::UnaryOperator::Plus
+                | ast::UnaryOperator::Minus
+                | ast::UnaryOperator::Complement => {
+                    let operand = integer_promotion(&operand)?;
+                    Ok(self::unary(operator, &operand)?)
+                }
+                _ => Err(ConstEvalError::NotConstant.into()),
+            }
+        }
+        ast::Expression::BinaryOperator(binary) => {
+            let operator = &binary.node.operator.node;
+            let lhs = expression_with(&binary.node.lhs.node, leaf)?;
+            match operator {
+                ast::BinaryOperator::LogicalAnd | ast::BinaryOperator::LogicalOr => {
+                    let lhs = truth(&lhs)?;
+                    let short_circuit = matches!(operator, ast::BinaryOperator::LogicalOr);
+                    let result = match lhs {
+                        Some(lhs) if lhs == short_circuit => Some(lhs),
+                        Some(_) => truth(&expression_with(&binary.node.rhs.node, leaf)?)?,
+                        None => None,
+                    };
+                    Ok(result.map_or_else(
+                        || Constant::undef(Dtype::INT),
+                        |result| Constant::int(result.into(), Dtype::INT),
+                    ))
+                }
+                ast::BinaryOperator::ShiftLeft | ast::BinaryOperator::ShiftRight => {
+                    let lhs = integer_promotion(&lhs)?;
+                    let rhs = integer_promotion(&expression_with(&binary.node.rhs.node, leaf)?)?;
+                    Ok(self::binary(operator, &lhs, &rhs)?)
+                }
+                ast::BinaryOperator::Index
+                | ast::BinaryOperator::Assign
+                | ast::BinaryOperator::AssignMultiply
+                | ast::BinaryOperator::AssignDivide
+                | ast::BinaryOperator::AssignModulo
+                | ast::BinaryOperator::AssignPlus
+                | ast::BinaryOperator::AssignMinus
+                | ast::BinaryOperator::AssignShiftLeft
+                | ast::BinaryOperator::AssignShiftRight
+                | ast::BinaryOperator::AssignBitwiseAnd
+                | ast::BinaryOperator::AssignBitwiseXor
+                | ast::BinaryOperator::AssignBitwiseOr => Err(ConstEvalError::NotConstant.into()),
+                _ => {
+                    let rhs = expression_with(&binary.node.rhs.node, leaf)?;
+                    let (lhs, rhs) = usual_arithmetic_conversion(operator, &lhs, &rhs)?;
+                    let result = self::binary(operator, &lhs, &rhs)?;
+                    if result.dtype() == Dtype::BOOL {
+                        Ok(typecast(&result, &Dtype::INT)?)
+                    } else {
+                        Ok(result)
+                    }
+                }
+            }
+        }
+        ast::Expression::Conditional(conditional) => {
+            let condition = truth(&expression_with(&conditional.node.condition.node, leaf)?)?;
+            let then_value = expression_with(&conditional.node.then_expression.node, leaf)?;
+            let else_value = expression_with(&conditional.node.else_expression.node, leaf)?;
+            let (then_value, else_value) =
+                usual_arithmetic_conversion(&ast::BinaryOperator::Plus, &then_value, &else_value)?;
+            Ok(match condition {
+                Some(true) => then_value,
+                Some(false) => else_value,
+                None => Constant::undef(then_value.dtype()),
+            })
+        }
+        _ => leaf(expr),
+    }
+}
+
+fn int(value: u128, width: usize, is_signed: bool) -> Constant {
+    let value = if is_signed {
+        sign_extension(value, width as u128)
+    } else {
+        trim_unnecessary_bits(value, width as u128)
+    };
+    Constant::Int {
+        value,
+        width,
+        is_signed,
+    }
+}
+
+fn float(value: f64, width: usize) -> Constant {
+    let value = if width == Dtype::SIZE_OF_FLOAT * Dtype::BITS_OF_BYTE {
+        value as f32 as f64
+    } else {
+        value
+    };
+    Constant::Float {
+        value: value.into(),
+        width,
+    }
+}
+
+fn int_to_int(value: u128, dtype: &Dtype) -> Constant {
+    let width = dtype.get_int_width().expect("`dtype` must be `Dtype::Int`");
+    if width == 1 {
+        Constant::int((value != 0).into(), dtype.clone())
+    } else {
+        // A signed value is kept sign-extended, so that truncating or extending it is the same
+        // as reinterpreting its lower bits.
+        int(value, width, dtype.is_int_signed())
+    }
+}
+
+fn binary_int(
+    op: &ast::BinaryOperator,
+    lhs: u128,
+    rhs: u128,
+    width: usize,
+    is_signed: bool,
+) -> Option<Result<Constant, ConstEvalError>> {
+    let compare = |holds: fn(Ordering) -> bool| {
+        let ordering = if is_signed {
+            (lhs as i128).cmp(&(rhs as i128))
+        } else {
+            lhs.cmp(&rhs)
+        };
+        Some(Ok(Constant::int(holds(ordering).into(), Dtype::BOOL)))
+    };
+
+    let result = match op {
+        ast::BinaryOperator::Plus => lhs.wrapping_add(rhs),
+        ast::BinaryOperator::Minus => lhs.wrapping_sub(rhs),
+        ast::BinaryOperator::Multiply => {
+            if is_signed {
+                (lhs as i128).wrapping_mul(rhs as i128) as u128
+            } else {
+                lhs.wrapping_mul(rhs)
+            }
+        }
+        ast::BinaryOperator::Divide | ast::BinaryOperator::Modulo if rhs == 0 => {
+            return Some(Err(ConstEvalError::DivisionByZero))
+        }
+        ast::BinaryOperator::Divide => {
+            if is_signed {
+                (lhs as i128).wrapping_div(rhs as i128) as u128
+            } else {
+                lhs / rhs
+            }
+        }
+        ast::BinaryOperator::Modulo => {
+            if is_signed {
+                (lhs as i128).wrapping_rem(rhs as i128) as u128
+            } else {
+                lhs % rhs
+            }
+        }
+        ast::BinaryOperator::ShiftLeft => lhs << rhs,
+        ast::BinaryOperator::ShiftRight => {
+            if is_signed {
+                ((lhs as i128) >> rhs) as u128
+            } else {
+                trim_unnecessary_bits(lhs, width as u128) >> rhs
+            }
+        }
+        ast::BinaryOperator::BitwiseAnd => lhs & rhs,
+        ast::BinaryOperator::BitwiseXor => lhs ^ rhs,
+        ast::BinaryOperator::BitwiseOr => lhs | rhs,
+        ast::BinaryOperator::Equals => return compare(|o| o.is_eq()),
+        ast::BinaryOperator::NotEquals => return compare(|o| o.is_ne()),
+        ast::BinaryOperator::Less => return compare(|o| o.is_lt()),
+        ast::BinaryOperator::Greater => return compare(|o| o.is_gt()),
+        ast::BinaryOperator::LessOrEqual => return compare(|o| o.is_le()),
+        ast::BinaryOperator::GreaterOrEqual => return compare(|o| o.is_ge()),
+        _ => return None,
+    };
+
+    Some(Ok(int(result, width, is_signed)))
+}
+
+fn binary_float(
+    op: &ast::BinaryOperator,
+    lhs: f64,
+    rhs: f64,
+    width: usize,
+) -> Option<Result<Constant, ConstEvalError>> {
+    let compare = |result: bool| Some(Ok(Constant::int(result.into(), Dtype::BOOL)));
+
+    let result = match op {
+        ast::BinaryOperator::Plus => lhs + rhs,
+        ast::BinaryOperator::Minus => lhs - rhs,
+        ast::BinaryOperator::Multiply => lhs * rhs,
+        ast::BinaryOperator::Divide if rhs == 0.0 => {
+            return Some(Err(ConstEvalError::DivisionByZero))
+        }
+        ast::BinaryOperator::Divide => lhs / rhs,
+        ast::BinaryOperator::Equals => return compare(lhs == rhs),
+        ast::BinaryOperator::NotEquals => return compare(lhs != rhs),
+        ast::BinaryOperator::Less => return compare(lhs < rhs),
+        ast::BinaryOperator::Greater => return compare(lhs > rhs),
+        ast::BinaryOperator::LessOrEqual => return compare(lhs <= rhs),
+        ast::BinaryOperator::GreaterOrEqual => return compare(lhs >= rhs),
+        _ => return None,
+    };
+
+    Some(Ok(float(result, width)))
+}
+
+/// Whether `constant` is nonzero, or `None` if it is undefined.
+fn truth(constant: &Constant) -> Result<Option<bool>, ConstEvalError> {
+    let result = typecast(constant, &Dtype::BOOL)?;
+    Ok(result.get_int().map(|(value, _, _)| value != 0))
+}
+
+fn integer_promotion(constant: &Constant) -> Result<Constant, ConstEvalError> {
+    let dtype = constant.dtype();
+    match dtype.get_int_width() {
+        Some(width) if width < Dtype::SIZE_OF_INT * Dtype::BITS_OF_BYTE => {
+            typecast(constant, &Dtype::INT)
+        }
+        _ => Ok(constant.clone()),
+    }
+}
+
+/// Converts the operands of `op` to their common type.
+fn usual_arithmetic_conversion(
+    op: &ast::BinaryOperator,
+    lhs: &Constant,
+    rhs: &Constant,
+) -> Result<(Constant, Constant), ConstEvalError> {
+    let lhs = integer_promotion(lhs)?;
+    let rhs = integer_promotion(rhs)?;
+    let (lhs_dtype, rhs_dtype) = (lhs.dtype(), rhs.dtype());
+
+    let dtype = match (&lhs_dtype, &rhs_dtype) {
+        (Dtype::Float { width: lhs_w, .. }, Dtype::Float { width: rhs_w, .. }) => {
+            Dtype::float(*lhs_w.max(rhs_w))
+        }
+        (Dtype::Float { .. }, Dtype::Int { .. }) => lhs_dtype,
+        (Dtype::Int { .. }, Dtype::Float { .. }) => rhs_dtype,
+        (
+            Dtype::Int {
+                width: lhs_w,
+                is_signed: lhs_s,
+                ..
+            },
+            Dtype::Int {
+                width: rhs_w,
+                is_signed: rhs_s,
+                ..
+            },
+        ) => {
+            let width = *lhs_w.max(rhs_w);
+            // A signed type wider than the unsigned one can represent all of its values.
+            let is_signed = if lhs_s == rhs_s {
+                *lhs_s
+            } else if lhs_w == rhs_w {
+                false
+            } else if lhs_w > rhs_w {
+                *lhs_s
+            } else {
+                *rhs_s
+            };
+            Dtype::int(width).set_signed(is_signed)
+        }
+        _ => {
+            return Err(ConstEvalError::InvalidBinaryOperands {
+                op: op.clone(),
+                lhs: lhs_dtype,
+                rhs: rhs_dtype,
+            })
+        }
+    };
+
+    Ok((typecast(&lhs, &dtype)?, typecast(&rhs, &dtype)?))
+}
+
+fn is_scalar(dtype: &Dtype) -> bool {
+    matches!(dtype, Dtype::Int { .. } | Dtype::Float { .. })
+}
+
+fn same_scalar_dtype(lhs: &Dtype, rhs: &Dtype) -> bool {
+    match (lhs, rhs) {
+        (
+            Dtype::Int {
+                width: lhs_w,
+                is_signed: lhs_s,
+                ..
+            },
+            Dtype::Int {
+                width: rhs_w,
+                is_signed: rhs_s,
+                ..
+            },
+        ) => lhs_w == rhs_w && lhs_s == rhs_s,
+        (Dtype::Float { width: lhs_w, .. }, Dtype::Float { width: rhs_w, .. }) => lhs_w == rhs_w,
+        _ => false,
+    }
+}
@@ -0,0 +1,232 @@
+use core::fmt;
+use std::collections::{BTreeSet, HashMap};
+
+use crate::ir::*;
+use crate::write_base::*;
+use crate::*;
+
+/// A line of the printed IR that is only on one side of a [`Diff`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    Removed(String),
+    Added(String),
+}
+
+/// The changes of one part of a translation unit: a struct, a global variable, the signature or
+/// the allocations of a function, or a block of a function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hunk {
+    /// Where the changes are, e.g., `@main b1`.
+    pub location: String,
+    /// The removed and added lines, in the order of the lines of the part before and after the
+    /// change. A changed line is removed and then added.
+    pub changes: Vec<Change>,
+}
+
+/// The differences between two translation units. See [`diff`].
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct Diff {
+    pub hunks: Vec<Hunk>,
+}
+
+impl Diff {
+    /// Whether the translation units are printed the same.
+    pub fn is_empty(&self) -> bool {
+        self.hunks.is_empty()
+    }
+
+    fn push<S: AsRef<str>>(&mut self, location: String, before: &[S], after: &[S]) {
+        let changes = diff_lines(before, after);
+        if !changes.is_empty() {
+            self.hunks.push(Hunk { location, changes });
+        }
+    }
+}
+
+impl fmt::Display for Diff {
+    /// Writes each hunk as its location followed by its lines, prefixed by `-` if removed and by
+    /// `+` if added.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for hunk in &self.hunks {
+            writeln!(f, "{}", hunk.location)?;
+            for change in &hunk.changes {
+                match change {
+                    Change::Removed(line) => writeln!(f, "  - {line}")?,
+                    Change::Added(line) => writeln!(f, "  + {line}")?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Compares the printed lines of `before` and `after`, e.g., the IR before and after a pass.
+///
+/// The structs, global variables and functions are matched up by their names, and the blocks of
+/// functions by their ids. Within each block, the phinodes, instructions and exit are compared as
+/// lines, so that an instruction inserted in the middle of a block is reported alone. However,
+/// renumbering instructions or blocks changes every line mentioning them.
+pub fn diff(before: &TranslationUnit, after: &TranslationUnit) -> Diff {
+    let mut diff = Diff::default();
+
+    let structs = before
+        .structs
+        .keys()
+        .chain(after.structs.keys())
+        .collect::<BTreeSet<_>>();
+    for name in structs {
+        diff.push(
+            format!("struct {name}"),
+            &struct_lines(name, &before.structs),
+            &struct_lines(name, &after.structs),
+        );
+    }
+
+    let names = before
+        .decls
+        .keys()
+        .chain(after.decls.keys())
+        .collect::<BTreeSet<_>>();
+    for name in names {
+        match (before.decls.get(name), after.decls.get(name)) {
+            (
+                Some(Declaration::Function {
+                    definition: Some(definition_before),
+                    ..
+                }),
+                Some(Declaration::Function {
+                    definition: Some(definition_after),
+                    ..
+                }),
+            ) => {
+                let header = |decl| decl_lines(name, decl).swap_remove(0);
+                diff.push(
+                    format!("@{name}"),
+                    &[header(&before.decls[name])],
+                    &[header(&after.decls[name])],
+                );
+                diff_definitions(&mut diff, name, definition_before, definition_after);
+            }
+            (decl_before, decl_after) => diff.push(
+                format!("@{name}"),
+                &decl_before.map_or(Vec::new(), |decl| decl_lines(name, decl)),
+                &decl_after.map_or(Vec::new(), |decl| decl_lines(name, decl)),
+            ),
+        }
+    }
+
+    diff
+}
+
+fn diff_definitions(
+    diff: &mut Diff,
+    name: &str,
+    before: &FunctionDefinition,
+    after: &FunctionDefinition,
+) {
+    diff.push(
+        format!("@{name} init"),
+        &init_lines(before),
+        &init_lines(after),
+    );
+
+    let bids = before
+        .blocks
+        .keys()
+        .chain(after.blocks.keys())
+        .collect::<BTreeSet<_>>();
+    for bid in bids {
+        diff.push(
+            format!("@{name} {bid}"),
+            &before
+                .blocks
+                .get(bid)
+                .map_or(Vec::new(), |block| block_lines(bid, block)),
+            &after
+                .blocks
+                .get(bid)
+                .map_or(Vec::new(), |block| block_lines(bid, block)),
+        );
+    }
+}
+
+/// The removed and added lines turning `before` into `after`, keeping a longest common
+/// subsequence of them.
+fn diff_lines<S: AsRef<str>>(before: &[S], after: &[S]) -> Vec<Change> {
+    let (n, m) = (before.len(), after.len());
+    // `lengths[i][j]` is the length of the longest common subsequence of `before[i..]` and
+    // `after[j..]`.
+    let mut lengths = vec![vec![0; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lengths[i][j] = if before[i].as_ref() == after[j].as_ref() {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut changes = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && before[i].as_ref() == after[j].as_ref() {
+            i += 1;
+            j += 1;
+        } else if j == m || (i < n && lengths[i + 1][j] >= lengths[i][j + 1]) {
+            changes.push(Change::Removed(before[i].as_ref().to_string()));
+            i += 1;
+        } else {
+            changes.push(Change::Added(after[j].as_ref().to_string()));
+            j += 1;
+        }
+    }
+    changes
+}
+
+fn printed_lines<T: WriteLine>(t: &T) -> Vec<String> {
+    let mut buffer = Vec::new();
+    t.write_line(0, &mut buffer)
+        .expect("writing to a buffer must succeed");
+    String::from_utf8(buffer)
+        .expect("printed IR must be UTF-8")
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn struct_lines(name: &str, structs: &HashMap<String, Option<Dtype>>) -> Vec<String> {
+    let struct_type = some_or!(structs.get(name), return Vec::new());
+    printed_lines(&TranslationUnit {
+        decls: BTreeMap::new(),
+        decl_order: Vec::new(),
+        structs: HashMap::from([(name.to_string(), struct_type.clone())]),
+        decl_locs: BTreeMap::new(),
+    })
+}
+
+fn decl_lines(name: &str, decl: &Declaration) -> Vec<String> {
+    printed_lines(&(&name.to_string(), decl))
+}
+
+fn init_lines(definition: &FunctionDefinition) -> Vec<String> {
+    let mut lines = vec![format!("bid: {}", definition.bid_init)];
+    lines.extend(
+        definition
+            .allocations
+            .iter()
+            .enumerate()
+            .map(|(aid, allocation)| {
+                let name = allocation
+                    .name()
+                    .map_or(String::new(), |name| format!(":{name}"));
+                format!("{}:{}{name}", RegisterId::local(aid), allocation.deref())
+            }),
+    );
+    lines
+}
+
+fn block_lines(bid: &BlockId, block: &Block) -> Vec<String> {
+    printed_lines(&(bid, block))
+}
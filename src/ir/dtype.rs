@@ -21,6 +21,21 @@ pub enum DtypeError {
         /// TODO(document)
         message: String,
     },
+    /// A C feature that KECC does not support.
+    #[error("{feature} is unsupported")]
+    Unsupported {
+        /// The name of the feature, e.g., `_Complex`.
+        feature: String,
+    },
+}
+
+impl DtypeError {
+    #[inline]
+    fn unsupported(feature: &str) -> Self {
+        Self::Unsupported {
+            feature: feature.to_string(),
+        }
+    }
 }
 
 /// TODO(document)
@@ -29,6 +44,32 @@ pub trait HasDtype {
     fn dtype(&self) -> Dtype;
 }
 
+/// Target-dependent parameters of the layout of data in memory.
+///
+/// The sizes of integer and floating-point types are given by their widths in the IR. The data
+/// layout decides the rest: the size of pointers and how strictly large types are aligned.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct DataLayout {
+    /// The size and natural alignment of pointers in bytes.
+    pub pointer_size: usize,
+    /// The largest alignment in bytes. The alignment of larger types is lowered to it.
+    pub max_align: usize,
+}
+
+impl DataLayout {
+    /// 64-bit RISC-V (LP64D), which KECC targets.
+    pub const RV64: Self = Self {
+        pointer_size: Dtype::SIZE_OF_POINTER,
+        max_align: 16,
+    };
+}
+
+impl Default for DataLayout {
+    fn default() -> Self {
+        Self::RV64
+    }
+}
+
 #[derive(Default)]
 struct BaseDtype {
     scalar: Option<ast::TypeSpecifier>,
@@ -37,16 +78,21 @@ struct BaseDtype {
     typedef_name: Option<String>,
     struct_type: Option<ast::StructType>,
     is_const: bool,
+    is_volatile: bool,
     is_typedef: bool,
 }
 
 /// TODO(document)
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Dtype {
     /// TODO(document)
     Unit {
         /// TODO(document)
         is_const: bool,
+
+        /// Whether it is `volatile`-qualified, so that accesses to its objects are not removed.
+        is_volatile: bool,
     },
     /// TODO(document)
     Int {
@@ -58,6 +104,9 @@ pub enum Dtype {
 
         /// TODO(document)
         is_const: bool,
+
+        /// See `Dtype::Unit::is_volatile`.
+        is_volatile: bool,
     },
     /// TODO(document)
     Float {
@@ -66,6 +115,9 @@ pub enum Dtype {
 
         /// TODO(document)
         is_const: bool,
+
+        /// See `Dtype::Unit::is_volatile`.
+        is_volatile: bool,
     },
     /// TODO(document)
     Pointer {
@@ -74,29 +126,63 @@ pub enum Dtype {
 
         /// TODO(document)
         is_const: bool,
+
+        /// See `Dtype::Unit::is_volatile`.
+        is_volatile: bool,
     },
-    /// TODO(document)
+    /// Array type with a fixed number of elements.
+    ///
+    /// Elements are laid out contiguously, so an array occupies `size` times the size of `inner`
+    /// bytes. The interpreter allocates and initializes all of its elements at once.
     Array {
-        /// TODO(document)
+        /// The type of the elements.
         inner: Box<Dtype>,
 
-        /// TODO(document)
+        /// The number of elements.
         size: usize,
     },
-    /// TODO(document)
+    /// Structure or union type.
+    ///
+    /// A structure is defined once in `TranslationUnit::structs` with its `fields` and layout.
+    /// Everywhere else, it is referred to by `name` only, with `fields` and `size_align_offsets`
+    /// being `None`. Unions share the table with structures as they share the tags in C.
     Struct {
-        /// TODO(document)
+        /// The name of the structure. Anonymous structures are given a fresh name by irgen.
         name: Option<String>,
 
-        /// TODO(document)
+        /// Named (or anonymous) fields in declaration order.
         fields: Option<Vec<Named<Dtype>>>,
 
         /// TODO(document)
         is_const: bool,
 
-        /// TODO(document)
+        /// See `Dtype::Unit::is_volatile`. The fields of a volatile structure are volatile as
+        /// well.
+        is_volatile: bool,
+
+        /// Whether it is a union, of which all the fields are at offset 0.
+        is_union: bool,
+
+        /// C-compatible layout, computed by `fill_size_align_offsets_of_struct`: the size of the
+        /// structure including its trailing padding, its alignment (the largest alignment of its
+        /// fields), and the offset of each field.
         size_align_offsets: Option<(usize, usize, Vec<usize>)>,
     },
+    /// Bit-field member of a structure or union, which is `width` bits of the integer type `inner`.
+    ///
+    /// It is stored in a storage unit of `inner` at the offset of the member, from its `offset`-th
+    /// least significant bit. A bit-field never straddles two storage units.
+    BitField {
+        /// The declared type.
+        inner: Box<Dtype>,
+
+        /// The number of bits.
+        width: usize,
+
+        /// The offset in bits in the storage unit, computed by
+        /// `fill_size_align_offsets_of_struct`.
+        offset: usize,
+    },
     /// TODO(document)
     Function {
         /// TODO(document)
@@ -112,6 +198,9 @@ pub enum Dtype {
 
         /// TODO(document)
         is_const: bool,
+
+        /// See `Dtype::Unit::is_volatile`.
+        is_volatile: bool,
     },
 }
 
@@ -136,6 +225,8 @@ impl BaseDtype {
                 self.is_typedef = true;
                 Ok(())
             }
+            // `static` and `extern` determine the linkage of the declared name, not its type.
+            ast::StorageClassSpecifier::Static | ast::StorageClassSpecifier::Extern => Ok(()),
             scs => Err(DtypeError::Misc {
                 message: format!("unsupported storage class specifier: {scs:#?}"),
             }),
@@ -197,7 +288,13 @@ impl BaseDtype {
                 }
                 self.struct_type = Some(struct_type.node.clone());
             }
-            _ => todo!("apply_type_specifier: support {:?}", type_specifier),
+            ast::TypeSpecifier::Complex => return Err(DtypeError::unsupported("`_Complex`")),
+            ast::TypeSpecifier::Atomic(_) => return Err(DtypeError::unsupported("`_Atomic`")),
+            ast::TypeSpecifier::Enum(_) => return Err(DtypeError::unsupported("enumeration")),
+            ast::TypeSpecifier::TypeOf(_) => return Err(DtypeError::unsupported("`typeof`")),
+            ast::TypeSpecifier::TS18661Float(_) => {
+                return Err(DtypeError::unsupported("`_FloatN` type"))
+            }
         }
 
         Ok(())
@@ -222,6 +319,11 @@ impl BaseDtype {
                 // duplicate `const` is allowed
                 self.is_const = true;
             }
+            ast::TypeQualifier::Volatile => {
+                // duplicate `volatile` is allowed
+                self.is_volatile = true;
+            }
+            ast::TypeQualifier::Atomic => return Err(DtypeError::unsupported("`_Atomic`")),
             tq => {
                 return Err(DtypeError::Misc {
                     message: format!("unsupported typq qualifier: {tq:#?}"),
@@ -344,7 +446,8 @@ impl TryFrom<BaseDtype> for Dtype {
                 && spec.signed_option.is_none()
                 && spec.typedef_name.is_none()
                 && spec.struct_type.is_none()
-                && !spec.is_const),
+                && !spec.is_const
+                && !spec.is_volatile),
             "BaseDtype is empty"
         );
 
@@ -355,11 +458,13 @@ impl TryFrom<BaseDtype> for Dtype {
                 && spec.struct_type.is_none())
             {
                 return Err(DtypeError::Misc {
-                    message: "`typedef` can only be used with `const`".to_string(),
+                    message: "`typedef` can only be used with qualifiers".to_string(),
                 });
             }
 
-            let dtype = Self::typedef(name).set_const(spec.is_const);
+            let dtype = Self::typedef(name)
+                .set_const(spec.is_const)
+                .set_volatile(spec.is_volatile);
 
             return Ok(dtype);
         }
@@ -371,12 +476,12 @@ impl TryFrom<BaseDtype> for Dtype {
                 && spec.typedef_name.is_none())
             {
                 return Err(DtypeError::Misc {
-                    message: "`struct` can only be used with `const`".to_string(),
+                    message: "`struct` can only be used with qualifiers".to_string(),
                 });
             }
 
             assert!(struct_type.identifier.is_some() || struct_type.declarations.is_some());
-            assert_eq!(struct_type.kind.node, ast::StructKind::Struct);
+            let is_union = struct_type.kind.node == ast::StructKind::Union;
             let struct_name = struct_type.identifier.map(|i| i.node.name);
             let fields = if let Some(declarations) = struct_type.declarations {
                 let fields = declarations
@@ -399,7 +504,9 @@ impl TryFrom<BaseDtype> for Dtype {
                 }
             }
 
-            let dtype = Self::structure(struct_name, fields).set_const(spec.is_const);
+            let dtype = Self::structure_or_union(is_union, struct_name, fields)
+                .set_const(spec.is_const)
+                .set_volatile(spec.is_volatile);
 
             return Ok(dtype);
         }
@@ -424,6 +531,7 @@ impl TryFrom<BaseDtype> for Dtype {
             0 => dtype,
             1 => match spec.size_modifiers[0] {
                 ast::TypeSpecifier::Short => Self::SHORT,
+                ast::TypeSpecifier::Long if dtype == Self::DOUBLE => Self::LONG_DOUBLE,
                 ast::TypeSpecifier::Long => Self::LONG,
                 _ => panic!(
                     "Dtype::try_from::<BaseDtype>: {:?} is not a size modifier",
@@ -466,7 +574,9 @@ impl TryFrom<BaseDtype> for Dtype {
             dtype = dtype.set_signed(is_signed);
         }
 
-        dtype = dtype.set_const(spec.is_const);
+        dtype = dtype
+            .set_const(spec.is_const)
+            .set_volatile(spec.is_volatile);
 
         Ok(dtype)
     }
@@ -545,12 +655,17 @@ impl Dtype {
     /// TODO(document)
     pub const SIZE_OF_DOUBLE: usize = 8;
 
+    /// `long double` is IEEE 754 quadruple precision on RV64. Its values are computed with `f64`
+    /// precision, but it has the size and alignment of the target type.
+    pub const SIZE_OF_LONG_DOUBLE: usize = 16;
+
     /// TODO(document)
     /// A boolean value cannot be signed.
     pub const BOOL: Self = Self::Int {
         width: 1,
         is_signed: false,
         is_const: false,
+        is_volatile: false,
     };
 
     /// TODO(document)
@@ -574,10 +689,16 @@ impl Dtype {
     /// TODO(document)
     pub const DOUBLE: Self = Self::float(Self::SIZE_OF_DOUBLE * Self::BITS_OF_BYTE);
 
+    /// See [`Dtype::SIZE_OF_LONG_DOUBLE`].
+    pub const LONG_DOUBLE: Self = Self::float(Self::SIZE_OF_LONG_DOUBLE * Self::BITS_OF_BYTE);
+
     /// TODO(document)
     #[inline]
     pub const fn unit() -> Self {
-        Self::Unit { is_const: false }
+        Self::Unit {
+            is_const: false,
+            is_volatile: false,
+        }
     }
 
     /// TODO(document)
@@ -587,6 +708,7 @@ impl Dtype {
             width,
             is_signed: true,
             is_const: false,
+            is_volatile: false,
         }
     }
 
@@ -596,6 +718,7 @@ impl Dtype {
         Self::Float {
             width,
             is_const: false,
+            is_volatile: false,
         }
     }
 
@@ -605,10 +728,14 @@ impl Dtype {
         Self::Pointer {
             inner: Box::new(inner),
             is_const: false,
+            is_volatile: false,
         }
     }
 
-    /// TODO(document)
+    /// The array of `size` elements of `base_dtype`. If `base_dtype` is an array itself, `size`
+    /// is applied to its innermost element type instead, so that applying the sizes of a
+    /// declarator in the source order gives the row-major layout: each row is contiguous, and
+    /// indexing an array with fewer indices than its rank gives a row.
     ///
     /// # Examples
     ///
@@ -643,10 +770,38 @@ impl Dtype {
             name,
             fields,
             is_const: false,
+            is_volatile: false,
+            is_union: false,
+            size_align_offsets: None,
+        }
+    }
+
+    /// The union named `name`, which is defined with `fields` if given.
+    #[inline]
+    pub fn union(name: Option<String>, fields: Option<Vec<Named<Self>>>) -> Self {
+        Self::Struct {
+            name,
+            fields,
+            is_const: false,
+            is_volatile: false,
+            is_union: true,
             size_align_offsets: None,
         }
     }
 
+    #[inline]
+    fn structure_or_union(
+        is_union: bool,
+        name: Option<String>,
+        fields: Option<Vec<Named<Self>>>,
+    ) -> Self {
+        if is_union {
+            Self::union(name, fields)
+        } else {
+            Self::structure(name, fields)
+        }
+    }
+
     pub fn fill_size_align_offsets_of_struct(
         self,
         structs: &HashMap<String, Option<Dtype>>,
@@ -655,55 +810,56 @@ impl Dtype {
             name,
             fields,
             is_const,
+            is_volatile,
+            is_union,
             size_align_offsets,
         } = self
         {
             assert!(
-                name.is_some() && fields.is_some() && !is_const && size_align_offsets.is_none()
+                name.is_some()
+                    && fields.is_some()
+                    && !is_const
+                    && !is_volatile
+                    && size_align_offsets.is_none()
             );
 
             let fields = fields.unwrap();
-            if fields.is_empty() {
-                return Ok(Self::Struct {
-                    name,
-                    fields: Some(fields),
-                    is_const,
-                    size_align_offsets: Some((0, 1, Vec::new())),
-                });
-            }
-
-            let align_of = fields
-                .iter()
-                .map(|f| f.size_align_of(structs))
-                .collect::<Result<Vec<_>, _>>()?
-                .into_iter()
-                .map(|(_, a)| a)
-                .max()
-                .unwrap_or(0);
-
-            let mut offsets = Vec::new();
-            let mut offset = 0;
-            for field in &fields {
-                let (size_of_dtype, align_of_dtype) = field.size_align_of(structs)?;
-
-                let pad = if (offset % align_of_dtype) != 0 {
-                    align_of_dtype - (offset % align_of_dtype)
-                } else {
-                    0
-                };
-
-                offset += pad;
-                offsets.push(offset);
-
-                offset += size_of_dtype;
-            }
+            let (size_of, align_of, offsets) = if is_union {
+                Self::union_layout_in(&fields, &DataLayout::default(), structs)?
+            } else {
+                Self::struct_layout_in(&fields, &DataLayout::default(), structs)?
+            };
 
-            let size_of = ((offset - 1) / align_of + 1) * align_of;
+            // The bit-fields of a union are at offset 0 in their storage units.
+            let fields = if is_union {
+                fields
+            } else {
+                let (_, _, bit_offsets) =
+                    Self::struct_bit_layout_in(&fields, &DataLayout::default(), structs)?;
+                izip!(fields, bit_offsets)
+                    .map(|(field, bit_offset)| {
+                        let (dtype, name) = field.destruct();
+                        let dtype = if let Self::BitField { inner, width, .. } = dtype {
+                            let (size_of_unit, _) = inner.size_align_of(structs)?;
+                            Self::BitField {
+                                inner,
+                                width,
+                                offset: bit_offset % (size_of_unit * Self::BITS_OF_BYTE),
+                            }
+                        } else {
+                            dtype
+                        };
+                        Ok(Named::new(name, dtype))
+                    })
+                    .collect::<Result<Vec<_>, DtypeError>>()?
+            };
 
             Ok(Self::Struct {
                 name,
                 fields: Some(fields),
                 is_const,
+                is_volatile,
+                is_union,
                 size_align_offsets: Some((size_of, align_of, offsets)),
             })
         } else {
@@ -713,6 +869,16 @@ impl Dtype {
         }
     }
 
+    /// The bit-field of `width` bits of `inner`, of which the offset is not computed yet.
+    #[inline]
+    pub fn bit_field(inner: Dtype, width: usize) -> Self {
+        Self::BitField {
+            inner: Box::new(inner),
+            width,
+            offset: 0,
+        }
+    }
+
     #[inline]
     pub fn function(ret: Dtype, params: Vec<Dtype>) -> Self {
         Self::Function {
@@ -726,6 +892,7 @@ impl Dtype {
         Self::Typedef {
             name,
             is_const: false,
+            is_volatile: false,
         }
     }
 
@@ -774,6 +941,26 @@ impl Dtype {
         }
     }
 
+    /// The declared type, width, and offset of the bit-field `self`.
+    #[inline]
+    pub fn get_bit_field(&self) -> Option<(&Self, usize, usize)> {
+        if let Self::BitField {
+            inner,
+            width,
+            offset,
+        } = self
+        {
+            Some((inner.deref(), *width, *offset))
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    pub fn is_union(&self) -> bool {
+        matches!(self, Self::Struct { is_union: true, .. })
+    }
+
     #[inline]
     pub fn get_struct_fields(&self) -> Option<&Option<Vec<Named<Self>>>> {
         if let Self::Struct { fields, .. } = self {
@@ -795,6 +982,89 @@ impl Dtype {
         }
     }
 
+    /// The definition of the struct named `name` in `structs`, the struct table of a translation
+    /// unit.
+    ///
+    /// Only the definition of a struct carries its fields and layout. Other struct dtypes refer to
+    /// it by name, which lets structs refer to themselves and to each other.
+    pub fn lookup_struct<'s>(
+        name: &str,
+        structs: &'s HashMap<String, Option<Dtype>>,
+    ) -> Result<&'s Self, DtypeError> {
+        structs
+            .get(name)
+            .ok_or_else(|| DtypeError::Misc {
+                message: format!("unknown struct name `{name}`"),
+            })?
+            .as_ref()
+            .ok_or_else(|| DtypeError::Misc {
+                message: format!("struct `{name}` is declared but never defined"),
+            })
+    }
+
+    /// The fields of the struct `self` refers to, looked up in `structs`.
+    pub fn resolve_struct_fields<'s>(
+        &self,
+        structs: &'s HashMap<String, Option<Dtype>>,
+    ) -> Result<&'s [Named<Self>], DtypeError> {
+        let definition = self.resolve_struct(structs)?;
+        Ok(definition
+            .get_struct_fields()
+            .and_then(Option::as_ref)
+            .expect("struct definition must have its fields"))
+    }
+
+    /// The size, alignment, and field offsets of the struct `self` refers to, looked up in
+    /// `structs`.
+    pub fn resolve_struct_layout<'s>(
+        &self,
+        structs: &'s HashMap<String, Option<Dtype>>,
+    ) -> Result<(usize, usize, &'s [usize]), DtypeError> {
+        let definition = self.resolve_struct(structs)?;
+        let (size_of, align_of, offsets) = definition
+            .get_struct_size_align_offsets()
+            .and_then(Option::as_ref)
+            .expect("struct definition must have its layout");
+        Ok((*size_of, *align_of, offsets))
+    }
+
+    /// Checks that the struct or union `self` refers to is not defined as the other of the two,
+    /// e.g., `union A` for `struct A { ... }`.
+    pub fn check_tag(&self, structs: &HashMap<String, Option<Dtype>>) -> Result<(), DtypeError> {
+        let name = some_or!(
+            self.get_struct_name().and_then(Option::as_ref),
+            return Ok(())
+        );
+        let definition = some_or!(structs.get(name).and_then(Option::as_ref), return Ok(()));
+        if definition.is_union() != self.is_union() {
+            return Err(Self::tag_mismatch(name));
+        }
+
+        Ok(())
+    }
+
+    fn tag_mismatch(name: &str) -> DtypeError {
+        DtypeError::Misc {
+            message: format!(
+                "use of '{name}' with tag type that does not match previous declaration"
+            ),
+        }
+    }
+
+    fn resolve_struct<'s>(
+        &self,
+        structs: &'s HashMap<String, Option<Dtype>>,
+    ) -> Result<&'s Self, DtypeError> {
+        let name = self
+            .get_struct_name()
+            .ok_or_else(|| DtypeError::Misc {
+                message: format!("`{self}` is not a struct type"),
+            })?
+            .as_ref()
+            .expect("struct should have its name");
+        Self::lookup_struct(name, structs)
+    }
+
     #[inline]
     pub fn get_function_inner(&self) -> Option<(&Self, &Vec<Self>)> {
         if let Self::Function { ret, params } = self {
@@ -823,12 +1093,13 @@ impl Dtype {
 
     pub fn is_const(&self) -> bool {
         match self {
-            Self::Unit { is_const }
+            Self::Unit { is_const, .. }
             | Self::Int { is_const, .. }
             | Self::Float { is_const, .. }
             | Self::Typedef { is_const, .. }
             | Self::Pointer { is_const, .. }
             | Self::Struct { is_const, .. } => *is_const,
+            Self::BitField { inner, .. } => inner.is_const(),
             Self::Function { .. } | Self::Array { .. } => true,
         }
     }
@@ -837,23 +1108,16 @@ impl Dtype {
     /// Check if `Dtype` is constant. if it is constant, the variable of `Dtype` is not assignable.
     pub fn is_immutable(&self, structs: &HashMap<String, Option<Dtype>>) -> bool {
         match self {
-            Self::Unit { is_const }
+            Self::Unit { is_const, .. }
             | Self::Int { is_const, .. }
             | Self::Float { is_const, .. }
             | Self::Pointer { is_const, .. } => *is_const,
+            Self::BitField { inner, .. } => inner.is_immutable(structs),
             Self::Array { .. } | Self::Function { .. } => true,
-            Self::Struct { name, is_const, .. } => {
-                let name = name.as_ref().expect("`name` must be exist");
-                let struct_type = structs
-                    .get(name)
-                    .expect("struct type matched with `name` must exist")
-                    .as_ref()
-                    .expect("`struct_type` must have its definition");
-                let fields = struct_type
-                    .get_struct_fields()
-                    .expect("`struct_type` must be struct type")
-                    .as_ref()
-                    .expect("`fields` must be `Some`");
+            Self::Struct { is_const, .. } => {
+                let fields = self
+                    .resolve_struct_fields(structs)
+                    .expect("struct type must be defined");
 
                 *is_const
                     // If any of the fields in the structure type is constant, return `true`.
@@ -884,74 +1148,213 @@ impl Dtype {
         }
     }
 
+    /// Whether the objects of `Dtype` are `volatile`, so that each access to them is kept. The
+    /// elements of a volatile array are volatile.
+    pub fn is_volatile(&self) -> bool {
+        match self {
+            Self::Unit { is_volatile, .. }
+            | Self::Int { is_volatile, .. }
+            | Self::Float { is_volatile, .. }
+            | Self::Typedef { is_volatile, .. }
+            | Self::Pointer { is_volatile, .. }
+            | Self::Struct { is_volatile, .. } => *is_volatile,
+            Self::Array { inner, .. } | Self::BitField { inner, .. } => inner.is_volatile(),
+            Self::Function { .. } => false,
+        }
+    }
+
     #[must_use]
     pub fn set_const(self, is_const: bool) -> Self {
         match self {
-            Self::Unit { .. } => Self::Unit { is_const },
+            Self::Unit { is_volatile, .. } => Self::Unit {
+                is_const,
+                is_volatile,
+            },
             Self::Int {
-                width, is_signed, ..
+                width,
+                is_signed,
+                is_volatile,
+                ..
             } => Self::Int {
                 width,
                 is_signed,
                 is_const,
+                is_volatile,
+            },
+            Self::Float {
+                width, is_volatile, ..
+            } => Self::Float {
+                width,
+                is_const,
+                is_volatile,
+            },
+            Self::Pointer {
+                inner, is_volatile, ..
+            } => Self::Pointer {
+                inner,
+                is_const,
+                is_volatile,
             },
-            Self::Float { width, .. } => Self::Float { width, is_const },
-            Self::Pointer { inner, .. } => Self::Pointer { inner, is_const },
             Self::Array { .. } => self,
             Self::Struct {
                 name,
                 fields,
+                is_volatile,
+                is_union,
                 size_align_offsets,
                 ..
             } => Self::Struct {
                 name,
                 fields,
                 is_const,
+                is_volatile,
+                is_union,
                 size_align_offsets,
             },
+            Self::BitField {
+                inner,
+                width,
+                offset,
+            } => Self::BitField {
+                inner: Box::new(inner.set_const(is_const)),
+                width,
+                offset,
+            },
             Self::Function { .. } => self,
-            Self::Typedef { name, .. } => Self::Typedef { name, is_const },
+            Self::Typedef {
+                name, is_volatile, ..
+            } => Self::Typedef {
+                name,
+                is_const,
+                is_volatile,
+            },
         }
     }
 
+    #[must_use]
+    pub fn set_volatile(self, is_volatile: bool) -> Self {
+        match self {
+            Self::Unit { is_const, .. } => Self::Unit {
+                is_const,
+                is_volatile,
+            },
+            Self::Int {
+                width,
+                is_signed,
+                is_const,
+                ..
+            } => Self::Int {
+                width,
+                is_signed,
+                is_const,
+                is_volatile,
+            },
+            Self::Float {
+                width, is_const, ..
+            } => Self::Float {
+                width,
+                is_const,
+                is_volatile,
+            },
+            Self::Pointer {
+                inner, is_const, ..
+            } => Self::Pointer {
+                inner,
+                is_const,
+                is_volatile,
+            },
+            Self::Array { .. } => self,
+            Self::Struct {
+                name,
+                fields,
+                is_const,
+                is_union,
+                size_align_offsets,
+                ..
+            } => Self::Struct {
+                name,
+                fields,
+                is_const,
+                is_volatile,
+                is_union,
+                size_align_offsets,
+            },
+            Self::BitField {
+                inner,
+                width,
+                offset,
+            } => Self::BitField {
+                inner: Box::new(inner.set_volatile(is_volatile)),
+                width,
+                offset,
+            },
+            Self::Function { .. } => self,
+            Self::Typedef { name, is_const, .. } => Self::Typedef {
+                name,
+                is_const,
+                is_volatile,
+            },
+        }
+    }
+
+    /// The unqualified version of `Dtype`, which is the type of the values of its objects.
+    #[must_use]
+    pub fn unqualified(self) -> Self {
+        self.set_const(false).set_volatile(false)
+    }
+
+    /// Size and alignment in bytes on the default target, [`DataLayout::RV64`].
     pub fn size_align_of(
         &self,
         structs: &HashMap<String, Option<Dtype>>,
+    ) -> Result<(usize, usize), DtypeError> {
+        self.size_align_of_in(&DataLayout::default(), structs)
+    }
+
+    /// Size and alignment in bytes on the target described by `layout`.
+    pub fn size_align_of_in(
+        &self,
+        layout: &DataLayout,
+        structs: &HashMap<String, Option<Dtype>>,
     ) -> Result<(usize, usize), DtypeError> {
         match self {
             Self::Unit { .. } => Ok((0, 1)),
             Self::Int { width, .. } | Self::Float { width, .. } => {
                 let size_of = (*width + Self::BITS_OF_BYTE - 1) / Self::BITS_OF_BYTE;
-                let align_of = size_of;
+                let align_of = std::cmp::min(size_of, layout.max_align);
 
                 Ok((size_of, align_of))
             }
-            Self::Pointer { .. } => Ok((Self::SIZE_OF_POINTER, Self::SIZE_OF_POINTER)),
+            Self::Pointer { .. } => Ok((
+                layout.pointer_size,
+                std::cmp::min(layout.pointer_size, layout.max_align),
+            )),
             Self::Array { inner, size, .. } => {
-                let (size_of_inner, align_of_inner) = inner.size_align_of(structs)?;
+                let (size_of_inner, align_of_inner) = inner.size_align_of_in(layout, structs)?;
 
                 Ok((
                     size * std::cmp::max(size_of_inner, align_of_inner),
                     align_of_inner,
                 ))
             }
-            Self::Struct { name, .. } => {
-                let name = name.as_ref().expect("`name` must be exist");
-                let struct_type = structs
-                    .get(name)
-                    .ok_or_else(|| DtypeError::Misc {
-                        message: format!("unknown struct name `{name}`"),
-                    })?
-                    .as_ref()
-                    .expect("`struct_type` must have its definition");
-                let (size_of, align_of, _) = struct_type
-                    .get_struct_size_align_offsets()
-                    .expect("`struct_type` must be struct type")
-                    .as_ref()
-                    .unwrap();
-
-                Ok((*size_of, *align_of))
+            Self::Struct { .. } => {
+                // The layout on the default target is computed once when the struct is defined.
+                if *layout == DataLayout::default() {
+                    let (size_of, align_of, _) = self.resolve_struct_layout(structs)?;
+                    return Ok((size_of, align_of));
+                }
+
+                let fields = self.resolve_struct_fields(structs)?;
+                let (size_of, align_of, _) = if self.resolve_struct(structs)?.is_union() {
+                    Self::union_layout_in(fields, layout, structs)?
+                } else {
+                    Self::struct_layout_in(fields, layout, structs)?
+                };
+
+                Ok((size_of, align_of))
             }
+            // A bit-field takes the storage unit of its declared type.
+            Self::BitField { inner, .. } => inner.size_align_of_in(layout, structs),
             Self::Function { .. } => Ok((0, 1)),
             Self::Typedef { .. } => Err(DtypeError::Misc {
                 message: "typedef should be replaced by real dtype".to_string(),
@@ -959,28 +1362,114 @@ impl Dtype {
         }
     }
 
+    /// Size, alignment, and offsets of the fields of a struct with `fields` on the target
+    /// described by `layout`, following the C rules: each field is placed at the next offset
+    /// aligned for it, and the struct is padded to a multiple of its largest field alignment. The
+    /// offset of a bit-field is that of its storage unit.
+    pub fn struct_layout_in(
+        fields: &[Named<Self>],
+        layout: &DataLayout,
+        structs: &HashMap<String, Option<Dtype>>,
+    ) -> Result<(usize, usize, Vec<usize>), DtypeError> {
+        let (size_of, align_of, bit_offsets) = Self::struct_bit_layout_in(fields, layout, structs)?;
+        let offsets = izip!(fields, bit_offsets)
+            .map(|(field, bit_offset)| {
+                if field.get_bit_field().is_some() {
+                    let (size_of_unit, _) = field.size_align_of_in(layout, structs)?;
+                    let bits_of_unit = size_of_unit * Self::BITS_OF_BYTE;
+                    Ok(bit_offset / bits_of_unit * size_of_unit)
+                } else {
+                    Ok(bit_offset / Self::BITS_OF_BYTE)
+                }
+            })
+            .collect::<Result<Vec<_>, DtypeError>>()?;
+
+        Ok((size_of, align_of, offsets))
+    }
+
+    /// Size and alignment of a struct with `fields` as in `struct_layout_in`, and the offsets of
+    /// its fields in bits.
+    ///
+    /// Bit-fields are packed as in the System V ABI: a bit-field follows the previous field right
+    /// away unless it would straddle two storage units of its declared type, in which case it
+    /// starts at the next storage unit. A bit-field of zero width only makes the next field start
+    /// at a new storage unit, and an unnamed bit-field does not affect the alignment of the struct.
+    fn struct_bit_layout_in(
+        fields: &[Named<Self>],
+        layout: &DataLayout,
+        structs: &HashMap<String, Option<Dtype>>,
+    ) -> Result<(usize, usize, Vec<usize>), DtypeError> {
+        if fields.is_empty() {
+            return Ok((0, 1, Vec::new()));
+        }
+
+        let mut align_of = 1;
+        let mut offsets = Vec::new();
+        let mut offset = 0;
+        for field in fields {
+            let (size_of_dtype, align_of_dtype) = field.size_align_of_in(layout, structs)?;
+            if let Some((_, width, _)) = field.get_bit_field() {
+                let bits_of_unit = size_of_dtype * Self::BITS_OF_BYTE;
+                if width == 0 || offset % bits_of_unit + width > bits_of_unit {
+                    offset = offset.next_multiple_of(bits_of_unit);
+                }
+                offsets.push(offset);
+                offset += width;
+
+                if field.name().is_none() {
+                    continue;
+                }
+            } else {
+                offset = offset.next_multiple_of(align_of_dtype * Self::BITS_OF_BYTE);
+                offsets.push(offset);
+                offset += size_of_dtype * Self::BITS_OF_BYTE;
+            }
+            align_of = std::cmp::max(align_of, align_of_dtype);
+        }
+
+        // The trailing padding makes the fields of each element of an array aligned.
+        let size_of = offset
+            .div_ceil(Self::BITS_OF_BYTE)
+            .next_multiple_of(align_of);
+
+        Ok((size_of, align_of, offsets))
+    }
+
+    /// Size, alignment, and offsets of the fields of a union with `fields` on the target described
+    /// by `layout`: every field is placed at offset 0, and the union is as large as its largest
+    /// field padded to a multiple of its largest field alignment.
+    pub fn union_layout_in(
+        fields: &[Named<Self>],
+        layout: &DataLayout,
+        structs: &HashMap<String, Option<Dtype>>,
+    ) -> Result<(usize, usize, Vec<usize>), DtypeError> {
+        if fields.is_empty() {
+            return Ok((0, 1, Vec::new()));
+        }
+
+        let size_aligns = fields
+            .iter()
+            .map(|f| f.size_align_of_in(layout, structs))
+            .collect::<Result<Vec<_>, _>>()?;
+        let align_of = size_aligns.iter().map(|(_, a)| *a).max().unwrap_or(0);
+        let size_of = size_aligns.iter().map(|(s, _)| *s).max().unwrap_or(0);
+        let size_of = size_of.div_ceil(align_of) * align_of;
+
+        Ok((size_of, align_of, vec![0; fields.len()]))
+    }
+
     pub fn get_offset_struct_field(
         &self,
         field_name: &str,
         structs: &HashMap<String, Option<Dtype>>,
     ) -> Option<(usize, Self)> {
-        if let Self::Struct { name, .. } = self {
-            let struct_name = name.as_ref().expect("`self` must have its name");
-            let struct_type = structs
-                .get(struct_name)
-                .expect("`structs` must have value matched with `struct_name`")
-                .as_ref()
-                .expect("`struct_type` must have its definition");
-            let fields = struct_type
-                .get_struct_fields()
-                .expect("`struct_type` must be struct type")
-                .as_ref()
-                .expect("`fields` must be `Some`");
-            let (_, _, offsets) = struct_type
-                .get_struct_size_align_offsets()
-                .expect("`struct_type` must be struct type")
-                .as_ref()
-                .expect("`offsets` must be `Some`");
+        if let Self::Struct { .. } = self {
+            let fields = self
+                .resolve_struct_fields(structs)
+                .expect("struct type must be defined");
+            let (_, _, offsets) = self
+                .resolve_struct_layout(structs)
+                .expect("struct type must be defined");
 
             assert_eq!(fields.len(), offsets.len());
             for (field, &offset) in izip!(fields, offsets) {
@@ -1008,11 +1497,15 @@ impl Dtype {
     pub fn set_signed(&self, is_signed: bool) -> Self {
         match self {
             Self::Int {
-                width, is_const, ..
+                width,
+                is_const,
+                is_volatile,
+                ..
             } => Self::Int {
                 width: *width,
                 is_signed,
                 is_const: *is_const,
+                is_volatile: *is_volatile,
             },
             _ => panic!("`signed` and `unsigned` only be applied to `Dtype::Int`"),
         }
@@ -1050,9 +1543,22 @@ impl Dtype {
             .declarators
             .iter()
             .map(|d| {
-                dtype
-                    .clone()
-                    .with_ast_declarator(&d.node.declarator.as_ref().unwrap().node)
+                let field = if let Some(declarator) = &d.node.declarator {
+                    dtype.clone().with_ast_declarator(&declarator.node)?
+                } else {
+                    Named::new(None, dtype.clone())
+                };
+                if let Some(bit_width) = &d.node.bit_width {
+                    let (field, name) = field.destruct();
+                    let field = field.with_ast_bit_width(&bit_width.node, name.is_some())?;
+                    Ok(Named::new(name, field))
+                } else if field.name().is_none() {
+                    Err(DtypeError::Misc {
+                        message: "declaration does not declare anything".to_string(),
+                    })
+                } else {
+                    Ok(field)
+                }
             })
             .collect::<Result<Vec<_>, _>>()?;
 
@@ -1077,6 +1583,49 @@ impl Dtype {
         }
     }
 
+    /// Generates the bit-field of `bit_width` bits of `self`, which is named if `is_named`.
+    fn with_ast_bit_width(
+        self,
+        bit_width: &ast::Expression,
+        is_named: bool,
+    ) -> Result<Self, DtypeError> {
+        let bits_of_dtype = match &self {
+            Self::Int { width, .. } => *width,
+            _ => {
+                return Err(DtypeError::Misc {
+                    message: format!("bit-field has non-integral type `{self}`"),
+                })
+            }
+        };
+
+        let width = Constant::try_from(bit_width)
+            .ok()
+            .and_then(|constant| constant.get_int())
+            .ok_or_else(|| DtypeError::Misc {
+                message: "bit-field width is not an integer constant expression".to_string(),
+            })?;
+        let width = match width {
+            (value, _, true) if (value as i128) < 0 => {
+                return Err(DtypeError::Misc {
+                    message: "bit-field has negative width".to_string(),
+                })
+            }
+            (value, _, _) => value as usize,
+        };
+        if width > bits_of_dtype {
+            return Err(DtypeError::Misc {
+                message: format!("width of bit-field ({width} bits) exceeds the width of its type"),
+            });
+        }
+        if width == 0 && is_named {
+            return Err(DtypeError::Misc {
+                message: "named bit-field has zero width".to_string(),
+            });
+        }
+
+        Ok(Self::bit_field(self, width))
+    }
+
     /// Generate `Dtype` based on declarator and `self` which has a scalar type.
     ///
     /// Let's say declaration is `const int * const * const a;`. In general `self` start with `const
@@ -1097,7 +1646,9 @@ impl Dtype {
                     for qualifier in pointer_qualifiers {
                         specifier.apply_pointer_qualifier(&qualifier.node)?;
                     }
-                    Self::pointer(self).set_const(specifier.is_const)
+                    Self::pointer(self)
+                        .set_const(specifier.is_const)
+                        .set_volatile(specifier.is_volatile)
                 }
                 ast::DerivedDeclarator::Array(array_decl) => {
                     assert!(array_decl.node.qualifiers.is_empty());
@@ -1120,10 +1671,14 @@ impl Dtype {
                 }
                 ast::DerivedDeclarator::KRFunction(kr_func_decl) => {
                     // K&R function is allowed only when it has no parameter
-                    assert!(kr_func_decl.is_empty());
+                    if !kr_func_decl.is_empty() {
+                        return Err(DtypeError::unsupported("K&R-style parameter list"));
+                    }
                     Self::function(self, Vec::new())
                 }
-                ast::DerivedDeclarator::Block(_) => panic!(),
+                ast::DerivedDeclarator::Block(_) => {
+                    return Err(DtypeError::unsupported("block pointer"))
+                }
             };
         }
 
@@ -1148,17 +1703,26 @@ impl Dtype {
     ///
     /// * `array_size` - the array size to add to `self`.
     pub fn with_ast_array_size(self, array_size: &ast::ArraySize) -> Result<Self, DtypeError> {
-        let expr = if let ast::ArraySize::VariableExpression(expr) = array_size {
-            &expr.node
-        } else {
-            return Err(DtypeError::Misc {
-                message: "`ArraySize` is unsupported except `ArraySize::VariableExpression`"
-                    .to_string(),
-            });
+        let expr = match array_size {
+            ast::ArraySize::VariableExpression(expr) => &expr.node,
+            ast::ArraySize::VariableUnknown => {
+                return Err(DtypeError::unsupported("variable length array"))
+            }
+            _ => {
+                return Err(DtypeError::Misc {
+                    message: "`ArraySize` is unsupported except `ArraySize::VariableExpression`"
+                        .to_string(),
+                })
+            }
         };
 
-        let constant = Constant::try_from(expr)
-            .expect("expression of `ArraySize::VariableExpression` must be constant value");
+        // A size that is not a constant makes a variable length array.
+        let constant = const_eval::expression(expr).map_err(|e| match e {
+            ConstEvalError::NotConstant => DtypeError::unsupported("variable length array"),
+            e => DtypeError::Misc {
+                message: e.to_string(),
+            },
+        })?;
 
         let (value, _, is_signed) = constant.get_int().ok_or_else(|| DtypeError::Misc {
             message: "expression is not an integer constant expression".to_string(),
@@ -1176,9 +1740,15 @@ impl Dtype {
     pub fn resolve_typedefs(self, typedefs: &HashMap<String, Dtype>) -> Result<Self, DtypeError> {
         let dtype = match self {
             Self::Unit { .. } | Self::Int { .. } | Self::Float { .. } => self,
-            Self::Pointer { inner, is_const } => {
+            Self::Pointer {
+                inner,
+                is_const,
+                is_volatile,
+            } => {
                 let inner = inner.resolve_typedefs(typedefs)?;
-                Self::pointer(inner).set_const(is_const)
+                Self::pointer(inner)
+                    .set_const(is_const)
+                    .set_volatile(is_volatile)
             }
             Self::Array { inner, size } => {
                 let inner = inner.resolve_typedefs(typedefs)?;
@@ -1191,6 +1761,8 @@ impl Dtype {
                 name,
                 fields,
                 is_const,
+                is_volatile,
+                is_union,
                 ..
             } => {
                 let (name, fields) = if let Some(fields) = fields {
@@ -1207,8 +1779,19 @@ impl Dtype {
                     assert!(name.is_some());
                     (name, fields)
                 };
-                Self::structure(name, fields).set_const(is_const)
+                Self::structure_or_union(is_union, name, fields)
+                    .set_const(is_const)
+                    .set_volatile(is_volatile)
             }
+            Self::BitField {
+                inner,
+                width,
+                offset,
+            } => Self::BitField {
+                inner: Box::new(inner.resolve_typedefs(typedefs)?),
+                width,
+                offset,
+            },
             Self::Function { ret, params } => {
                 let ret = ret.resolve_typedefs(typedefs)?;
                 let params = params
@@ -1218,7 +1801,11 @@ impl Dtype {
 
                 Self::function(ret, params)
             }
-            Self::Typedef { name, is_const } => {
+            Self::Typedef {
+                name,
+                is_const,
+                is_volatile,
+            } => {
                 let dtype = typedefs
                     .get(&name)
                     .ok_or_else(|| DtypeError::Misc {
@@ -1226,8 +1813,9 @@ impl Dtype {
                     })?
                     .clone();
                 let is_const = dtype.is_const() || is_const;
+                let is_volatile = dtype.is_volatile() || is_volatile;
 
-                dtype.set_const(is_const)
+                dtype.set_const(is_const).set_volatile(is_volatile)
             }
         };
 
@@ -1243,7 +1831,11 @@ impl Dtype {
     ) -> Result<Self, DtypeError> {
         let dtype = match self {
             Self::Unit { .. } | Self::Int { .. } | Self::Float { .. } => self,
-            Self::Pointer { inner, is_const } => {
+            Self::Pointer {
+                inner,
+                is_const,
+                is_volatile,
+            } => {
                 // Pointer types can have an undeclared struct type as inner.
                 //
                 // For example, consider `struct A { struct B *p }`, even if `struct B` has not
@@ -1252,12 +1844,16 @@ impl Dtype {
                     if fields.is_none() {
                         let name = name.as_ref().expect("`name` must be `Some`");
                         let _ = structs.entry(name.to_string()).or_insert(None);
-                        return Ok(Self::pointer(*inner).set_const(is_const));
+                        return Ok(Self::pointer(*inner)
+                            .set_const(is_const)
+                            .set_volatile(is_volatile));
                     }
                 }
 
                 let resolved_inner = inner.resolve_structs(structs, tempid_counter)?;
-                Self::pointer(resolved_inner).set_const(is_const)
+                Self::pointer(resolved_inner)
+                    .set_const(is_const)
+                    .set_volatile(is_volatile)
             }
             Self::Array { inner, size } => {
                 let inner = inner.resolve_structs(structs, tempid_counter)?;
@@ -1270,17 +1866,21 @@ impl Dtype {
                 name,
                 fields,
                 is_const,
+                is_volatile,
+                is_union,
                 ..
             } => {
                 let (name, fields) = if let Some(fields) = fields {
+                    // A field may point to the struct being defined, of which the layout is not
+                    // needed yet, but may not be of its type as it is incomplete until here.
                     let fields = fields
                         .into_iter()
                         .map(|f| {
                             let (d, name) = f.destruct();
-                            let d = d.resolve_structs(structs, tempid_counter).unwrap();
-                            Named::new(name, d)
+                            let d = d.resolve_structs(structs, tempid_counter)?;
+                            Ok(Named::new(name, d))
                         })
-                        .collect::<Vec<_>>();
+                        .collect::<Result<Vec<_>, DtypeError>>()?;
 
                     let name = if let Some(name) = name {
                         name
@@ -1289,7 +1889,8 @@ impl Dtype {
                         *tempid_counter += 1;
                         format!("%t{tempid}")
                     };
-                    let resolved_struct = Self::structure(Some(name.clone()), Some(fields));
+                    let resolved_struct =
+                        Self::structure_or_union(is_union, Some(name.clone()), Some(fields));
                     let filled_struct =
                         resolved_struct.fill_size_align_offsets_of_struct(structs)?;
 
@@ -1307,16 +1908,21 @@ impl Dtype {
                     let struct_type = structs.get(&name).ok_or_else(|| DtypeError::Misc {
                         message: format!("unknown struct name `{name}`"),
                     })?;
-                    if struct_type.is_none() {
-                        return Err(DtypeError::Misc {
-                            message: format!("variable has incomplete type 'struct {name}'"),
-                        });
+                    let kind = if is_union { "union" } else { "struct" };
+                    let struct_type = struct_type.as_ref().ok_or_else(|| DtypeError::Misc {
+                        message: format!("variable has incomplete type '{kind} {name}'"),
+                    })?;
+                    if struct_type.is_union() != is_union {
+                        return Err(Self::tag_mismatch(&name));
                     }
 
                     (name, fields)
                 };
-                Self::structure(Some(name), fields).set_const(is_const)
+                Self::structure_or_union(is_union, Some(name), fields)
+                    .set_const(is_const)
+                    .set_volatile(is_volatile)
             }
+            Self::BitField { .. } => self,
             Self::Function { ret, params } => {
                 let ret = ret.resolve_structs(structs, tempid_counter)?;
                 let params = params
@@ -1336,29 +1942,48 @@ impl Dtype {
 impl fmt::Display for Dtype {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Self::Unit { is_const } => write!(f, "{}unit", if *is_const { "const " } else { "" }),
+            Self::Unit {
+                is_const,
+                is_volatile,
+            } => write!(f, "{}unit", qualifiers(*is_const, *is_volatile)),
             Self::Int {
                 width,
                 is_signed,
                 is_const,
+                is_volatile,
             } => write!(
                 f,
                 "{}{}{}",
-                if *is_const { "const " } else { "" },
+                qualifiers(*is_const, *is_volatile),
                 if *is_signed { "i" } else { "u" },
                 width
             ),
-            Self::Float { width, is_const } => {
-                write!(f, "{}f{}", if *is_const { "const " } else { "" }, width)
+            Self::Float {
+                width,
+                is_const,
+                is_volatile,
+            } => {
+                write!(f, "{}f{}", qualifiers(*is_const, *is_volatile), width)
             }
-            Self::Pointer { inner, is_const } => {
-                write!(f, "{}*{}", inner, if *is_const { "const" } else { "" })
+            Self::Pointer {
+                inner,
+                is_const,
+                is_volatile,
+            } => {
+                write!(
+                    f,
+                    "{}*{}",
+                    inner,
+                    qualifiers(*is_const, *is_volatile).trim_end()
+                )
             }
             Self::Array { inner, size, .. } => write!(f, "[{size} x {inner}]",),
             Self::Struct {
                 name,
                 fields,
                 is_const,
+                is_volatile,
+                is_union,
                 ..
             } => {
                 let fields = if let Some(fields) = fields {
@@ -1379,22 +2004,39 @@ impl fmt::Display for Dtype {
                 };
                 write!(
                     f,
-                    "{}struct {}{}",
-                    if *is_const { "const " } else { "" },
+                    "{}{} {}{}",
+                    qualifiers(*is_const, *is_volatile),
+                    if *is_union { "union" } else { "struct" },
                     if let Some(name) = name { name } else { "%anon" },
                     fields
                 )
             }
+            Self::BitField { inner, width, .. } => write!(f, "{inner}:{width}"),
             Self::Function { ret, params } => {
                 write!(f, "[ret:{} params:({})]", ret, params.iter().format(", "))
             }
-            Self::Typedef { name, is_const } => {
-                write!(f, "{}{}", if *is_const { "const " } else { "" }, name)
+            Self::Typedef {
+                name,
+                is_const,
+                is_volatile,
+            } => {
+                write!(f, "{}{}", qualifiers(*is_const, *is_volatile), name)
             }
         }
     }
 }
 
+/// The qualifiers of a dtype as they are written before it, each followed by a space.
+#[inline]
+fn qualifiers(is_const: bool, is_volatile: bool) -> &'static str {
+    match (is_const, is_volatile) {
+        (false, false) => "",
+        (true, false) => "const ",
+        (false, true) => "volatile ",
+        (true, true) => "const volatile ",
+    }
+}
+
 impl Default for Dtype {
     fn default() -> Self {
         // default dtype is `int`(i32)
@@ -1409,7 +2051,7 @@ fn check_no_duplicate_field(fields: &[Named<Dtype>], field_names: &mut HashSet<S
             if !field_names.insert(name.clone()) {
                 return false;
             }
-        } else {
+        } else if field.get_bit_field().is_none() {
             let field_dtype = field.deref();
             let fields = field_dtype
                 .get_struct_fields()
@@ -5,6 +5,18 @@ use itertools::izip;
 use crate::ir::*;
 use crate::utils::IsEquiv;
 
+/// Whether `lhs` and `rhs` are the same modulo the ids of blocks.
+///
+/// Blocks are matched up by traversing each function from its entry block in breadth-first
+/// order, so that two functions are equivalent if they differ only in how their blocks are
+/// numbered, even their entry blocks. Unreachable blocks are matched up in the order of their ids
+/// instead. Since the registers of phinodes and instructions are named after their blocks, they
+/// are renamed along. Everything else, including the order of the instructions within each
+/// block, must be the same.
+pub fn equiv(lhs: &TranslationUnit, rhs: &TranslationUnit) -> bool {
+    lhs.is_equiv(rhs)
+}
+
 impl IsEquiv for TranslationUnit {
     fn is_equiv(&self, other: &Self) -> bool {
         if self.decls.len() != other.decls.len() {
@@ -33,13 +45,23 @@ impl IsEquiv for Declaration {
     fn is_equiv(&self, other: &Self) -> bool {
         match (self, other) {
             (
-                Self::Variable { dtype, initializer },
+                Self::Variable {
+                    dtype,
+                    initializer,
+                    linkage,
+                    is_definition,
+                },
                 Self::Variable {
                     dtype: dtype_other,
                     initializer: initializer_other,
+                    linkage: linkage_other,
+                    is_definition: is_definition_other,
                 },
             ) => {
-                if dtype != dtype_other {
+                if dtype != dtype_other
+                    || linkage != linkage_other
+                    || is_definition != is_definition_other
+                {
                     return false;
                 }
 
@@ -49,13 +71,15 @@ impl IsEquiv for Declaration {
                 Self::Function {
                     signature,
                     definition,
+                    linkage,
                 },
                 Self::Function {
                     signature: signature_other,
                     definition: definition_other,
+                    linkage: linkage_other,
                 },
             ) => {
-                if signature != signature_other {
+                if signature != signature_other || linkage != linkage_other {
                     return false;
                 }
 
@@ -135,18 +159,21 @@ fn is_equiv_instruction(
                 lhs,
                 rhs,
                 dtype,
+                flags,
             },
             Instruction::BinOp {
                 op: op_other,
                 lhs: lhs_other,
                 rhs: rhs_other,
                 dtype: dtype_other,
+                flags: flags_other,
             },
         ) => {
             op == op_other
                 && is_equiv_operand(lhs, lhs_other, map)
                 && is_equiv_operand(rhs, rhs_other, map)
                 && dtype == dtype_other
+                && flags == flags_other
         }
         (
             Instruction::UnaryOp { op, operand, dtype },
@@ -159,15 +186,28 @@ fn is_equiv_instruction(
             op == op_other && is_equiv_operand(operand, operand_other, map) && dtype == dtype_other
         }
         (
-            Instruction::Store { ptr, value },
+            Instruction::Store {
+                ptr,
+                value,
+                volatile,
+            },
             Instruction::Store {
                 ptr: ptr_other,
                 value: value_other,
+                volatile: volatile_other,
             },
-        ) => is_equiv_operand(ptr, ptr_other, map) && is_equiv_operand(value, value_other, map),
-        (Instruction::Load { ptr }, Instruction::Load { ptr: ptr_other }) => {
+        ) => {
             is_equiv_operand(ptr, ptr_other, map)
+                && is_equiv_operand(value, value_other, map)
+                && volatile == volatile_other
         }
+        (
+            Instruction::Load { ptr, volatile },
+            Instruction::Load {
+                ptr: ptr_other,
+                volatile: volatile_other,
+            },
+        ) => is_equiv_operand(ptr, ptr_other, map) && volatile == volatile_other,
         (
             Instruction::Call {
                 callee,
@@ -207,6 +247,25 @@ fn is_equiv_instruction(
                 && is_equiv_operand(offset, offset_other, map)
                 && dtype == dtype_other
         }
+        (
+            Instruction::Select {
+                condition,
+                then_value,
+                else_value,
+                dtype,
+            },
+            Instruction::Select {
+                condition: condition_other,
+                then_value: then_value_other,
+                else_value: else_value_other,
+                dtype: dtype_other,
+            },
+        ) => {
+            is_equiv_operand(condition, condition_other, map)
+                && is_equiv_operand(then_value, then_value_other, map)
+                && is_equiv_operand(else_value, else_value_other, map)
+                && dtype == dtype_other
+        }
         _ => false,
     }
 }
@@ -337,10 +396,6 @@ impl IsEquiv for FunctionDefinition {
             return false;
         }
 
-        if self.bid_init != other.bid_init {
-            return false;
-        }
-
         let preorder = traverse_preorder(&self.blocks, self.bid_init);
         let preorder_other = traverse_preorder(&other.blocks, other.bid_init);
         assert_eq!(preorder.len(), preorder_other.len());
@@ -350,9 +405,8 @@ impl IsEquiv for FunctionDefinition {
             let _ = map.insert(*f, *t);
         }
 
-        if map.get(&self.bid_init) != Some(&other.bid_init) {
-            return false;
-        }
+        // The traversals start from the entry blocks, so they are always matched up.
+        assert_eq!(map.get(&self.bid_init), Some(&other.bid_init));
 
         for (f, t) in &map {
             let lhs = self.blocks.get(f).unwrap();
@@ -2,7 +2,10 @@ use core::fmt;
 use core::iter;
 use core::mem;
 use ordered_float::OrderedFloat;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
 use thiserror::Error;
 
 use itertools::izip;
@@ -44,6 +47,11 @@ pub enum Value {
         name: String,
         fields: Vec<Named<Value>>,
     },
+    /// A union is kept as the bytes of its memory, since the member stored to it last is unknown.
+    Union {
+        name: String,
+        bytes: Vec<Byte>,
+    },
 }
 
 impl TryFrom<Constant> for Value {
@@ -62,7 +70,7 @@ impl TryFrom<Constant> for Value {
                 width,
                 is_signed,
             },
-            Constant::Float { value, width } => Self::Float { value, width },
+            Constant::Float { value, width } => Self::float(value.into_inner(), width),
             _ => panic!(),
         };
 
@@ -91,6 +99,7 @@ impl HasDtype for Value {
                     .collect();
                 Dtype::structure(Some(name.clone()), Some(fields))
             }
+            Self::Union { name, .. } => Dtype::union(Some(name.clone()), None),
         }
     }
 }
@@ -115,8 +124,20 @@ impl Value {
         }
     }
 
+    /// Creates a float value, rounding `value` to single precision if `width` is that of `float`.
+    ///
+    /// Since every operation of the calculator goes through here, single-precision arithmetic is
+    /// rounded after each operation. This gives the same results as computing in `f32` directly,
+    /// because `f64` is precise enough for `+`, `-`, `*` and `/` of `f32` operands not to suffer
+    /// from double rounding.
     #[inline]
     fn float(value: f64, width: usize) -> Self {
+        let value = if width == Dtype::SIZE_OF_FLOAT * Dtype::BITS_OF_BYTE {
+            value as f32 as f64
+        } else {
+            value
+        };
+
         Self::Float {
             value: value.into(),
             width,
@@ -185,24 +206,26 @@ impl Value {
             } => Self::int(u128::default(), *width, *is_signed),
             Dtype::Float { width, .. } => Self::float(f64::default(), *width),
             Dtype::Pointer { inner, .. } => Self::nullptr(inner.deref().clone()),
+            Dtype::BitField { inner, .. } => Self::default_from_dtype(inner, structs)?,
             Dtype::Array { inner, size } => {
                 let values = iter::repeat(Self::default_from_dtype(inner, structs))
                     .take(*size)
                     .collect::<Result<Vec<_>, _>>()?;
                 Self::array(inner.deref().clone(), values)
             }
+            Dtype::Struct { name, .. } if dtype.is_union() => {
+                let name = name.as_ref().expect("struct should have its name");
+                let size = dtype.size_align_of(structs).map_err(|_| ())?.0;
+                Self::Union {
+                    name: name.clone(),
+                    bytes: vec![Byte::concrete(0); size],
+                }
+            }
             Dtype::Struct { name, .. } => {
                 let name = name.as_ref().expect("struct should have its name");
-                let struct_type = structs
-                    .get(name)
-                    .expect("struct type matched with `name` must exist")
-                    .as_ref()
-                    .expect("`struct_type` must have its definition");
-                let fields = struct_type
-                    .get_struct_fields()
-                    .expect("`struct_type` must be struct type")
-                    .as_ref()
-                    .expect("`fields` must be `Some`");
+                let fields = dtype
+                    .resolve_struct_fields(structs)
+                    .expect("struct type must be defined");
 
                 let fields = fields
                     .iter()
@@ -221,20 +244,36 @@ impl Value {
         Ok(value)
     }
 
+    /// The value of `initializer` for an object of `dtype`. The address of a global name in the
+    /// initializer of a pointer points into the memory block `global_bid` maps the name to.
     #[allow(clippy::result_unit_err)]
     pub fn try_from_initializer(
         initializer: &ast::Initializer,
         dtype: &Dtype,
         structs: &HashMap<String, Option<Dtype>>,
+        global_bid: &dyn Fn(&str) -> Option<usize>,
     ) -> Result<Self, ()> {
         match initializer {
             ast::Initializer::Expression(expr) => match dtype {
+                Dtype::Pointer { inner, .. } if get_address_initializer(&expr.node).is_some() => {
+                    let (name, offset) =
+                        get_address_initializer(&expr.node).expect("checked by the guard");
+                    let bid = global_bid(name).ok_or(())?;
+                    Ok(Self::pointer(Some(bid), offset, inner.deref().clone()))
+                }
                 Dtype::Int { .. } | Dtype::Float { .. } | Dtype::Pointer { .. } => {
                     let constant = Constant::try_from(&expr.node)?;
                     let value = Self::try_from(constant)?;
 
                     calculator::calculate_typecast(value, dtype.clone())
                 }
+                Dtype::BitField { inner, width, .. } => {
+                    let value =
+                        Self::try_from_initializer(initializer, inner, structs, global_bid)?;
+                    let (value, bits, is_signed) = value.get_int().ok_or(())?;
+                    let value = Byte::bits_to_u128(value, 0, *width, is_signed);
+                    Ok(Self::int(value, bits, is_signed))
+                }
                 _ => Err(()),
             },
             ast::Initializer::List(items) => match dtype {
@@ -248,6 +287,7 @@ impl Value {
                                     &items[i].node.initializer.node,
                                     &inner_dtype,
                                     structs,
+                                    global_bid,
                                 )
                             } else {
                                 Self::default_from_dtype(&inner_dtype, structs)
@@ -257,28 +297,59 @@ impl Value {
 
                     Ok(Self::array(inner_dtype, values))
                 }
+                // The first member is initialized, and the rest of the bytes are zero.
+                Dtype::Struct { name, .. } if dtype.is_union() => {
+                    let name = name.as_ref().expect("struct should have its name");
+                    let size = dtype.size_align_of(structs).map_err(|_| ())?.0;
+                    let mut bytes = match (
+                        items.first(),
+                        dtype
+                            .resolve_struct_fields(structs)
+                            .map_err(|_| ())?
+                            .first(),
+                    ) {
+                        (Some(item), Some(field)) => {
+                            let value = Self::try_from_initializer(
+                                &item.node.initializer.node,
+                                field.deref(),
+                                structs,
+                                global_bid,
+                            )?;
+                            Byte::value_to_bytes(&value, structs)
+                        }
+                        _ => Vec::new(),
+                    };
+                    bytes.resize(size, Byte::concrete(0));
+
+                    Ok(Self::Union {
+                        name: name.clone(),
+                        bytes,
+                    })
+                }
                 Dtype::Struct { name, .. } => {
                     let name = name.as_ref().expect("struct should have its name");
-                    let struct_type = structs
-                        .get(name)
-                        .expect("struct type matched with `name` must exist")
-                        .as_ref()
-                        .expect("`struct_type` must have its definition");
-                    let fields = struct_type
-                        .get_struct_fields()
-                        .expect("`struct_type` must be struct type")
-                        .as_ref()
-                        .expect("`fields` must be `Some`");
+                    let fields = dtype
+                        .resolve_struct_fields(structs)
+                        .expect("struct type must be defined");
 
+                    // Unnamed bit-fields are not initialized by the items.
+                    let mut items = items.iter();
                     let fields = fields
                         .iter()
-                        .enumerate()
-                        .map(|(i, f)| {
-                            let value = if let Some(item) = items.get(i) {
+                        .map(|f| {
+                            let is_unnamed_bit_field =
+                                f.name().is_none() && f.get_bit_field().is_some();
+                            let item = if is_unnamed_bit_field {
+                                None
+                            } else {
+                                items.next()
+                            };
+                            let value = if let Some(item) = item {
                                 Self::try_from_initializer(
                                     &item.node.initializer.node,
                                     f.deref(),
                                     structs,
+                                    global_bid,
                                 )?
                             } else {
                                 Self::default_from_dtype(f.deref(), structs)?
@@ -304,8 +375,36 @@ pub enum InterpreterError {
     NoMainFunction,
     #[error("ir has no function definition of {} function", func_name)]
     NoFunctionDefinition { func_name: String },
+    #[error("ir has no variable definition of {var_name} variable")]
+    NoVariableDefinition { var_name: String },
     #[error("ir has no structure definition of {struct_name} structure")]
     NoStructureDefinition { struct_name: String },
+    #[error("ir has more than one global named {name}")]
+    DuplicateGlobal { name: String },
+    #[error("global {name} is mapped to memory block {bid} of global {other}")]
+    DuplicateGlobalBlock {
+        name: String,
+        bid: usize,
+        other: String,
+    },
+    #[error("allocating {requested} bytes exceeds the memory limit of {limit} bytes")]
+    OutOfMemory { requested: usize, limit: usize },
+    #[error("{pc} / aborted: {msg}")]
+    Aborted { msg: String, pc: Pc },
+    #[error("replay: {msg}")]
+    Replay { msg: String },
+    #[error("{func_name}:{pc} / use of the undefined value of `{operand}`")]
+    UndefinedValue {
+        func_name: String,
+        pc: Pc,
+        operand: Operand,
+    },
+    #[error("{func_name}:{pc} / the `{flag}` flag of the instruction does not hold")]
+    FlagViolation {
+        func_name: String,
+        pc: Pc,
+        flag: String,
+    },
     #[error("{func_name}:{pc} / {msg}")]
     Misc {
         func_name: String,
@@ -318,21 +417,32 @@ pub enum InterpreterError {
 pub struct Pc {
     pub bid: BlockId,
     pub iid: usize,
+    /// Where the instruction at `bid:iid` is in the C source, if known.
+    pub loc: Option<SourceLoc>,
 }
 
 impl fmt::Display for Pc {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}:{}", self.bid, self.iid)
+        write!(f, "{}:{}", self.bid, self.iid)?;
+        if let Some(loc) = self.loc {
+            write!(f, " (line {loc})")?;
+        }
+        Ok(())
     }
 }
 
 impl Pc {
     fn new(bid: BlockId) -> Pc {
-        Pc { bid, iid: 0 }
+        Pc {
+            bid,
+            iid: 0,
+            loc: None,
+        }
     }
 
     fn increment(&mut self) {
         self.iid += 1;
+        self.loc = None;
     }
 }
 
@@ -348,7 +458,15 @@ impl RegisterMap {
             .expect("`rid` must be assigned before it can be used")
     }
 
+    /// Assigns `value` to `rid`.
+    ///
+    /// Unit values carry no information, so they are not stored: results of `store`, `nop` and
+    /// calls to void functions leave the map untouched, and reading a unit-typed register does
+    /// not consult the map.
     fn write(&mut self, rid: RegisterId, value: Value) {
+        if value == Value::Unit {
+            return;
+        }
         let _unused = self.inner.insert(rid, value);
     }
 }
@@ -370,14 +488,22 @@ struct GlobalMap {
 
 impl GlobalMap {
     /// Create a bi-directional mapping between `var` and `bid`.
+    ///
+    /// Fails if `var` or `bid` is already mapped, leaving the map unchanged.
     fn insert(&mut self, var: String, bid: usize) -> Result<(), InterpreterError> {
-        if self.var_to_bid.insert(var.clone(), bid).is_some() {
-            panic!("variable name should be unique in IR")
+        if self.var_to_bid.contains_key(&var) {
+            return Err(InterpreterError::DuplicateGlobal { name: var });
         }
-        if self.bid_to_var.insert(bid, var).is_some() {
-            panic!("`bid` is connected to only one `var`")
+        if let Some(other) = self.bid_to_var.get(&bid) {
+            return Err(InterpreterError::DuplicateGlobalBlock {
+                name: var,
+                bid,
+                other: other.clone(),
+            });
         }
 
+        let _unused = self.var_to_bid.insert(var.clone(), bid);
+        let _unused = self.bid_to_var.insert(bid, var);
         Ok(())
     }
 
@@ -422,21 +548,25 @@ mod calculator {
         width: usize,
         is_signed: bool,
     ) -> Result<Value, ()> {
+        // The operations wrap around at 128 bits, and the result is truncated to `width` bits
+        // below. Since signed values are sign-extended to 128 bits, the lower `width` bits of the
+        // result are the same as if the operation were done in `width` bits.
         let result = match op {
-            // TODO: explain why plus & minus do not need to consider `is_signed'
-            ast::BinaryOperator::Plus => (lhs as i128 + rhs as i128) as u128,
-            ast::BinaryOperator::Minus => (lhs as i128 - rhs as i128) as u128,
+            // Plus and minus do not need to consider `is_signed` because they are the same
+            // operations in two's complement.
+            ast::BinaryOperator::Plus => lhs.wrapping_add(rhs),
+            ast::BinaryOperator::Minus => lhs.wrapping_sub(rhs),
             ast::BinaryOperator::Multiply => {
                 if is_signed {
-                    (lhs as i128 * rhs as i128) as u128
+                    (lhs as i128).wrapping_mul(rhs as i128) as u128
                 } else {
-                    lhs * rhs
+                    lhs.wrapping_mul(rhs)
                 }
             }
             ast::BinaryOperator::Divide => {
                 assert!(rhs != 0);
                 if is_signed {
-                    (lhs as i128 / rhs as i128) as u128
+                    (lhs as i128).wrapping_div(rhs as i128) as u128
                 } else {
                     lhs / rhs
                 }
@@ -444,7 +574,7 @@ mod calculator {
             ast::BinaryOperator::Modulo => {
                 assert!(rhs != 0);
                 if is_signed {
-                    (lhs as i128 % rhs as i128) as u128
+                    (lhs as i128).wrapping_rem(rhs as i128) as u128
                 } else {
                     lhs % rhs
                 }
@@ -472,8 +602,7 @@ mod calculator {
                 } else {
                     // logical shift right
                     assert!(rhs < (width as u128));
-                    let bit_mask = (1u128 << width as u128) - 1;
-                    let lhs = lhs & bit_mask;
+                    let lhs = trim_unnecessary_bits(lhs, width as u128);
                     lhs >> rhs
                 }
             }
@@ -660,6 +789,23 @@ mod calculator {
                     let result = (!(bid == other_bid && offset == other_offset)).into();
                     Ok(Value::int(result, 1, false))
                 }
+                // Relational comparison is only defined between pointers into the same block.
+                ast::BinaryOperator::Less
+                | ast::BinaryOperator::Greater
+                | ast::BinaryOperator::LessOrEqual
+                | ast::BinaryOperator::GreaterOrEqual => {
+                    if bid != other_bid {
+                        return Err(());
+                    }
+
+                    let result = match op {
+                        ast::BinaryOperator::Less => offset < other_offset,
+                        ast::BinaryOperator::Greater => offset > other_offset,
+                        ast::BinaryOperator::LessOrEqual => offset <= other_offset,
+                        _ => offset >= other_offset,
+                    };
+                    Ok(Value::int(result.into(), 1, false))
+                }
                 _ => todo!(
                     "calculate_binary_operator_expression: not supported case for \
                      {:?} between pointer and integer value",
@@ -675,6 +821,87 @@ mod calculator {
         }
     }
 
+    /// The flag among `flags` that does not hold for `lhs op rhs`, if any. Undefined operands and
+    /// operations that fail by themselves, e.g., dividing by zero, violate no flag.
+    pub fn violated_flag(
+        op: &ast::BinaryOperator,
+        flags: &BinOpFlags,
+        lhs: &Value,
+        rhs: &Value,
+    ) -> Option<&'static str> {
+        let (lhs, width, is_signed) = lhs.get_int()?;
+        let (rhs, _, _) = rhs.get_int()?;
+
+        let mask = if width >= 128 {
+            u128::MAX
+        } else {
+            (1 << width) - 1
+        };
+        let unsigned = |value: u128| value & mask;
+        let signed = |value: u128| ((value << (128 - width)) as i128) >> (128 - width);
+        let fits_unsigned = |value: Option<u128>| value.is_some_and(|value| value & !mask == 0);
+        let fits_signed =
+            |value: Option<i128>| value.is_some_and(|value| matches!(value >> (width - 1), 0 | -1));
+        let (ul, ur, sl, sr) = (unsigned(lhs), unsigned(rhs), signed(lhs), signed(rhs));
+
+        let (nuw, nsw, exact) = match op {
+            ast::BinaryOperator::Plus => (
+                fits_unsigned(ul.checked_add(ur)),
+                fits_signed(sl.checked_add(sr)),
+                true,
+            ),
+            ast::BinaryOperator::Minus => (
+                fits_unsigned(ul.checked_sub(ur)),
+                fits_signed(sl.checked_sub(sr)),
+                true,
+            ),
+            ast::BinaryOperator::Multiply => (
+                fits_unsigned(ul.checked_mul(ur)),
+                fits_signed(sl.checked_mul(sr)),
+                true,
+            ),
+            ast::BinaryOperator::ShiftLeft => {
+                if ur >= width as u128 {
+                    return None;
+                }
+                // The shift is reversible if and only if no meaningful bit is shifted out.
+                let result = lhs << ur;
+                (
+                    unsigned(result) >> ur == ul,
+                    signed(result) >> ur == sl,
+                    true,
+                )
+            }
+            ast::BinaryOperator::Divide => {
+                if ur == 0 {
+                    return None;
+                }
+                let remainder = if is_signed {
+                    sl.wrapping_rem(sr) != 0
+                } else {
+                    ul % ur != 0
+                };
+                (true, true, !remainder)
+            }
+            ast::BinaryOperator::ShiftRight => {
+                if ur >= width as u128 {
+                    return None;
+                }
+                (true, true, ul & ((1 << ur) - 1) == 0)
+            }
+            _ => return None,
+        };
+
+        [
+            (flags.nuw && !nuw, "nuw"),
+            (flags.nsw && !nsw, "nsw"),
+            (flags.exact && !exact, "exact"),
+        ]
+        .into_iter()
+        .find(|(violated, _)| *violated)
+        .map(|(_, flag)| flag)
+    }
+
     pub fn calculate_unary_operator_expression(
         op: &ast::UnaryOperator,
         operand: Value,
@@ -685,31 +912,36 @@ mod calculator {
                 value,
                 width,
                 is_signed,
-            } => {
-                match op {
-                    ast::UnaryOperator::Plus => Ok(Value::int(value, width, is_signed)),
-                    ast::UnaryOperator::Minus => {
-                        let result = if is_signed {
-                            (-(value as i128)) as u128
-                        } else {
-                            let value = (-(value as i128)) as u128;
-                            trim_unnecessary_bits(value, width as u128)
-                        };
-                        Ok(Value::int(result, width, is_signed))
-                    }
-                    ast::UnaryOperator::Negate => {
-                        // Check if it is boolean
-                        assert!(width == 1);
-                        let result = (value == 0).into();
-                        Ok(Value::int(result, width, is_signed))
-                    }
-                    _ => todo!(
-                        "calculate_unary_operator_expression: not supported case for {:?} {:?}",
-                        op,
-                        operand,
-                    ),
+            } => match op {
+                ast::UnaryOperator::Plus => Ok(Value::int(value, width, is_signed)),
+                ast::UnaryOperator::Minus => {
+                    let value = value.wrapping_neg();
+                    let result = if is_signed {
+                        sign_extension(value, width as u128)
+                    } else {
+                        trim_unnecessary_bits(value, width as u128)
+                    };
+                    Ok(Value::int(result, width, is_signed))
                 }
-            }
+                ast::UnaryOperator::Negate => {
+                    let result = (value == 0).into();
+                    Ok(Value::int(result, width, is_signed))
+                }
+                ast::UnaryOperator::Complement => {
+                    let value = !value;
+                    let result = if is_signed {
+                        sign_extension(value, width as u128)
+                    } else {
+                        trim_unnecessary_bits(value, width as u128)
+                    };
+                    Ok(Value::int(result, width, is_signed))
+                }
+                _ => todo!(
+                    "calculate_unary_operator_expression: not supported case for {:?} {:?}",
+                    op,
+                    operand,
+                ),
+            },
             Value::Float { value, width } => match op {
                 ast::UnaryOperator::Plus => Ok(Value::float(value.into_inner(), width)),
                 ast::UnaryOperator::Minus => Ok(Value::float(-value.into_inner(), width)),
@@ -734,6 +966,35 @@ mod calculator {
 
         match (value, dtype) {
             (Value::Undef { .. }, dtype) => Ok(Value::undef(dtype)),
+            // Casting to a boolean normalizes any nonzero value to 1.
+            (
+                Value::Int { value, .. },
+                Dtype::Int {
+                    width: 1,
+                    is_signed,
+                    ..
+                },
+            ) => Ok(Value::int((value != 0).into(), 1, is_signed)),
+            (
+                Value::Float { value, .. },
+                Dtype::Int {
+                    width: 1,
+                    is_signed,
+                    ..
+                },
+            ) => Ok(Value::int((value.into_inner() != 0.0).into(), 1, is_signed)),
+            (
+                Value::Pointer { bid, offset, .. },
+                Dtype::Int {
+                    width: 1,
+                    is_signed,
+                    ..
+                },
+            ) => Ok(Value::int(
+                (bid.is_some() || offset != 0).into(),
+                1,
+                is_signed,
+            )),
             (
                 Value::Int { value, width, .. },
                 Dtype::Int {
@@ -765,9 +1026,11 @@ mod calculator {
                 let size = (width - 1) / Dtype::BITS_OF_BYTE + 1;
                 let casted_value = match (is_signed, size) {
                     (true, Dtype::SIZE_OF_FLOAT) => value as i128 as f32 as f64,
-                    (true, Dtype::SIZE_OF_DOUBLE) => value as i128 as f64,
+                    (true, Dtype::SIZE_OF_DOUBLE | Dtype::SIZE_OF_LONG_DOUBLE) => {
+                        value as i128 as f64
+                    }
                     (false, Dtype::SIZE_OF_FLOAT) => value as f32 as f64,
-                    (false, Dtype::SIZE_OF_DOUBLE) => value as f64,
+                    (false, Dtype::SIZE_OF_DOUBLE | Dtype::SIZE_OF_LONG_DOUBLE) => value as f64,
                     _ => panic!(
                         "calculate_typecast: not supported case \
                             typecast int to float when `width` is {width}"
@@ -792,22 +1055,26 @@ mod calculator {
                 },
             ) => {
                 let casted_value = if is_signed {
-                    value.into_inner() as i128 as u128
+                    sign_extension(value.into_inner() as i128 as u128, width as u128)
                 } else {
-                    value.into_inner() as u128
+                    trim_unnecessary_bits(value.into_inner() as u128, width as u128)
                 };
                 Ok(Value::int(casted_value, width, is_signed))
             }
             (Value::Float { value, .. }, Dtype::Float { width, .. }) => {
                 Ok(Value::float(value.into_inner(), width))
             }
+            (Value::Pointer { bid, offset, .. }, Dtype::Pointer { inner, .. }) => {
+                Ok(Value::pointer(bid, offset, *inner))
+            }
             (value, dtype) => todo!("calculate_typecast ({:?}) {:?}", value, dtype),
         }
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone)]
-enum Byte {
+/// A byte of the memory of the interpreter.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Byte {
     Undef,
     Concrete(u8),
     Pointer {
@@ -820,6 +1087,10 @@ enum Byte {
 #[derive(Default, Debug, PartialEq)]
 struct Memory {
     inner: Vec<Option<Vec<Byte>>>,
+    /// Total size of the live blocks in bytes.
+    size: usize,
+    /// Upper bound of `size`, if any.
+    limit: Option<usize>,
 }
 
 impl Byte {
@@ -833,11 +1104,19 @@ impl Byte {
         Self::Pointer { bid, offset, index }
     }
 
+    /// The byte as a plain number.
+    ///
+    /// Pointers that do not point into a memory block (e.g. null) are plain addresses, so their
+    /// bytes are concrete as well.
     fn get_concrete(&self) -> Option<u8> {
-        if let Self::Concrete(byte) = self {
-            Some(*byte)
-        } else {
-            None
+        match self {
+            Self::Concrete(byte) => Some(*byte),
+            Self::Pointer {
+                bid: None,
+                offset,
+                index,
+            } => Some(Self::u128_to_bytes(*offset as u128, Dtype::SIZE_OF_POINTER)[*index]),
+            _ => None,
         }
     }
 
@@ -875,6 +1154,47 @@ impl Byte {
         u128::from_le_bytes(array)
     }
 
+    /// The `width` bits of `value` from its `offset`-th least significant bit, extended to `u128`
+    /// with their sign if `is_signed`.
+    fn bits_to_u128(value: u128, offset: usize, width: usize, is_signed: bool) -> u128 {
+        if width == 0 {
+            return 0;
+        }
+        let value = (value >> offset) & (u128::MAX >> (128 - width));
+        if is_signed && (value >> (width - 1)) & 1 == 1 {
+            value | (u128::MAX << width)
+        } else {
+            value
+        }
+    }
+
+    /// Writes the int `value` to the bit-field of `width` bits from the `offset`-th bit of the
+    /// storage unit `bytes`, keeping the other bits of it. Undefined bytes of the storage unit
+    /// become zero, and an undefined `value` leaves it as it is.
+    fn write_bit_field(bytes: &mut [Self], value: &Value, offset: usize, width: usize) {
+        let (value, _, _) = some_or!(value.get_int(), return);
+        let unit = bytes
+            .iter()
+            .map(|b| b.get_concrete().unwrap_or(0))
+            .collect::<Vec<_>>();
+        let unit = Self::bytes_to_u128(&unit, false);
+        let mask = Self::bits_to_u128(u128::MAX, 0, width, false) << offset;
+        let unit = (unit & !mask) | ((value << offset) & mask);
+        let unit = Self::u128_to_bytes(unit, bytes.len());
+        for (byte, concrete) in izip!(bytes.iter_mut(), unit) {
+            *byte = Self::concrete(concrete);
+        }
+    }
+
+    /// Reads a value of `dtype` from `bytes`, regardless of the type of the value the bytes are
+    /// stored from, as a program reading a union member does.
+    ///
+    /// Concrete bytes are read as on the little-endian target: the low byte of an int is its first
+    /// byte, and a float can be read as an int of the same size and vice versa. Concrete bytes
+    /// read as a pointer make a pointer to the address they represent, which is null if they are
+    /// all zero. Bytes of a pointer into a memory block have no concrete address, so only a
+    /// pointer reading all of them in order gets a value; other reads of them are undefined, as
+    /// are reads of undefined bytes.
     fn bytes_to_value<'b, I>(
         bytes: &mut I,
         dtype: &Dtype,
@@ -898,6 +1218,12 @@ impl Byte {
                     return Ok(Value::undef(dtype.clone()))
                 );
                 let value = Self::bytes_to_u128(&value, *is_signed);
+                // A boolean occupies a whole byte, and any nonzero byte reads as true.
+                let value = if *width == 1 {
+                    (value != 0).into()
+                } else {
+                    value
+                };
                 Ok(Value::int(value, *width, *is_signed))
             }
             Dtype::Float { width, .. } => {
@@ -911,6 +1237,7 @@ impl Byte {
                     return Ok(Value::undef(dtype.clone()))
                 );
                 let value = Self::bytes_to_u128(&value, false);
+                // `long double` keeps its `f64` value in the lower bytes.
                 let value = if size == Dtype::SIZE_OF_FLOAT {
                     f32::from_bits(value as u32) as f64
                 } else {
@@ -929,7 +1256,21 @@ impl Byte {
                         .iter()
                         .map(|b| b.get_pointer())
                         .collect::<Option<Vec<_>>>(),
-                    return Ok(Value::undef(dtype.clone()))
+                    {
+                        let address = some_or!(
+                            bytes
+                                .iter()
+                                .map(|b| b.get_concrete())
+                                .collect::<Option<Vec<_>>>(),
+                            return Ok(Value::undef(dtype.clone()))
+                        );
+                        let address = Self::bytes_to_u128(&address, false);
+                        return Ok(Value::pointer(
+                            None,
+                            address as isize,
+                            inner.deref().clone(),
+                        ));
+                    }
                 );
 
                 let (bid, offset, _) = value.first().expect("not empty");
@@ -940,12 +1281,29 @@ impl Byte {
                         .enumerate()
                         .all(|(idx, ptr)| *ptr == (*bid, *offset, idx))
                     {
-                        Value::undef(inner.deref().clone())
+                        Value::undef(dtype.clone())
                     } else {
                         Value::pointer(*bid, *offset, inner.deref().clone())
                     },
                 )
             }
+            Dtype::BitField {
+                inner,
+                width,
+                offset,
+            } => {
+                let (size, _) = inner.size_align_of(structs).unwrap();
+                let unit = Self::bytes_to_value(
+                    bytes,
+                    &Dtype::int(size * Dtype::BITS_OF_BYTE).set_signed(false),
+                    structs,
+                )?;
+                let (unit, _, _) =
+                    some_or!(unit.get_int(), return Ok(Value::undef(*inner.clone())));
+                let (bits, is_signed) = (inner.get_int_width().unwrap(), inner.is_int_signed());
+                let value = Self::bits_to_u128(unit, *offset, *width, is_signed);
+                Ok(Value::int(value, bits, is_signed))
+            }
             Dtype::Array { inner, size } => {
                 let (inner_size, inner_align) = inner.size_align_of(structs).unwrap();
                 let padding = std::cmp::max(inner_size, inner_align) - inner_size;
@@ -963,24 +1321,25 @@ impl Byte {
                     values,
                 })
             }
+            Dtype::Struct { name, .. } if dtype.is_union() => {
+                let name = name.as_ref().expect("struct should have its name");
+                let size = dtype.size_align_of(structs).unwrap().0;
+                let bytes = bytes.by_ref().take(size).cloned().collect::<Vec<_>>();
+
+                Ok(Value::Union {
+                    name: name.clone(),
+                    bytes,
+                })
+            }
             Dtype::Struct { name, .. } => {
                 let name = name.as_ref().expect("struct should have its name");
-                let struct_type = structs
-                    .get(name)
-                    .expect("struct type matched with `name` must exist")
-                    .as_ref()
-                    .expect("`struct_type` must have its definition");
-                let fields = struct_type
-                    .get_struct_fields()
-                    .expect("`struct_type` must be struct type")
-                    .as_ref()
-                    .expect("`fields` must be `Some`");
-                let (size, _, offsets) = struct_type
-                    .get_struct_size_align_offsets()
-                    .expect("`struct_type` must be struct type")
-                    .as_ref()
-                    .expect("`offsets` must be `Some`");
-                let bytes = bytes.by_ref().take(*size).cloned().collect::<Vec<_>>();
+                let fields = dtype
+                    .resolve_struct_fields(structs)
+                    .expect("struct type must be defined");
+                let (size, _, offsets) = dtype
+                    .resolve_struct_layout(structs)
+                    .expect("struct type must be defined");
+                let bytes = bytes.by_ref().take(size).cloned().collect::<Vec<_>>();
 
                 assert_eq!(fields.len(), offsets.len());
                 let fields = izip!(fields, offsets)
@@ -1020,7 +1379,9 @@ impl Byte {
                 let size = value.dtype().size_align_of(structs).unwrap().0;
                 let value_bits: u128 = match size {
                     Dtype::SIZE_OF_FLOAT => (float_value.into_inner() as f32).to_bits() as u128,
-                    Dtype::SIZE_OF_DOUBLE => (float_value.into_inner()).to_bits() as u128,
+                    Dtype::SIZE_OF_DOUBLE | Dtype::SIZE_OF_LONG_DOUBLE => {
+                        (float_value.into_inner()).to_bits() as u128
+                    }
                     _ => panic!("value_to_bytes: {size} is not a valid float size"),
                 };
 
@@ -1048,20 +1409,30 @@ impl Byte {
                     .collect()
             }
             Value::Struct { name, fields } => {
-                let struct_type = structs
-                    .get(name)
-                    .expect("struct type matched with `name` must exist")
-                    .as_ref()
-                    .expect("`struct_type` must have its definition");
+                let struct_type =
+                    Dtype::lookup_struct(name, structs).expect("struct type must be defined");
                 let (size_of, _, offsets) = struct_type
-                    .get_struct_size_align_offsets()
-                    .expect("`struct_type` must be struct type")
-                    .as_ref()
-                    .expect("`offsets` must be `Some`");
-                let mut values = iter::repeat(Byte::Undef).take(*size_of).collect::<Vec<_>>();
+                    .resolve_struct_layout(structs)
+                    .expect("struct type must be defined");
+                let dtypes = struct_type
+                    .resolve_struct_fields(structs)
+                    .expect("struct type must be defined");
+                let mut values = iter::repeat(Byte::Undef).take(size_of).collect::<Vec<_>>();
 
                 assert_eq!(fields.len(), offsets.len());
-                izip!(fields, offsets).for_each(|(f, o)| {
+                izip!(fields, dtypes, offsets).for_each(|(f, d, o)| {
+                    // Bit-fields may share their storage unit with others.
+                    if let Some((inner, width, offset)) = d.get_bit_field() {
+                        let size_of_unit = inner.size_align_of(structs).unwrap().0;
+                        Self::write_bit_field(
+                            &mut values[*o..(*o + size_of_unit)],
+                            f.deref(),
+                            offset,
+                            width,
+                        );
+                        return;
+                    }
+
                     let result = Self::value_to_bytes(f.deref(), structs);
                     let size_of_data = f.deref().dtype().size_align_of(structs).unwrap().0;
                     let _unused = values.splice(*o..(*o + size_of_data), result);
@@ -1069,6 +1440,7 @@ impl Byte {
 
                 values
             }
+            Value::Union { bytes, .. } => bytes.clone(),
         }
     }
 }
@@ -1079,10 +1451,36 @@ impl Memory {
         dtype: &Dtype,
         structs: &HashMap<String, Option<Dtype>>,
     ) -> Result<usize, InterpreterError> {
+        self.alloc_bytes(dtype.size_align_of(structs).unwrap().0)
+    }
+
+    /// Allocates an uninitialized block of `size` bytes.
+    fn alloc_bytes(&mut self, size: usize) -> Result<usize, InterpreterError> {
+        let bid = self.alloc_filled(size, Byte::Undef)?;
+        Ok(bid.expect("the host must have memory for the block"))
+    }
+
+    /// Allocates a block of `size` copies of `byte`, or returns `None` if the host has no memory
+    /// for it.
+    fn alloc_filled(&mut self, size: usize, byte: Byte) -> Result<Option<usize>, InterpreterError> {
+        if let Some(limit) = self.limit {
+            if self.size.saturating_add(size) > limit {
+                return Err(InterpreterError::OutOfMemory {
+                    requested: size,
+                    limit,
+                });
+            }
+        }
+        let mut block = Vec::new();
+        if block.try_reserve_exact(size).is_err() {
+            return Ok(None);
+        }
+        block.resize(size, byte);
+        self.size += size;
+
         let bid = self.inner.len();
-        self.inner
-            .push(Some(Byte::block_from_dtype(dtype, structs)));
-        Ok(bid)
+        self.inner.push(Some(block));
+        Ok(Some(bid))
     }
 
     fn dealloc(
@@ -1098,10 +1496,16 @@ impl Memory {
             block.as_mut().unwrap().len(),
             dtype.size_align_of(structs).unwrap().0
         );
-        *block = None;
+        self.dealloc_bytes(bid);
         Ok(())
     }
 
+    /// Deallocates a block allocated by `alloc_bytes`.
+    fn dealloc_bytes(&mut self, bid: usize) {
+        let block = self.inner[bid].take().expect("`bid` must be allocated");
+        self.size -= block.len();
+    }
+
     fn load(
         &self,
         bid: usize,
@@ -1142,7 +1546,329 @@ impl Memory {
     }
 }
 
-#[derive(Debug, PartialEq)]
+/// Nondeterministic inputs observed by a program, in the order it observed them.
+///
+/// Each entry is written as a line `<source> <value>`, where `source` is the function that
+/// produced the value and `value` is an integer (`i32 -1`), a float given by its bits
+/// (`f64 0x3ff0000000000000`), or `unit`. A replayed execution must observe exactly the logged
+/// inputs, in order.
+#[derive(Default, Debug, PartialEq, Clone)]
+struct ReplayLog {
+    entries: VecDeque<(String, Value)>,
+}
+
+impl ReplayLog {
+    fn parse(text: &str) -> Result<Self, String> {
+        let entries = text
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                let mut tokens = line.split_whitespace();
+                let source = tokens.next().expect("`line` is not empty").to_string();
+                let value = match (tokens.next(), tokens.next()) {
+                    (Some("unit"), None) => Value::unit(),
+                    (Some(dtype), Some(value)) => {
+                        let (kind, width) = dtype.split_at(1);
+                        let width = width.parse::<usize>().map_err(|e| e.to_string())?;
+                        match kind {
+                            "i" => {
+                                let value = value.parse::<i128>().map_err(|e| e.to_string())?;
+                                Value::int(
+                                    sign_extension(value as u128, width as u128),
+                                    width,
+                                    true,
+                                )
+                            }
+                            "u" => Value::int(
+                                value.parse::<u128>().map_err(|e| e.to_string())?,
+                                width,
+                                false,
+                            ),
+                            "f" => {
+                                let bits = value.trim_start_matches("0x");
+                                let bits =
+                                    u64::from_str_radix(bits, 16).map_err(|e| e.to_string())?;
+                                Value::float(f64::from_bits(bits), width)
+                            }
+                            _ => return Err(format!("unknown dtype `{dtype}`")),
+                        }
+                    }
+                    _ => return Err(format!("malformed entry `{line}`")),
+                };
+
+                Ok((source, value))
+            })
+            .collect::<Result<_, String>>()?;
+
+        Ok(Self { entries })
+    }
+
+    fn push(&mut self, source: &str, value: &Value) -> Result<(), String> {
+        match value {
+            Value::Unit | Value::Int { .. } | Value::Float { .. } => {
+                self.entries.push_back((source.to_string(), value.clone()));
+                Ok(())
+            }
+            _ => Err(format!("cannot record `{value:?}` returned by `{source}`")),
+        }
+    }
+
+    fn pop(&mut self, source: &str) -> Result<Value, String> {
+        let (expected, value) = self
+            .entries
+            .pop_front()
+            .ok_or_else(|| format!("log ended before a call to `{source}`"))?;
+        if expected != source {
+            return Err(format!(
+                "execution diverged: logged a call to `{expected}`, but `{source}` was called"
+            ));
+        }
+
+        Ok(value)
+    }
+
+    /// Checks that the execution made every call logged.
+    fn finish(&self) -> Result<(), String> {
+        match self.entries.front() {
+            Some((source, _)) => Err(format!(
+                "execution diverged: the program exited before the logged call to `{source}`"
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for ReplayLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (source, value) in &self.entries {
+            match value {
+                Value::Int {
+                    value,
+                    width,
+                    is_signed: true,
+                } => writeln!(
+                    f,
+                    "{source} i{width} {}",
+                    sign_extension(*value, *width as u128) as i128
+                )?,
+                Value::Int { value, width, .. } => writeln!(f, "{source} u{width} {value}")?,
+                Value::Float { value, width } => {
+                    writeln!(f, "{source} f{width} {:#x}", value.into_inner().to_bits())?
+                }
+                _ => writeln!(f, "{source} unit")?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Heap block that was still allocated when the program terminated.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Leak {
+    pub size: usize,
+    /// Function that called the allocator.
+    pub func_name: String,
+    /// Call site of the allocator.
+    pub pc: Pc,
+}
+
+impl fmt::Display for Leak {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} bytes allocated at {}:{} are never freed",
+            self.size, self.func_name, self.pc
+        )
+    }
+}
+
+/// Host implementation of a function that is declared but not defined in the IR.
+pub type ExternFn = Box<dyn FnMut(&[Value]) -> Value>;
+
+/// IR interpreter with host-side configuration.
+///
+/// [`interp`] runs a translation unit with the default configuration. Embedders that want to
+/// hook into the execution, e.g. by providing native implementations of external functions,
+/// configure an `Interpreter` and call [`Interpreter::run`] instead.
+#[derive(Default)]
+pub struct Interpreter {
+    externs: HashMap<String, ExternFn>,
+    seed: Option<u32>,
+    record: Option<PathBuf>,
+    replay: Option<PathBuf>,
+    leak_check: bool,
+    leaks: Vec<Leak>,
+    undef_check: bool,
+    flag_check: bool,
+    memory_limit: Option<usize>,
+    profiling: bool,
+    profile: Profile,
+}
+
+impl fmt::Debug for Interpreter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut externs = self.externs.keys().collect::<Vec<_>>();
+        externs.sort();
+        f.debug_struct("Interpreter")
+            .field("externs", &externs)
+            .field("seed", &self.seed)
+            .field("record", &self.record)
+            .field("replay", &self.replay)
+            .field("leak_check", &self.leak_check)
+            .field("leaks", &self.leaks)
+            .field("undef_check", &self.undef_check)
+            .field("flag_check", &self.flag_check)
+            .field("memory_limit", &self.memory_limit)
+            .field("profiling", &self.profiling)
+            .field("profile", &self.profile)
+            .finish()
+    }
+}
+
+impl Interpreter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `f` as the implementation of the function `name`.
+    ///
+    /// It is called whenever the interpreted program calls `name` and the IR only declares the
+    /// function. A definition in the IR always takes precedence over a registered function.
+    pub fn register_extern<F>(&mut self, name: &str, f: F) -> &mut Self
+    where
+        F: FnMut(&[Value]) -> Value + 'static,
+    {
+        let _unused = self.externs.insert(name.to_string(), Box::new(f));
+        self
+    }
+
+    /// Sets the initial seed of the `rand` builtin, as if the program had called `srand(seed)`
+    /// before `main`. Without it, `rand` behaves as if seeded with 1, as C requires.
+    pub fn seed(&mut self, seed: u32) -> &mut Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Records every nondeterministic input of the next runs into `path`: bytes read from stdin,
+    /// the results of `rand`, `clock` and `time`, and the results of external functions.
+    ///
+    /// The log is written even if the execution fails, so that the failure can be replayed.
+    pub fn record<P: AsRef<Path>>(&mut self, path: P) -> &mut Self {
+        self.record = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Feeds the nondeterministic inputs recorded by [`Interpreter::record`] from `path` back to
+    /// the program instead of reading them from the host, reproducing the recorded execution.
+    pub fn replay<P: AsRef<Path>>(&mut self, path: P) -> &mut Self {
+        self.replay = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Makes the next runs collect the heap blocks that are not freed when the program
+    /// terminates. They are available from [`Interpreter::leaks`] afterwards.
+    pub fn leak_check(&mut self, enable: bool) -> &mut Self {
+        self.leak_check = enable;
+        self
+    }
+
+    /// Heap blocks leaked by the last run, ordered by allocation. It is empty unless
+    /// [`Interpreter::leak_check`] is enabled.
+    pub fn leaks(&self) -> &[Leak] {
+        &self.leaks
+    }
+
+    /// Makes the next runs fail with [`InterpreterError::UndefinedValue`] as soon as an operation
+    /// computes with an undefined value, e.g., one loaded from uninitialized memory.
+    ///
+    /// Otherwise, the result of such an operation is undefined as well, and the runs only fail
+    /// when the control flow or a memory access depends on an undefined value. Undefined values
+    /// can always be stored, passed to blocks and functions, and returned.
+    pub fn undef_check(&mut self, enable: bool) -> &mut Self {
+        self.undef_check = enable;
+        self
+    }
+
+    /// Makes the next runs fail with [`InterpreterError::FlagViolation`] as soon as the promise of
+    /// a flag of a `BinOp` instruction does not hold, e.g., an `add nsw` overflows.
+    ///
+    /// Otherwise, such an instruction results in the wrapped-around or truncated value, as if it
+    /// had no flag.
+    pub fn flag_check(&mut self, enable: bool) -> &mut Self {
+        self.flag_check = enable;
+        self
+    }
+
+    /// Limits the total size of the memory blocks alive at the same time, including global and
+    /// local variables, to `bytes`. An allocation exceeding it fails with
+    /// [`InterpreterError::OutOfMemory`].
+    pub fn memory_limit(&mut self, bytes: usize) -> &mut Self {
+        self.memory_limit = Some(bytes);
+        self
+    }
+
+    /// Makes the next runs count how many times each block is entered and how many of its
+    /// instructions are executed. The counts are available from [`Interpreter::profile`].
+    pub fn profiling(&mut self, enable: bool) -> &mut Self {
+        self.profiling = enable;
+        self
+    }
+
+    /// Execution counts of the last run. It is empty unless [`Interpreter::profiling`] is enabled.
+    pub fn profile(&self) -> &Profile {
+        &self.profile
+    }
+
+    /// Runs the `main` function of `ir` with `args`.
+    pub fn run(
+        &mut self,
+        ir: &TranslationUnit,
+        args: Vec<Value>,
+    ) -> Result<Value, InterpreterError> {
+        let replay = self
+            .replay
+            .as_ref()
+            .map(|path| {
+                fs::read_to_string(path)
+                    .map_err(|e| e.to_string())
+                    .and_then(|text| ReplayLog::parse(&text))
+                    .map_err(|msg| InterpreterError::Replay {
+                        msg: format!("{}: {msg}", path.display()),
+                    })
+            })
+            .transpose()?;
+
+        self.leaks.clear();
+        self.profile = Profile::default();
+        let mut init_state = State::new(ir, self, args)?;
+        init_state.replay = replay;
+        let result = init_state.run().and_then(|value| {
+            if let Some(replay) = &init_state.replay {
+                replay
+                    .finish()
+                    .map_err(|msg| InterpreterError::Replay { msg })?;
+            }
+            Ok(value)
+        });
+        let record = mem::take(&mut init_state.record);
+        let heap = mem::take(&mut init_state.heap);
+
+        if self.leak_check && result.is_ok() {
+            self.leaks = heap.into_values().collect();
+        }
+
+        if let Some(path) = &self.record {
+            fs::write(path, record.to_string()).map_err(|e| InterpreterError::Replay {
+                msg: format!("{}: {e}", path.display()),
+            })?;
+        }
+
+        result
+    }
+}
+
+#[derive(Debug)]
 struct State<'i> {
     /// Maps each global variable to a pointer value.
     ///
@@ -1152,10 +1878,25 @@ struct State<'i> {
     pub stack: Vec<StackFrame<'i>>,
     pub memory: Memory,
     pub ir: &'i TranslationUnit,
+    pub interpreter: &'i mut Interpreter,
+    /// State of the pseudo-random number generator behind `rand` and `srand`.
+    pub rand_state: u32,
+    /// Virtual clock behind `clock` and `time`, advanced by one tick per executed step.
+    pub clock: u64,
+    /// Nondeterministic inputs observed so far, written out if [`Interpreter::record`] is set.
+    pub record: ReplayLog,
+    /// Recorded inputs to feed back to the program instead of asking the host.
+    pub replay: Option<ReplayLog>,
+    /// Blocks allocated by `malloc` and `calloc` and not freed yet, indexed by their `bid`.
+    pub heap: BTreeMap<usize, Leak>,
 }
 
 impl<'i> State<'i> {
-    fn new(ir: &'i TranslationUnit, args: Vec<Value>) -> Result<State<'_>, InterpreterError> {
+    fn new(
+        ir: &'i TranslationUnit,
+        interpreter: &'i mut Interpreter,
+        args: Vec<Value>,
+    ) -> Result<State<'i>, InterpreterError> {
         // Interpreter starts with the main function
         let func_name = String::from("main");
         let func = ir
@@ -1172,12 +1913,23 @@ impl<'i> State<'i> {
             })?;
 
         // Create State
+        let rand_state = interpreter.seed.unwrap_or(1);
+        let memory = Memory {
+            limit: interpreter.memory_limit,
+            ..Default::default()
+        };
         let mut state = State {
             global_map: GlobalMap::default(),
             stack_frame: StackFrame::new(func_def.bid_init, func_name, func_def),
             stack: Vec::new(),
-            memory: Default::default(),
+            memory,
             ir,
+            interpreter,
+            rand_state,
+            clock: 0,
+            record: ReplayLog::default(),
+            replay: None,
+            heap: BTreeMap::new(),
         };
 
         state.alloc_global_variables()?;
@@ -1190,43 +1942,56 @@ impl<'i> State<'i> {
     }
 
     fn alloc_global_variables(&mut self) -> Result<(), InterpreterError> {
-        for (name, decl) in &self.ir.decls {
+        // Globals are allocated in the order they are printed, i.e., the variables first, so that
+        // the printed IR runs with the same pointers.
+        let decls = self
+            .ir
+            .decls_in_order()
+            .sorted_by_key(|(_, decl)| decl.get_function().is_some());
+        for (name, decl) in decls {
+            // Variables defined elsewhere have no memory, and accessing them fails.
+            if let Declaration::Variable {
+                is_definition: false,
+                ..
+            } = decl
+            {
+                continue;
+            }
+
             // Memory allocation
             let bid = self.memory.alloc(&decl.dtype(), &self.ir.structs)?;
             self.global_map.insert(name.clone(), bid)?;
+        }
 
-            // Initialize allocated memory space
-            match decl {
-                Declaration::Variable { dtype, initializer } => {
-                    let value = if let Some(initializer) = initializer {
-                        Value::try_from_initializer(initializer, dtype, &self.ir.structs).map_err(
-                            |_| InterpreterError::Misc {
-                                func_name: self.stack_frame.func_name.clone(),
-                                pc: self.stack_frame.pc,
-                                msg: format!(
-                                    "fail to translate `Initializer` and `{dtype}` to `Value`"
-                                ),
-                            },
-                        )?
-                    } else {
-                        Value::default_from_dtype(dtype, &self.ir.structs)
-                            .expect("default value must be derived from `dtype`")
-                    };
+        // Initialize allocated memory space once every global has its memory, so that the
+        // initializers may take the addresses of the globals allocated after them.
+        for (name, decl) in self.ir.decls_in_order() {
+            let (dtype, initializer) = some_or!(decl.get_variable(), continue);
+            let bid = some_or!(self.global_map.get_bid(name), continue);
+            let value = if let Some(initializer) = initializer {
+                Value::try_from_initializer(initializer, dtype, &self.ir.structs, &|name| {
+                    self.global_map.get_bid(name)
+                })
+                .map_err(|_| InterpreterError::Misc {
+                    func_name: self.stack_frame.func_name.clone(),
+                    pc: self.stack_frame.pc,
+                    msg: format!("fail to translate `Initializer` and `{dtype}` to `Value`"),
+                })?
+            } else {
+                Value::default_from_dtype(dtype, &self.ir.structs)
+                    .expect("default value must be derived from `dtype`")
+            };
 
-                    self.memory
-                        .store(bid, 0, &value, &self.ir.structs)
-                        .map_err(|_| InterpreterError::Misc {
-                            func_name: self.stack_frame.func_name.clone(),
-                            pc: self.stack_frame.pc,
-                            msg: format!(
-                                "fail to store {:?} into memory with bid: {}, offset: {}",
-                                value, bid, 0,
-                            ),
-                        })?
-                }
-                // If functin declaration, skip initialization
-                Declaration::Function { .. } => (),
-            }
+            self.memory
+                .store(bid, 0, &value, &self.ir.structs)
+                .map_err(|_| InterpreterError::Misc {
+                    func_name: self.stack_frame.func_name.clone(),
+                    pc: self.stack_frame.pc,
+                    msg: format!(
+                        "fail to store {:?} into memory with bid: {}, offset: {}",
+                        value, bid, 0,
+                    ),
+                })?
         }
 
         Ok(())
@@ -1256,6 +2021,13 @@ impl<'i> State<'i> {
     }
 
     fn step(&mut self) -> Result<Option<Value>, InterpreterError> {
+        self.clock += 1;
+        if self.interpreter.profiling {
+            self.interpreter
+                .profile
+                .count(&self.stack_frame.func_name, self.stack_frame.pc);
+        }
+
         let block = self
             .stack_frame
             .func_def
@@ -1265,6 +2037,7 @@ impl<'i> State<'i> {
 
         // If it's time to execute an instruction, do so.
         if let Some(instr) = block.instructions.get(self.stack_frame.pc.iid) {
+            self.stack_frame.pc.loc = instr.loc();
             self.interp_instruction(instr)?;
             return Ok(None);
         }
@@ -1314,9 +2087,13 @@ impl<'i> State<'i> {
         // Check that the dtype of each args matches the expected
         if !(args.len() == signature.params.len()
             && izip!(args, &signature.params)
-                .all(|(a, d)| a.dtype().set_const(false) == d.clone().set_const(false)))
+                .all(|(a, d)| a.dtype().unqualified() == d.clone().unqualified()))
         {
-            panic!("dtype of args and params must be compatible")
+            return Err(InterpreterError::Misc {
+                func_name: self.stack_frame.func_name.clone(),
+                pc: self.stack_frame.pc,
+                msg: "dtype of args and params must be compatible".into(),
+            });
         }
 
         args.iter()
@@ -1334,7 +2111,7 @@ impl<'i> State<'i> {
 
         assert_eq!(arg.args.len(), block.phinodes.len());
         for (a, d) in izip!(&arg.args, &block.phinodes) {
-            assert!(a.dtype().set_const(false) == d.deref().clone().set_const(false));
+            assert!(a.dtype().unqualified() == d.deref().clone().unqualified());
         }
 
         arg.args
@@ -1364,19 +2141,18 @@ impl<'i> State<'i> {
                 arg_then,
                 arg_else,
             } => {
-                let value = self.interp_operand(condition)?;
-                let (value, width, _) = value.get_int().expect("`condition` must be `Value::Int`");
-                // Check if it is boolean
-                assert!(width == 1);
+                let value = self.interp_defined_operand(condition)?;
+                let (value, _, _) = value.get_int().expect("`condition` must be `Value::Int`");
 
-                self.interp_jump(if value == 1 { arg_then } else { arg_else })
+                // As in C, any nonzero condition is true.
+                self.interp_jump(if value != 0 { arg_then } else { arg_else })
             }
             BlockExit::Switch {
                 value,
                 default,
                 cases,
             } => {
-                let value = self.interp_operand(value)?;
+                let value = self.interp_defined_operand(value)?;
 
                 // TODO: consider different integer `width` in the future
                 let arg = cases
@@ -1394,9 +2170,45 @@ impl<'i> State<'i> {
     fn interp_instruction(&mut self, instruction: &Instruction) -> Result<(), InterpreterError> {
         let result = match instruction {
             Instruction::Nop => Value::unit(),
-            Instruction::BinOp { op, lhs, rhs, .. } => {
-                let lhs = self.interp_operand(lhs)?;
-                let rhs = self.interp_operand(rhs)?;
+            Instruction::BinOp {
+                op,
+                lhs,
+                rhs,
+                flags,
+                ..
+            } => {
+                let lhs = self.interp_computed_operand(lhs)?;
+                let rhs = self.interp_computed_operand(rhs)?;
+
+                if self.interpreter.flag_check {
+                    if let Some(flag) = calculator::violated_flag(op, flags, &lhs, &rhs) {
+                        return Err(InterpreterError::FlagViolation {
+                            func_name: self.stack_frame.func_name.clone(),
+                            pc: self.stack_frame.pc,
+                            flag: flag.to_string(),
+                        });
+                    }
+                }
+
+                if let (Value::Pointer { bid, .. }, Value::Pointer { bid: other_bid, .. }) =
+                    (&lhs, &rhs)
+                {
+                    if bid != other_bid
+                        && !matches!(
+                            op,
+                            ast::BinaryOperator::Equals | ast::BinaryOperator::NotEquals
+                        )
+                    {
+                        return Err(InterpreterError::Misc {
+                            func_name: self.stack_frame.func_name.clone(),
+                            pc: self.stack_frame.pc,
+                            msg: format!(
+                                "`{}` compares pointers into different memory blocks",
+                                op.write_operation()
+                            ),
+                        });
+                    }
+                }
 
                 calculator::calculate_binary_operator_expression(op, lhs, rhs).map_err(|_| {
                     InterpreterError::Misc {
@@ -1407,7 +2219,7 @@ impl<'i> State<'i> {
                 })?
             }
             Instruction::UnaryOp { op, operand, .. } => {
-                let operand = self.interp_operand(operand)?;
+                let operand = self.interp_computed_operand(operand)?;
 
                 calculator::calculate_unary_operator_expression(op, operand).map_err(|_| {
                     InterpreterError::Misc {
@@ -1418,7 +2230,7 @@ impl<'i> State<'i> {
                 })?
             }
             Instruction::Store { ptr, value, .. } => {
-                let ptr = self.interp_operand(ptr)?;
+                let ptr = self.interp_defined_operand(ptr)?;
                 let value = self.interp_operand(value)?;
                 let (bid, offset, _) = self.interp_ptr(&ptr)?;
                 self.memory
@@ -1433,12 +2245,12 @@ impl<'i> State<'i> {
                 Value::Unit
             }
             Instruction::Load { ptr, .. } => {
-                let ptr = self.interp_operand(ptr)?;
+                let ptr = self.interp_defined_operand(ptr)?;
                 let (bid, offset, dtype) = self.interp_ptr(&ptr)?;
                 self.memory.load(bid, offset, &dtype, &self.ir.structs)?
             }
             Instruction::Call { callee, args, .. } => {
-                let ptr = self.interp_operand(callee)?;
+                let ptr = self.interp_defined_operand(callee)?;
 
                 // Get function name from pointer
                 let (bid, _, _) = ptr.get_pointer().expect("`ptr` must be `Value::Pointer`");
@@ -1456,12 +2268,10 @@ impl<'i> State<'i> {
                 let (func_signature, func_def) = func
                     .get_function()
                     .expect("`func` must be function declaration");
-                let func_def =
-                    func_def
-                        .as_ref()
-                        .ok_or_else(|| InterpreterError::NoFunctionDefinition {
-                            func_name: callee_name.clone(),
-                        })?;
+                let func_def = some_or!(
+                    func_def.as_ref(),
+                    return self.interp_extern_call(&callee_name, func_signature, args)
+                );
 
                 let block_init = func_def
                     .blocks
@@ -1469,11 +2279,14 @@ impl<'i> State<'i> {
                     .expect("init block must exists");
 
                 if !(args.len() == block_init.phinodes.len()
-                    && izip!(args, &block_init.phinodes).all(|(a, d)| {
-                        a.dtype().set_const(false) == d.deref().clone().set_const(false)
-                    }))
+                    && izip!(args, &block_init.phinodes)
+                        .all(|(a, d)| a.dtype().unqualified() == d.deref().clone().unqualified()))
                 {
-                    panic!("dtype of args and phinodes of init block must be compatible");
+                    return Err(InterpreterError::Misc {
+                        func_name: self.stack_frame.func_name.clone(),
+                        pc: self.stack_frame.pc,
+                        msg: "dtype of args and phinodes of init block must be compatible".into(),
+                    });
                 }
 
                 let args = self.interp_args(func_signature, args)?;
@@ -1492,7 +2305,7 @@ impl<'i> State<'i> {
                 value,
                 target_dtype,
             } => {
-                let value = self.interp_operand(value)?;
+                let value = self.interp_computed_operand(value)?;
                 calculator::calculate_typecast(value, target_dtype.clone()).map_err(|_| {
                     InterpreterError::Misc {
                         func_name: self.stack_frame.func_name.clone(),
@@ -1502,10 +2315,10 @@ impl<'i> State<'i> {
                 })?
             }
             Instruction::GetElementPtr { ptr, offset, dtype } => {
-                let ptr = self.interp_operand(ptr)?;
+                let ptr = self.interp_defined_operand(ptr)?;
 
                 let (value, _, _) = self
-                    .interp_operand(offset)?
+                    .interp_defined_operand(offset)?
                     .get_int()
                     .expect("`idx` must be `Value::Int`");
 
@@ -1522,6 +2335,17 @@ impl<'i> State<'i> {
 
                 Value::pointer(*bid, offset, inner_dtype.clone())
             }
+            Instruction::Select {
+                condition,
+                then_value,
+                else_value,
+                ..
+            } => {
+                let value = self.interp_defined_operand(condition)?;
+                let (value, _, _) = value.get_int().expect("`condition` must be `Value::Int`");
+
+                self.interp_operand(if value != 0 { then_value } else { else_value })?
+            }
         };
 
         let register = RegisterId::temp(self.stack_frame.pc.bid, self.stack_frame.pc.iid);
@@ -1531,9 +2355,283 @@ impl<'i> State<'i> {
         Ok(())
     }
 
+    /// Calls the host function registered for `callee_name`, which has no definition in the IR.
+    fn interp_extern_call(
+        &mut self,
+        callee_name: &str,
+        signature: &FunctionSignature,
+        args: &[Operand],
+    ) -> Result<(), InterpreterError> {
+        let args = self.interp_args(signature, args)?;
+        let result = if self.interpreter.externs.contains_key(callee_name) {
+            self.observe(callee_name, |state| {
+                let func = state
+                    .interpreter
+                    .externs
+                    .get_mut(callee_name)
+                    .expect("`callee_name` must be registered");
+                Ok(func(&args))
+            })?
+        } else {
+            self.interp_builtin(callee_name, signature, &args)?
+        };
+
+        if result.dtype().unqualified() != signature.ret.clone().unqualified() {
+            return Err(InterpreterError::Misc {
+                func_name: self.stack_frame.func_name.clone(),
+                pc: self.stack_frame.pc,
+                msg: format!(
+                    "external function `{callee_name}` returned `{}` instead of `{}`",
+                    result.dtype(),
+                    signature.ret
+                ),
+            });
+        }
+
+        let register = RegisterId::temp(self.stack_frame.pc.bid, self.stack_frame.pc.iid);
+        self.stack_frame.registers.write(register, result);
+        self.stack_frame.pc.increment();
+
+        Ok(())
+    }
+
+    /// Executes the C library functions the interpreter provides by itself.
+    ///
+    /// They are deterministic so that runs are reproducible across machines: `rand` is an LCG
+    /// seeded by `srand` (or [`Interpreter::seed`]), and `clock` and `time` read the virtual
+    /// clock, which ticks once per executed step. A tick counts as a microsecond, matching the
+    /// POSIX `CLOCKS_PER_SEC`. `getchar` is the only one reading from the host.
+    fn interp_builtin(
+        &mut self,
+        name: &str,
+        signature: &FunctionSignature,
+        args: &[Value],
+    ) -> Result<Value, InterpreterError> {
+        const CLOCKS_PER_SEC: u64 = 1_000_000;
+
+        match name {
+            "rand" => self.observe(name, |state| {
+                // The same generator as glibc's `TYPE_0` random state.
+                state.rand_state = state
+                    .rand_state
+                    .wrapping_mul(1_103_515_245)
+                    .wrapping_add(12345)
+                    & 0x7fff_ffff;
+                state.builtin_result(name, signature, state.rand_state as u128)
+            }),
+            "srand" => {
+                let (seed, _, _) = args
+                    .first()
+                    .and_then(Value::get_int)
+                    .ok_or_else(|| self.builtin_error(name, "expects an integer seed"))?;
+                self.rand_state = seed as u32;
+                Ok(Value::unit())
+            }
+            "clock" => self.observe(name, |state| {
+                state.builtin_result(name, signature, state.clock as u128)
+            }),
+            "time" => {
+                let value = self.observe(name, |state| {
+                    let seconds = state.clock / CLOCKS_PER_SEC;
+                    state.builtin_result(name, signature, seconds as u128)
+                })?;
+                if let Some(ptr @ Value::Pointer { bid: Some(_), .. }) = args.first() {
+                    let (bid, offset, dtype) = self.interp_ptr(ptr)?;
+                    let value = calculator::calculate_typecast(value.clone(), dtype)
+                        .map_err(|_| self.builtin_error(name, "cannot store the current time"))?;
+                    self.memory
+                        .store(bid, offset, &value, &self.ir.structs)
+                        .map_err(|_| self.builtin_error(name, "cannot store the current time"))?;
+                }
+                Ok(value)
+            }
+            "malloc" | "calloc" => {
+                let inner = signature
+                    .ret
+                    .get_pointer_inner()
+                    .ok_or_else(|| self.builtin_error(name, "must return a pointer"))?
+                    .clone();
+                // The size in bytes, or `None` if it does not fit in `usize`.
+                let size = args
+                    .iter()
+                    .try_fold(Some(1usize), |size, arg| {
+                        let (value, _, _) = arg.get_int()?;
+                        Some(size.and_then(|size| size.checked_mul(value.try_into().ok()?)))
+                    })
+                    .ok_or_else(|| self.builtin_error(name, "expects integer sizes"))?;
+                let byte = if name == "calloc" {
+                    Byte::concrete(0)
+                } else {
+                    Byte::Undef
+                };
+                // Like the C library, fails with a null pointer if the block is too large.
+                let bid = match size {
+                    Some(size) => self.memory.alloc_filled(size, byte)?,
+                    None => None,
+                };
+                let (Some(bid), Some(size)) = (bid, size) else {
+                    return Ok(Value::pointer(None, 0, inner));
+                };
+                let _unused = self.heap.insert(
+                    bid,
+                    Leak {
+                        size,
+                        func_name: self.stack_frame.func_name.clone(),
+                        pc: self.stack_frame.pc,
+                    },
+                );
+                Ok(Value::pointer(Some(bid), 0, inner))
+            }
+            "free" => {
+                match args.first() {
+                    Some(Value::Pointer { bid: None, .. }) => (),
+                    Some(Value::Pointer {
+                        bid: Some(bid),
+                        offset: 0,
+                        ..
+                    }) if self.heap.contains_key(bid) => {
+                        let _unused = self.heap.remove(bid);
+                        self.memory.dealloc_bytes(*bid);
+                    }
+                    _ => {
+                        return Err(self.builtin_error(
+                            name,
+                            "expects a pointer returned by `malloc` or `calloc`",
+                        ))
+                    }
+                }
+                Ok(Value::unit())
+            }
+            "abort" => Err(InterpreterError::Aborted {
+                msg: format!("`abort` is called in `{}`", self.stack_frame.func_name),
+                pc: self.stack_frame.pc,
+            }),
+            // glibc and musl: `__assert_fail(expr, file, line, func)`
+            // newlib: `__assert_func(file, line, func, expr)`
+            "__assert_fail" | "__assert_func" => {
+                let (expr, file, line, func) = if name == "__assert_fail" {
+                    (args.first(), args.get(1), args.get(2), args.get(3))
+                } else {
+                    (args.get(3), args.first(), args.get(1), args.get(2))
+                };
+                let string = |arg: Option<&Value>| {
+                    arg.and_then(|ptr| self.read_c_string(ptr))
+                        .unwrap_or_else(|| "?".to_string())
+                };
+                let line = line
+                    .and_then(Value::get_int)
+                    .map(|(line, _, _)| line.to_string())
+                    .unwrap_or_else(|| "?".to_string());
+
+                Err(InterpreterError::Aborted {
+                    msg: format!(
+                        "assertion `{}` failed in `{}` at {}:{line}",
+                        string(expr),
+                        string(func),
+                        string(file)
+                    ),
+                    pc: self.stack_frame.pc,
+                })
+            }
+            // `assert` declared as a function rather than included from `<assert.h>`
+            "assert" => match args.first().and_then(Value::get_int) {
+                Some((0, _, _)) => Err(InterpreterError::Aborted {
+                    msg: format!("assertion failed in `{}`", self.stack_frame.func_name),
+                    pc: self.stack_frame.pc,
+                }),
+                _ => Ok(Value::unit()),
+            },
+            "getchar" => self.observe(name, |state| {
+                let mut byte = [0u8];
+                let result = match std::io::stdin().read(&mut byte) {
+                    Ok(1) => byte[0] as u128,
+                    // `EOF`
+                    _ => -1i128 as u128,
+                };
+                state.builtin_result(name, signature, result)
+            }),
+            _ => Err(InterpreterError::NoFunctionDefinition {
+                func_name: name.to_string(),
+            }),
+        }
+    }
+
+    /// Produces a nondeterministic input of the program, either by `produce` or, when replaying,
+    /// from the replay log.
+    fn observe<F>(&mut self, source: &str, produce: F) -> Result<Value, InterpreterError>
+    where
+        F: FnOnce(&mut Self) -> Result<Value, InterpreterError>,
+    {
+        let value = if let Some(replay) = self.replay.as_mut() {
+            replay
+                .pop(source)
+                .map_err(|msg| InterpreterError::Replay { msg })?
+        } else {
+            produce(self)?
+        };
+
+        if self.interpreter.record.is_some() {
+            self.record
+                .push(source, &value)
+                .map_err(|msg| InterpreterError::Replay { msg })?;
+        }
+
+        Ok(value)
+    }
+
+    /// Converts the integer result of a builtin to the return type it is declared with.
+    fn builtin_result(
+        &self,
+        name: &str,
+        signature: &FunctionSignature,
+        result: u128,
+    ) -> Result<Value, InterpreterError> {
+        calculator::calculate_typecast(Value::int(result, 128, true), signature.ret.clone())
+            .map_err(|_| self.builtin_error(name, "has an unexpected return type"))
+    }
+
+    /// Reads the NUL-terminated string `ptr` points to.
+    fn read_c_string(&self, ptr: &Value) -> Option<String> {
+        let (bid, offset, _) = ptr.get_pointer()?;
+        let bid = (*bid)?;
+        let mut bytes = Vec::new();
+        for i in 0.. {
+            let byte = self
+                .memory
+                .load(bid, offset + i, &Dtype::CHAR, &self.ir.structs)
+                .ok()?;
+            let (byte, _, _) = byte.get_int()?;
+            if byte == 0 {
+                break;
+            }
+            bytes.push(byte as u8);
+        }
+
+        Some(String::from_utf8_lossy(&bytes).into_owned())
+    }
+
+    fn builtin_error(&self, name: &str, msg: &str) -> InterpreterError {
+        InterpreterError::Misc {
+            func_name: self.stack_frame.func_name.clone(),
+            pc: self.stack_frame.pc,
+            msg: format!("builtin `{name}` {msg}"),
+        }
+    }
+
     fn interp_operand(&self, operand: &Operand) -> Result<Value, InterpreterError> {
         match operand {
+            Operand::Constant(Constant::GlobalVariable { name, .. })
+                if self.global_map.get_bid(name).is_none() =>
+            {
+                Err(InterpreterError::NoVariableDefinition {
+                    var_name: name.clone(),
+                })
+            }
             Operand::Constant(value) => Ok(self.interp_constant(value.clone())),
+            Operand::Register {
+                dtype: Dtype::Unit { .. },
+                ..
+            } => Ok(Value::unit()),
             Operand::Register { rid, .. } => Ok(self.stack_frame.registers.read(*rid).clone()),
         }
     }
@@ -1557,6 +2655,30 @@ impl<'i> State<'i> {
         }
     }
 
+    /// Evaluates `operand`, on which the control flow or a memory access depends, so that its
+    /// value must not be undefined.
+    fn interp_defined_operand(&self, operand: &Operand) -> Result<Value, InterpreterError> {
+        let value = self.interp_operand(operand)?;
+        if let Value::Undef { .. } = value {
+            return Err(InterpreterError::UndefinedValue {
+                func_name: self.stack_frame.func_name.clone(),
+                pc: self.stack_frame.pc,
+                operand: operand.clone(),
+            });
+        }
+        Ok(value)
+    }
+
+    /// Evaluates `operand` of a computation, which may be undefined unless
+    /// [`Interpreter::undef_check`] is enabled.
+    fn interp_computed_operand(&self, operand: &Operand) -> Result<Value, InterpreterError> {
+        if self.interpreter.undef_check {
+            self.interp_defined_operand(operand)
+        } else {
+            self.interp_operand(operand)
+        }
+    }
+
     fn interp_ptr(&mut self, pointer: &Value) -> Result<(usize, isize, Dtype), InterpreterError> {
         let (bid, offset, dtype) = pointer
             .get_pointer()
@@ -1578,6 +2700,5 @@ impl<'i> State<'i> {
 
 #[inline]
 pub fn interp(ir: &TranslationUnit, args: Vec<Value>) -> Result<Value, InterpreterError> {
-    let mut init_state = State::new(ir, args)?;
-    init_state.run()
+    Interpreter::default().run(ir, args)
 }
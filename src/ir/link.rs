@@ -0,0 +1,207 @@
+//! Linking of translation units.
+
+use std::collections::{BTreeSet, HashMap};
+
+use thiserror::Error;
+
+use crate::ir::*;
+use crate::*;
+
+/// A reason translation units cannot be linked together.
+#[derive(Debug, PartialEq, Eq, Clone, Error)]
+pub enum LinkError {
+    #[error("struct {name} is defined differently in different translation units")]
+    ConflictingStructs { name: String },
+    #[error("@{name} is declared with incompatible types")]
+    ConflictingDeclarations { name: String },
+    #[error("@{name} is defined more than once")]
+    MultipleDefinitions { name: String },
+}
+
+/// Links `units` into a single translation unit, e.g., the IR of the files of a C program.
+///
+/// The declarations of a name with external linkage in different units must be compatible, and
+/// at most one of them may define the name. Even tentative definitions, e.g., `int x;`, may not be
+/// repeated across units, as with `-fno-common`. The declarations are merged into the definition
+/// if any.
+///
+/// A name with internal linkage, i.e., declared `static`, is local to its unit. If the name is
+/// used by another unit as well, it is renamed to `{name}_{index}` in its unit, where `index` is
+/// the index of the unit, suffixed with `_` until no other name clashes with it.
+///
+/// Structs of the same name must have the same fields in every unit that defines them.
+///
+/// The location of a name is that of its definition if any, and otherwise that of its declaration
+/// in the first unit.
+pub fn link<I>(units: I) -> Result<TranslationUnit, LinkError>
+where
+    I: IntoIterator<Item = TranslationUnit>,
+{
+    let mut units = units.into_iter().collect::<Vec<_>>();
+    rename_internals(&mut units);
+
+    let mut result = TranslationUnit {
+        decls: BTreeMap::new(),
+        decl_order: Vec::new(),
+        structs: HashMap::new(),
+        decl_locs: BTreeMap::new(),
+    };
+    for unit in units {
+        for (name, fields) in unit.structs {
+            match result.structs.get(&name) {
+                Some(Some(old_fields)) if fields.as_ref().is_some_and(|f| f != old_fields) => {
+                    return Err(LinkError::ConflictingStructs { name });
+                }
+                Some(Some(_)) => {}
+                _ => {
+                    let _unused = result.structs.insert(name, fields);
+                }
+            }
+        }
+
+        for (name, loc) in unit.decl_locs {
+            if unit
+                .decls
+                .get(&name)
+                .is_some_and(Declaration::is_definition)
+            {
+                let _unused = result.decl_locs.insert(name, loc);
+            } else {
+                let _unused = result.decl_locs.entry(name).or_insert(loc);
+            }
+        }
+
+        for name in unit.decl_order {
+            if !result.decl_order.contains(&name) {
+                result.decl_order.push(name);
+            }
+        }
+
+        for (name, decl) in unit.decls {
+            let old_decl = some_or!(result.decls.remove(&name), {
+                let _unused = result.decls.insert(name, decl);
+                continue;
+            });
+            let decl = merge(&name, old_decl, decl)?;
+            let _unused = result.decls.insert(name, decl);
+        }
+    }
+
+    Ok(result)
+}
+
+/// Merges two declarations of the external name `name` from different units.
+fn merge(name: &str, lhs: Declaration, rhs: Declaration) -> Result<Declaration, LinkError> {
+    if !lhs.is_compatible(&rhs) {
+        return Err(LinkError::ConflictingDeclarations {
+            name: name.to_string(),
+        });
+    }
+    if lhs.is_definition() && rhs.is_definition() {
+        return Err(LinkError::MultipleDefinitions {
+            name: name.to_string(),
+        });
+    }
+
+    Ok(if rhs.is_definition() { rhs } else { lhs })
+}
+
+/// Renames the names with internal linkage that are also used by other units.
+fn rename_internals(units: &mut [TranslationUnit]) {
+    let mut taken = units
+        .iter()
+        .flat_map(|unit| unit.decls.keys().cloned())
+        .collect::<BTreeSet<_>>();
+
+    for index in 0..units.len() {
+        let clashes = units[index]
+            .decls
+            .iter()
+            .filter(|(name, decl)| {
+                decl.linkage() == Linkage::Internal
+                    && units
+                        .iter()
+                        .enumerate()
+                        .any(|(other, unit)| other != index && unit.decls.contains_key(*name))
+            })
+            .map(|(name, _)| name.clone())
+            .collect::<Vec<_>>();
+        if clashes.is_empty() {
+            continue;
+        }
+
+        let mut renames = HashMap::new();
+        for name in clashes {
+            let mut new_name = format!("{name}_{index}");
+            while taken.contains(&new_name) {
+                new_name.push('_');
+            }
+            let _unused = taken.insert(new_name.clone());
+            let _unused = renames.insert(name, new_name);
+        }
+
+        let unit = &mut units[index];
+        unit.decls = std::mem::take(&mut unit.decls)
+            .into_iter()
+            .map(|(name, decl)| (renames.get(&name).cloned().unwrap_or(name), decl))
+            .collect();
+        for name in &mut unit.decl_order {
+            if let Some(new_name) = renames.get(name) {
+                name.clone_from(new_name);
+            }
+        }
+        unit.decl_locs = std::mem::take(&mut unit.decl_locs)
+            .into_iter()
+            .map(|(name, loc)| (renames.get(&name).cloned().unwrap_or(name), loc))
+            .collect();
+        let mut renamer = Renamer { renames: &renames };
+        for decl in unit.decls.values_mut() {
+            match decl {
+                Declaration::Function {
+                    definition: Some(definition),
+                    ..
+                } => renamer.rewrite_function(definition),
+                Declaration::Variable {
+                    initializer: Some(initializer),
+                    ..
+                } => renamer.rewrite_initializer(initializer),
+                _ => (),
+            }
+        }
+    }
+}
+
+/// Renames the global variables an operand refers to.
+#[derive(Debug)]
+struct Renamer<'a> {
+    renames: &'a HashMap<String, String>,
+}
+
+impl Renamer<'_> {
+    /// Renames the global names whose addresses `initializer` takes.
+    fn rewrite_initializer(&mut self, initializer: &mut ast::Initializer) {
+        match initializer {
+            ast::Initializer::Expression(expr) => {
+                let (name, offset) = some_or!(get_address_initializer(&expr.node), return);
+                if let Some(new_name) = self.renames.get(name) {
+                    expr.node = address_initializer(new_name, offset);
+                }
+            }
+            ast::Initializer::List(items) => {
+                for item in items {
+                    self.rewrite_initializer(&mut item.node.initializer.node);
+                }
+            }
+        }
+    }
+}
+
+impl IrRewriter for Renamer<'_> {
+    fn rewrite_operand(&mut self, operand: &mut Operand) {
+        if let Operand::Constant(Constant::GlobalVariable { name, .. }) = operand {
+            if let Some(new_name) = self.renames.get(name) {
+                name.clone_from(new_name);
+            }
+        }
+    }
+}
@@ -1,9 +1,21 @@
 //! The intermediate representation.
 
+pub mod analysis;
+mod builder;
+pub mod const_eval;
+mod diff;
 mod dtype;
 mod equiv;
 mod interp;
+mod link;
 mod parse;
+mod profile;
+#[cfg(feature = "serde")]
+mod serialize;
+mod stats;
+mod unreachable;
+mod verify;
+pub mod visit;
 mod visualize;
 mod write_ir;
 
@@ -12,39 +24,112 @@ use core::fmt;
 use core::ops::{Deref, DerefMut};
 use hexf_parse::{parse_hexf32, parse_hexf64};
 use itertools::Itertools;
-use lang_c::ast;
+use lang_c::{ast, span};
 use ordered_float::OrderedFloat;
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::hash::{Hash, Hasher};
 
+use crate::some_or;
 use crate::write_base::*;
-pub use dtype::{Dtype, DtypeError, HasDtype};
-pub use interp::{interp, Value};
+pub use builder::FunctionBuilder;
+pub use const_eval::ConstEvalError;
+pub use diff::{diff, Change, Diff, Hunk};
+pub use dtype::{DataLayout, Dtype, DtypeError, HasDtype};
+pub use equiv::equiv;
+pub use interp::{interp, Byte, ExternFn, Interpreter, InterpreterError, Leak, Pc, Value};
+pub use link::{link, LinkError};
 pub use parse::Parse;
-pub use visualize::Visualizer;
-
-#[derive(Debug, Clone, PartialEq)]
+pub use profile::{BlockCount, Profile};
+pub use stats::{stats, FunctionStats, Stats};
+pub use unreachable::{remove_unreachable_blocks, unreachable_blocks};
+pub use verify::{verify, VerifyError};
+pub use visit::{IrRewriter, IrVisitor};
+pub use visualize::{cfg_dot, Visualizer};
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TranslationUnit {
     pub decls: BTreeMap<String, Declaration>,
+    /// The names of `decls` in the order they are declared, e.g., in the C source or the IR text.
+    /// Global variables are allocated, and declarations printed, in this order. See
+    /// [`TranslationUnit::decls_in_order`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub decl_order: Vec<String>,
     pub structs: HashMap<String, Option<Dtype>>,
+    /// Where the declarations are in the C source, if the unit is translated from C. Locations
+    /// are not printed, and are ignored when comparing translation units.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub decl_locs: BTreeMap<String, SourceLoc>,
+}
+
+impl PartialEq for TranslationUnit {
+    fn eq(&self, other: &Self) -> bool {
+        self.decls == other.decls && self.structs == other.structs
+    }
+}
+
+impl TranslationUnit {
+    /// The declarations in the order they are declared. The declarations missing from
+    /// `decl_order`, e.g., those added by a pass, follow the others in the order of their names.
+    pub fn decls_in_order(&self) -> impl Iterator<Item = (&String, &Declaration)> {
+        let ordered = self.decl_order.iter().collect::<HashSet<_>>();
+        self.decl_order
+            .iter()
+            .unique()
+            .filter_map(|name| self.decls.get_key_value(name))
+            .chain(
+                self.decls
+                    .iter()
+                    .filter(move |(name, _)| !ordered.contains(name)),
+            )
+    }
+
+    /// Where the declaration of `name` is in the C source, if known.
+    pub fn decl_loc(&self, name: &str) -> Option<SourceLoc> {
+        self.decl_locs.get(name).copied()
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Declaration {
     Variable {
         dtype: Dtype,
+        /// The constant values the variable is initialized with. A pointer is initialized by `0`
+        /// or by the address of a global name, as built by [`address_initializer`].
+        #[cfg_attr(feature = "serde", serde(with = "serialize::initializer"))]
         initializer: Option<ast::Initializer>,
+        linkage: Linkage,
+        /// Whether the variable is defined in the translation unit. A variable declared `extern`
+        /// without an initializer is defined elsewhere, and a defined variable without an
+        /// initializer is initialized with zeros.
+        is_definition: bool,
     },
     Function {
         signature: FunctionSignature,
         definition: Option<FunctionDefinition>,
+        linkage: Linkage,
     },
 }
 
+/// The linkage of a global name.
+///
+/// Names with external linkage are visible to other translation units, so codegen declares their
+/// symbols `.globl`. Names with internal linkage, i.e., those declared `static`, stay local to the
+/// translation unit.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Linkage {
+    #[default]
+    External,
+    Internal,
+}
+
 impl TryFrom<Dtype> for Declaration {
     type Error = DtypeError;
 
-    /// Create an appropriate declaration according to `dtype`.
+    /// Create an appropriate declaration according to `dtype`. The declaration has external
+    /// linkage, and a variable is defined.
     ///
     /// # Example
     ///
@@ -68,10 +153,16 @@ impl TryFrom<Dtype> for Declaration {
             | Dtype::Struct { .. } => Ok(Declaration::Variable {
                 dtype,
                 initializer: None,
+                linkage: Linkage::External,
+                is_definition: true,
+            }),
+            Dtype::BitField { .. } => Err(DtypeError::Misc {
+                message: "Only a member of a struct can be a bit-field".to_string(),
             }),
             Dtype::Function { .. } => Ok(Declaration::Function {
                 signature: FunctionSignature::new(dtype),
                 definition: None,
+                linkage: Linkage::External,
             }),
             Dtype::Typedef { .. } => panic!("typedef should be replaced by real dtype"),
         }
@@ -80,7 +171,10 @@ impl TryFrom<Dtype> for Declaration {
 
 impl Declaration {
     pub fn get_variable(&self) -> Option<(&Dtype, &Option<ast::Initializer>)> {
-        if let Self::Variable { dtype, initializer } = self {
+        if let Self::Variable {
+            dtype, initializer, ..
+        } = self
+        {
             Some((dtype, initializer))
         } else {
             None
@@ -91,6 +185,7 @@ impl Declaration {
         if let Self::Function {
             signature,
             definition,
+            ..
         } = self
         {
             Some((signature, definition))
@@ -105,6 +200,7 @@ impl Declaration {
         if let Self::Function {
             signature,
             definition,
+            ..
         } = self
         {
             Some((signature, definition))
@@ -113,6 +209,21 @@ impl Declaration {
         }
     }
 
+    pub fn linkage(&self) -> Linkage {
+        match self {
+            Self::Variable { linkage, .. } | Self::Function { linkage, .. } => *linkage,
+        }
+    }
+
+    /// Returns `true` if the declaration defines the name, i.e., it is a variable that is not only
+    /// declared `extern` or a function with a body.
+    pub fn is_definition(&self) -> bool {
+        match self {
+            Self::Variable { is_definition, .. } => *is_definition,
+            Self::Function { definition, .. } => definition.is_some(),
+        }
+    }
+
     /// Check if type is conflicting for pre-declared one
     pub fn is_compatible(&self, other: &Declaration) -> bool {
         match (self, other) {
@@ -128,6 +239,78 @@ impl Declaration {
     }
 }
 
+/// The initializer of a pointer to `offset` bytes past the start of the global `name`, printed as
+/// `&(name)` or, e.g., `(&(name)) + 8`. Unlike in C, the offset counts bytes whatever the dtype of
+/// `name` is.
+pub fn address_initializer(name: &str, offset: isize) -> ast::Expression {
+    fn node<T>(node: T) -> Box<span::Node<T>> {
+        Box::new(span::Node::new(node, span::Span::none()))
+    }
+
+    let identifier = ast::Identifier {
+        name: name.to_string(),
+    };
+    let address = ast::Expression::UnaryOperator(node(ast::UnaryOperatorExpression {
+        operator: span::Node::new(ast::UnaryOperator::Address, span::Span::none()),
+        operand: node(ast::Expression::Identifier(node(identifier))),
+    }));
+    if offset == 0 {
+        return address;
+    }
+
+    let integer = ast::Integer {
+        base: ast::IntegerBase::Decimal,
+        number: offset.unsigned_abs().to_string().into_boxed_str(),
+        suffix: ast::IntegerSuffix {
+            size: ast::IntegerSize::Long,
+            unsigned: false,
+            imaginary: false,
+        },
+    };
+    let operator = if offset < 0 {
+        ast::BinaryOperator::Minus
+    } else {
+        ast::BinaryOperator::Plus
+    };
+    ast::Expression::BinaryOperator(node(ast::BinaryOperatorExpression {
+        operator: span::Node::new(operator, span::Span::none()),
+        lhs: node(address),
+        rhs: node(ast::Expression::Constant(node(ast::Constant::Integer(
+            integer,
+        )))),
+    }))
+}
+
+/// The global name and the offset in bytes of `expr`, if it is built by [`address_initializer`].
+pub fn get_address_initializer(expr: &ast::Expression) -> Option<(&str, isize)> {
+    fn address(expr: &ast::Expression) -> Option<&str> {
+        let ast::Expression::UnaryOperator(unary) = expr else {
+            return None;
+        };
+        match (&unary.node.operator.node, &unary.node.operand.node) {
+            (ast::UnaryOperator::Address, ast::Expression::Identifier(identifier)) => {
+                Some(&identifier.node.name)
+            }
+            _ => None,
+        }
+    }
+
+    if let Some(name) = address(expr) {
+        return Some((name, 0));
+    }
+
+    let ast::Expression::BinaryOperator(binary) = expr else {
+        return None;
+    };
+    let name = address(&binary.node.lhs.node)?;
+    let offset = Constant::try_from(&binary.node.rhs.node).ok()?.get_int()?.0 as isize;
+    match binary.node.operator.node {
+        ast::BinaryOperator::Plus => Some((name, offset)),
+        ast::BinaryOperator::Minus => Some((name, -offset)),
+        _ => None,
+    }
+}
+
 impl HasDtype for Declaration {
     fn dtype(&self) -> Dtype {
         match self {
@@ -137,7 +320,15 @@ impl HasDtype for Declaration {
     }
 }
 
+/// The return type and the parameter types of a function.
+///
+/// A struct is passed and returned by value as an operand of its struct type. The callee copies
+/// each parameter into an allocation of its own before using it, so the caller's operand is never
+/// modified, and the caller stores a returned struct into an allocation when it needs its address,
+/// e.g., for `f().x`. The backend lowers these operands as its ABI requires, e.g., by passing the
+/// struct in memory and the address for the result as a hidden pointer parameter.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FunctionSignature {
     pub ret: Dtype,
     pub params: Vec<Dtype>,
@@ -162,12 +353,13 @@ impl HasDtype for FunctionSignature {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FunctionDefinition {
     /// Memory allocations for local variables.  The allocation is performed at the beginning of a
     /// function invocation.
     pub allocations: Vec<Named<Dtype>>,
 
-    /// Basic blocks.
+    /// Basic blocks, visited in the order of their ids.
     pub blocks: BTreeMap<BlockId, Block>,
 
     /// The initial block id.
@@ -175,6 +367,7 @@ pub struct FunctionDefinition {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BlockId(pub usize);
 
 impl fmt::Display for BlockId {
@@ -184,6 +377,7 @@ impl fmt::Display for BlockId {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Block {
     pub phinodes: Vec<Named<Dtype>>,
     pub instructions: Vec<Named<Instruction>>,
@@ -191,25 +385,36 @@ pub struct Block {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Instruction {
     Nop,
+    /// The `flags` are promises on the operands that optimizations may rely on. See
+    /// [`BinOpFlags`].
     BinOp {
+        #[cfg_attr(feature = "serde", serde(with = "serialize::binary_operator"))]
         op: ast::BinaryOperator,
         lhs: Operand,
         rhs: Operand,
         dtype: Dtype,
+        flags: BinOpFlags,
     },
     UnaryOp {
+        #[cfg_attr(feature = "serde", serde(with = "serialize::unary_operator"))]
         op: ast::UnaryOperator,
         operand: Operand,
         dtype: Dtype,
     },
+    /// A `volatile` store or load accesses memory that may be observed or changed outside the
+    /// program, e.g., a device register. Optimizations must neither remove it nor reorder it with
+    /// other volatile accesses, even if its value is known or unused.
     Store {
         ptr: Operand,
         value: Operand,
+        volatile: bool,
     },
     Load {
         ptr: Operand,
+        volatile: bool,
     },
     Call {
         callee: Operand,
@@ -228,6 +433,97 @@ pub enum Instruction {
         offset: Operand,
         dtype: Dtype,
     },
+    /// `Select` results in `then_value` if `condition` is nonzero and `else_value` otherwise,
+    /// without branching.
+    Select {
+        condition: Operand,
+        then_value: Operand,
+        else_value: Operand,
+        dtype: Dtype,
+    },
+}
+
+/// Promises that a `BinOp` instruction does not wrap around or lose precision.
+///
+/// - `nuw` and `nsw` on `add`, `sub`, `mul` and `shl`: the result, computed on the operands read as
+///   unsigned and signed integers respectively, fits in the type without wrapping around. For
+///   `shl`, this holds if and only if the bits shifted out are all zero, resp. all equal to the
+///   sign bit of the result.
+/// - `exact` on `div` and `shr`: no nonzero remainder, resp. no nonzero bit, is discarded.
+///
+/// An instruction whose promise does not hold has undefined behavior as soon as it runs, as
+/// [`Interpreter::flag_check`] reports it, whether or not its result is used. Unlike LLVM's
+/// poison values, it is not enough that the result of such an instruction is never used. So
+/// optimizations may assume, e.g., that `%x + 1 > %x` for `add nsw`, but an optimization must drop
+/// the flags it can no longer guarantee when it changes the operands or the opcode of an
+/// instruction, or when it moves an instruction to where it may run although it would not have,
+/// e.g., out of a loop that may run zero times.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BinOpFlags {
+    pub nuw: bool,
+    pub nsw: bool,
+    pub exact: bool,
+}
+
+impl BinOpFlags {
+    /// The flags C guarantees for `op` on operands of `dtype`, which irgen sets: signed integer
+    /// arithmetic overflowing is undefined behavior in C, hence `nsw` for `+`, `-`, `*` and `<<`
+    /// on signed integers. Unsigned arithmetic wraps around, and no flag is set.
+    pub fn of_c(op: &ast::BinaryOperator, dtype: &Dtype) -> Self {
+        let is_signed = matches!(
+            dtype,
+            Dtype::Int {
+                is_signed: true,
+                ..
+            }
+        );
+        let nsw = is_signed
+            && matches!(
+                op,
+                ast::BinaryOperator::Plus
+                    | ast::BinaryOperator::Minus
+                    | ast::BinaryOperator::Multiply
+                    | ast::BinaryOperator::ShiftLeft
+            );
+        Self {
+            nsw,
+            ..Self::default()
+        }
+    }
+
+    /// Whether no flag is set.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Whether the flags may be set on `op`.
+    pub fn is_valid_for(&self, op: &ast::BinaryOperator) -> bool {
+        let wraps = matches!(
+            op,
+            ast::BinaryOperator::Plus
+                | ast::BinaryOperator::Minus
+                | ast::BinaryOperator::Multiply
+                | ast::BinaryOperator::ShiftLeft
+        );
+        let discards = matches!(
+            op,
+            ast::BinaryOperator::Divide | ast::BinaryOperator::ShiftRight
+        );
+        (wraps || !(self.nuw || self.nsw)) && (discards || !self.exact)
+    }
+}
+
+impl fmt::Display for BinOpFlags {
+    /// Writes each set flag preceded by a space, in the order `nuw`, `nsw`, `exact`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (set, name) in [(self.nuw, "nuw"), (self.nsw, "nsw"), (self.exact, "exact")] {
+            if set {
+                write!(f, " {name}")?;
+            }
+        }
+        Ok(())
+    }
 }
 
 impl HasDtype for Instruction {
@@ -243,20 +539,80 @@ impl HasDtype for Instruction {
                 target_dtype: dtype,
                 ..
             }
-            | Self::GetElementPtr { dtype, .. } => dtype.clone(),
-            Self::Load { ptr } => ptr
+            | Self::GetElementPtr { dtype, .. }
+            | Self::Select { dtype, .. } => dtype.clone(),
+            Self::Load { ptr, .. } => ptr
                 .dtype()
                 .get_pointer_inner()
                 .expect("Load instruction must have pointer value as operand")
                 .clone()
-                .set_const(false),
+                .unqualified(),
         }
     }
 }
 
 impl Instruction {
     pub fn has_no_side_effects(&self) -> bool {
-        !matches!(self, Self::Store { .. } | Self::Call { .. })
+        !matches!(
+            self,
+            Self::Store { .. } | Self::Call { .. } | Self::Load { volatile: true, .. }
+        )
+    }
+
+    /// Whether the instruction is a volatile load or store.
+    pub fn is_volatile(&self) -> bool {
+        matches!(
+            self,
+            Self::Store { volatile: true, .. } | Self::Load { volatile: true, .. }
+        )
+    }
+
+    /// The operands of the instruction, in the order they appear.
+    pub fn operands(&self) -> Vec<&Operand> {
+        match self {
+            Self::Nop => Vec::new(),
+            Self::BinOp { lhs, rhs, .. } => vec![lhs, rhs],
+            Self::UnaryOp { operand, .. } => vec![operand],
+            Self::Store { ptr, value, .. } => vec![ptr, value],
+            Self::Load { ptr, .. } => vec![ptr],
+            Self::Call { callee, args, .. } => {
+                let mut operands = vec![callee];
+                operands.extend(args);
+                operands
+            }
+            Self::TypeCast { value, .. } => vec![value],
+            Self::GetElementPtr { ptr, offset, .. } => vec![ptr, offset],
+            Self::Select {
+                condition,
+                then_value,
+                else_value,
+                ..
+            } => vec![condition, then_value, else_value],
+        }
+    }
+
+    /// Mutable references to the operands of the instruction, in the order they appear.
+    pub fn operands_mut(&mut self) -> Vec<&mut Operand> {
+        match self {
+            Self::Nop => Vec::new(),
+            Self::BinOp { lhs, rhs, .. } => vec![lhs, rhs],
+            Self::UnaryOp { operand, .. } => vec![operand],
+            Self::Store { ptr, value, .. } => vec![ptr, value],
+            Self::Load { ptr, .. } => vec![ptr],
+            Self::Call { callee, args, .. } => {
+                let mut operands = vec![callee];
+                operands.extend(args);
+                operands
+            }
+            Self::TypeCast { value, .. } => vec![value],
+            Self::GetElementPtr { ptr, offset, .. } => vec![ptr, offset],
+            Self::Select {
+                condition,
+                then_value,
+                else_value,
+                ..
+            } => vec![condition, then_value, else_value],
+        }
     }
 }
 
@@ -294,6 +650,7 @@ impl WriteOp for ast::UnaryOperator {
             Self::Plus => "plus",
             Self::Minus => "minus",
             Self::Negate => "negate",
+            Self::Complement => "not",
             _ => todo!(
                 "ast::UnaryOperator::WriteOp: write operation for {:?} is needed",
                 self
@@ -307,14 +664,28 @@ impl fmt::Display for Instruction {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Instruction::Nop => write!(f, "nop"),
-            Instruction::BinOp { op, lhs, rhs, .. } => {
-                write!(f, "{} {} {}", op.write_operation(), lhs, rhs)
-            }
+            Instruction::BinOp {
+                op,
+                lhs,
+                rhs,
+                flags,
+                ..
+            } => write!(f, "{}{flags} {lhs} {rhs}", op.write_operation()),
             Instruction::UnaryOp { op, operand, .. } => {
                 write!(f, "{} {}", op.write_operation(), operand)
             }
-            Instruction::Store { ptr, value } => write!(f, "store {value} {ptr}"),
-            Instruction::Load { ptr } => write!(f, "load {ptr}"),
+            Instruction::Store {
+                ptr,
+                value,
+                volatile,
+            } => {
+                let volatile = if *volatile { "volatile " } else { "" };
+                write!(f, "store {volatile}{value} {ptr}")
+            }
+            Instruction::Load { ptr, volatile } => {
+                let volatile = if *volatile { "volatile " } else { "" };
+                write!(f, "load {volatile}{ptr}")
+            }
             Instruction::Call { callee, args, .. } => {
                 write!(
                     f,
@@ -331,11 +702,18 @@ impl fmt::Display for Instruction {
             Instruction::GetElementPtr { ptr, offset, .. } => {
                 write!(f, "getelementptr {ptr} offset {offset}")
             }
+            Instruction::Select {
+                condition,
+                then_value,
+                else_value,
+                ..
+            } => write!(f, "select {condition}, {then_value}, {else_value}"),
         }
     }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum BlockExit {
     Jump {
         arg: JumpArg,
@@ -357,6 +735,35 @@ pub enum BlockExit {
 }
 
 impl BlockExit {
+    /// The operands of the exit including those of its jump arguments, in the order they appear.
+    pub fn operands(&self) -> Vec<&Operand> {
+        let mut operands = match self {
+            Self::ConditionalJump { condition, .. } => vec![condition],
+            Self::Switch { value, .. } | Self::Return { value } => vec![value],
+            Self::Jump { .. } | Self::Unreachable => Vec::new(),
+        };
+        for arg in self.jump_args() {
+            operands.extend(&arg.args);
+        }
+        operands
+    }
+
+    /// The jump arguments of the exit, in the order they appear.
+    pub fn jump_args(&self) -> Vec<&JumpArg> {
+        match self {
+            Self::Jump { arg } => vec![arg],
+            Self::ConditionalJump {
+                arg_then, arg_else, ..
+            } => vec![arg_then, arg_else],
+            Self::Switch { default, cases, .. } => {
+                let mut args = vec![default];
+                args.extend(cases.iter().map(|(_, arg)| arg));
+                args
+            }
+            Self::Return { .. } | Self::Unreachable => Vec::new(),
+        }
+    }
+
     pub fn walk_jump_args<F>(&mut self, mut f: F)
     where
         F: FnMut(&mut JumpArg),
@@ -412,6 +819,7 @@ impl fmt::Display for BlockExit {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct JumpArg {
     pub bid: BlockId,
     pub args: Vec<Operand>,
@@ -437,6 +845,7 @@ impl fmt::Display for JumpArg {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Operand {
     Constant(Constant),
     Register { rid: RegisterId, dtype: Dtype },
@@ -495,6 +904,7 @@ impl HasDtype for Operand {
 }
 
 #[derive(Debug, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum RegisterId {
     /// Registers holding pointers to local allocations.
     ///
@@ -594,6 +1004,7 @@ impl Hash for RegisterId {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Constant {
     Undef {
         dtype: Dtype,
@@ -626,11 +1037,9 @@ impl TryFrom<&ast::Constant> for Constant {
     fn try_from(constant: &ast::Constant) -> Result<Self, Self::Error> {
         match constant {
             ast::Constant::Integer(integer) => {
-                let dtype = match integer.suffix.size {
-                    ast::IntegerSize::Int => Dtype::INT,
-                    ast::IntegerSize::Long => Dtype::LONG,
-                    ast::IntegerSize::LongLong => Dtype::LONGLONG,
-                };
+                if integer.suffix.imaginary {
+                    return Err(());
+                }
 
                 let pat = match integer.base {
                     ast::IntegerBase::Decimal => Self::DECIMAL,
@@ -638,24 +1047,10 @@ impl TryFrom<&ast::Constant> for Constant {
                     ast::IntegerBase::Hexadecimal => Self::HEXADECIMAL,
                     ast::IntegerBase::Binary => Self::BINARY,
                 };
+                let value = u128::from_str_radix(integer.number.deref(), pat).map_err(|_| ())?;
+                let dtype = Self::dtype_of_integer(value, integer)?;
 
-                let value = if integer.suffix.unsigned {
-                    u128::from_str_radix(integer.number.deref(), pat).unwrap()
-                } else {
-                    i128::from_str_radix(integer.number.deref(), pat).unwrap() as u128
-                };
-
-                let is_signed = !integer.suffix.unsigned && {
-                    // Even if `suffix` represents `signed`, integer literal cannot be translated
-                    // to minus value. For this reason, if the sign bit is on, dtype automatically
-                    // transformed to `unsigned`. Let's say integer literal is `0xFFFFFFFF`,
-                    // it translated to unsigned integer even though it has no `U` suffix.
-                    let width = dtype.get_int_width().unwrap();
-                    let threshold = 1u128 << (width as u128 - 1);
-                    value < threshold
-                };
-
-                Ok(Self::int(value, dtype.set_signed(is_signed)))
+                Ok(Self::int(value, dtype))
             }
             ast::Constant::Float(float) => {
                 let pat = match float.base {
@@ -697,7 +1092,19 @@ impl TryFrom<&ast::Constant> for Constant {
                         (Dtype::DOUBLE, value)
                     }
                     ast::FloatFormat::LongDouble => {
-                        panic!("`FloatFormat::LongDouble` is_unsupported")
+                        let value = match pat {
+                            Self::DECIMAL => float.number.parse::<f64>().unwrap(),
+                            Self::HEXADECIMAL => {
+                                let mut hex_number = "0x".to_string();
+                                hex_number.push_str(float.number.deref());
+                                parse_hexf64(&hex_number, true).unwrap()
+                            }
+                            _ => panic!(
+                                "Constant::try_from::<&ast::Constant>: \
+                                 {pat:?} is not a pattern of `pat`"
+                            ),
+                        };
+                        (Dtype::LONG_DOUBLE, value)
                     }
                     ast::FloatFormat::TS18661Format(_) => {
                         panic!("`FloatFormat::TS18661Format` is_unsupported")
@@ -707,15 +1114,74 @@ impl TryFrom<&ast::Constant> for Constant {
                 Ok(Self::float(value, dtype))
             }
             ast::Constant::Character(character) => {
-                let dtype = Dtype::CHAR;
-                let value = character.parse::<char>().unwrap() as u128;
+                // Wide and multicharacter constants are not supported.
+                let text = character
+                    .strip_prefix('\'')
+                    .and_then(|text| text.strip_suffix('\''))
+                    .ok_or(())?;
+                let [byte] = unescape(text).ok_or(())?[..] else {
+                    return Err(());
+                };
 
-                Ok(Self::int(value, dtype))
+                Ok(Self::int(byte as i8 as u128, Dtype::CHAR))
             }
         }
     }
 }
 
+/// The bytes of `text`, the characters between the quotes of a C string literal or character
+/// constant, with its escape sequences, e.g., `\n`, `\x41` and `\101`, decoded. Returns `None`
+/// if an escape sequence is invalid or does not fit in a byte.
+pub(crate) fn unescape(text: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut buf = [0; 4];
+            bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            continue;
+        }
+
+        let c = chars.next()?;
+        let byte = match c {
+            'n' => b'\n',
+            't' => b'\t',
+            'r' => b'\r',
+            'a' => 0x07,
+            'b' => 0x08,
+            'f' => 0x0c,
+            'v' => 0x0b,
+            '\\' | '\'' | '"' | '?' => c as u8,
+            '0'..='7' => {
+                let mut value = c.to_digit(8).expect("`c` must be an octal digit");
+                for _ in 0..2 {
+                    let digit = some_or!(chars.peek().and_then(|c| c.to_digit(8)), break);
+                    value = value * 8 + digit;
+                    let _unused = chars.next();
+                }
+                u8::try_from(value).ok()?
+            }
+            'x' => {
+                let mut value = 0u32;
+                let mut has_digits = false;
+                while let Some(digit) = chars.peek().and_then(|c| c.to_digit(16)) {
+                    value = value.checked_mul(16)? + digit;
+                    has_digits = true;
+                    let _unused = chars.next();
+                }
+                if !has_digits {
+                    return None;
+                }
+                u8::try_from(value).ok()?
+            }
+            _ => return None,
+        };
+        bytes.push(byte);
+    }
+
+    Some(bytes)
+}
+
 impl TryFrom<&ast::Expression> for Constant {
     type Error = ();
 
@@ -748,6 +1214,38 @@ impl Constant {
     const HEXADECIMAL: u32 = 16;
     const BINARY: u32 = 2;
 
+    /// The type of the integer constant `integer` of `value`, which is the first of the
+    /// candidates for its suffix and base that can represent it (C17 6.4.4.1p5).
+    ///
+    /// A decimal constant without the `u` suffix is signed, and the others may be unsigned. A
+    /// decimal constant too large for `long long` is `unsigned long long` as in GCC.
+    fn dtype_of_integer(value: u128, integer: &ast::Integer) -> Result<Dtype, ()> {
+        let candidates = [Dtype::INT, Dtype::LONG, Dtype::LONGLONG];
+        let sizes = match integer.suffix.size {
+            ast::IntegerSize::Int => &candidates[..],
+            ast::IntegerSize::Long => &candidates[1..],
+            ast::IntegerSize::LongLong => &candidates[2..],
+        };
+        let signs: &[bool] = if integer.suffix.unsigned {
+            &[false]
+        } else if integer.base == ast::IntegerBase::Decimal {
+            &[true]
+        } else {
+            &[true, false]
+        };
+
+        let fits = |dtype: &Dtype| {
+            let width = dtype.get_int_width().unwrap() - usize::from(dtype.is_int_signed());
+            value < 1u128 << width
+        };
+        sizes
+            .iter()
+            .flat_map(|size| signs.iter().map(|is_signed| size.set_signed(*is_signed)))
+            .find(fits)
+            .or_else(|| Some(Dtype::LONGLONG.set_signed(false)).filter(fits))
+            .ok_or(())
+    }
+
     #[inline]
     pub fn is_integer_constant(&self) -> bool {
         matches!(self, Self::Int { .. })
@@ -853,6 +1351,9 @@ impl Constant {
         }
 
         match (&self, &target_dtype) {
+            (Constant::Int { value, .. }, Dtype::Int { width: 1, .. }) => {
+                Constant::int((*value != 0).into(), target_dtype)
+            }
             (
                 Constant::Int { value, width, .. },
                 Dtype::Int {
@@ -906,7 +1407,14 @@ impl Constant {
 }
 
 #[inline]
+/// Interprets the lower `width` bits of `value` as a signed integer, and extends its sign bit to
+/// the upper bits.
 pub fn sign_extension(value: u128, width: u128) -> u128 {
+    if width >= u128::BITS as u128 {
+        return value;
+    }
+
+    let value = trim_unnecessary_bits(value, width);
     let base = 1u128 << (width - 1);
     if value >= base {
         let bit_mask = -1i128 << (width as i128);
@@ -916,8 +1424,13 @@ pub fn sign_extension(value: u128, width: u128) -> u128 {
     }
 }
 
+/// Clears the bits of `value` above the lower `width` bits.
 #[inline]
 pub fn trim_unnecessary_bits(value: u128, width: u128) -> u128 {
+    if width >= u128::BITS as u128 {
+        return value;
+    }
+
     let bit_mask = (1u128 << width) - 1;
     value & bit_mask
 }
@@ -965,10 +1478,45 @@ impl HasDtype for Constant {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// A position in the C source a part of the IR is translated from, counting lines and columns
+/// from 1. Columns count bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SourceLoc {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for SourceLoc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// A value with an optional name and source location.
+///
+/// The source location is not printed, and is ignored when comparing or hashing.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Named<T> {
     name: Option<String>,
     inner: T,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    loc: Option<SourceLoc>,
+}
+
+impl<T: PartialEq> PartialEq for Named<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && self.inner == other.inner
+    }
+}
+
+impl<T: Eq> Eq for Named<T> {}
+
+impl<T: Hash> Hash for Named<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.name.hash(state);
+        self.inner.hash(state);
+    }
 }
 
 impl<T> Deref for Named<T> {
@@ -986,13 +1534,26 @@ impl<T> DerefMut for Named<T> {
 
 impl<T> Named<T> {
     pub fn new(name: Option<String>, inner: T) -> Self {
-        Self { name, inner }
+        Self {
+            name,
+            inner,
+            loc: None,
+        }
+    }
+
+    pub fn with_loc(self, loc: Option<SourceLoc>) -> Self {
+        Self { loc, ..self }
     }
 
     pub fn name(&self) -> Option<&String> {
         self.name.as_ref()
     }
 
+    /// Where the value is in the C source, if known.
+    pub fn loc(&self) -> Option<SourceLoc> {
+        self.loc
+    }
+
     pub fn destruct(self) -> (T, Option<String>) {
         (self.inner, self.name)
     }
@@ -1,5 +1,6 @@
 use std::fs;
 use std::path::Path;
+use std::str::FromStr;
 
 use lang_c::*;
 
@@ -10,20 +11,24 @@ use crate::*;
 
 peg::parser! {
     grammar ir_parse() for str {
-        rule whitespace() = quiet!{[' ' | '\n' | '\t']}
+        rule whitespace() = quiet!{[' ' | '\n' | '\t'] / comment()}
+
+        // Comments run from `;` to the end of the line, e.g., `; error state` after `<unreachable>`.
+        rule comment() = ";" (!"\n" [_])*
 
         rule _() = whitespace()*
 
         rule __() = whitespace()+
 
         pub rule translation_unit() -> TranslationUnit
-            = _ named_structs:(named_struct() ** __) _ ds:(named_decl() ** __) _ {
+            = _ named_structs:(named_struct() ** __) _ ds:(named_decl() ** __) _ {?
                 let mut structs = HashMap::new();
                 for named_struct in &named_structs {
                     let name = named_struct.name.as_ref().unwrap();
                     let struct_type = &named_struct.inner;
-                    let result = structs.insert(name.clone(), struct_type.clone());
-                    assert!(result.is_none());
+                    if structs.insert(name.clone(), struct_type.clone()).is_some() {
+                        return Err("structs of distinct names");
+                    }
                 }
 
                 // Resolve struct type in structs
@@ -36,12 +41,21 @@ peg::parser! {
                 }
 
                 let mut decls = BTreeMap::new();
+                let mut decl_order = Vec::new();
                 for decl in ds {
-                    let result = decls.insert(decl.name.unwrap(), decl.inner);
-                    assert!(result.is_none());
+                    let name = decl.name.unwrap();
+                    decl_order.push(name.clone());
+                    if decls.insert(name, decl.inner).is_some() {
+                        return Err("declarations of distinct names");
+                    }
                 }
 
-                TranslationUnit { decls, structs }
+                Ok(TranslationUnit {
+                    decls,
+                    decl_order,
+                    structs,
+                    decl_locs: BTreeMap::new(),
+                })
             }
 
         rule named_struct() -> Named<Option<Dtype>> =
@@ -53,15 +67,28 @@ peg::parser! {
                 let struct_type = Dtype::structure(Some(id.clone()), Some(fields));
                 Named::new(Some(id), Some(struct_type))
             }
+        /
+            "union" __ id:id() _ ":" _ "{" _ fields:(struct_field() ** (_ "," _)) _ "}"  {
+                let union_type = Dtype::union(Some(id.clone()), Some(fields));
+                Named::new(Some(id), Some(union_type))
+            }
         /
             "<named_struct>" {
                 todo!()
             }
 
         rule struct_field() -> Named<Dtype> =
+            "%anon" _ ":" _ dtype:dtype() _ ":" _ width:number() {
+                Named::new(None, Dtype::bit_field(dtype, width))
+            }
+        /
             "%anon" _ ":" _ dtype:dtype() {
                 Named::new(None, dtype)
             }
+        /
+            id:id() _ ":" _ dtype:dtype() _ ":" _ width:number() {
+                Named::new(Some(id), Dtype::bit_field(dtype, width))
+            }
         /
             id:id() _ ":" _ dtype:dtype() {
                 Named::new(Some(id), dtype)
@@ -72,32 +99,52 @@ peg::parser! {
             }
 
         rule named_decl() -> Named<Declaration> =
-            "var" __ dtype:dtype() __ var:global_variable() _ "=" _ initializer:initializer() {
+            linkage:linkage() "var" __ dtype:dtype() __ var:global_variable() _ "=" _ initializer:initializer() {
                 Named::new(Some(var), Declaration::Variable {
                     dtype,
                     initializer,
+                    linkage,
+                    is_definition: true,
+                })
+            }
+        /
+            linkage:linkage() "var" __ dtype:dtype() __ var:global_variable() {
+                Named::new(Some(var), Declaration::Variable {
+                    dtype,
+                    initializer: None,
+                    linkage,
+                    is_definition: false,
                 })
             }
         /
-            "fun" __ dtype:dtype() __ var:global_variable() _ "(" params:(dtype() ** (_ "," _)) _ ")" _ "{" _ fun_body:fun_body() _ "}" {
+            linkage:linkage() "fun" __ dtype:dtype() __ var:global_variable() _ "(" params:(dtype() ** (_ "," _)) _ ")" _ "{" _ fun_body:fun_body() _ "}" {
                 Named::new(Some(var), Declaration::Function {
                     signature: FunctionSignature::new(Dtype::function(dtype, params)),
                     definition: Some(fun_body),
+                    linkage,
                 })
             }
         /
-            "fun" __ dtype:dtype() __ var:global_variable() _ "(" params:(dtype() ** (_ "," _)) _ ")" {
+            linkage:linkage() "fun" __ dtype:dtype() __ var:global_variable() _ "(" params:(dtype() ** (_ "," _)) _ ")" {
                 Named::new(Some(var), Declaration::Function {
                     signature: FunctionSignature::new(Dtype::function(dtype, params)),
                     definition: None,
+                    linkage,
                 })
             }
 
+        rule linkage() -> Linkage =
+            "static" __ { Linkage::Internal }
+        /
+            "" { Linkage::External }
+
         rule dtype() -> Dtype =
-            inner:dtype_inner() is_consts:(is_const_of_pointer() ** _) {
+            inner:dtype_inner() qualifiers:(qualifiers_of_pointer() ** _) {
                 let mut inner = inner;
-                for is_const in is_consts {
-                    inner = Dtype::pointer(inner).set_const(is_const);
+                for (is_const, is_volatile) in qualifiers {
+                    inner = Dtype::pointer(inner)
+                        .set_const(is_const)
+                        .set_volatile(is_volatile);
                 }
                 inner
             }
@@ -122,15 +169,25 @@ peg::parser! {
             "struct" __ id:id() {
                 Dtype::structure(Some(id), None)
             }
+        /
+            "union" __ id:id() {
+                Dtype::union(Some(id), None)
+            }
         /
             "const" __ dtype:dtype_inner() { dtype.set_const(true) }
+        /
+            "volatile" __ dtype:dtype_inner() { dtype.set_volatile(true) }
         /
             expected!("dtype")
 
-        rule is_const_of_pointer() -> bool =
-            _ "*" _ "const" { true }
+        rule qualifiers_of_pointer() -> (bool, bool) =
+            _ "*" _ "const" __ "volatile" { (true, true) }
+        /
+            _ "*" _ "const" { (true, false) }
+        /
+            _ "*" _ "volatile" { (false, true) }
         /
-            _ "*" { false }
+            _ "*" { (false, false) }
 
         rule id() -> String =
             n:$(['_' | 'a'..='z' | 'A'..='Z']['_' | 'a'..='z' | 'A'..='Z' | '0'..='9']*) {
@@ -164,8 +221,8 @@ peg::parser! {
             }
 
         rule allocation() -> (usize, Named<Dtype>)
-            = "%l" number:number() ":" dtype:dtype() ":" name:id() {
-                (number, Named::new(Some(name), dtype))
+            = "%l" number:number() ":" dtype:dtype() name:(":" name:id() { name })? {
+                (number, Named::new(name, dtype))
             }
 
         rule block() -> (BlockId, Block)
@@ -192,6 +249,12 @@ peg::parser! {
             }
         / expected!("number")
 
+        // Integer constants may take up to 128 bits.
+        rule wide_number() -> u128
+            = n:$(['0'..='9']+) {?
+                n.parse().or(Err("128-bit number"))
+            }
+
         rule float_number() -> f64
             = f:$(['0'..='9']+['.']['0'..='9']+) {
                 f.parse().unwrap()
@@ -217,27 +280,61 @@ peg::parser! {
                 // the dtype of the result can be `*i32` or `*[5 x i32]` in the current KECC.
                 // For this reason, we need to check the dtype of the result to confirm the dtype
                 // of `GetElementPtr` instruction when parsing IR.
-                let instruction = if let Instruction::GetElementPtr { ptr, offset, .. } = instruction {
-                    Instruction::GetElementPtr { ptr, offset, dtype }
-                } else {
-                    instruction
+                //
+                // Similarly, the dtype of `UnaryOp` and `BinOp` instructions is that of the result,
+                // e.g., `i1` for `negate`, which may differ from that of their operands.
+                let instruction = match instruction {
+                    Instruction::GetElementPtr { ptr, offset, .. } => {
+                        Instruction::GetElementPtr { ptr, offset, dtype }
+                    }
+                    Instruction::UnaryOp { op, operand, .. } => {
+                        Instruction::UnaryOp { op, operand, dtype }
+                    }
+                    Instruction::BinOp {
+                        op, lhs, rhs, flags, ..
+                    } => Instruction::BinOp {
+                        op,
+                        lhs,
+                        rhs,
+                        dtype,
+                        flags,
+                    },
+                    instruction => instruction,
                 };
 
                 (bid, number, Named::new(name, instruction))
             }
         / expected!("instruction")
 
+        rule volatile() -> bool =
+            "volatile" __ {
+                true
+            }
+        /
+            "" {
+                false
+            }
+
+        rule binop_flags() -> BinOpFlags =
+            nuw:(__ "nuw")? nsw:(__ "nsw")? exact:(__ "exact")? {
+                BinOpFlags {
+                    nuw: nuw.is_some(),
+                    nsw: nsw.is_some(),
+                    exact: exact.is_some(),
+                }
+            }
+
         rule instruction_inner() -> Instruction =
             "nop" {
                 Instruction::Nop
             }
         /
-            "load" __ ptr:operand() {
-                Instruction::Load { ptr }
+            "load" __ volatile:volatile() ptr:operand() {
+                Instruction::Load { ptr, volatile }
             }
         /
-            "store" __ value:operand() __ ptr:operand() {
-                Instruction::Store { ptr, value }
+            "store" __ volatile:volatile() value:operand() __ ptr:operand() {
+                Instruction::Store { ptr, value, volatile }
             }
         /
             "call" __ callee:operand() _ "(" _ args:(operand() ** (_ "," _)) _ ")" {
@@ -260,6 +357,16 @@ peg::parser! {
             "typecast" __ value:operand() __ "to" __ target_dtype:dtype() {
                 Instruction::TypeCast { value, target_dtype }
             }
+        /
+            "select" __ condition:operand() _ "," _ then_value:operand() _ "," _ else_value:operand() {
+                let dtype = then_value.dtype();
+                Instruction::Select {
+                    condition,
+                    then_value,
+                    else_value,
+                    dtype,
+                }
+            }
         /
             op:unary_op() __ operand:operand() {
                 let dtype = operand.dtype();
@@ -270,7 +377,7 @@ peg::parser! {
                 }
             }
         /
-            op:arith_op() __ lhs:operand() __ rhs:operand() {
+            op:arith_op() flags:binop_flags() __ lhs:operand() __ rhs:operand() {
                 let dtype = lhs.dtype();
                 assert_eq!(&dtype, &rhs.dtype());
                 Instruction::BinOp {
@@ -278,10 +385,11 @@ peg::parser! {
                     lhs,
                     rhs,
                     dtype,
+                    flags,
                 }
             }
         /
-            op:shift_op() __ lhs:operand() __ rhs:operand() {
+            op:shift_op() flags:binop_flags() __ lhs:operand() __ rhs:operand() {
                 let dtype = lhs.dtype();
                 assert_eq!(&dtype, &rhs.dtype());
                 Instruction::BinOp {
@@ -289,6 +397,7 @@ peg::parser! {
                     lhs,
                     rhs,
                     dtype,
+                    flags,
                 }
             }
         /
@@ -299,10 +408,11 @@ peg::parser! {
                     lhs,
                     rhs,
                     dtype: Dtype::BOOL,
+                    flags: BinOpFlags::default(),
                 }
             }
         /
-            op:bitwise_op() __ lhs:operand() __ rhs:operand() {
+            op:bitwise_op() flags:binop_flags() __ lhs:operand() __ rhs:operand() {
                 let dtype = lhs.dtype();
                 assert_eq!(&dtype, &rhs.dtype());
                 Instruction::BinOp {
@@ -310,6 +420,7 @@ peg::parser! {
                     lhs,
                     rhs,
                     dtype,
+                    flags,
                 }
             }
         /
@@ -368,6 +479,8 @@ peg::parser! {
             "minus" { ast::UnaryOperator::Minus }
         /
             "negate" { ast::UnaryOperator::Negate }
+        /
+            "not" { ast::UnaryOperator::Complement }
 
         rule exit() -> BlockExit =
             "j" __ arg:jump_arg() {
@@ -386,7 +499,7 @@ peg::parser! {
                 BlockExit::Return { value }
             }
         /
-            "unreachable" {
+            ("<unreachable>" / "unreachable") {
                 BlockExit::Unreachable
             }
 
@@ -399,12 +512,12 @@ peg::parser! {
                 Constant::minus(Constant::float(f, Dtype::float(64))) // TODO: the right dtype
             }
         /
-            n:number() {
-                Constant::int(n as _, Dtype::int(128)) // TODO: the right dtype
+            n:wide_number() {
+                Constant::int(n, Dtype::int(128)) // TODO: the right dtype
             }
         /
-            "-" n:number() {
-                Constant::minus(Constant::int(n as _, Dtype::int(128))) // TODO: the right dtype
+            "-" n:wide_number() {
+                Constant::minus(Constant::int(n, Dtype::int(128))) // TODO: the right dtype
             }
         /
             "undef" {
@@ -497,7 +610,7 @@ peg::parser! {
                 todo!()
             }
 
-        rule ast_initializer() -> ast::Initializer =
+        pub rule ast_initializer() -> ast::Initializer =
             expr:ast_expression() {
                 let expr = Box::new(span::Node::new(expr, span::Span::none()));
                 ast::Initializer::Expression(expr)
@@ -523,6 +636,18 @@ peg::parser! {
             }
 
         rule ast_expression() -> ast::Expression =
+            "(" _ "&(" _ name:id() _ ")" _ ")" _ minus:("+" { false } / "-" { true }) _ offset:ast_integer() {?
+                let offset = Constant::try_from(&ast::Constant::Integer(offset))
+                    .ok()
+                    .and_then(|offset| Some(offset.get_int()?.0 as isize))
+                    .ok_or("offset")?;
+                Ok(address_initializer(&name, if minus { -offset } else { offset }))
+            }
+        /
+            "&(" _ name:id() _ ")" {
+                address_initializer(&name, 0)
+            }
+        /
             constant:ast_constant() {
                 let constant = Box::new(span::Node::new(constant, span::Span::none()));
                 ast::Expression::Constant(constant)
@@ -567,6 +692,10 @@ peg::parser! {
             integer:ast_integer() {
                 ast::Constant::Integer(integer)
             }
+        /
+            character:$("'" ("\\" [_] / [^ '\'' | '\\'])+ "'") {
+                ast::Constant::Character(character.to_string())
+            }
         /
             "<ast_constant>" {
                 todo!()
@@ -615,22 +744,35 @@ peg::parser! {
             }
 
         rule ast_integer_suffix() -> ast::IntegerSuffix =
-            ['l' | 'L'] {
+            ['u' | 'U'] size:ast_integer_size() {
                 ast::IntegerSuffix {
-                    size: ast::IntegerSize::Long,
-                    unsigned: false,
+                    size,
+                    unsigned: true,
                     imaginary: false,
                 }
             }
         /
-            "" {
+            size:ast_integer_size() unsigned:['u' | 'U']? {
                 ast::IntegerSuffix {
-                    size: ast::IntegerSize::Int,
-                    unsigned: false,
+                    size,
+                    unsigned: unsigned.is_some(),
                     imaginary: false,
                 }
             }
 
+        rule ast_integer_size() -> ast::IntegerSize =
+            ("ll" / "LL") {
+                ast::IntegerSize::LongLong
+            }
+        /
+            ['l' | 'L'] {
+                ast::IntegerSize::Long
+            }
+        /
+            "" {
+                ast::IntegerSize::Int
+            }
+
         rule ast_float() ->  ast::Float =
             number:$(['0'..='9']+['.']['0'..='9']*) suffix:ast_float_suffix() {
                 ast::Float {
@@ -651,6 +793,13 @@ peg::parser! {
                     imaginary: false,
                 }
             }
+        /
+            ['l' | 'L'] {
+                ast::FloatSuffix {
+                    format: ast::FloatFormat::LongDouble,
+                    imaginary: false,
+                }
+            }
         /
             "" {
                 ast::FloatSuffix {
@@ -677,10 +826,28 @@ impl<P: AsRef<Path>> Translate<P> for Parse {
 
     fn translate(&mut self, source: &P) -> Result<Self::Target, Self::Error> {
         let ir = fs::read_to_string(source).map_err(Error::Io)?;
-        ir_parse::translation_unit(&ir).map_err(Error::Parse)
+        ir.parse()
+    }
+}
+
+impl FromStr for TranslationUnit {
+    type Err = Error;
+
+    fn from_str(ir: &str) -> Result<Self, Self::Err> {
+        ir_parse::translation_unit(ir).map_err(Error::Parse)
     }
 }
 
+/// Parses a global variable initializer in the form it is printed in the textual IR.
+#[cfg(feature = "serde")]
+pub(crate) fn initializer(
+    text: &str,
+) -> Result<ast::Initializer, peg::error::ParseError<peg::str::LineCol>> {
+    let initializer = ir_parse::ast_initializer(text)?;
+    initializer.assert_supported();
+    Ok(initializer)
+}
+
 #[inline]
 fn resolve_structs(struct_type: Dtype, structs: &mut HashMap<String, Option<Dtype>>) {
     let name = struct_type
@@ -0,0 +1,189 @@
+use core::fmt;
+use std::collections::BTreeMap;
+use std::io::{Result, Write};
+
+use crate::ir::*;
+use crate::write_base::*;
+use crate::*;
+
+/// Execution counts of a block.
+#[derive(Default, Debug, PartialEq, Eq, Clone, Copy)]
+pub struct BlockCount {
+    /// The number of times the block is entered.
+    pub hits: usize,
+    /// The number of executed instructions of the block, where executing the block exit counts as
+    /// an instruction.
+    pub instructions: usize,
+}
+
+/// Execution counts of the blocks of a program, collected by the interpreter.
+///
+/// A profile is exported with one line `@<function> <block> <hits> <instructions>` per executed
+/// block, e.g., `@main b2 10 43`, and read back with [`Profile::parse`], so that the counts of a
+/// run can guide the optimization of the same IR, e.g., by [`crate::opt::ProfileGuidedUnroll`].
+#[derive(Default, Debug, PartialEq, Eq, Clone)]
+pub struct Profile {
+    functions: BTreeMap<String, BTreeMap<BlockId, BlockCount>>,
+}
+
+impl Profile {
+    /// Counts the execution of the instruction (or block exit) at `pc` of `func_name`.
+    pub(crate) fn count(&mut self, func_name: &str, pc: Pc) {
+        let blocks = if let Some(blocks) = self.functions.get_mut(func_name) {
+            blocks
+        } else {
+            self.functions.entry(func_name.to_string()).or_default()
+        };
+        let count = blocks.entry(pc.bid).or_default();
+
+        if pc.iid == 0 {
+            count.hits += 1;
+        }
+        count.instructions += 1;
+    }
+
+    /// Parses a profile exported by its [`fmt::Display`] implementation.
+    pub fn parse(text: &str) -> std::result::Result<Self, String> {
+        let mut profile = Self::default();
+        for line in text.lines().filter(|line| !line.trim().is_empty()) {
+            let malformed = || format!("malformed entry `{line}`");
+            let [func_name, bid, hits, instructions] =
+                line.split_whitespace().collect::<Vec<_>>()[..]
+            else {
+                return Err(malformed());
+            };
+            let func_name = func_name.strip_prefix('@').ok_or_else(malformed)?;
+            let bid = bid
+                .strip_prefix('b')
+                .and_then(|bid| bid.parse::<usize>().ok())
+                .ok_or_else(malformed)?;
+            let count = BlockCount {
+                hits: hits.parse::<usize>().map_err(|e| e.to_string())?,
+                instructions: instructions.parse::<usize>().map_err(|e| e.to_string())?,
+            };
+            let _unused = profile
+                .functions
+                .entry(func_name.to_string())
+                .or_default()
+                .insert(BlockId(bid), count);
+        }
+        Ok(profile)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.functions.is_empty()
+    }
+
+    /// Execution counts of the block `bid` of `func_name`. Blocks that are never executed have
+    /// zero counts.
+    pub fn block(&self, func_name: &str, bid: BlockId) -> BlockCount {
+        self.functions
+            .get(func_name)
+            .and_then(|blocks| blocks.get(&bid))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Executed blocks of `func_name` with their counts.
+    pub fn blocks(&self, func_name: &str) -> impl Iterator<Item = (BlockId, BlockCount)> + '_ {
+        self.functions
+            .get(func_name)
+            .into_iter()
+            .flat_map(|blocks| blocks.iter().map(|(bid, count)| (*bid, *count)))
+    }
+
+    /// The number of executed instructions of `func_name`, excluding those of its callees.
+    pub fn function_instructions(&self, func_name: &str) -> usize {
+        self.blocks(func_name)
+            .map(|(_, count)| count.instructions)
+            .sum()
+    }
+
+    /// The number of executed instructions of the whole program.
+    pub fn total_instructions(&self) -> usize {
+        self.functions
+            .keys()
+            .map(|func_name| self.function_instructions(func_name))
+            .sum()
+    }
+
+    /// Writes a report of where the execution of `ir` spent its time: functions and blocks ranked
+    /// by executed instructions, followed by the IR of the executed functions annotated with the
+    /// counts of each block.
+    pub fn write_report(&self, ir: &TranslationUnit, write: &mut dyn Write) -> Result<()> {
+        let total = self.total_instructions().max(1);
+        let percent = |instructions: usize| instructions as f64 * 100.0 / total as f64;
+
+        let mut functions = self
+            .functions
+            .keys()
+            .map(|func_name| (self.function_instructions(func_name), func_name))
+            .collect::<Vec<_>>();
+        functions.sort_by(|lhs, rhs| rhs.cmp(lhs));
+
+        writeln!(write, "functions by executed instructions:")?;
+        for (instructions, func_name) in &functions {
+            writeln!(
+                write,
+                "{instructions:>12} {:>6.2}%  @{func_name}",
+                percent(*instructions)
+            )?;
+        }
+
+        let mut blocks = self
+            .functions
+            .iter()
+            .flat_map(|(func_name, blocks)| {
+                blocks
+                    .iter()
+                    .map(move |(bid, count)| (count.instructions, count.hits, func_name, *bid))
+            })
+            .collect::<Vec<_>>();
+        blocks.sort_by(|lhs, rhs| rhs.cmp(lhs));
+
+        writeln!(write, "\nblocks by executed instructions:")?;
+        for (instructions, hits, func_name, bid) in &blocks {
+            writeln!(
+                write,
+                "{instructions:>12} {:>6.2}%  @{func_name}:{bid} ({hits} hits)",
+                percent(*instructions)
+            )?;
+        }
+
+        for (name, decl) in &ir.decls {
+            let (_, definition) = some_or!(decl.get_function(), continue);
+            let definition = some_or!(definition.as_ref(), continue);
+            if !self.functions.contains_key(name) {
+                continue;
+            }
+
+            writeln!(write, "\nfun @{name}:")?;
+            for (bid, block) in &definition.blocks {
+                let count = self.block(name, *bid);
+                writeln!(
+                    write,
+                    "\nblock {bid}:\t\t\t\t; {} hits, {} instructions",
+                    count.hits, count.instructions
+                )?;
+                (bid, block).write_line(1, write)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (func_name, blocks) in &self.functions {
+            for (bid, count) in blocks {
+                writeln!(
+                    f,
+                    "@{func_name} {bid} {} {}",
+                    count.hits, count.instructions
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
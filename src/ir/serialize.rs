@@ -0,0 +1,209 @@
+//! Serialization of the parts of the IR borrowed from the C AST, which do not implement serde's
+//! traits themselves.
+//!
+//! Operators are serialized as they are printed in the textual IR, e.g., `"add"` or `"cmp eq"`,
+//! and initializers as they are printed in global variable declarations, e.g., `"{1, -(2)}"`.
+
+use lang_c::ast;
+use serde::de::Error as _;
+use serde::ser::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::ir::parse;
+use crate::write_base::*;
+
+const BINARY_OPERATORS: [ast::BinaryOperator; 16] = [
+    ast::BinaryOperator::Multiply,
+    ast::BinaryOperator::Divide,
+    ast::BinaryOperator::Modulo,
+    ast::BinaryOperator::Plus,
+    ast::BinaryOperator::Minus,
+    ast::BinaryOperator::ShiftLeft,
+    ast::BinaryOperator::ShiftRight,
+    ast::BinaryOperator::Equals,
+    ast::BinaryOperator::NotEquals,
+    ast::BinaryOperator::Less,
+    ast::BinaryOperator::LessOrEqual,
+    ast::BinaryOperator::Greater,
+    ast::BinaryOperator::GreaterOrEqual,
+    ast::BinaryOperator::BitwiseAnd,
+    ast::BinaryOperator::BitwiseXor,
+    ast::BinaryOperator::BitwiseOr,
+];
+
+const UNARY_OPERATORS: [ast::UnaryOperator; 4] = [
+    ast::UnaryOperator::Plus,
+    ast::UnaryOperator::Minus,
+    ast::UnaryOperator::Negate,
+    ast::UnaryOperator::Complement,
+];
+
+fn serialize_operator<T, S>(op: &T, operators: &[T], serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: WriteOp + PartialEq + std::fmt::Debug,
+    S: Serializer,
+{
+    if !operators.contains(op) {
+        return Err(S::Error::custom(format!("unsupported operator {op:?}")));
+    }
+    op.write_operation().serialize(serializer)
+}
+
+fn deserialize_operator<'de, T, D>(operators: &[T], deserializer: D) -> Result<T, D::Error>
+where
+    T: WriteOp + Clone,
+    D: Deserializer<'de>,
+{
+    let name = String::deserialize(deserializer)?;
+    operators
+        .iter()
+        .find(|op| op.write_operation() == name)
+        .cloned()
+        .ok_or_else(|| D::Error::custom(format!("unknown operator `{name}`")))
+}
+
+pub(crate) mod binary_operator {
+    use super::*;
+
+    pub(crate) fn serialize<S: Serializer>(
+        op: &ast::BinaryOperator,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serialize_operator(op, &BINARY_OPERATORS, serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<ast::BinaryOperator, D::Error> {
+        deserialize_operator(&BINARY_OPERATORS, deserializer)
+    }
+}
+
+pub(crate) mod unary_operator {
+    use super::*;
+
+    pub(crate) fn serialize<S: Serializer>(
+        op: &ast::UnaryOperator,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serialize_operator(op, &UNARY_OPERATORS, serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<ast::UnaryOperator, D::Error> {
+        deserialize_operator(&UNARY_OPERATORS, deserializer)
+    }
+}
+
+pub(crate) mod initializer {
+    use super::*;
+
+    pub(crate) fn serialize<S: Serializer>(
+        initializer: &Option<ast::Initializer>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        initializer
+            .as_ref()
+            .map(write_initializer)
+            .transpose()
+            .map_err(S::Error::custom)?
+            .serialize(serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<ast::Initializer>, D::Error> {
+        Option::<String>::deserialize(deserializer)?
+            .map(|text| parse::initializer(&text))
+            .transpose()
+            .map_err(|e| D::Error::custom(format!("invalid initializer: {e:?}")))
+    }
+}
+
+/// Writes `initializer` in the syntax the IR parser accepts.
+fn write_initializer(initializer: &ast::Initializer) -> Result<String, String> {
+    match initializer {
+        ast::Initializer::Expression(expr) => write_expression(&expr.node),
+        ast::Initializer::List(items) => {
+            let items = items
+                .iter()
+                .map(|item| {
+                    if !item.node.designation.is_empty() {
+                        return Err("unsupported designated initializer".to_string());
+                    }
+                    write_initializer(&item.node.initializer.node)
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(format!("{{{}}}", items.join(", ")))
+        }
+    }
+}
+
+fn write_expression(expr: &ast::Expression) -> Result<String, String> {
+    match expr {
+        ast::Expression::Constant(constant) => write_constant(&constant.node),
+        ast::Expression::UnaryOperator(unary) => {
+            let op = match unary.node.operator.node {
+                ast::UnaryOperator::Plus => "+",
+                ast::UnaryOperator::Minus => "-",
+                _ => return Err(format!("unsupported initializer {expr:?}")),
+            };
+            let ast::Expression::Constant(constant) = &unary.node.operand.node else {
+                return Err(format!("unsupported initializer {expr:?}"));
+            };
+            Ok(format!("{op}({})", write_constant(&constant.node)?))
+        }
+        _ => Err(format!("unsupported initializer {expr:?}")),
+    }
+}
+
+fn write_constant(constant: &ast::Constant) -> Result<String, String> {
+    match constant {
+        ast::Constant::Integer(integer) => {
+            let base = match integer.base {
+                ast::IntegerBase::Decimal => "",
+                ast::IntegerBase::Octal => "0",
+                ast::IntegerBase::Hexadecimal => "0x",
+                ast::IntegerBase::Binary => {
+                    return Err(format!("unsupported integer {integer:?}"));
+                }
+            };
+            let suffix = match &integer.suffix {
+                ast::IntegerSuffix {
+                    size: ast::IntegerSize::Int,
+                    unsigned: false,
+                    imaginary: false,
+                } => "",
+                ast::IntegerSuffix {
+                    size: ast::IntegerSize::Long,
+                    unsigned: false,
+                    imaginary: false,
+                } => "l",
+                _ => return Err(format!("unsupported integer {integer:?}")),
+            };
+            Ok(format!("{base}{}{suffix}", integer.number))
+        }
+        ast::Constant::Float(float) => {
+            let suffix = match (&float.base, &float.suffix) {
+                (
+                    ast::FloatBase::Decimal,
+                    ast::FloatSuffix {
+                        format: ast::FloatFormat::Float,
+                        imaginary: false,
+                    },
+                ) => "f",
+                (
+                    ast::FloatBase::Decimal,
+                    ast::FloatSuffix {
+                        format: ast::FloatFormat::Double,
+                        imaginary: false,
+                    },
+                ) => "",
+                _ => return Err(format!("unsupported float {float:?}")),
+            };
+            Ok(format!("{}{suffix}", float.number))
+        }
+        ast::Constant::Character(_) => Err(format!("unsupported constant {constant:?}")),
+    }
+}
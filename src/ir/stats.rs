@@ -0,0 +1,147 @@
+use core::fmt;
+use std::collections::BTreeMap;
+
+use crate::ir::*;
+use crate::write_base::*;
+
+/// Static counts of the code of a function definition.
+#[derive(Default, Debug, PartialEq, Eq, Clone)]
+pub struct FunctionStats {
+    pub blocks: usize,
+    pub phinodes: usize,
+    /// The number of local allocations.
+    pub allocations: usize,
+    /// The number of instructions of each opcode, e.g., `add` or `cmp lt`, where block exits are
+    /// not instructions.
+    pub instructions: BTreeMap<String, usize>,
+}
+
+impl FunctionStats {
+    fn new(definition: &FunctionDefinition) -> Self {
+        let mut instructions = BTreeMap::<_, usize>::new();
+        for instr in definition
+            .blocks
+            .values()
+            .flat_map(|block| block.instructions.iter())
+        {
+            *instructions.entry(opcode(instr)).or_default() += 1;
+        }
+
+        Self {
+            blocks: definition.blocks.len(),
+            phinodes: definition
+                .blocks
+                .values()
+                .map(|block| block.phinodes.len())
+                .sum(),
+            allocations: definition.allocations.len(),
+            instructions,
+        }
+    }
+
+    /// The number of instructions of all opcodes.
+    pub fn total_instructions(&self) -> usize {
+        self.instructions.values().sum()
+    }
+
+    fn add(&mut self, other: &Self) {
+        self.blocks += other.blocks;
+        self.phinodes += other.phinodes;
+        self.allocations += other.allocations;
+        for (opcode, count) in &other.instructions {
+            *self.instructions.entry(opcode.clone()).or_default() += count;
+        }
+    }
+}
+
+/// Static counts of the code of a translation unit, e.g., to compare the code before and after
+/// optimizations. See [`stats`].
+#[derive(Default, Debug, PartialEq, Eq, Clone)]
+pub struct Stats {
+    /// The counts of each function definition by its name. Declared but undefined functions are not
+    /// included.
+    pub functions: BTreeMap<String, FunctionStats>,
+}
+
+impl Stats {
+    /// The counts summed over all function definitions.
+    pub fn total(&self) -> FunctionStats {
+        let mut total = FunctionStats::default();
+        for function in self.functions.values() {
+            total.add(function);
+        }
+        total
+    }
+}
+
+/// Counts the functions, blocks, phinodes, allocations and instructions of `ir`.
+///
+/// The counts are printed as a table by the `Display` implementation of [`Stats`].
+pub fn stats(ir: &TranslationUnit) -> Stats {
+    let functions = ir
+        .decls
+        .iter()
+        .filter_map(|(name, decl)| {
+            let (_, definition) = decl.get_function()?;
+            Some((name.clone(), FunctionStats::new(definition.as_ref()?)))
+        })
+        .collect();
+
+    Stats { functions }
+}
+
+fn opcode(instr: &Instruction) -> String {
+    match instr {
+        Instruction::Nop => "nop".to_string(),
+        Instruction::BinOp { op, .. } => op.write_operation(),
+        Instruction::UnaryOp { op, .. } => op.write_operation(),
+        Instruction::Store { .. } => "store".to_string(),
+        Instruction::Load { .. } => "load".to_string(),
+        Instruction::Call { .. } => "call".to_string(),
+        Instruction::TypeCast { .. } => "typecast".to_string(),
+        Instruction::GetElementPtr { .. } => "getelementptr".to_string(),
+        Instruction::Select { .. } => "select".to_string(),
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let total = self.total();
+        let name_width = self
+            .functions
+            .keys()
+            .map(|name| name.len() + 1)
+            .chain(["function".len(), "total".len(), "opcode".len()])
+            .chain(total.instructions.keys().map(String::len))
+            .max()
+            .unwrap_or_default();
+
+        writeln!(
+            f,
+            "{:name_width$} {:>8} {:>8} {:>11} {:>12}",
+            "function", "blocks", "phinodes", "allocations", "instructions"
+        )?;
+        let rows = self
+            .functions
+            .iter()
+            .map(|(name, function)| (format!("@{name}"), function))
+            .chain([("total".to_string(), &total)]);
+        for (name, function) in rows {
+            writeln!(
+                f,
+                "{name:name_width$} {:>8} {:>8} {:>11} {:>12}",
+                function.blocks,
+                function.phinodes,
+                function.allocations,
+                function.total_instructions()
+            )?;
+        }
+
+        writeln!(f, "\n{:name_width$} {:>8}", "opcode", "count")?;
+        for (opcode, count) in &total.instructions {
+            writeln!(f, "{opcode:name_width$} {count:>8}")?;
+        }
+
+        Ok(())
+    }
+}
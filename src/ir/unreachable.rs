@@ -0,0 +1,55 @@
+//! Unreachable blocks.
+
+use std::collections::BTreeSet;
+
+use crate::ir::analysis::Cfg;
+use crate::ir::*;
+use crate::*;
+
+/// The blocks of `definition` not reachable from its entry block, e.g., the blocks irgen starts
+/// after a `return` or a `break`.
+pub fn unreachable_blocks(definition: &FunctionDefinition) -> BTreeSet<BlockId> {
+    let cfg = Cfg::new(definition);
+    definition
+        .blocks
+        .keys()
+        .filter(|bid| !cfg.is_reachable(**bid))
+        .copied()
+        .collect()
+}
+
+/// Removes the blocks of `definition` not reachable from its entry block, returning them.
+///
+/// The remaining blocks never jump to the removed ones, so their jump arguments stay as they are.
+/// Since a register is used only where its definition dominates the use, they do not use the
+/// registers of the removed blocks either, unless the code fails to verify; such operands are
+/// replaced with `undef` of their dtypes.
+pub fn remove_unreachable_blocks(definition: &mut FunctionDefinition) -> BTreeSet<BlockId> {
+    let removed = unreachable_blocks(definition);
+    if removed.is_empty() {
+        return removed;
+    }
+
+    definition.blocks.retain(|bid, _| !removed.contains(bid));
+    Undefiner { removed: &removed }.rewrite_function(definition);
+    removed
+}
+
+/// Replaces the uses of the registers of the removed blocks with `undef`.
+#[derive(Debug)]
+struct Undefiner<'a> {
+    removed: &'a BTreeSet<BlockId>,
+}
+
+impl IrRewriter for Undefiner<'_> {
+    fn rewrite_operand(&mut self, operand: &mut Operand) {
+        let (rid, dtype) = some_or!(operand.get_register(), return);
+        let bid = match rid {
+            RegisterId::Local { .. } => return,
+            RegisterId::Arg { bid, .. } | RegisterId::Temp { bid, .. } => bid,
+        };
+        if self.removed.contains(bid) {
+            *operand = Operand::constant(Constant::undef(dtype.clone()));
+        }
+    }
+}
@@ -0,0 +1,465 @@
+//! Well-formedness checks of the IR.
+
+use itertools::izip;
+use lang_c::ast;
+use thiserror::Error;
+
+use crate::ir::analysis::{Cfg, Dominators};
+use crate::ir::*;
+use crate::*;
+
+/// A violation of the well-formedness of the IR.
+#[derive(Debug, PartialEq, Eq, Clone, Error)]
+pub enum VerifyError {
+    #[error("{func_name} / {msg}")]
+    Function { func_name: String, msg: String },
+    /// `pc.iid` is the number of instructions of the block if the violation is in its exit.
+    #[error("{func_name}:{pc} / {msg}")]
+    Instruction {
+        func_name: String,
+        pc: Pc,
+        msg: String,
+    },
+}
+
+/// Checks that `ir` is well-formed:
+///
+/// - the entry block of each function takes the function's parameters as its phinodes,
+/// - every register is defined before used, i.e., its definition dominates its uses, and is used
+///   with the dtype it is defined with,
+/// - every operand has the dtype its instruction or block exit expects,
+/// - every jump targets an existing block with as many arguments as the target has phinodes, each
+///   with the dtype of the corresponding phinode, and
+/// - every global variable constant refers to a declaration.
+///
+/// Registers of unreachable blocks are checked only for their existence and dtypes.
+pub fn verify(ir: &TranslationUnit) -> Result<(), VerifyError> {
+    for (name, decl) in &ir.decls {
+        let (signature, definition) = some_or!(decl.get_function(), continue);
+        let definition = some_or!(definition.as_ref(), continue);
+        Verifier::new(ir, name, signature, definition)?.verify()?;
+    }
+
+    Ok(())
+}
+
+/// Whether `lhs` and `rhs` are the same dtype, disregarding their top-level qualifiers.
+fn same_dtype(lhs: &Dtype, rhs: &Dtype) -> bool {
+    lhs.clone().unqualified() == rhs.clone().unqualified()
+}
+
+fn is_bool(dtype: &Dtype) -> bool {
+    dtype.get_int_width() == Some(1)
+}
+
+fn is_scalar(dtype: &Dtype) -> bool {
+    matches!(
+        dtype,
+        Dtype::Int { .. } | Dtype::Float { .. } | Dtype::Pointer { .. }
+    )
+}
+
+#[derive(Debug)]
+struct Verifier<'a> {
+    ir: &'a TranslationUnit,
+    func_name: &'a str,
+    signature: &'a FunctionSignature,
+    definition: &'a FunctionDefinition,
+    dominators: Dominators,
+    /// The location being checked.
+    pc: Pc,
+}
+
+impl<'a> Verifier<'a> {
+    fn new(
+        ir: &'a TranslationUnit,
+        func_name: &'a str,
+        signature: &'a FunctionSignature,
+        definition: &'a FunctionDefinition,
+    ) -> Result<Self, VerifyError> {
+        if !definition.blocks.contains_key(&definition.bid_init) {
+            return Err(VerifyError::Function {
+                func_name: func_name.to_string(),
+                msg: format!("entry block {} does not exist", definition.bid_init),
+            });
+        }
+
+        let mut verifier = Self {
+            ir,
+            func_name,
+            signature,
+            definition,
+            dominators: Dominators::new(&Cfg::new(definition)),
+            pc: Pc {
+                bid: definition.bid_init,
+                iid: 0,
+                loc: None,
+            },
+        };
+
+        for (bid, block) in &definition.blocks {
+            verifier.pc = Pc {
+                bid: *bid,
+                iid: block.instructions.len(),
+                loc: None,
+            };
+            for arg in block.exit.jump_args() {
+                if !definition.blocks.contains_key(&arg.bid) {
+                    return Err(verifier.error(format!("jump to nonexistent block {}", arg.bid)));
+                }
+            }
+        }
+
+        Ok(verifier)
+    }
+
+    fn verify(&mut self) -> Result<(), VerifyError> {
+        let entry = &self.definition.blocks[&self.definition.bid_init];
+        if entry.phinodes.len() != self.signature.params.len() {
+            return Err(VerifyError::Function {
+                func_name: self.func_name.to_string(),
+                msg: format!(
+                    "entry block takes {} arguments, but the function takes {} parameters",
+                    entry.phinodes.len(),
+                    self.signature.params.len()
+                ),
+            });
+        }
+        for (aid, (phinode, param)) in izip!(&entry.phinodes, &self.signature.params).enumerate() {
+            if !same_dtype(phinode, param) {
+                return Err(VerifyError::Function {
+                    func_name: self.func_name.to_string(),
+                    msg: format!(
+                        "entry block argument {aid} is `{}`, but the parameter is `{param}`",
+                        &**phinode
+                    ),
+                });
+            }
+        }
+
+        for (aid, allocation) in self.definition.allocations.iter().enumerate() {
+            if allocation.size_align_of(&self.ir.structs).is_err() {
+                return Err(VerifyError::Function {
+                    func_name: self.func_name.to_string(),
+                    msg: format!(
+                        "allocation {aid} has an incomplete dtype `{}`",
+                        &**allocation
+                    ),
+                });
+            }
+        }
+
+        for (bid, block) in &self.definition.blocks {
+            for (iid, instr) in block.instructions.iter().enumerate() {
+                self.pc = Pc {
+                    bid: *bid,
+                    iid,
+                    loc: instr.loc(),
+                };
+                self.verify_instruction(instr)?;
+            }
+
+            self.pc = Pc {
+                bid: *bid,
+                iid: block.instructions.len(),
+                loc: None,
+            };
+            self.verify_exit(&block.exit)?;
+        }
+
+        Ok(())
+    }
+
+    fn error(&self, msg: String) -> VerifyError {
+        VerifyError::Instruction {
+            func_name: self.func_name.to_string(),
+            pc: self.pc,
+            msg,
+        }
+    }
+
+    fn expect(&self, cond: bool, msg: impl FnOnce() -> String) -> Result<(), VerifyError> {
+        if cond {
+            Ok(())
+        } else {
+            Err(self.error(msg()))
+        }
+    }
+
+    /// Checks that `operand` is well-formed, and returns its dtype.
+    fn verify_operand(&self, operand: &Operand) -> Result<Dtype, VerifyError> {
+        match operand {
+            Operand::Constant(constant) => {
+                if let Constant::GlobalVariable { name, dtype } = constant {
+                    let decl = self.ir.decls.get(name).ok_or_else(|| {
+                        self.error(format!("`@{name}` refers to nonexistent declaration"))
+                    })?;
+                    self.expect(same_dtype(&decl.dtype(), dtype), || {
+                        format!(
+                            "`@{name}` is used as `{dtype}`, but declared as `{}`",
+                            decl.dtype()
+                        )
+                    })?;
+                }
+                Ok(constant.dtype())
+            }
+            Operand::Register { rid, dtype } => {
+                let defined = self.verify_register(rid)?;
+                self.expect(same_dtype(&defined, dtype), || {
+                    format!("`{rid}` is used as `{dtype}`, but defined as `{defined}`")
+                })?;
+                Ok(dtype.clone())
+            }
+        }
+    }
+
+    /// Checks that `rid` is defined before the current location, and returns the dtype it is
+    /// defined with.
+    fn verify_register(&self, rid: &RegisterId) -> Result<Dtype, VerifyError> {
+        let undefined = || self.error(format!("`{rid}` is not defined"));
+
+        let (bid, dtype) = match rid {
+            RegisterId::Local { aid } => {
+                let allocation = self
+                    .definition
+                    .allocations
+                    .get(*aid)
+                    .ok_or_else(undefined)?;
+                return Ok(Dtype::pointer((**allocation).clone()));
+            }
+            RegisterId::Arg { bid, aid } => {
+                let block = self.definition.blocks.get(bid).ok_or_else(undefined)?;
+                let phinode = block.phinodes.get(*aid).ok_or_else(undefined)?;
+                (*bid, (**phinode).clone())
+            }
+            RegisterId::Temp { bid, iid } => {
+                let block = self.definition.blocks.get(bid).ok_or_else(undefined)?;
+                let instr = block.instructions.get(*iid).ok_or_else(undefined)?;
+                // Loads from non-pointers have no dtype, and are reported where they are defined.
+                if let Instruction::Load { ptr, .. } = &**instr {
+                    if ptr.dtype().get_pointer_inner().is_none() {
+                        return Err(self.error(format!("`{rid}` is malformed")));
+                    }
+                }
+                if *bid == self.pc.bid && *iid >= self.pc.iid {
+                    return Err(self.error(format!("`{rid}` is used before defined")));
+                }
+                (*bid, instr.dtype())
+            }
+        };
+
+        if self.dominators.is_reachable(self.pc.bid) {
+            self.expect(self.dominators.dominates(bid, self.pc.bid), || {
+                format!("the definition of `{rid}` does not dominate its use")
+            })?;
+        }
+
+        Ok(dtype)
+    }
+
+    fn verify_instruction(&self, instr: &Instruction) -> Result<(), VerifyError> {
+        match instr {
+            Instruction::Nop => {}
+            Instruction::BinOp {
+                op,
+                lhs,
+                rhs,
+                dtype,
+                flags,
+            } => {
+                let lhs = self.verify_operand(lhs)?;
+                let rhs = self.verify_operand(rhs)?;
+                self.expect(
+                    flags.is_empty() || (flags.is_valid_for(op) && lhs.get_int_width().is_some()),
+                    || format!("`{}{flags}` is invalid on `{lhs}`", op.write_operation()),
+                )?;
+                match op {
+                    ast::BinaryOperator::ShiftLeft | ast::BinaryOperator::ShiftRight => {
+                        self.expect(
+                            lhs.get_int_width().is_some() && rhs.get_int_width().is_some(),
+                            || format!("cannot shift `{lhs}` by `{rhs}`"),
+                        )?;
+                        self.expect(same_dtype(&lhs, dtype), || {
+                            format!("shifting `{lhs}` results in `{dtype}`")
+                        })?;
+                    }
+                    ast::BinaryOperator::Equals
+                    | ast::BinaryOperator::NotEquals
+                    | ast::BinaryOperator::Less
+                    | ast::BinaryOperator::Greater
+                    | ast::BinaryOperator::LessOrEqual
+                    | ast::BinaryOperator::GreaterOrEqual => {
+                        self.expect(same_dtype(&lhs, &rhs) && is_scalar(&lhs), || {
+                            format!("cannot compare `{lhs}` with `{rhs}`")
+                        })?;
+                        self.expect(is_bool(dtype), || {
+                            format!("comparison results in `{dtype}`, not `i1`")
+                        })?;
+                    }
+                    _ => {
+                        self.expect(same_dtype(&lhs, &rhs) && is_scalar(&lhs), || {
+                            format!("operands `{lhs}` and `{rhs}` of `{op:?}` mismatch")
+                        })?;
+                        self.expect(same_dtype(&lhs, dtype), || {
+                            format!("`{op:?}` of `{lhs}` operands results in `{dtype}`")
+                        })?;
+                    }
+                }
+            }
+            Instruction::UnaryOp { op, operand, dtype } => {
+                let operand = self.verify_operand(operand)?;
+                self.expect(is_scalar(&operand), || {
+                    format!("`{op:?}` cannot apply to `{operand}`")
+                })?;
+                if *op == ast::UnaryOperator::Negate {
+                    self.expect(is_bool(dtype), || {
+                        format!("negation results in `{dtype}`, not `i1`")
+                    })?;
+                } else {
+                    self.expect(same_dtype(&operand, dtype), || {
+                        format!("`{op:?}` of `{operand}` results in `{dtype}`")
+                    })?;
+                }
+            }
+            Instruction::Store { ptr, value, .. } => {
+                let ptr = self.verify_operand(ptr)?;
+                let value = self.verify_operand(value)?;
+                let inner = ptr
+                    .get_pointer_inner()
+                    .ok_or_else(|| self.error(format!("cannot store to `{ptr}`")))?;
+                self.expect(same_dtype(inner, &value), || {
+                    format!("cannot store `{value}` to `{ptr}`")
+                })?;
+            }
+            Instruction::Load { ptr, .. } => {
+                let ptr = self.verify_operand(ptr)?;
+                self.expect(ptr.get_pointer_inner().is_some(), || {
+                    format!("cannot load from `{ptr}`")
+                })?;
+            }
+            Instruction::Call {
+                callee,
+                args,
+                return_type,
+            } => {
+                let callee = self.verify_operand(callee)?;
+                let (ret, params) = callee
+                    .get_pointer_inner()
+                    .and_then(Dtype::get_function_inner)
+                    .ok_or_else(|| self.error(format!("cannot call `{callee}`")))?;
+                self.expect(args.len() == params.len(), || {
+                    format!(
+                        "`{callee}` takes {} arguments, but {} are given",
+                        params.len(),
+                        args.len()
+                    )
+                })?;
+                for (arg, param) in izip!(args, params) {
+                    let arg = self.verify_operand(arg)?;
+                    self.expect(same_dtype(&arg, param), || {
+                        format!("`{arg}` is given for a `{param}` parameter")
+                    })?;
+                }
+                self.expect(same_dtype(ret, return_type), || {
+                    format!("`{callee}` returns `{ret}`, not `{return_type}`")
+                })?;
+            }
+            Instruction::TypeCast {
+                value,
+                target_dtype,
+            } => {
+                let value = self.verify_operand(value)?;
+                self.expect(is_scalar(&value) && is_scalar(target_dtype), || {
+                    format!("cannot cast `{value}` to `{target_dtype}`")
+                })?;
+            }
+            Instruction::GetElementPtr { ptr, offset, dtype } => {
+                let ptr = self.verify_operand(ptr)?;
+                let offset = self.verify_operand(offset)?;
+                self.expect(ptr.get_pointer_inner().is_some(), || {
+                    format!("`getelementptr` cannot apply to `{ptr}`")
+                })?;
+                self.expect(offset.get_int_width().is_some(), || {
+                    format!("`getelementptr` cannot offset by `{offset}`")
+                })?;
+                self.expect(dtype.get_pointer_inner().is_some(), || {
+                    format!("`getelementptr` results in `{dtype}`, not a pointer")
+                })?;
+            }
+            Instruction::Select {
+                condition,
+                then_value,
+                else_value,
+                dtype,
+            } => {
+                let condition = self.verify_operand(condition)?;
+                let then_value = self.verify_operand(then_value)?;
+                let else_value = self.verify_operand(else_value)?;
+                self.expect(is_bool(&condition), || {
+                    format!("select condition is `{condition}`, not `i1`")
+                })?;
+                self.expect(
+                    same_dtype(&then_value, dtype) && same_dtype(&else_value, dtype),
+                    || format!("cannot select `{then_value}` or `{else_value}` as `{dtype}`"),
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn verify_exit(&self, exit: &BlockExit) -> Result<(), VerifyError> {
+        match exit {
+            BlockExit::Jump { .. } | BlockExit::Unreachable => {}
+            BlockExit::ConditionalJump { condition, .. } => {
+                let condition = self.verify_operand(condition)?;
+                self.expect(is_bool(&condition), || {
+                    format!("branch condition is `{condition}`, not `i1`")
+                })?;
+            }
+            BlockExit::Switch { value, cases, .. } => {
+                let value = self.verify_operand(value)?;
+                self.expect(value.get_int_width().is_some(), || {
+                    format!("cannot switch on `{value}`")
+                })?;
+                for (case, _) in cases {
+                    self.expect(same_dtype(&case.dtype(), &value), || {
+                        format!("case `{case}` does not match `{value}`")
+                    })?;
+                }
+            }
+            BlockExit::Return { value } => {
+                let value = self.verify_operand(value)?;
+                self.expect(same_dtype(&value, &self.signature.ret), || {
+                    format!(
+                        "returns `{value}`, but the function returns `{}`",
+                        self.signature.ret
+                    )
+                })?;
+            }
+        }
+
+        for arg in exit.jump_args() {
+            let target = &self.definition.blocks[&arg.bid];
+            self.expect(arg.args.len() == target.phinodes.len(), || {
+                format!(
+                    "{} takes {} arguments, but {} are given",
+                    arg.bid,
+                    target.phinodes.len(),
+                    arg.args.len()
+                )
+            })?;
+            for (operand, phinode) in izip!(&arg.args, &target.phinodes) {
+                let dtype = self.verify_operand(operand)?;
+                self.expect(same_dtype(&dtype, phinode), || {
+                    format!(
+                        "`{dtype}` is given for a `{}` argument of {}",
+                        &**phinode, arg.bid
+                    )
+                })?;
+            }
+        }
+
+        Ok(())
+    }
+}
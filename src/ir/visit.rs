@@ -0,0 +1,203 @@
+//! Traversals of function definitions.
+//!
+//! `IrVisitor` walks a function definition by reference and `IrRewriter` by mutable reference. Both
+//! visit the allocations, then each block in the order of its id: its phinodes, its instructions
+//! and finally its exit. Each `visit_*` (resp. `rewrite_*`) method defaults to the corresponding
+//! `walk_*` function, which visits the children of the node, so an implementation only overrides
+//! the methods for the nodes it is interested in. An overriding method can call the `walk_*`
+//! function itself to keep visiting the children.
+//!
+//! # Example
+//!
+//! Replacing every use of a register by a constant:
+//!
+//! ```
+//! use kecc::ir::*;
+//!
+//! struct Replace {
+//!     rid: RegisterId,
+//!     value: Constant,
+//! }
+//!
+//! impl IrRewriter for Replace {
+//!     fn rewrite_operand(&mut self, operand: &mut Operand) {
+//!         if operand.get_register().map(|(rid, _)| rid) == Some(&self.rid) {
+//!             *operand = Operand::constant(self.value.clone());
+//!         }
+//!     }
+//! }
+//! ```
+
+use crate::ir::*;
+
+/// A traversal of a function definition by reference. See the [module documentation](self).
+pub trait IrVisitor {
+    fn visit_function(&mut self, definition: &FunctionDefinition) {
+        walk_function(self, definition);
+    }
+
+    /// Visits the allocation `aid`, which defines the register `%l{aid}`.
+    fn visit_allocation(&mut self, _aid: usize, _allocation: &Named<Dtype>) {}
+
+    fn visit_block(&mut self, bid: BlockId, block: &Block) {
+        walk_block(self, bid, block);
+    }
+
+    /// Visits the phinode defining `rid`.
+    fn visit_phinode(&mut self, _rid: RegisterId, _phinode: &Named<Dtype>) {}
+
+    /// Visits the instruction defining `rid`.
+    fn visit_instruction(&mut self, rid: RegisterId, instruction: &Named<Instruction>) {
+        walk_instruction(self, rid, instruction);
+    }
+
+    /// Visits the exit of the block `bid`.
+    fn visit_block_exit(&mut self, bid: BlockId, exit: &BlockExit) {
+        walk_block_exit(self, bid, exit);
+    }
+
+    fn visit_jump_arg(&mut self, arg: &JumpArg) {
+        walk_jump_arg(self, arg);
+    }
+
+    fn visit_operand(&mut self, _operand: &Operand) {}
+}
+
+pub fn walk_function<V: IrVisitor + ?Sized>(visitor: &mut V, definition: &FunctionDefinition) {
+    for (aid, allocation) in definition.allocations.iter().enumerate() {
+        visitor.visit_allocation(aid, allocation);
+    }
+    for (bid, block) in &definition.blocks {
+        visitor.visit_block(*bid, block);
+    }
+}
+
+pub fn walk_block<V: IrVisitor + ?Sized>(visitor: &mut V, bid: BlockId, block: &Block) {
+    for (aid, phinode) in block.phinodes.iter().enumerate() {
+        visitor.visit_phinode(RegisterId::arg(bid, aid), phinode);
+    }
+    for (iid, instruction) in block.instructions.iter().enumerate() {
+        visitor.visit_instruction(RegisterId::temp(bid, iid), instruction);
+    }
+    visitor.visit_block_exit(bid, &block.exit);
+}
+
+pub fn walk_instruction<V: IrVisitor + ?Sized>(
+    visitor: &mut V,
+    _rid: RegisterId,
+    instruction: &Named<Instruction>,
+) {
+    for operand in instruction.operands() {
+        visitor.visit_operand(operand);
+    }
+}
+
+pub fn walk_block_exit<V: IrVisitor + ?Sized>(visitor: &mut V, _bid: BlockId, exit: &BlockExit) {
+    match exit {
+        BlockExit::ConditionalJump { condition, .. } => visitor.visit_operand(condition),
+        BlockExit::Switch { value, .. } | BlockExit::Return { value } => {
+            visitor.visit_operand(value)
+        }
+        BlockExit::Jump { .. } | BlockExit::Unreachable => {}
+    }
+    for arg in exit.jump_args() {
+        visitor.visit_jump_arg(arg);
+    }
+}
+
+pub fn walk_jump_arg<V: IrVisitor + ?Sized>(visitor: &mut V, arg: &JumpArg) {
+    for operand in &arg.args {
+        visitor.visit_operand(operand);
+    }
+}
+
+/// A traversal of a function definition by mutable reference. See the [module
+/// documentation](self).
+///
+/// Note that the register ids given to the methods are those of the nodes when they are visited.
+/// A rewriter that inserts or removes phinodes or instructions is responsible for keeping the
+/// rest of the function consistent.
+pub trait IrRewriter {
+    fn rewrite_function(&mut self, definition: &mut FunctionDefinition) {
+        walk_function_mut(self, definition);
+    }
+
+    /// Rewrites the allocation `aid`, which defines the register `%l{aid}`.
+    fn rewrite_allocation(&mut self, _aid: usize, _allocation: &mut Named<Dtype>) {}
+
+    fn rewrite_block(&mut self, bid: BlockId, block: &mut Block) {
+        walk_block_mut(self, bid, block);
+    }
+
+    /// Rewrites the phinode defining `rid`.
+    fn rewrite_phinode(&mut self, _rid: RegisterId, _phinode: &mut Named<Dtype>) {}
+
+    /// Rewrites the instruction defining `rid`.
+    fn rewrite_instruction(&mut self, rid: RegisterId, instruction: &mut Named<Instruction>) {
+        walk_instruction_mut(self, rid, instruction);
+    }
+
+    /// Rewrites the exit of the block `bid`.
+    fn rewrite_block_exit(&mut self, bid: BlockId, exit: &mut BlockExit) {
+        walk_block_exit_mut(self, bid, exit);
+    }
+
+    fn rewrite_jump_arg(&mut self, arg: &mut JumpArg) {
+        walk_jump_arg_mut(self, arg);
+    }
+
+    fn rewrite_operand(&mut self, _operand: &mut Operand) {}
+}
+
+pub fn walk_function_mut<R: IrRewriter + ?Sized>(
+    rewriter: &mut R,
+    definition: &mut FunctionDefinition,
+) {
+    for (aid, allocation) in definition.allocations.iter_mut().enumerate() {
+        rewriter.rewrite_allocation(aid, allocation);
+    }
+    for (bid, block) in &mut definition.blocks {
+        rewriter.rewrite_block(*bid, block);
+    }
+}
+
+pub fn walk_block_mut<R: IrRewriter + ?Sized>(rewriter: &mut R, bid: BlockId, block: &mut Block) {
+    for (aid, phinode) in block.phinodes.iter_mut().enumerate() {
+        rewriter.rewrite_phinode(RegisterId::arg(bid, aid), phinode);
+    }
+    for (iid, instruction) in block.instructions.iter_mut().enumerate() {
+        rewriter.rewrite_instruction(RegisterId::temp(bid, iid), instruction);
+    }
+    rewriter.rewrite_block_exit(bid, &mut block.exit);
+}
+
+pub fn walk_instruction_mut<R: IrRewriter + ?Sized>(
+    rewriter: &mut R,
+    _rid: RegisterId,
+    instruction: &mut Named<Instruction>,
+) {
+    for operand in instruction.operands_mut() {
+        rewriter.rewrite_operand(operand);
+    }
+}
+
+pub fn walk_block_exit_mut<R: IrRewriter + ?Sized>(
+    rewriter: &mut R,
+    _bid: BlockId,
+    exit: &mut BlockExit,
+) {
+    match exit {
+        BlockExit::ConditionalJump { condition, .. } => rewriter.rewrite_operand(condition),
+        BlockExit::Switch { value, .. } | BlockExit::Return { value } => {
+            rewriter.rewrite_operand(value)
+        }
+        BlockExit::Jump { .. } | BlockExit::Unreachable => {}
+    }
+    exit.walk_jump_args(|arg| rewriter.rewrite_jump_arg(arg));
+}
+
+pub fn walk_jump_arg_mut<R: IrRewriter + ?Sized>(rewriter: &mut R, arg: &mut JumpArg) {
+    for operand in &mut arg.args {
+        rewriter.rewrite_operand(operand);
+    }
+}
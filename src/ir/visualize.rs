@@ -1,9 +1,13 @@
 //! Visualize IR.
 
 use std::collections::HashMap;
+use std::fmt::Write as _;
+
+use itertools::Itertools;
 
 use crate::ir::*;
 use crate::some_or;
+use crate::write_base::*;
 use crate::Translate;
 
 #[derive(Default, Debug)]
@@ -23,12 +27,13 @@ impl Translate<TranslationUnit> for Visualizer {
         let mut subgraphs = Vec::new();
 
         // TODO: Add variables and structs information
-        for (name, decl) in &source.decls {
+        for (name, decl) in source.decls_in_order() {
             match decl {
                 Declaration::Variable { .. } => {}
                 Declaration::Function {
                     signature,
                     definition,
+                    ..
                 } => {
                     let definition = some_or!(definition, continue);
                     let subgraph = self.translate_function(name, signature, definition)?;
@@ -40,7 +45,7 @@ impl Translate<TranslationUnit> for Visualizer {
         let mut edges = Vec::new();
 
         // Add edges between subgraphs
-        for (name, decl) in &source.decls {
+        for (name, decl) in source.decls_in_order() {
             if let Declaration::Function { definition, .. } = decl {
                 let definition = some_or!(definition, continue);
 
@@ -226,3 +231,79 @@ impl Visualizer {
         Ok(format!("subgraph \"cluster.{name}.{bid}\" {{\n{inner}\n}}"))
     }
 }
+
+/// Renders the control-flow graph of the function `name` as a graph in the DOT language of
+/// Graphviz, e.g., to be rendered by `dot -Tpng`.
+///
+/// Each block is a node listing its phinodes, instructions and exit as they are printed in the
+/// IR, where the entry block has a double border. Each edge is labeled with the condition under
+/// which it is taken, if any, and with the arguments passed along it.
+pub fn cfg_dot(name: &str, definition: &FunctionDefinition) -> String {
+    let mut lines = vec![
+        format!("digraph \"{}\" {{", escape(name)),
+        "node [shape=box, fontname=monospace];".to_string(),
+    ];
+
+    for (bid, block) in &definition.blocks {
+        let mut body = Vec::new();
+        (bid, block)
+            .write_line(0, &mut body)
+            .expect("writing to a vector must succeed");
+        let body = String::from_utf8(body).expect("the IR must be printed in UTF-8");
+        let mut label = String::new();
+        for line in format!("block {bid}:\n{body}").lines() {
+            write!(label, "{}\\l", escape(line)).expect("writing to a string must succeed");
+        }
+        let peripheries = if *bid == definition.bid_init {
+            ", peripheries=2"
+        } else {
+            ""
+        };
+        lines.push(format!("\"{bid}\" [label=\"{label}\"{peripheries}];"));
+    }
+
+    for (bid, block) in &definition.blocks {
+        let edges = match &block.exit {
+            BlockExit::Jump { arg } => vec![(None, arg)],
+            BlockExit::ConditionalJump {
+                arg_then, arg_else, ..
+            } => vec![
+                (Some("true".to_string()), arg_then),
+                (Some("false".to_string()), arg_else),
+            ],
+            BlockExit::Switch { default, cases, .. } => cases
+                .iter()
+                .map(|(constant, arg)| (Some(constant.to_string()), arg))
+                .chain([(Some("default".to_string()), default)])
+                .collect(),
+            BlockExit::Return { .. } | BlockExit::Unreachable => Vec::new(),
+        };
+
+        for (condition, arg) in edges {
+            let args = if arg.args.is_empty() {
+                None
+            } else {
+                Some(format!("({})", arg.args.iter().join(", ")))
+            };
+            let label = condition
+                .into_iter()
+                .chain(args)
+                .map(|line| escape(&line))
+                .join("\\n");
+            let label = if label.is_empty() {
+                String::new()
+            } else {
+                format!(" [label=\"{label}\"]")
+            };
+            lines.push(format!("\"{bid}\" -> \"{}\"{label};", arg.bid));
+        }
+    }
+
+    lines.push("}".to_string());
+    lines.join("\n")
+}
+
+/// Escapes `text` to be put in a quoted string of DOT.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
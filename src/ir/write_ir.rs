@@ -1,5 +1,6 @@
 use crate::ir::*;
 
+use core::fmt;
 use std::io::{Result, Write};
 
 use crate::write_base::*;
@@ -7,8 +8,8 @@ use crate::*;
 
 impl WriteLine for TranslationUnit {
     fn write_line(&self, indent: usize, write: &mut dyn Write) -> Result<()> {
-        // TODO: consider KECC IR parser in the future.
-        for (name, struct_type) in &self.structs {
+        // Structs are printed in the order of their names so that the output is stable.
+        for (name, struct_type) in self.structs.iter().sorted_by(|lhs, rhs| lhs.0.cmp(rhs.0)) {
             let definition = if let Some(struct_type) = struct_type {
                 let fields = struct_type
                     .get_struct_fields()
@@ -33,15 +34,21 @@ impl WriteLine for TranslationUnit {
                 "opaque".to_string()
             };
 
-            writeln!(write, "struct {name} : {definition}")?;
+            // An opaque union is printed as a struct, as they share the names.
+            let kind = if struct_type.as_ref().is_some_and(Dtype::is_union) {
+                "union"
+            } else {
+                "struct"
+            };
+            writeln!(write, "{kind} {name} : {definition}")?;
         }
 
-        for (name, decl) in &self.decls {
+        for (name, decl) in self.decls_in_order() {
             let _ = some_or!(decl.get_variable(), continue);
             (name, decl).write_line(indent, write)?;
         }
 
-        for (name, decl) in &self.decls {
+        for (name, decl) in self.decls_in_order() {
             let _ = some_or!(decl.get_function(), continue);
             writeln!(write)?;
             (name, decl).write_line(indent, write)?;
@@ -51,28 +58,50 @@ impl WriteLine for TranslationUnit {
     }
 }
 
+impl fmt::Display for TranslationUnit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut buffer = Vec::new();
+        self.write_line(0, &mut buffer).map_err(|_| fmt::Error)?;
+        f.write_str(&String::from_utf8(buffer).map_err(|_| fmt::Error)?)
+    }
+}
+
 impl WriteLine for (&String, &Declaration) {
     fn write_line(&self, indent: usize, write: &mut dyn Write) -> Result<()> {
         let name = self.0;
         let decl = self.1;
 
+        if decl.linkage() == Linkage::Internal {
+            write!(write, "static ")?;
+        }
+
         match decl {
-            Declaration::Variable { dtype, initializer } => {
-                writeln!(
-                    write,
-                    "var {} @{} = {}",
-                    dtype,
-                    name,
-                    if let Some(init) = initializer {
-                        init.write_string()
-                    } else {
-                        "default".to_string()
-                    }
-                )?;
+            Declaration::Variable {
+                dtype,
+                initializer,
+                is_definition,
+                ..
+            } => {
+                if *is_definition {
+                    writeln!(
+                        write,
+                        "var {} @{} = {}",
+                        dtype,
+                        name,
+                        if let Some(init) = initializer {
+                            init.write_string()
+                        } else {
+                            "default".to_string()
+                        }
+                    )?;
+                } else {
+                    writeln!(write, "var {dtype} @{name}")?;
+                }
             }
             Declaration::Function {
                 signature,
                 definition,
+                ..
             } => {
                 let params = signature.params.iter().format(", ");
 
@@ -38,15 +38,15 @@
 use core::convert::TryFrom;
 use core::fmt;
 use core::mem;
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::ops::Deref;
 
 use lang_c::ast::*;
 use lang_c::driver::Parse;
-use lang_c::span::Node;
+use lang_c::span::{Node, Span};
 use thiserror::Error;
 
-use crate::ir::{DtypeError, HasDtype, Named};
+use crate::ir::{ConstEvalError, DtypeError, HasDtype, Named};
 use crate::*;
 
 use itertools::izip;
@@ -85,16 +85,44 @@ pub enum IrgenErrorMessage {
     },
     #[error("{dtype_error}")]
     InvalidDtype { dtype_error: DtypeError },
+    #[error("invalid initializer, {const_eval_error}")]
+    InvalidInitializer {
+        #[from]
+        const_eval_error: ConstEvalError,
+    },
     #[error("l-value required as {message}")]
     RequireLvalue { message: String },
+    /// A C feature that KECC does not support, at `loc` in the C source.
+    #[error("{}{feature} is unsupported", .loc.map_or(String::new(), |loc| format!("{loc}: ")))]
+    Unsupported {
+        feature: String,
+        loc: Option<ir::SourceLoc>,
+    },
+    /// A call that does not match the declaration of the callee, at `loc` in the C source.
+    #[error("{}{message}", .loc.map_or(String::new(), |loc| format!("{loc}: ")))]
+    InvalidCall {
+        message: String,
+        loc: Option<ir::SourceLoc>,
+    },
 }
 
 #[derive(Default, Debug)]
 pub struct Irgen {
     decls: BTreeMap<String, ir::Declaration>,
+    /// The names of `decls` in the order they are first declared.
+    decl_order: Vec<String>,
     typedefs: HashMap<String, ir::Dtype>,
     structs: HashMap<String, Option<ir::Dtype>>,
     struct_tempid_counter: usize,
+    /// Where the declarations are in the C source.
+    decl_locs: BTreeMap<String, ir::SourceLoc>,
+    /// The lines and columns of the C source being translated, if known.
+    source_map: Option<SourceMap>,
+    /// The names of the global arrays of the string literals, by their bytes.
+    string_literals: HashMap<Vec<u8>, String>,
+    /// The functions declared without a prototype, e.g., `int f();`, whose parameters are
+    /// unknown.
+    unprototyped: HashSet<String>,
 }
 
 impl Translate<Parse> for Irgen {
@@ -102,7 +130,10 @@ impl Translate<Parse> for Irgen {
     type Error = IrgenError;
 
     fn translate(&mut self, source: &Parse) -> Result<Self::Target, Self::Error> {
-        self.translate(&source.unit)
+        self.source_map = Some(SourceMap::new(&source.source));
+        let result = self.translate(&source.unit);
+        self.source_map = None;
+        result
     }
 }
 
@@ -116,8 +147,14 @@ impl Translate<TranslationUnit> for Irgen {
                 ExternalDeclaration::Declaration(ref var) => {
                     self.add_declaration(&var.node)?;
                 }
-                ExternalDeclaration::StaticAssert(_) => {
-                    panic!("ExternalDeclaration::StaticAssert is unsupported")
+                ExternalDeclaration::StaticAssert(ref static_assert) => {
+                    return Err(IrgenError::new(
+                        format!("{static_assert:#?}"),
+                        IrgenErrorMessage::Unsupported {
+                            feature: "`_Static_assert`".to_string(),
+                            loc: self.loc(static_assert.span),
+                        },
+                    ));
                 }
                 ExternalDeclaration::FunctionDefinition(ref func) => {
                     self.add_function_definition(&func.node)?;
@@ -126,26 +163,33 @@ impl Translate<TranslationUnit> for Irgen {
         }
 
         let decls = mem::take(&mut self.decls);
+        let decl_order = mem::take(&mut self.decl_order);
         let structs = mem::take(&mut self.structs);
-        Ok(Self::Target { decls, structs })
+        let decl_locs = mem::take(&mut self.decl_locs);
+        self.string_literals.clear();
+        Ok(Self::Target {
+            decls,
+            decl_order,
+            structs,
+            decl_locs,
+        })
     }
 }
 
 impl Irgen {
-    const BID_INIT: ir::BlockId = ir::BlockId(0);
-    // `0` is used to create `BID_INIT`
-    const BID_COUNTER_INIT: usize = 1;
     const TEMPID_COUNTER_INIT: usize = 0;
 
     /// Add a declaration. It can be either a struct, typedef, or a variable.
     fn add_declaration(&mut self, source: &Declaration) -> Result<(), IrgenError> {
+        let (linkage, is_extern) = linkage_of_specifiers(&source.specifiers)
+            .map_err(|e| IrgenError::new(format!("{source:#?}"), e))?;
+        let loc = source
+            .specifiers
+            .first()
+            .and_then(|specifier| self.loc(specifier.span));
         let (base_dtype, is_typedef) =
-            ir::Dtype::try_from_ast_declaration_specifiers(&source.specifiers).map_err(|e| {
-                IrgenError::new(
-                    format!("{source:#?}"),
-                    IrgenErrorMessage::InvalidDtype { dtype_error: e },
-                )
-            })?;
+            ir::Dtype::try_from_ast_declaration_specifiers(&source.specifiers)
+                .map_err(|e| IrgenError::new(format!("{source:#?}"), invalid_dtype(e, loc)))?;
         let base_dtype = base_dtype.resolve_typedefs(&self.typedefs).map_err(|e| {
             IrgenError::new(
                 format!("{source:#?}"),
@@ -154,6 +198,12 @@ impl Irgen {
         })?;
 
         let base_dtype = if let ir::Dtype::Struct { name, fields, .. } = &base_dtype {
+            base_dtype.check_tag(&self.structs).map_err(|e| {
+                IrgenError::new(
+                    format!("{source:#?}"),
+                    IrgenErrorMessage::InvalidDtype { dtype_error: e },
+                )
+            })?;
             if let Some(name) = name {
                 let _ = self.structs.entry(name.to_string()).or_insert(None);
             }
@@ -183,7 +233,7 @@ impl Irgen {
                 .map_err(|e| {
                     IrgenError::new(
                         format!("{source:#?}"),
-                        IrgenErrorMessage::InvalidDtype { dtype_error: e },
+                        invalid_dtype(e, self.loc(init_decl.span)),
                     )
                 })?
                 .deref()
@@ -231,20 +281,31 @@ impl Irgen {
                 )
             })?;
 
+            match &mut decl {
+                ir::Declaration::Variable {
+                    linkage: var_linkage,
+                    is_definition,
+                    ..
+                } => {
+                    *var_linkage = linkage;
+                    *is_definition = !is_extern;
+                }
+                ir::Declaration::Function {
+                    linkage: func_linkage,
+                    ..
+                } => *func_linkage = linkage,
+            }
+
             // If `initializer` exists, convert initializer to a constant value
             if let Some(initializer) = init_decl.node.initializer.as_ref() {
-                if !is_valid_initializer(&initializer.node, &dtype, &self.structs) {
-                    return Err(IrgenError::new(
-                        format!("{source:#?}"),
-                        IrgenErrorMessage::Misc {
-                            message: "initializer is not valid".to_string(),
-                        },
-                    ));
-                }
+                let initializer = self
+                    .fold_initializer(&initializer.node, &dtype)
+                    .map_err(|e| IrgenError::new(format!("{source:#?}"), e))?;
 
                 match &mut decl {
                     ir::Declaration::Variable {
                         initializer: var_initializer,
+                        is_definition,
                         ..
                     } => {
                         if var_initializer.is_some() {
@@ -253,7 +314,9 @@ impl Irgen {
                                 IrgenErrorMessage::Redefinition { name },
                             ));
                         }
-                        *var_initializer = Some(initializer.node.clone());
+                        // An initialized variable is defined even if it is declared `extern`.
+                        *var_initializer = Some(initializer);
+                        *is_definition = true;
                     }
                     ir::Declaration::Function { .. } => {
                         return Err(IrgenError::new(
@@ -267,10 +330,12 @@ impl Irgen {
                 }
             }
 
+            self.add_prototype(&name, declarator);
+            self.add_decl_loc(&name, init_decl.span, decl.is_definition());
             self.add_decl(&name, decl)?;
         }
 
-        Ok(())
+        self.add_string_literals()
     }
 
     /// Add a function definition.
@@ -282,12 +347,16 @@ impl Irgen {
         let name = name_of_declarator(declarator);
         let name_of_params = name_of_params_from_function_declarator(declarator)
             .expect("declarator is not from function definition");
+        let (linkage, _) = linkage_of_specifiers(specifiers).map_err(|e| {
+            IrgenError::new(format!("specs: {specifiers:#?}\ndecl: {declarator:#?}"), e)
+        })?;
 
+        let loc = self.loc(source.declarator.span);
         let (base_dtype, is_typedef) = ir::Dtype::try_from_ast_declaration_specifiers(specifiers)
             .map_err(|e| {
             IrgenError::new(
                 format!("specs: {specifiers:#?}\ndecl: {declarator:#?}"),
-                IrgenErrorMessage::InvalidDtype { dtype_error: e },
+                invalid_dtype(e, loc),
             )
         })?;
 
@@ -305,7 +374,7 @@ impl Irgen {
             .map_err(|e| {
                 IrgenError::new(
                     format!("specs: {specifiers:#?}\ndecl: {declarator:#?}"),
-                    IrgenErrorMessage::InvalidDtype { dtype_error: e },
+                    invalid_dtype(e, loc),
                 )
             })?
             .deref()
@@ -320,7 +389,16 @@ impl Irgen {
         let signature = ir::FunctionSignature::new(dtype.clone());
 
         // Adds new declaration if nothing has been declared before
-        let decl = ir::Declaration::try_from(dtype).unwrap();
+        let mut decl = ir::Declaration::try_from(dtype).unwrap();
+        if let ir::Declaration::Function {
+            linkage: func_linkage,
+            ..
+        } = &mut decl
+        {
+            *func_linkage = linkage;
+        }
+        self.add_prototype(&name, declarator);
+        self.add_decl_loc(&name, source.declarator.span, true);
         self.add_decl(&name, decl)?;
 
         // Prepare scope for global variable
@@ -338,25 +416,28 @@ impl Irgen {
         // Prepares for irgen pass.
         let mut irgen = IrgenFunc {
             return_type: signature.ret.clone(),
-            bid_init: Irgen::BID_INIT,
-            phinodes_init: Vec::new(),
-            allocations: Vec::new(),
-            blocks: BTreeMap::new(),
-            bid_counter: Irgen::BID_COUNTER_INIT,
+            builder: ir::FunctionBuilder::new(signature.clone()),
             tempid_counter: Irgen::TEMPID_COUNTER_INIT,
             typedefs: &self.typedefs,
             structs: &self.structs,
             // Initial symbol table has scope for global variable already
             symbol_table: vec![global_scope],
+            source_map: self.source_map.as_ref(),
+            switch_labels: Vec::new(),
+            string_literals: &mut self.string_literals,
+            unprototyped: &self.unprototyped,
         };
-        let mut context = Context::new(irgen.bid_init);
+        let bid_init = irgen.builder.entry();
+        let mut context = Context::new(bid_init);
+        // The parameters are stored at the function's declarator.
+        context.loc = irgen.loc(source.declarator.span);
 
         // Enter variable scope for alloc registers matched with function parameters
         irgen.enter_scope();
 
         // Creates the init block that stores arguments.
         irgen
-            .translate_parameter_decl(&signature, irgen.bid_init, &name_of_params, &mut context)
+            .translate_parameter_decl(&signature, bid_init, &name_of_params, &mut context)
             .map_err(|e| {
                 IrgenError::new(format!("specs: {specifiers:#?}\ndecl: {declarator:#?}"), e)
             })?;
@@ -365,7 +446,7 @@ impl Irgen {
         irgen.translate_stmt(&source.statement.node, &mut context, None, None)?;
 
         // Creates the end block
-        let ret = signature.ret.set_const(false);
+        let ret = signature.ret.unqualified();
         let value = if ret == ir::Dtype::unit() {
             ir::Operand::constant(ir::Constant::unit())
         } else if ret == ir::Dtype::INT {
@@ -385,11 +466,7 @@ impl Irgen {
         // Exit variable scope created above
         irgen.exit_scope();
 
-        let func_def = ir::FunctionDefinition {
-            allocations: irgen.allocations,
-            blocks: irgen.blocks,
-            bid_init: irgen.bid_init,
-        };
+        let func_def = irgen.builder.finish();
 
         let decl = self
             .decls
@@ -411,17 +488,294 @@ impl Irgen {
             panic!("`{name}` must be function declaration")
         }
 
+        self.add_string_literals()
+    }
+
+    /// Adds the global arrays of the string literals that are not declared yet.
+    fn add_string_literals(&mut self) -> Result<(), IrgenError> {
+        let mut string_literals = self
+            .string_literals
+            .iter()
+            .filter(|(_, name)| !self.decls.contains_key(*name))
+            .map(|(bytes, name)| (name.clone(), bytes.clone()))
+            .collect::<Vec<_>>();
+        string_literals.sort();
+
+        for (name, bytes) in string_literals {
+            let decl = ir::Declaration::Variable {
+                dtype: string_literal_dtype(bytes.len()),
+                initializer: Some(initializer_of_bytes(&bytes)),
+                linkage: ir::Linkage::Internal,
+                is_definition: true,
+            };
+            self.add_decl(&name, decl)?;
+        }
+
         Ok(())
     }
 
+    /// The location in the C source of the code at `span`, if known.
+    fn loc(&self, span: Span) -> Option<ir::SourceLoc> {
+        self.source_map.as_ref()?.loc(span.start)
+    }
+
+    /// Evaluates the expressions of `initializer` for a variable of `dtype`, and returns the
+    /// initializer of their values converted to the dtypes of the objects they initialize. The
+    /// initializer is fully braced first. A pointer is initialized by the null pointer constant or
+    /// by an address constant, which becomes an [`ir::address_initializer`].
+    fn fold_initializer(
+        &mut self,
+        initializer: &Initializer,
+        dtype: &ir::Dtype,
+    ) -> Result<Initializer, IrgenErrorMessage> {
+        let initializer = &brace_initializer(initializer, dtype, &self.structs)?;
+        let invalid = || IrgenErrorMessage::Misc {
+            message: "initializer is not valid".to_string(),
+        };
+
+        match initializer {
+            Initializer::Expression(expr) => {
+                let expr = if let ir::Dtype::Pointer { .. } = dtype {
+                    if let Some((name, offset, _)) = self.address_constant(&expr.node)? {
+                        ir::address_initializer(&name, offset)
+                    } else {
+                        let value = self.constant_expression(&expr.node)?;
+                        if !value.get_int().is_some_and(|(value, _, _)| value == 0) {
+                            return Err(invalid());
+                        }
+                        literal_of_constant(&value).ok_or_else(invalid)?
+                    }
+                } else {
+                    let value = self.constant_expression(&expr.node)?;
+                    let value = match dtype {
+                        ir::Dtype::Int { .. } | ir::Dtype::Float { .. } => {
+                            ir::const_eval::typecast(&value, dtype)?
+                        }
+                        // The value is truncated to the width of a bit-field when it is stored.
+                        ir::Dtype::BitField { inner, .. } => {
+                            ir::const_eval::typecast(&value, inner)?
+                        }
+                        _ => return Err(invalid()),
+                    };
+                    literal_of_constant(&value).ok_or_else(invalid)?
+                };
+                Ok(Initializer::Expression(Box::new(Node::new(
+                    expr,
+                    Span::none(),
+                ))))
+            }
+            Initializer::List(items) => {
+                let dtypes = subobjects_of(dtype, &self.structs)?.ok_or_else(invalid)?;
+                let items = izip!(items, dtypes)
+                    .map(|(item, dtype)| {
+                        let initializer =
+                            self.fold_initializer(&item.node.initializer.node, &dtype)?;
+                        let item = InitializerListItem {
+                            designation: item.node.designation.clone(),
+                            initializer: Box::new(Node::new(
+                                initializer,
+                                item.node.initializer.span,
+                            )),
+                        };
+                        Ok(Node::new(item, Span::none()))
+                    })
+                    .collect::<Result<_, IrgenErrorMessage>>()?;
+                Ok(Initializer::List(items))
+            }
+        }
+    }
+
+    /// Evaluates the constant expression `expr`, which may contain `sizeof` and `_Alignof`.
+    fn constant_expression(&self, expr: &Expression) -> Result<ir::Constant, IrgenErrorMessage> {
+        ir::const_eval::expression_with(expr, &mut |expr| {
+            let (dtype, is_size) = match expr {
+                Expression::SizeOfTy(size_of) => (self.dtype_of_type_name(&size_of.node.0)?, true),
+                Expression::AlignOf(align_of) => {
+                    (self.dtype_of_type_name(&align_of.node.0)?, false)
+                }
+                // Only the operands that are names have their dtypes known without translation.
+                Expression::SizeOfVal(size_of) => match &size_of.node.0.node {
+                    Expression::Identifier(identifier) => {
+                        let decl = self.decls.get(&identifier.node.name);
+                        (decl.ok_or(ConstEvalError::NotConstant)?.dtype(), true)
+                    }
+                    _ => return Err(ConstEvalError::NotConstant.into()),
+                },
+                _ => return Err(ConstEvalError::NotConstant.into()),
+            };
+            let (size_of, align_of) = dtype
+                .size_align_of(&self.structs)
+                .map_err(|e| IrgenErrorMessage::InvalidDtype { dtype_error: e })?;
+            Ok(ir::Constant::int(
+                if is_size { size_of } else { align_of } as u128,
+                ir::Dtype::LONG.set_signed(false),
+            ))
+        })
+    }
+
+    /// The pointer `expr` evaluates to if it is an address constant, as the global name it points
+    /// into, the offset in bytes and the dtype it points to.
+    fn address_constant(
+        &mut self,
+        expr: &Expression,
+    ) -> Result<Option<(String, isize, ir::Dtype)>, IrgenErrorMessage> {
+        match expr {
+            // An array decays to the pointer to its first element, and a function to the pointer
+            // to it.
+            Expression::Identifier(_) | Expression::StringLiteral(_) => {
+                let (name, offset, dtype) = some_or!(self.lvalue_constant(expr)?, return Ok(None));
+                Ok(match dtype {
+                    ir::Dtype::Array { inner, .. } => Some((name, offset, inner.deref().clone())),
+                    ir::Dtype::Function { .. } => Some((name, offset, dtype)),
+                    _ => None,
+                })
+            }
+            Expression::UnaryOperator(unary)
+                if unary.node.operator.node == UnaryOperator::Address =>
+            {
+                self.lvalue_constant(&unary.node.operand.node)
+            }
+            Expression::BinaryOperator(binary) => match binary.node.operator.node {
+                BinaryOperator::Plus | BinaryOperator::Minus => self.pointer_offset_constant(
+                    &binary.node.lhs.node,
+                    &binary.node.rhs.node,
+                    binary.node.operator.node == BinaryOperator::Minus,
+                ),
+                _ => Ok(None),
+            },
+            _ => Ok(None),
+        }
+    }
+
+    /// The pointer `lhs + rhs`, or `lhs - rhs` if `is_minus`, if it is an address constant. Either
+    /// operand of the addition may be the pointer.
+    fn pointer_offset_constant(
+        &mut self,
+        lhs: &Expression,
+        rhs: &Expression,
+        is_minus: bool,
+    ) -> Result<Option<(String, isize, ir::Dtype)>, IrgenErrorMessage> {
+        let ((name, offset, dtype), index) = match self.address_constant(lhs)? {
+            Some(ptr) => (ptr, rhs),
+            None if !is_minus => (some_or!(self.address_constant(rhs)?, return Ok(None)), lhs),
+            None => return Ok(None),
+        };
+        let (index, _, _) =
+            self.constant_expression(index)?
+                .get_int()
+                .ok_or_else(|| IrgenErrorMessage::Misc {
+                    message: "the offset of a pointer must be an integer".to_string(),
+                })?;
+        let (size_of, _) = dtype
+            .size_align_of(&self.structs)
+            .map_err(|e| IrgenErrorMessage::InvalidDtype { dtype_error: e })?;
+        let offset_of = index as isize * size_of as isize;
+        let offset = if is_minus {
+            offset - offset_of
+        } else {
+            offset + offset_of
+        };
+        Ok(Some((name, offset, dtype)))
+    }
+
+    /// The object `expr` designates if its address is an address constant, as the global name it
+    /// is in, its offset in bytes and its dtype.
+    fn lvalue_constant(
+        &mut self,
+        expr: &Expression,
+    ) -> Result<Option<(String, isize, ir::Dtype)>, IrgenErrorMessage> {
+        match expr {
+            Expression::Identifier(identifier) => {
+                let name = &identifier.node.name;
+                Ok(self
+                    .decls
+                    .get(name)
+                    .map(|decl| (name.clone(), 0, decl.dtype())))
+            }
+            Expression::StringLiteral(string_literal) => {
+                let bytes = bytes_of_string_literal(&string_literal.node)?;
+                let dtype = string_literal_dtype(bytes.len());
+                let name = name_of_string_literal(&mut self.string_literals, bytes);
+                Ok(Some((name, 0, dtype)))
+            }
+            Expression::BinaryOperator(binary)
+                if binary.node.operator.node == BinaryOperator::Index =>
+            {
+                self.pointer_offset_constant(&binary.node.lhs.node, &binary.node.rhs.node, false)
+            }
+            Expression::Member(member) => {
+                let object = match member.node.operator.node {
+                    MemberOperator::Direct => self.lvalue_constant(&member.node.expression.node)?,
+                    MemberOperator::Indirect => {
+                        self.address_constant(&member.node.expression.node)?
+                    }
+                };
+                let (name, offset, dtype) = some_or!(object, return Ok(None));
+                let (field_offset, dtype) = some_or!(
+                    dtype.get_offset_struct_field(&member.node.identifier.node.name, &self.structs),
+                    return Ok(None)
+                );
+                // A bit-field has no address.
+                if dtype.get_bit_field().is_some() {
+                    return Ok(None);
+                }
+                Ok(Some((name, offset + field_offset as isize, dtype)))
+            }
+            Expression::UnaryOperator(unary)
+                if unary.node.operator.node == UnaryOperator::Indirection =>
+            {
+                self.address_constant(&unary.node.operand.node)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// The type named by `type_name`, e.g., in `sizeof`.
+    fn dtype_of_type_name(
+        &self,
+        type_name: &Node<TypeName>,
+    ) -> Result<ir::Dtype, IrgenErrorMessage> {
+        ir::Dtype::try_from(&type_name.node)
+            .and_then(|dtype| dtype.resolve_typedefs(&self.typedefs))
+            .map_err(|e| invalid_dtype(e, self.loc(type_name.span)))
+    }
+
+    /// Records that `name` is declared at `span`. The location of the definition is kept over
+    /// those of the other declarations, and otherwise that of the first declaration.
+    fn add_decl_loc(&mut self, name: &str, span: Span, is_definition: bool) {
+        let loc = some_or!(self.loc(span), return);
+        let is_defined = self
+            .decls
+            .get(name)
+            .is_some_and(ir::Declaration::is_definition);
+        if is_definition && !is_defined {
+            let _unused = self.decl_locs.insert(name.to_string(), loc);
+        } else {
+            let _unused = self.decl_locs.entry(name.to_string()).or_insert(loc);
+        }
+    }
+
     /// Adds a possibly existing declaration.
     ///
+    /// The declaration keeps the linkage and the definition of the previous one, if any.
+    ///
     /// Returns error if the previous declearation is incompatible with `decl`.
-    fn add_decl(&mut self, name: &str, decl: ir::Declaration) -> Result<(), IrgenError> {
-        let old_decl = some_or!(
-            self.decls.insert(name.to_string(), decl.clone()),
-            return Ok(())
-        );
+    /// Records whether the function `name`, declared by `declarator`, has a prototype. A function
+    /// declared with a prototype keeps it even if it is declared without one later.
+    fn add_prototype(&mut self, name: &str, declarator: &Declarator) {
+        if !is_unprototyped(declarator) {
+            let _unused = self.unprototyped.remove(name);
+        } else if !self.decls.contains_key(name) {
+            let _unused = self.unprototyped.insert(name.to_string());
+        }
+    }
+
+    fn add_decl(&mut self, name: &str, mut decl: ir::Declaration) -> Result<(), IrgenError> {
+        let old_decl = some_or!(self.decls.get(name), {
+            self.decl_order.push(name.to_string());
+            let _unused = self.decls.insert(name.to_string(), decl);
+            return Ok(());
+        });
 
         // Check if type is conflicting for pre-declared one
         if !old_decl.is_compatible(&decl) {
@@ -434,6 +788,67 @@ impl Irgen {
             ));
         }
 
+        if decl.linkage() == ir::Linkage::Internal && old_decl.linkage() == ir::Linkage::External {
+            return Err(IrgenError::new(
+                name.to_string(),
+                IrgenErrorMessage::Misc {
+                    message: format!(
+                        "static declaration of `{name}` follows non-static declaration"
+                    ),
+                },
+            ));
+        }
+
+        match (&mut decl, old_decl) {
+            (
+                ir::Declaration::Variable {
+                    initializer,
+                    linkage,
+                    is_definition,
+                    ..
+                },
+                ir::Declaration::Variable {
+                    initializer: old_initializer,
+                    linkage: old_linkage,
+                    is_definition: old_is_definition,
+                    ..
+                },
+            ) => {
+                if initializer.is_some() && old_initializer.is_some() {
+                    return Err(IrgenError::new(
+                        name.to_string(),
+                        IrgenErrorMessage::Redefinition {
+                            name: name.to_string(),
+                        },
+                    ));
+                }
+                if initializer.is_none() {
+                    initializer.clone_from(old_initializer);
+                }
+                *linkage = *old_linkage;
+                *is_definition |= *old_is_definition;
+            }
+            (
+                ir::Declaration::Function {
+                    definition,
+                    linkage,
+                    ..
+                },
+                ir::Declaration::Function {
+                    definition: old_definition,
+                    linkage: old_linkage,
+                    ..
+                },
+            ) => {
+                if definition.is_none() {
+                    definition.clone_from(old_definition);
+                }
+                *linkage = *old_linkage;
+            }
+            _ => unreachable!("compatible declarations are of the same kind"),
+        }
+
+        let _unused = self.decls.insert(name.to_string(), decl);
         Ok(())
     }
 }
@@ -445,6 +860,9 @@ struct Context {
     bid: ir::BlockId,
     /// Current instructions of the block.
     instrs: Vec<Named<ir::Instruction>>,
+    /// Where the C code being translated is, which is attached to the inserted instructions.
+    /// Update it with [`IrgenFunc::loc`] when starting to translate a statement or an expression.
+    loc: Option<ir::SourceLoc>,
 }
 
 impl Context {
@@ -453,6 +871,7 @@ impl Context {
         Self {
             bid,
             instrs: Vec::new(),
+            loc: None,
         }
     }
 
@@ -462,7 +881,7 @@ impl Context {
         instr: ir::Instruction,
     ) -> Result<ir::Operand, IrgenErrorMessage> {
         let dtype = instr.dtype();
-        self.instrs.push(Named::new(None, instr));
+        self.instrs.push(Named::new(None, instr).with_loc(self.loc));
 
         Ok(ir::Operand::register(
             ir::RegisterId::temp(self.bid, self.instrs.len() - 1),
@@ -471,21 +890,24 @@ impl Context {
     }
 }
 
+/// The labels of a `switch` statement being translated.
+#[derive(Debug)]
+struct SwitchLabels {
+    /// The dtype of the controlling expression, to which the values of the cases are converted.
+    dtype: ir::Dtype,
+    /// The values of the `case` labels and their blocks, in the order they appear.
+    cases: Vec<(ir::Constant, ir::BlockId)>,
+    /// The block of the `default` label, if any.
+    default: Option<ir::BlockId>,
+}
+
 /// A C function being translated.
 struct IrgenFunc<'i> {
     /// return type of the function.
     return_type: ir::Dtype,
-    /// initial block id for the function, typically 0.
-    bid_init: ir::BlockId,
-    /// arguments represented as initial phinodes. Order must be the same of that given in the C
-    /// function.
-    phinodes_init: Vec<Named<ir::Dtype>>,
-    /// local allocations.
-    allocations: Vec<Named<ir::Dtype>>,
-    /// Map from block id to basic blocks
-    blocks: BTreeMap<ir::BlockId, ir::Block>,
-    /// current block id. `blocks` must have an entry for all ids less then this
-    bid_counter: usize,
+    /// The function being built. Its entry block has the arguments as phinodes, in the same order
+    /// as given in the C function.
+    builder: ir::FunctionBuilder,
     /// current temporary id. Used to create temporary names in the IR for e.g,
     tempid_counter: usize,
     /// Usable definitions
@@ -495,14 +917,26 @@ struct IrgenFunc<'i> {
     structs: &'i HashMap<String, Option<ir::Dtype>>,
     /// Current symbol table. The initial symbol table has the global variables.
     symbol_table: Vec<HashMap<String, ir::Operand>>,
+    /// The lines and columns of the C source, if known.
+    source_map: Option<&'i SourceMap>,
+    /// The labels of the enclosing `switch` statements, the innermost last.
+    switch_labels: Vec<SwitchLabels>,
+    /// The names of the global arrays of the string literals of the translation unit, by their
+    /// bytes. The arrays are declared after the function is translated.
+    string_literals: &'i mut HashMap<Vec<u8>, String>,
+    /// The functions declared without a prototype.
+    unprototyped: &'i HashSet<String>,
 }
 
 impl IrgenFunc<'_> {
     /// Allocate a new block id.
     fn alloc_bid(&mut self) -> ir::BlockId {
-        let bid = self.bid_counter;
-        self.bid_counter += 1;
-        ir::BlockId(bid)
+        self.builder.append_block()
+    }
+
+    /// The location in the C source of the code at `span`, if known.
+    fn loc(&self, span: Span) -> Option<ir::SourceLoc> {
+        self.source_map?.loc(span.start)
     }
 
     /// Allocate a new temporary id.
@@ -512,30 +946,21 @@ impl IrgenFunc<'_> {
         format!("t{tempid}")
     }
 
-    /// Create a new allocation with type given by `alloc`.
-    fn insert_alloc(&mut self, alloc: Named<ir::Dtype>) -> usize {
-        self.allocations.push(alloc);
-        self.allocations.len() - 1
+    /// Create a new allocation with type given by `alloc`, returning the pointer to it.
+    fn insert_alloc(&mut self, alloc: Named<ir::Dtype>) -> ir::Operand {
+        self.builder.push_alloc(alloc)
     }
 
     /// Insert a new block `context` with exit instruction `exit`.
     ///
     /// # Panic
     ///
-    /// Panics if another block with the same bid as `context` already existed.
+    /// Panics if another block with the same bid as `context` was already inserted.
     fn insert_block(&mut self, context: Context, exit: ir::BlockExit) {
-        let block = ir::Block {
-            phinodes: if context.bid == self.bid_init {
-                self.phinodes_init.clone()
-            } else {
-                Vec::new()
-            },
-            instructions: context.instrs,
-            exit,
-        };
-        if self.blocks.insert(context.bid, block).is_some() {
-            panic!("the bid `{}` is defined multiple time", context.bid)
+        for instr in context.instrs {
+            let _unused = self.builder.push_named_instr(context.bid, instr);
         }
+        self.builder.terminate(context.bid, exit);
     }
 
     /// Enter a scope and create a new symbol table entry, i.e, we are at a `{` in the function.
@@ -572,192 +997,2544 @@ impl IrgenFunc<'_> {
         Ok(())
     }
 
+    /// The pointer to the variable `name`, looking it up from the innermost scope.
+    fn lookup_symbol_table(&self, name: &str) -> Result<ir::Operand, IrgenErrorMessage> {
+        for scope in self.symbol_table.iter().rev() {
+            if let Some(operand) = scope.get(name) {
+                return Ok(operand.clone());
+            }
+        }
+
+        Err(IrgenErrorMessage::Misc {
+            message: format!("use of undeclared identifier `{name}`"),
+        })
+    }
+
     /// Transalte a C statement `stmt` under the current block `context`, with `continue` block
     /// `bid_continue` and break block `bid_break`.
     fn translate_stmt(
         &mut self,
-        _stmt: &Statement,
-        _context: &mut Context,
-        _bid_continue: Option<ir::BlockId>,
-        _bid_break: Option<ir::BlockId>,
+        stmt: &Statement,
+        context: &mut Context,
+        bid_continue: Option<ir::BlockId>,
+        bid_break: Option<ir::BlockId>,
     ) -> Result<(), IrgenError> {
-        todo!("Homework: IR Generation")
-    }
-
-    /// Translate parameter declaration of the functions to IR.
-    ///
-    /// For example, given the following C function from [`foo.c`][foo]:
-    ///
-    /// ```C
-    /// int foo(int x, int y, int z) {
-    ///    if (x == y) { return y; }
-    ///    else { return z; }
-    /// }
-    /// ```
-    ///
-    /// The IR before this function looks roughly as follows:
-    ///
-    /// ```text
-    /// fun i32 @foo (i32, i32, i32) {
-    ///   init:
-    ///     bid: b0
-    ///     allocations:
-    ///
-    ///   block b0:
-    ///     %b0:p0:i32:x
-    ///     %b0:p1:i32:y
-    ///     %b0:p2:i32:z
-    /// ```
-    ///
-    /// With the following arguments :
-    /// ```ignore
-    /// signature = FunctionSignature { ret: ir::INT, params: vec![ir::INT, ir::INT, ir::INT] }
-    /// bid_init = 0
-    /// name_of_params = ["x", "y", "z"]
-    /// context = // omitted
-    ///  ```
-    ///
-    /// Resulting IR after this function should be roughly follows:
-    /// ```text
-    /// fun i32 @foo (i32, i32, i32) {
-    ///   init:
-    ///     bid: b0
-    ///     allocations:
-    ///       %l0:i32:x
-    ///       %l1:i32:y
-    ///       %l2:i32:z
-    ///
-    ///   block b0:
-    ///     %b0:p0:i32:x
-    ///     %b0:p1:i32:y
-    ///     %b0:p2:i32:z
-    ///     %b0:i0:unit = store %b0:p0:i32 %l0:i32*
-    ///     %b0:i1:unit = store %b0:p1:i32 %l1:i32*
-    ///     %b0:i2:unit = store %b0:p2:i32 %l2:i32*
-    /// ```
-    /// 
-    /// In particular, note that it is added to the local allocation list and store them to the
-    /// initial phinodes.
-    ///
-    /// Note that the resulting IR is **a** solution. If you can think of a better way to
-    /// translate parameters, feel free to do so.
-    ///
-    /// [foo]: https://github.com/kaist-cp/kecc-public/blob/main/examples/c/foo.c
-    fn translate_parameter_decl(
-        &mut self,
-        _signature: &ir::FunctionSignature,
-        _bid_init: ir::BlockId,
-        _name_of_params: &[String],
-        _context: &mut Context,
-    ) -> Result<(), IrgenErrorMessage> {
-        todo!("Homework: IR Generation")
-    }
-}
+        match stmt {
+            Statement::Compound(items) => {
+                self.enter_scope();
+
+                for item in items {
+                    context.loc = self.loc(item.span);
+                    match &item.node {
+                        BlockItem::Declaration(decl) => {
+                            self.translate_decl(&decl.node, context)
+                                .map_err(|e| IrgenError::new(format!("{decl:#?}"), e))?;
+                        }
+                        BlockItem::StaticAssert(static_assert) => {
+                            return Err(IrgenError::new(
+                                format!("{static_assert:#?}"),
+                                IrgenErrorMessage::Unsupported {
+                                    feature: "`_Static_assert`".to_string(),
+                                    loc: self.loc(static_assert.span),
+                                },
+                            ));
+                        }
+                        BlockItem::Statement(stmt) => {
+                            self.translate_stmt(&stmt.node, context, bid_continue, bid_break)?;
+                        }
+                    }
+                }
 
-#[inline]
-fn name_of_declarator(declarator: &Declarator) -> String {
-    let declarator_kind = &declarator.kind;
-    match &declarator_kind.node {
-        DeclaratorKind::Abstract => panic!("DeclaratorKind::Abstract is unsupported"),
-        DeclaratorKind::Identifier(identifier) => identifier.node.name.clone(),
-        DeclaratorKind::Declarator(declarator) => name_of_declarator(&declarator.node),
-    }
-}
+                self.exit_scope();
+            }
+            Statement::Expression(expr) => {
+                if let Some(expr) = expr {
+                    context.loc = self.loc(expr.span);
+                    let _unused = self
+                        .translate_expr_rvalue(&expr.node, context)
+                        .map_err(|e| IrgenError::new(format!("{expr:#?}"), e))?;
+                }
+            }
+            Statement::If(stmt) => {
+                let bid_then = self.alloc_bid();
+                let bid_else = self.alloc_bid();
+                let bid_end = self.alloc_bid();
+
+                let condition = &stmt.node.condition;
+                context.loc = self.loc(condition.span);
+                self.translate_condition(
+                    &condition.node,
+                    mem::replace(context, Context::new(bid_end)),
+                    bid_then,
+                    bid_else,
+                )
+                .map_err(|e| IrgenError::new(format!("{condition:#?}"), e))?;
+
+                let mut context_then = Context::new(bid_then);
+                context_then.loc = self.loc(stmt.node.then_statement.span);
+                self.translate_stmt(
+                    &stmt.node.then_statement.node,
+                    &mut context_then,
+                    bid_continue,
+                    bid_break,
+                )?;
+                self.insert_block(
+                    context_then,
+                    ir::BlockExit::Jump {
+                        arg: ir::JumpArg::new(bid_end, Vec::new()),
+                    },
+                );
+
+                let mut context_else = Context::new(bid_else);
+                if let Some(else_stmt) = &stmt.node.else_statement {
+                    context_else.loc = self.loc(else_stmt.span);
+                    self.translate_stmt(
+                        &else_stmt.node,
+                        &mut context_else,
+                        bid_continue,
+                        bid_break,
+                    )?;
+                }
+                self.insert_block(
+                    context_else,
+                    ir::BlockExit::Jump {
+                        arg: ir::JumpArg::new(bid_end, Vec::new()),
+                    },
+                );
+            }
+            Statement::While(stmt) => {
+                let bid_cond = self.alloc_bid();
+                let bid_body = self.alloc_bid();
+                let bid_end = self.alloc_bid();
+
+                self.insert_block(
+                    mem::replace(context, Context::new(bid_end)),
+                    ir::BlockExit::Jump {
+                        arg: ir::JumpArg::new(bid_cond, Vec::new()),
+                    },
+                );
 
-#[inline]
-fn name_of_params_from_function_declarator(declarator: &Declarator) -> Option<Vec<String>> {
-    let declarator_kind = &declarator.kind;
-    match &declarator_kind.node {
-        DeclaratorKind::Abstract => panic!("DeclaratorKind::Abstract is unsupported"),
-        DeclaratorKind::Identifier(_) => {
-            name_of_params_from_derived_declarators(&declarator.derived)
-        }
-        DeclaratorKind::Declarator(next_declarator) => {
-            name_of_params_from_function_declarator(&next_declarator.node)
-                .or_else(|| name_of_params_from_derived_declarators(&declarator.derived))
-        }
-    }
-}
+                let condition = &stmt.node.expression;
+                let mut context_cond = Context::new(bid_cond);
+                context_cond.loc = self.loc(condition.span);
+                self.translate_condition(&condition.node, context_cond, bid_body, bid_end)
+                    .map_err(|e| IrgenError::new(format!("{condition:#?}"), e))?;
 
-#[inline]
-fn name_of_params_from_derived_declarators(
-    derived_decls: &[Node<DerivedDeclarator>],
-) -> Option<Vec<String>> {
-    for derived_decl in derived_decls {
-        match &derived_decl.node {
-            DerivedDeclarator::Function(func_decl) => {
-                let name_of_params = func_decl
-                    .node
-                    .parameters
-                    .iter()
-                    .map(|p| name_of_parameter_declaration(&p.node))
-                    .collect::<Option<Vec<_>>>()
-                    .unwrap_or_default();
-                return Some(name_of_params);
+                self.translate_loop_body(&stmt.node.statement, bid_body, bid_cond, bid_end)?;
             }
-            DerivedDeclarator::KRFunction(_kr_func_decl) => {
-                // K&R function is allowed only when it has no parameter
-                return Some(Vec::new());
+            Statement::DoWhile(stmt) => {
+                let bid_body = self.alloc_bid();
+                let bid_cond = self.alloc_bid();
+                let bid_end = self.alloc_bid();
+
+                self.insert_block(
+                    mem::replace(context, Context::new(bid_end)),
+                    ir::BlockExit::Jump {
+                        arg: ir::JumpArg::new(bid_body, Vec::new()),
+                    },
+                );
+
+                self.translate_loop_body(&stmt.node.statement, bid_body, bid_cond, bid_end)?;
+
+                let condition = &stmt.node.expression;
+                let mut context_cond = Context::new(bid_cond);
+                context_cond.loc = self.loc(condition.span);
+                self.translate_condition(&condition.node, context_cond, bid_body, bid_end)
+                    .map_err(|e| IrgenError::new(format!("{condition:#?}"), e))?;
             }
-            _ => (),
+            Statement::For(stmt) => {
+                // The variables declared by the initializer are only visible in the loop.
+                self.enter_scope();
+
+                let initializer = &stmt.node.initializer;
+                context.loc = self.loc(initializer.span);
+                match &initializer.node {
+                    ForInitializer::Empty => {}
+                    ForInitializer::Expression(expr) => {
+                        let _unused = self
+                            .translate_expr_rvalue(&expr.node, context)
+                            .map_err(|e| IrgenError::new(format!("{expr:#?}"), e))?;
+                    }
+                    ForInitializer::Declaration(decl) => {
+                        self.translate_decl(&decl.node, context)
+                            .map_err(|e| IrgenError::new(format!("{decl:#?}"), e))?;
+                    }
+                    ForInitializer::StaticAssert(static_assert) => {
+                        return Err(IrgenError::new(
+                            format!("{static_assert:#?}"),
+                            IrgenErrorMessage::Unsupported {
+                                feature: "`_Static_assert`".to_string(),
+                                loc: self.loc(static_assert.span),
+                            },
+                        ));
+                    }
+                }
+
+                let bid_cond = self.alloc_bid();
+                let bid_body = self.alloc_bid();
+                let bid_step = self.alloc_bid();
+                let bid_end = self.alloc_bid();
+
+                self.insert_block(
+                    mem::replace(context, Context::new(bid_end)),
+                    ir::BlockExit::Jump {
+                        arg: ir::JumpArg::new(bid_cond, Vec::new()),
+                    },
+                );
+
+                let mut context_cond = Context::new(bid_cond);
+                if let Some(condition) = &stmt.node.condition {
+                    context_cond.loc = self.loc(condition.span);
+                    self.translate_condition(&condition.node, context_cond, bid_body, bid_end)
+                        .map_err(|e| IrgenError::new(format!("{condition:#?}"), e))?;
+                } else {
+                    self.insert_block(
+                        context_cond,
+                        ir::BlockExit::Jump {
+                            arg: ir::JumpArg::new(bid_body, Vec::new()),
+                        },
+                    );
+                }
+
+                self.translate_loop_body(&stmt.node.statement, bid_body, bid_step, bid_end)?;
+
+                let mut context_step = Context::new(bid_step);
+                if let Some(step) = &stmt.node.step {
+                    context_step.loc = self.loc(step.span);
+                    let _unused = self
+                        .translate_expr_rvalue(&step.node, &mut context_step)
+                        .map_err(|e| IrgenError::new(format!("{step:#?}"), e))?;
+                }
+                self.insert_block(
+                    context_step,
+                    ir::BlockExit::Jump {
+                        arg: ir::JumpArg::new(bid_cond, Vec::new()),
+                    },
+                );
+
+                self.exit_scope();
+            }
+            Statement::Switch(stmt) => {
+                let expression = &stmt.node.expression;
+                context.loc = self.loc(expression.span);
+                let value = self
+                    .translate_expr_rvalue(&expression.node, context)
+                    .map_err(|e| IrgenError::new(format!("{expression:#?}"), e))?;
+                let dtype = value.dtype();
+                if !matches!(dtype, ir::Dtype::Int { .. }) {
+                    return Err(IrgenError::new(
+                        format!("{expression:#?}"),
+                        IrgenErrorMessage::Misc {
+                            message: format!("switch quantity of `{dtype}` is not an integer"),
+                        },
+                    ));
+                }
+                let value = self
+                    .translate_integer_promotion(value, context)
+                    .map_err(|e| IrgenError::new(format!("{expression:#?}"), e))?;
+                let dtype = value.dtype();
+
+                let bid_end = self.alloc_bid();
+                let bid_body = self.alloc_bid();
+                let context_switch = mem::replace(context, Context::new(bid_end));
+
+                // The code before the first label is unreachable, and is put in a block of its
+                // own. Each label starts a new block, which the code before it falls through to.
+                self.switch_labels.push(SwitchLabels {
+                    dtype,
+                    cases: Vec::new(),
+                    default: None,
+                });
+                let mut context_body = Context::new(bid_body);
+                context_body.loc = self.loc(stmt.node.statement.span);
+                let result = self.translate_stmt(
+                    &stmt.node.statement.node,
+                    &mut context_body,
+                    bid_continue,
+                    Some(bid_end),
+                );
+                let labels = self
+                    .switch_labels
+                    .pop()
+                    .expect("the labels of the switch statement must be pushed");
+                result?;
+                self.insert_block(
+                    context_body,
+                    ir::BlockExit::Jump {
+                        arg: ir::JumpArg::new(bid_end, Vec::new()),
+                    },
+                );
+
+                let cases = labels
+                    .cases
+                    .into_iter()
+                    .map(|(value, bid)| (value, ir::JumpArg::new(bid, Vec::new())))
+                    .collect();
+                let default = ir::JumpArg::new(labels.default.unwrap_or(bid_end), Vec::new());
+                self.insert_block(
+                    context_switch,
+                    ir::BlockExit::Switch {
+                        value,
+                        default,
+                        cases,
+                    },
+                );
+            }
+            Statement::Labeled(stmt) => {
+                let label = &stmt.node.label;
+                let bid = self.alloc_bid();
+                self.add_switch_label(label, bid)
+                    .map_err(|e| IrgenError::new(format!("{label:#?}"), e))?;
+
+                self.insert_block(
+                    mem::replace(context, Context::new(bid)),
+                    ir::BlockExit::Jump {
+                        arg: ir::JumpArg::new(bid, Vec::new()),
+                    },
+                );
+                context.loc = self.loc(stmt.node.statement.span);
+                self.translate_stmt(&stmt.node.statement.node, context, bid_continue, bid_break)?;
+            }
+            Statement::Continue | Statement::Break => {
+                let (bid, name, scope) = if *stmt == Statement::Continue {
+                    (bid_continue, "continue", "loop")
+                } else {
+                    (bid_break, "break", "loop or switch")
+                };
+                let bid = bid.ok_or_else(|| {
+                    IrgenError::new(
+                        name.to_string(),
+                        IrgenErrorMessage::Misc {
+                            message: format!("`{name}` statement not in {scope} statement"),
+                        },
+                    )
+                })?;
+
+                // The code after the jump is unreachable, and is put in a new block.
+                let bid_end = self.alloc_bid();
+                self.insert_block(
+                    mem::replace(context, Context::new(bid_end)),
+                    ir::BlockExit::Jump {
+                        arg: ir::JumpArg::new(bid, Vec::new()),
+                    },
+                );
+            }
+            Statement::Return(expr) => {
+                let value = match expr {
+                    Some(expr) => {
+                        context.loc = self.loc(expr.span);
+                        let value = self
+                            .translate_expr_rvalue(&expr.node, context)
+                            .map_err(|e| IrgenError::new(format!("{expr:#?}"), e))?;
+                        self.translate_typecast(value, self.return_type.clone(), context)
+                            .map_err(|e| IrgenError::new(format!("{expr:#?}"), e))?
+                    }
+                    None => ir::Operand::constant(ir::Constant::unit()),
+                };
+
+                // The code after `return` is unreachable, and is put in a new block.
+                let bid_end = self.alloc_bid();
+                self.insert_block(
+                    mem::replace(context, Context::new(bid_end)),
+                    ir::BlockExit::Return { value },
+                );
+            }
+            stmt => {
+                let feature = match stmt {
+                    Statement::Goto(_) => "`goto` statement",
+                    Statement::Asm(_) => "`asm` statement",
+                    _ => "statement",
+                };
+                return Err(IrgenError::new(
+                    format!("{stmt:#?}"),
+                    IrgenErrorMessage::Unsupported {
+                        feature: feature.to_string(),
+                        loc: context.loc,
+                    },
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Translates the body `stmt` of a loop into the block `bid_body`. `continue` in the body, as
+    /// well as its end, jumps to `bid_continue`, and `break` jumps to `bid_break`.
+    fn translate_loop_body(
+        &mut self,
+        stmt: &Node<Statement>,
+        bid_body: ir::BlockId,
+        bid_continue: ir::BlockId,
+        bid_break: ir::BlockId,
+    ) -> Result<(), IrgenError> {
+        let mut context = Context::new(bid_body);
+        context.loc = self.loc(stmt.span);
+        self.enter_scope();
+        self.translate_stmt(
+            &stmt.node,
+            &mut context,
+            Some(bid_continue),
+            Some(bid_break),
+        )?;
+        self.exit_scope();
+        self.insert_block(
+            context,
+            ir::BlockExit::Jump {
+                arg: ir::JumpArg::new(bid_continue, Vec::new()),
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Adds `label`, which starts the block `bid`, to the labels of the innermost `switch`
+    /// statement.
+    fn add_switch_label(
+        &mut self,
+        label: &Node<Label>,
+        bid: ir::BlockId,
+    ) -> Result<(), IrgenErrorMessage> {
+        let unsupported = |feature: &str| IrgenErrorMessage::Unsupported {
+            feature: feature.to_string(),
+            loc: self.loc(label.span),
+        };
+        let label = &label.node;
+        let name = match label {
+            Label::Case(_) => "case",
+            Label::Default => "default",
+            Label::Identifier(_) => return Err(unsupported("`goto` label")),
+            Label::CaseRange(_) => return Err(unsupported("case range")),
+        };
+        let labels = self
+            .switch_labels
+            .last_mut()
+            .ok_or_else(|| IrgenErrorMessage::Misc {
+                message: format!("`{name}` label not in switch statement"),
+            })?;
+
+        if let Label::Case(expr) = label {
+            let value = ir::const_eval::expression(&expr.node)
+                .and_then(|value| ir::const_eval::typecast(&value, &labels.dtype))
+                .map_err(|e| IrgenErrorMessage::Misc {
+                    message: format!("invalid case label, {e}"),
+                })?;
+            if labels.cases.iter().any(|(case, _)| *case == value) {
+                return Err(IrgenErrorMessage::Misc {
+                    message: format!("duplicate case value `{value}`"),
+                });
+            }
+            labels.cases.push((value, bid));
+        } else {
+            if labels.default.is_some() {
+                return Err(IrgenErrorMessage::Misc {
+                    message: "multiple default labels in one switch".to_string(),
+                });
+            }
+            labels.default = Some(bid);
+        }
+
+        Ok(())
+    }
+
+    /// Translates the declaration of local variables `decl`, storing their initial values if
+    /// given.
+    fn translate_decl(
+        &mut self,
+        decl: &Declaration,
+        context: &mut Context,
+    ) -> Result<(), IrgenErrorMessage> {
+        let loc = context.loc;
+        let unsupported = |feature: &str| IrgenErrorMessage::Unsupported {
+            feature: feature.to_string(),
+            loc,
+        };
+        let (base_dtype, is_typedef) =
+            ir::Dtype::try_from_ast_declaration_specifiers(&decl.specifiers)
+                .map_err(|e| invalid_dtype(e, loc))?;
+        if is_typedef {
+            return Err(unsupported("typedef in a function"));
+        }
+        if decl
+            .specifiers
+            .iter()
+            .any(|specifier| matches!(specifier.node, DeclarationSpecifier::StorageClass(_)))
+        {
+            return Err(unsupported("storage class specifier in a function"));
+        }
+        if matches!(
+            base_dtype,
+            ir::Dtype::Struct {
+                fields: Some(_),
+                ..
+            }
+        ) {
+            return Err(unsupported("struct or union definition in a function"));
+        }
+        base_dtype
+            .check_tag(self.structs)
+            .map_err(|e| IrgenErrorMessage::InvalidDtype { dtype_error: e })?;
+
+        for init_decl in &decl.declarators {
+            let declarator = &init_decl.node.declarator.node;
+            let name = name_of_declarator(declarator);
+            let dtype = base_dtype
+                .clone()
+                .with_ast_declarator(declarator)
+                .and_then(|dtype| dtype.into_inner().resolve_typedefs(self.typedefs))
+                .map_err(|e| invalid_dtype(e, loc))?;
+            if dtype.get_function_inner().is_some() {
+                return Err(unsupported(&format!(
+                    "declaration of function `{name}` in a function"
+                )));
+            }
+            if is_invalid_structure(&dtype, self.structs) {
+                return Err(IrgenErrorMessage::Misc {
+                    message: "incomplete struct type".to_string(),
+                });
+            }
+
+            let initializer = init_decl
+                .node
+                .initializer
+                .as_ref()
+                .map(|initializer| brace_initializer(&initializer.node, &dtype, self.structs))
+                .transpose()?;
+            match initializer {
+                Some(Initializer::Expression(expr)) => {
+                    let value = self.translate_expr_rvalue(&expr.node, context)?;
+                    let value = self.translate_typecast(value, dtype.clone(), context)?;
+                    let _unused = self.translate_alloc(name, dtype, Some(value), context)?;
+                }
+                Some(initializer) => {
+                    let ptr = self.translate_alloc(name, dtype.clone(), None, context)?;
+                    self.translate_clear_bit_fields(ptr.clone(), &dtype, context)?;
+                    self.translate_initializer(ptr, &dtype, Some(&initializer), context)?;
+                }
+                None => {
+                    let ptr = self.translate_alloc(name, dtype.clone(), None, context)?;
+                    self.translate_clear_bit_fields(ptr, &dtype, context)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Stores the values of `initializer`, which is fully braced, to the object of `dtype` that
+    /// `ptr` points to, and zeros to the scalars it leaves out, which are all of them if it is not
+    /// given.
+    fn translate_initializer(
+        &mut self,
+        ptr: ir::Operand,
+        dtype: &ir::Dtype,
+        initializer: Option<&Initializer>,
+        context: &mut Context,
+    ) -> Result<(), IrgenErrorMessage> {
+        let items = match initializer {
+            Some(Initializer::Expression(expr)) => {
+                let value = self.translate_expr_rvalue(&expr.node, context)?;
+                let value = self.translate_typecast(value, dtype.clone(), context)?;
+                let _unused = context.insert_instruction(store_instruction(ptr, value))?;
+                return Ok(());
+            }
+            Some(Initializer::List(items)) => items.as_slice(),
+            None => &[],
+        };
+
+        let subobjects = match dtype {
+            ir::Dtype::Array { inner, size } => {
+                let (size_of, _) = self.size_align_of(inner)?;
+                (0..*size)
+                    .map(|i| (i * size_of, inner.deref().clone()))
+                    .collect()
+            }
+            ir::Dtype::Struct { .. } => {
+                let fields = dtype
+                    .resolve_struct_fields(self.structs)
+                    .map_err(|e| IrgenErrorMessage::InvalidDtype { dtype_error: e })?;
+                let (_, _, offsets) = dtype
+                    .resolve_struct_layout(self.structs)
+                    .map_err(|e| IrgenErrorMessage::InvalidDtype { dtype_error: e })?;
+                izip!(offsets.iter().copied(), fields.iter())
+                    .filter(|(_, field)| !is_unnamed_bit_field(field))
+                    .take(if dtype.is_union() { 1 } else { usize::MAX })
+                    .map(|(offset, field)| (offset, field.deref().clone()))
+                    .collect::<Vec<_>>()
+            }
+            _ => {
+                let zero = match dtype {
+                    ir::Dtype::Int { .. } => ir::Constant::int(0, dtype.clone()),
+                    ir::Dtype::Float { .. } => ir::Constant::float(0.0, dtype.clone()),
+                    _ => ir::Constant::int(0, ir::Dtype::LONG),
+                };
+                let value =
+                    self.translate_typecast(ir::Operand::constant(zero), dtype.clone(), context)?;
+                let _unused = context.insert_instruction(store_instruction(ptr, value))?;
+                return Ok(());
+            }
+        };
+
+        for (i, (offset, dtype)) in subobjects.into_iter().enumerate() {
+            let initializer = items.get(i).map(|item| &item.node.initializer.node);
+            let offset = ir::Operand::constant(ir::Constant::int(offset as u128, ir::Dtype::LONG));
+
+            // The storage unit of a bit-field is already cleared.
+            if dtype.get_bit_field().is_some() {
+                let expr = some_or!(
+                    if let Some(Initializer::Expression(expr)) = initializer {
+                        Some(expr)
+                    } else {
+                        None
+                    },
+                    continue
+                );
+                let ptr = context.insert_instruction(ir::Instruction::GetElementPtr {
+                    ptr: ptr.clone(),
+                    offset,
+                    dtype: ir::Dtype::pointer(self.storage_unit_dtype(&dtype)?.set_const(false)),
+                })?;
+                let value = self.translate_expr_rvalue(&expr.node, context)?;
+                let _unused =
+                    self.translate_write(ptr, Some(&dtype.set_const(false)), value, context)?;
+                continue;
+            }
+
+            let ptr = context.insert_instruction(ir::Instruction::GetElementPtr {
+                ptr: ptr.clone(),
+                offset,
+                dtype: ir::Dtype::pointer(dtype.clone()),
+            })?;
+            self.translate_initializer(ptr, &dtype, initializer, context)?;
+        }
+
+        Ok(())
+    }
+
+    /// Stores zeros to the storage units of the bit-fields in the object of `dtype` that `ptr`
+    /// points to, as a bit-field is stored by updating the bits of its storage unit.
+    fn translate_clear_bit_fields(
+        &mut self,
+        ptr: ir::Operand,
+        dtype: &ir::Dtype,
+        context: &mut Context,
+    ) -> Result<(), IrgenErrorMessage> {
+        let mut units = Vec::new();
+        self.bit_field_units_of(dtype, 0, &mut units)?;
+        units.dedup();
+
+        for (offset, dtype) in units {
+            let ptr = context.insert_instruction(ir::Instruction::GetElementPtr {
+                ptr: ptr.clone(),
+                offset: ir::Operand::constant(ir::Constant::int(offset as u128, ir::Dtype::LONG)),
+                dtype: ir::Dtype::pointer(dtype.clone()),
+            })?;
+            let zero = ir::Operand::constant(ir::Constant::int(0, dtype.unqualified()));
+            let _unused = context.insert_instruction(store_instruction(ptr, zero))?;
+        }
+
+        Ok(())
+    }
+
+    /// Pushes the offsets and the dtypes of the storage units of the bit-fields in an object of
+    /// `dtype` at `offset` to `units`.
+    fn bit_field_units_of(
+        &self,
+        dtype: &ir::Dtype,
+        offset: usize,
+        units: &mut Vec<(usize, ir::Dtype)>,
+    ) -> Result<(), IrgenErrorMessage> {
+        match dtype {
+            ir::Dtype::Array { inner, size } => {
+                let (size_of, _) = self.size_align_of(inner)?;
+                for i in 0..*size {
+                    self.bit_field_units_of(inner, offset + i * size_of, units)?;
+                }
+            }
+            ir::Dtype::Struct { .. } => {
+                let fields = dtype
+                    .resolve_struct_fields(self.structs)
+                    .map_err(|e| IrgenErrorMessage::InvalidDtype { dtype_error: e })?;
+                let (_, _, offsets) = dtype
+                    .resolve_struct_layout(self.structs)
+                    .map_err(|e| IrgenErrorMessage::InvalidDtype { dtype_error: e })?;
+                for (field_offset, field) in izip!(offsets.iter(), fields.iter()) {
+                    if field.get_bit_field().is_some() {
+                        let unit_dtype = self.storage_unit_dtype(field)?.set_const(false);
+                        units.push((offset + field_offset, unit_dtype));
+                    } else {
+                        self.bit_field_units_of(field, offset + field_offset, units)?;
+                    }
+                }
+            }
+            _ => (),
+        }
+
+        Ok(())
+    }
+
+    /// Allocates the local variable `var` of `dtype`, storing `value` to it if given, and returns
+    /// the pointer to it.
+    fn translate_alloc(
+        &mut self,
+        var: String,
+        dtype: ir::Dtype,
+        value: Option<ir::Operand>,
+        context: &mut Context,
+    ) -> Result<ir::Operand, IrgenErrorMessage> {
+        let ptr = self.insert_alloc(Named::new(Some(var.clone()), dtype));
+        self.insert_symbol_table_entry(var, ptr.clone())?;
+
+        if let Some(value) = value {
+            let _unused = context.insert_instruction(store_instruction(ptr.clone(), value))?;
+        }
+
+        Ok(ptr)
+    }
+
+    /// Translates `condition`, and ends the block `context` with a branch to `bid_then` if it is
+    /// nonzero and to `bid_else` otherwise.
+    fn translate_condition(
+        &mut self,
+        condition: &Expression,
+        mut context: Context,
+        bid_then: ir::BlockId,
+        bid_else: ir::BlockId,
+    ) -> Result<(), IrgenErrorMessage> {
+        let condition = self.translate_expr_rvalue(condition, &mut context)?;
+        let condition = self.translate_typecast(condition, ir::Dtype::BOOL, &mut context)?;
+        self.insert_block(
+            context,
+            ir::BlockExit::ConditionalJump {
+                condition,
+                arg_then: ir::JumpArg::new(bid_then, Vec::new()),
+                arg_else: ir::JumpArg::new(bid_else, Vec::new()),
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Translates `expr` into the pointer to the object it designates.
+    fn translate_expr_lvalue(
+        &mut self,
+        expr: &Expression,
+        context: &mut Context,
+    ) -> Result<ir::Operand, IrgenErrorMessage> {
+        match expr {
+            Expression::Identifier(identifier) => self.lookup_symbol_table(&identifier.node.name),
+            Expression::UnaryOperator(unary)
+                if unary.node.operator.node == UnaryOperator::Indirection =>
+            {
+                self.translate_indirection(&unary.node.operand.node, context)
+            }
+            Expression::Member(member) => self.translate_member(&member.node, context),
+            Expression::StringLiteral(string_literal) => {
+                self.translate_string_literal(&string_literal.node)
+            }
+            Expression::BinaryOperator(binary)
+                if binary.node.operator.node == BinaryOperator::Index =>
+            {
+                self.translate_index(&binary.node.lhs.node, &binary.node.rhs.node, context)
+            }
+            _ => Err(IrgenErrorMessage::RequireLvalue {
+                message: "the operand".to_string(),
+            }),
+        }
+    }
+
+    /// Translates `*operand` into the pointer to the object it designates, which is the value of
+    /// `operand`.
+    fn translate_indirection(
+        &mut self,
+        operand: &Expression,
+        context: &mut Context,
+    ) -> Result<ir::Operand, IrgenErrorMessage> {
+        let ptr = self.translate_expr_rvalue(operand, context)?;
+        if ptr.dtype().get_pointer_inner().is_none() {
+            return Err(IrgenErrorMessage::Misc {
+                message: format!(
+                    "indirection requires pointer operand, not `{}`",
+                    ptr.dtype()
+                ),
+            });
+        }
+        Ok(ptr)
+    }
+
+    /// Translates `string_literal` into the pointer to the global array of its bytes, which is
+    /// shared by the string literals of the same bytes.
+    fn translate_string_literal(
+        &mut self,
+        string_literal: &StringLiteral,
+    ) -> Result<ir::Operand, IrgenErrorMessage> {
+        let bytes = bytes_of_string_literal(string_literal)?;
+        let dtype = string_literal_dtype(bytes.len());
+        let name = name_of_string_literal(self.string_literals, bytes);
+        Ok(ir::Operand::constant(ir::Constant::global_variable(
+            name, dtype,
+        )))
+    }
+
+    /// Translates `lhs[rhs]`, or equivalently `*(lhs + rhs)`, into the pointer to the element it
+    /// designates. Either of `lhs` and `rhs` may be the pointer, or the array decaying to one.
+    fn translate_index(
+        &mut self,
+        lhs: &Expression,
+        rhs: &Expression,
+        context: &mut Context,
+    ) -> Result<ir::Operand, IrgenErrorMessage> {
+        let lhs = self.translate_expr_rvalue(lhs, context)?;
+        let rhs = self.translate_expr_rvalue(rhs, context)?;
+        let (ptr, index) = match (lhs.dtype(), rhs.dtype()) {
+            (ir::Dtype::Pointer { .. }, ir::Dtype::Int { .. }) => (lhs, rhs),
+            (ir::Dtype::Int { .. }, ir::Dtype::Pointer { .. }) => (rhs, lhs),
+            (lhs_dtype, rhs_dtype) => {
+                return Err(IrgenErrorMessage::Misc {
+                    message: format!("cannot subscript `{lhs_dtype}` with `{rhs_dtype}`"),
+                })
+            }
+        };
+        self.translate_pointer_offset(&BinaryOperator::Plus, ptr, index, context)
+    }
+
+    /// Translates `s.f` or `p->f` into the pointer to the member `f`, which may be that of an
+    /// anonymous struct member of the struct.
+    fn translate_member(
+        &mut self,
+        member: &MemberExpression,
+        context: &mut Context,
+    ) -> Result<ir::Operand, IrgenErrorMessage> {
+        let (ptr, bit_field) = self.translate_member_unit(member, context)?;
+        if bit_field.is_some() {
+            return Err(IrgenErrorMessage::Misc {
+                message: format!(
+                    "address of bit-field `{}` requested",
+                    member.identifier.node.name
+                ),
+            });
+        }
+        Ok(ptr)
+    }
+
+    /// Translates `s.f` or `p->f` as [`Self::translate_member`] does, except that a bit-field `f`
+    /// is translated into the pointer to its storage unit, which is given with its dtype.
+    fn translate_member_unit(
+        &mut self,
+        member: &MemberExpression,
+        context: &mut Context,
+    ) -> Result<(ir::Operand, Option<ir::Dtype>), IrgenErrorMessage> {
+        let ptr = match member.operator.node {
+            MemberOperator::Direct if is_lvalue(&member.expression.node) => {
+                self.translate_expr_lvalue(&member.expression.node, context)?
+            }
+            // A struct that is not an object, e.g., the result of `f()` in `f().x`, is stored to a
+            // temporary one first.
+            MemberOperator::Direct => {
+                let value = self.translate_expr_rvalue(&member.expression.node, context)?;
+                let tempid = self.alloc_tempid();
+                let ptr = self.insert_alloc(Named::new(Some(tempid), value.dtype()));
+                let _unused = context.insert_instruction(store_instruction(ptr.clone(), value))?;
+                ptr
+            }
+            MemberOperator::Indirect => {
+                self.translate_indirection(&member.expression.node, context)?
+            }
+        };
+        let dtype = ptr.dtype();
+        let inner = dtype.get_pointer_inner().expect("`ptr` must be a pointer");
+        if !matches!(inner, ir::Dtype::Struct { .. }) {
+            return Err(IrgenErrorMessage::Misc {
+                message: format!(
+                    "member reference base type `{inner}` is not a structure or union"
+                ),
+            });
+        }
+        let _unused = inner
+            .resolve_struct_fields(self.structs)
+            .map_err(|e| IrgenErrorMessage::InvalidDtype { dtype_error: e })?;
+
+        let name = &member.identifier.node.name;
+        let (offset, field_dtype) = inner
+            .get_offset_struct_field(name, self.structs)
+            .ok_or_else(|| IrgenErrorMessage::Misc {
+                message: format!("no member named `{name}` in `{inner}`"),
+            })?;
+        // The members of a const or volatile struct are const or volatile as well.
+        let field_dtype = if inner.is_const() {
+            field_dtype.set_const(true)
+        } else {
+            field_dtype
+        };
+        let field_dtype = if inner.is_volatile() {
+            field_dtype.set_volatile(true)
+        } else {
+            field_dtype
+        };
+        let (dtype, bit_field) = if field_dtype.get_bit_field().is_some() {
+            (self.storage_unit_dtype(&field_dtype)?, Some(field_dtype))
+        } else {
+            (field_dtype, None)
+        };
+
+        let ptr = context.insert_instruction(ir::Instruction::GetElementPtr {
+            ptr,
+            offset: ir::Operand::constant(ir::Constant::int(offset as u128, ir::Dtype::LONG)),
+            dtype: ir::Dtype::pointer(dtype),
+        })?;
+        Ok((ptr, bit_field))
+    }
+
+    /// Translates the operand `expr` of an assignment, or of an increment or a decrement, into
+    /// the pointer to the object it designates. If it is a bit-field, the pointer to its storage
+    /// unit is given with its dtype.
+    fn translate_assignee(
+        &mut self,
+        expr: &Expression,
+        context: &mut Context,
+    ) -> Result<(ir::Operand, Option<ir::Dtype>), IrgenErrorMessage> {
+        if let Expression::Member(member) = expr {
+            return self.translate_member_unit(&member.node, context);
+        }
+        Ok((self.translate_expr_lvalue(expr, context)?, None))
+    }
+
+    /// Loads the value of the object `ptr` points to, or of the bit-field `bit_field` in it.
+    fn translate_read(
+        &mut self,
+        ptr: ir::Operand,
+        bit_field: Option<&ir::Dtype>,
+        context: &mut Context,
+    ) -> Result<ir::Operand, IrgenErrorMessage> {
+        let bit_field = some_or!(bit_field, return self.translate_load(ptr, context));
+        let unit = context.insert_instruction(load_instruction(ptr))?;
+        self.translate_bit_field_extract(unit, bit_field, context)
+    }
+
+    /// Stores `value` to the object `ptr` points to, or to the bit-field `bit_field` in it,
+    /// returning the value stored.
+    ///
+    /// A bit-field is stored by loading its storage unit, replacing its bits with those of the
+    /// value, and storing the storage unit back.
+    fn translate_write(
+        &mut self,
+        ptr: ir::Operand,
+        bit_field: Option<&ir::Dtype>,
+        value: ir::Operand,
+        context: &mut Context,
+    ) -> Result<ir::Operand, IrgenErrorMessage> {
+        let bit_field = some_or!(bit_field, return self.translate_store(ptr, value, context));
+        let (inner, width, offset) = bit_field.get_bit_field().expect("`bit_field` must be one");
+        if inner.is_const() {
+            return Err(IrgenErrorMessage::RequireLvalue {
+                message: "the left operand of an assignment, which is not modifiable".to_string(),
+            });
+        }
+        let unit_dtype = ptr
+            .dtype()
+            .get_pointer_inner()
+            .expect("a storage unit must be pointed to")
+            .clone()
+            .unqualified();
+        let bits = unit_dtype
+            .get_int_width()
+            .expect("a storage unit must be an int");
+        let constant =
+            |value: u128| ir::Operand::constant(ir::Constant::int(value, unit_dtype.clone()));
+        let mask = (u128::MAX >> (128 - width)) << offset;
+
+        let value = self.translate_typecast(value, inner.clone(), context)?;
+        let value = self.translate_typecast(value, unit_dtype.clone(), context)?;
+        let value = bit_op(
+            BinaryOperator::ShiftLeft,
+            value,
+            constant(offset as u128),
+            context,
+        )?;
+        let value = bit_op(BinaryOperator::BitwiseAnd, value, constant(mask), context)?;
+
+        let unit = context.insert_instruction(load_instruction(ptr.clone()))?;
+        let unit = bit_op(
+            BinaryOperator::BitwiseAnd,
+            unit,
+            constant(!mask & (u128::MAX >> (128 - bits))),
+            context,
+        )?;
+        let unit = bit_op(BinaryOperator::BitwiseOr, unit, value, context)?;
+        let _unused = context.insert_instruction(store_instruction(ptr, unit.clone()))?;
+
+        self.translate_bit_field_extract(unit, bit_field, context)
+    }
+
+    /// The value of the bit-field `bit_field` in the storage unit `unit`, which is moved to the
+    /// most significant bits and back to sign-extend it.
+    fn translate_bit_field_extract(
+        &mut self,
+        unit: ir::Operand,
+        bit_field: &ir::Dtype,
+        context: &mut Context,
+    ) -> Result<ir::Operand, IrgenErrorMessage> {
+        let (inner, width, offset) = bit_field.get_bit_field().expect("`bit_field` must be one");
+        let dtype = unit.dtype().unqualified();
+        let bits = dtype
+            .get_int_width()
+            .expect("a storage unit must be an int");
+        let dtype = dtype.set_signed(inner.is_int_signed());
+        let constant =
+            |value: usize| ir::Operand::constant(ir::Constant::int(value as u128, dtype.clone()));
+
+        let value = self.translate_typecast(unit, dtype.clone(), context)?;
+        let value = bit_op(
+            BinaryOperator::ShiftLeft,
+            value,
+            constant(bits - offset - width),
+            context,
+        )?;
+        let value = bit_op(
+            BinaryOperator::ShiftRight,
+            value,
+            constant(bits - width),
+            context,
+        )?;
+        let dtype = if width < ir::Dtype::INT.get_int_width().unwrap() {
+            ir::Dtype::INT
+        } else {
+            inner.clone()
+        };
+        self.translate_typecast(value, dtype, context)
+    }
+
+    /// The dtype of the storage unit of the bit-field `bit_field`, an unsigned integer as large
+    /// as its declared type.
+    fn storage_unit_dtype(&self, bit_field: &ir::Dtype) -> Result<ir::Dtype, IrgenErrorMessage> {
+        let (inner, _, _) = bit_field.get_bit_field().expect("`bit_field` must be one");
+        let (size_of, _) = self.size_align_of(inner)?;
+        Ok(ir::Dtype::int(size_of * ir::Dtype::BITS_OF_BYTE)
+            .set_signed(false)
+            .set_const(inner.is_const())
+            .set_volatile(inner.is_volatile()))
+    }
+
+    /// Translates `expr` into its value.
+    fn translate_expr_rvalue(
+        &mut self,
+        expr: &Expression,
+        context: &mut Context,
+    ) -> Result<ir::Operand, IrgenErrorMessage> {
+        match expr {
+            Expression::Identifier(_) | Expression::StringLiteral(_) => {
+                let ptr = self.translate_expr_lvalue(expr, context)?;
+                self.translate_load(ptr, context)
+            }
+            Expression::Member(member) => {
+                let (ptr, bit_field) = self.translate_member_unit(&member.node, context)?;
+                self.translate_read(ptr, bit_field.as_ref(), context)
+            }
+            Expression::Constant(constant) => {
+                let constant = ir::Constant::try_from(&constant.node).map_err(|_| {
+                    IrgenErrorMessage::Misc {
+                        message: format!("unsupported constant `{:?}`", constant.node),
+                    }
+                })?;
+                Ok(ir::Operand::constant(constant))
+            }
+            Expression::UnaryOperator(unary) => self.translate_unary_op(
+                &unary.node.operator.node,
+                &unary.node.operand.node,
+                context,
+            ),
+            Expression::BinaryOperator(binary) => self.translate_binary_op(
+                &binary.node.operator.node,
+                &binary.node.lhs.node,
+                &binary.node.rhs.node,
+                context,
+            ),
+            Expression::Call(call) => self.translate_func_call(call, context),
+            Expression::Conditional(conditional) => {
+                self.translate_conditional(&conditional.node, context)
+            }
+            Expression::Comma(exprs) => {
+                // The values of the expressions but the last are discarded.
+                let (last, exprs) = exprs.split_last().expect("comma has operands");
+                for expr in exprs {
+                    let _unused = self.translate_expr_rvalue(&expr.node, context)?;
+                }
+                self.translate_expr_rvalue(&last.node, context)
+            }
+            Expression::Cast(cast) => {
+                let dtype = self.dtype_of_type_name(&cast.node.type_name)?;
+                let value = self.translate_expr_rvalue(&cast.node.expression.node, context)?;
+                self.translate_typecast(value, dtype, context)
+            }
+            Expression::SizeOfTy(size_of) => {
+                let dtype = self.dtype_of_type_name(&size_of.node.0)?;
+                let (size_of, _) = self.size_align_of(&dtype)?;
+                Ok(ir::Operand::constant(ir::Constant::int(
+                    size_of as u128,
+                    ir::Dtype::LONG.set_signed(false),
+                )))
+            }
+            Expression::SizeOfVal(size_of) => {
+                let dtype = self.translate_expr_dtype(&size_of.node.0.node)?;
+                let (size_of, _) = self.size_align_of(&dtype)?;
+                Ok(ir::Operand::constant(ir::Constant::int(
+                    size_of as u128,
+                    ir::Dtype::LONG.set_signed(false),
+                )))
+            }
+            Expression::AlignOf(align_of) => {
+                let dtype = self.dtype_of_type_name(&align_of.node.0)?;
+                let (_, align_of) = self.size_align_of(&dtype)?;
+                Ok(ir::Operand::constant(ir::Constant::int(
+                    align_of as u128,
+                    ir::Dtype::LONG.set_signed(false),
+                )))
+            }
+            _ => Err(IrgenErrorMessage::Unsupported {
+                feature: "expression".to_string(),
+                loc: context.loc,
+            }),
+        }
+    }
+
+    /// The type of `expr`, which is translated into a block that is thrown away, so that it is not
+    /// evaluated as in `sizeof expr`. An array operand does not decay.
+    fn translate_expr_dtype(&mut self, expr: &Expression) -> Result<ir::Dtype, IrgenErrorMessage> {
+        let builder = self.builder.clone();
+        let tempid_counter = self.tempid_counter;
+
+        let mut context = Context::new(self.alloc_bid());
+        let dtype = if is_lvalue(expr) {
+            self.translate_expr_lvalue(expr, &mut context).map(|ptr| {
+                ptr.dtype()
+                    .get_pointer_inner()
+                    .expect("an lvalue must be translated into a pointer")
+                    .clone()
+            })
+        } else {
+            self.translate_expr_rvalue(expr, &mut context)
+                .map(|value| value.dtype())
+        };
+
+        self.builder = builder;
+        self.tempid_counter = tempid_counter;
+        dtype
+    }
+
+    /// The type named by `type_name`, e.g., in a cast.
+    fn dtype_of_type_name(
+        &self,
+        type_name: &Node<TypeName>,
+    ) -> Result<ir::Dtype, IrgenErrorMessage> {
+        ir::Dtype::try_from(&type_name.node)
+            .and_then(|dtype| dtype.resolve_typedefs(self.typedefs))
+            .map_err(|e| invalid_dtype(e, self.loc(type_name.span)))
+    }
+
+    /// The size and alignment of `dtype`, which must be complete.
+    fn size_align_of(&self, dtype: &ir::Dtype) -> Result<(usize, usize), IrgenErrorMessage> {
+        dtype
+            .size_align_of(self.structs)
+            .map_err(|e| IrgenErrorMessage::InvalidDtype { dtype_error: e })
+    }
+
+    /// The value of the object `ptr` points to. A function is not loaded, but stands for the
+    /// pointer to it, and an array decays to the pointer to its first element.
+    fn translate_load(
+        &mut self,
+        ptr: ir::Operand,
+        context: &mut Context,
+    ) -> Result<ir::Operand, IrgenErrorMessage> {
+        let dtype = ptr.dtype();
+        let inner = dtype
+            .get_pointer_inner()
+            .expect("an lvalue must be translated into a pointer");
+        if inner.get_function_inner().is_some() {
+            return Ok(ptr);
+        }
+        if let Some(element) = inner.get_array_inner() {
+            let dtype = ir::Dtype::pointer(element.clone());
+            return context.insert_instruction(ir::Instruction::GetElementPtr {
+                ptr,
+                offset: ir::Operand::constant(ir::Constant::int(0, ir::Dtype::LONG)),
+                dtype,
+            });
+        }
+
+        context.insert_instruction(load_instruction(ptr))
+    }
+
+    fn translate_unary_op(
+        &mut self,
+        op: &UnaryOperator,
+        operand: &Expression,
+        context: &mut Context,
+    ) -> Result<ir::Operand, IrgenErrorMessage> {
+        match op {
+            UnaryOperator::Plus => {
+                let value = self.translate_expr_rvalue(operand, context)?;
+                self.expect_arithmetic(op, &value)?;
+                self.translate_integer_promotion(value, context)
+            }
+            UnaryOperator::Minus | UnaryOperator::Complement => {
+                let value = self.translate_expr_rvalue(operand, context)?;
+                self.expect_arithmetic(op, &value)?;
+                if *op == UnaryOperator::Complement && value.dtype().get_int_width().is_none() {
+                    return Err(IrgenErrorMessage::Misc {
+                        message: format!("invalid operand `{}` to `~`", value.dtype()),
+                    });
+                }
+                let value = self.translate_integer_promotion(value, context)?;
+                let dtype = value.dtype();
+                context.insert_instruction(ir::Instruction::UnaryOp {
+                    op: op.clone(),
+                    operand: value,
+                    dtype,
+                })
+            }
+            UnaryOperator::Negate => {
+                let value = self.translate_expr_rvalue(operand, context)?;
+                let value = self.translate_typecast(value, ir::Dtype::BOOL, context)?;
+                let result = context.insert_instruction(ir::Instruction::UnaryOp {
+                    op: op.clone(),
+                    operand: value,
+                    dtype: ir::Dtype::BOOL,
+                })?;
+                self.translate_typecast(result, ir::Dtype::INT, context)
+            }
+            UnaryOperator::PreIncrement
+            | UnaryOperator::PreDecrement
+            | UnaryOperator::PostIncrement
+            | UnaryOperator::PostDecrement => {
+                let (ptr, bit_field) = self.translate_assignee(operand, context)?;
+                let old = self.translate_read(ptr.clone(), bit_field.as_ref(), context)?;
+                let bin_op = if matches!(
+                    op,
+                    UnaryOperator::PreIncrement | UnaryOperator::PostIncrement
+                ) {
+                    BinaryOperator::Plus
+                } else {
+                    BinaryOperator::Minus
+                };
+                let one = ir::Operand::constant(ir::Constant::int(1, ir::Dtype::INT));
+                let new = self.translate_arithmetic(&bin_op, old.clone(), one, context)?;
+                let new = self.translate_write(ptr, bit_field.as_ref(), new, context)?;
+
+                Ok(
+                    if matches!(
+                        op,
+                        UnaryOperator::PreIncrement | UnaryOperator::PreDecrement
+                    ) {
+                        new
+                    } else {
+                        old
+                    },
+                )
+            }
+            UnaryOperator::Address => self.translate_expr_lvalue(operand, context),
+            UnaryOperator::Indirection => {
+                let ptr = self.translate_indirection(operand, context)?;
+                self.translate_load(ptr, context)
+            }
+        }
+    }
+
+    fn translate_binary_op(
+        &mut self,
+        op: &BinaryOperator,
+        lhs: &Expression,
+        rhs: &Expression,
+        context: &mut Context,
+    ) -> Result<ir::Operand, IrgenErrorMessage> {
+        match op {
+            BinaryOperator::Assign => {
+                let (ptr, bit_field) = self.translate_assignee(lhs, context)?;
+                let value = self.translate_expr_rvalue(rhs, context)?;
+                self.translate_write(ptr, bit_field.as_ref(), value, context)
+            }
+            BinaryOperator::Multiply
+            | BinaryOperator::Divide
+            | BinaryOperator::Modulo
+            | BinaryOperator::Plus
+            | BinaryOperator::Minus
+            | BinaryOperator::ShiftLeft
+            | BinaryOperator::ShiftRight
+            | BinaryOperator::Less
+            | BinaryOperator::Greater
+            | BinaryOperator::LessOrEqual
+            | BinaryOperator::GreaterOrEqual
+            | BinaryOperator::Equals
+            | BinaryOperator::NotEquals
+            | BinaryOperator::BitwiseAnd
+            | BinaryOperator::BitwiseXor
+            | BinaryOperator::BitwiseOr => {
+                let lhs = self.translate_expr_rvalue(lhs, context)?;
+                let rhs = self.translate_expr_rvalue(rhs, context)?;
+                self.translate_arithmetic(op, lhs, rhs, context)
+            }
+            BinaryOperator::LogicalAnd | BinaryOperator::LogicalOr => {
+                self.translate_logical_op(op, lhs, rhs, context)
+            }
+            BinaryOperator::Index => {
+                let ptr = self.translate_index(lhs, rhs, context)?;
+                self.translate_load(ptr, context)
+            }
+            _ => {
+                let arith_op = arithmetic_of_compound_assignment(op).ok_or_else(|| {
+                    IrgenErrorMessage::Misc {
+                        message: format!("unsupported binary operator `{op:?}`"),
+                    }
+                })?;
+
+                // The object is designated once, and loaded after `rhs` is evaluated.
+                let (ptr, bit_field) = self.translate_assignee(lhs, context)?;
+                let rhs = self.translate_expr_rvalue(rhs, context)?;
+                let lhs = self.translate_read(ptr.clone(), bit_field.as_ref(), context)?;
+                let value = self.translate_arithmetic(&arith_op, lhs, rhs, context)?;
+                self.translate_write(ptr, bit_field.as_ref(), value, context)
+            }
+        }
+    }
+
+    /// Translates `lhs && rhs` or `lhs || rhs`, evaluating `rhs` only if `lhs` does not decide
+    /// the result. The truth value of the operator is passed to the block after it as an
+    /// argument, and the translation continues in that block.
+    ///
+    /// A chain of logical operators nested on the left, e.g., `a && b && c`, is translated in a
+    /// loop over its operands rather than recursively, so long chains do not exhaust the stack.
+    fn translate_logical_op(
+        &mut self,
+        op: &BinaryOperator,
+        lhs: &Expression,
+        rhs: &Expression,
+        context: &mut Context,
+    ) -> Result<ir::Operand, IrgenErrorMessage> {
+        let mut chain = vec![(op, rhs)];
+        let mut first = lhs;
+        while let Expression::BinaryOperator(expr) = first {
+            let op = &expr.node.operator.node;
+            if !matches!(op, BinaryOperator::LogicalAnd | BinaryOperator::LogicalOr) {
+                break;
+            }
+            chain.push((op, &expr.node.rhs.node));
+            first = &expr.node.lhs.node;
+        }
+
+        let lhs = self.translate_expr_rvalue(first, context)?;
+        let mut lhs = self.translate_typecast(lhs, ir::Dtype::BOOL, context)?;
+        for (op, rhs) in chain.into_iter().rev() {
+            lhs = self.translate_short_circuit(op, lhs, rhs, context)?;
+        }
+
+        self.translate_typecast(lhs, ir::Dtype::INT, context)
+    }
+
+    /// Translates one operator of a chain of logical operators, whose left operand is already
+    /// translated into the boolean `lhs`, and returns its boolean result.
+    fn translate_short_circuit(
+        &mut self,
+        op: &BinaryOperator,
+        lhs: ir::Operand,
+        rhs: &Expression,
+        context: &mut Context,
+    ) -> Result<ir::Operand, IrgenErrorMessage> {
+        let bid_rhs = self.alloc_bid();
+        let bid_end = self.alloc_bid();
+        let result = self
+            .builder
+            .push_phinode(bid_end, Named::new(None, ir::Dtype::BOOL));
+
+        // `lhs` decides the result if it is false for `&&`, or true for `||`.
+        let is_and = *op == BinaryOperator::LogicalAnd;
+        let arg_rhs = ir::JumpArg::new(bid_rhs, Vec::new());
+        let arg_end = ir::JumpArg::new(
+            bid_end,
+            vec![ir::Operand::constant(ir::Constant::int(
+                u128::from(!is_and),
+                ir::Dtype::BOOL,
+            ))],
+        );
+        let (arg_then, arg_else) = if is_and {
+            (arg_rhs, arg_end)
+        } else {
+            (arg_end, arg_rhs)
+        };
+        let loc = context.loc;
+        self.insert_block(
+            mem::replace(context, Context::new(bid_rhs)),
+            ir::BlockExit::ConditionalJump {
+                condition: lhs,
+                arg_then,
+                arg_else,
+            },
+        );
+
+        context.loc = loc;
+        let rhs = self.translate_expr_rvalue(rhs, context)?;
+        let rhs = self.translate_typecast(rhs, ir::Dtype::BOOL, context)?;
+        self.insert_block(
+            mem::replace(context, Context::new(bid_end)),
+            ir::BlockExit::Jump {
+                arg: ir::JumpArg::new(bid_end, vec![rhs]),
+            },
+        );
+
+        context.loc = loc;
+        Ok(result)
+    }
+
+    /// Translates `condition ? then_expression : else_expression`.
+    ///
+    /// If both arms can be evaluated regardless of the condition, they are, and the result is
+    /// selected by a `Select` instruction. Otherwise, only the arm chosen by the condition is
+    /// evaluated, and its value is passed to the block after the arms as an argument, where the
+    /// translation continues.
+    fn translate_conditional(
+        &mut self,
+        expr: &ConditionalExpression,
+        context: &mut Context,
+    ) -> Result<ir::Operand, IrgenErrorMessage> {
+        let condition = self.translate_expr_rvalue(&expr.condition.node, context)?;
+        let condition = self.translate_typecast(condition, ir::Dtype::BOOL, context)?;
+
+        if self.is_speculatable(&expr.then_expression.node)
+            && self.is_speculatable(&expr.else_expression.node)
+        {
+            let then_value = self.translate_expr_rvalue(&expr.then_expression.node, context)?;
+            let else_value = self.translate_expr_rvalue(&expr.else_expression.node, context)?;
+            let dtype = conditional_dtype(&then_value.dtype(), &else_value.dtype())?;
+            let then_value = self.translate_typecast(then_value, dtype.clone(), context)?;
+            let else_value = self.translate_typecast(else_value, dtype.clone(), context)?;
+            if dtype.is_scalar() {
+                return context.insert_instruction(ir::Instruction::Select {
+                    condition,
+                    then_value,
+                    else_value,
+                    dtype,
+                });
+            }
+
+            // Other values, e.g., structs, are selected by branching to the next block with them.
+            let bid_end = self.alloc_bid();
+            let result = self.builder.push_phinode(bid_end, Named::new(None, dtype));
+            let loc = context.loc;
+            self.insert_block(
+                mem::replace(context, Context::new(bid_end)),
+                ir::BlockExit::ConditionalJump {
+                    condition,
+                    arg_then: ir::JumpArg::new(bid_end, vec![then_value]),
+                    arg_else: ir::JumpArg::new(bid_end, vec![else_value]),
+                },
+            );
+            context.loc = loc;
+            return Ok(result);
+        }
+
+        let bid_then = self.alloc_bid();
+        let bid_else = self.alloc_bid();
+        let bid_end = self.alloc_bid();
+
+        let loc = context.loc;
+        self.insert_block(
+            mem::replace(context, Context::new(bid_end)),
+            ir::BlockExit::ConditionalJump {
+                condition,
+                arg_then: ir::JumpArg::new(bid_then, Vec::new()),
+                arg_else: ir::JumpArg::new(bid_else, Vec::new()),
+            },
+        );
+        context.loc = loc;
+
+        // The arms are converted to the type of the result, which depends on both of them, before
+        // their blocks are inserted.
+        let mut context_then = Context::new(bid_then);
+        context_then.loc = loc;
+        let then_value =
+            self.translate_expr_rvalue(&expr.then_expression.node, &mut context_then)?;
+        let mut context_else = Context::new(bid_else);
+        context_else.loc = loc;
+        let else_value =
+            self.translate_expr_rvalue(&expr.else_expression.node, &mut context_else)?;
+
+        let dtype = conditional_dtype(&then_value.dtype(), &else_value.dtype())?;
+        let (result, args_then, args_else) = if matches!(dtype, ir::Dtype::Unit { .. }) {
+            (
+                ir::Operand::constant(ir::Constant::unit()),
+                Vec::new(),
+                Vec::new(),
+            )
+        } else {
+            let then_value =
+                self.translate_typecast(then_value, dtype.clone(), &mut context_then)?;
+            let else_value =
+                self.translate_typecast(else_value, dtype.clone(), &mut context_else)?;
+            let result = self.builder.push_phinode(bid_end, Named::new(None, dtype));
+            (result, vec![then_value], vec![else_value])
+        };
+        self.insert_block(
+            context_then,
+            ir::BlockExit::Jump {
+                arg: ir::JumpArg::new(bid_end, args_then),
+            },
+        );
+        self.insert_block(
+            context_else,
+            ir::BlockExit::Jump {
+                arg: ir::JumpArg::new(bid_end, args_else),
+            },
+        );
+
+        Ok(result)
+    }
+
+    /// Whether `expr` has no side effects and cannot fail, so that it can be evaluated even if its
+    /// value is not used.
+    ///
+    /// Reading a volatile object is a side effect. `+`, `-` and `*` are left out, as irgen promises
+    /// with `nsw` that they do not overflow, which may not hold for an arm that is not taken.
+    fn is_speculatable(&self, expr: &Expression) -> bool {
+        match expr {
+            Expression::Identifier(identifier) => self
+                .lookup_symbol_table(&identifier.node.name)
+                .is_ok_and(|ptr| !is_volatile_object(&ptr)),
+            Expression::Constant(_) | Expression::StringLiteral(_) => true,
+            Expression::UnaryOperator(unary) => {
+                matches!(
+                    unary.node.operator.node,
+                    UnaryOperator::Plus
+                        | UnaryOperator::Minus
+                        | UnaryOperator::Complement
+                        | UnaryOperator::Negate
+                        | UnaryOperator::Address
+                ) && self.is_speculatable(&unary.node.operand.node)
+            }
+            // Division and shifts are left out, as they fail for some operands.
+            Expression::BinaryOperator(binary) => {
+                matches!(
+                    binary.node.operator.node,
+                    BinaryOperator::Less
+                        | BinaryOperator::Greater
+                        | BinaryOperator::LessOrEqual
+                        | BinaryOperator::GreaterOrEqual
+                        | BinaryOperator::Equals
+                        | BinaryOperator::NotEquals
+                        | BinaryOperator::BitwiseAnd
+                        | BinaryOperator::BitwiseXor
+                        | BinaryOperator::BitwiseOr
+                ) && self.is_speculatable(&binary.node.lhs.node)
+                    && self.is_speculatable(&binary.node.rhs.node)
+            }
+            Expression::Cast(cast) => self.is_speculatable(&cast.node.expression.node),
+            _ => false,
+        }
+    }
+
+    /// Converts `value` to the type of the object `ptr` points to and stores it, returning the
+    /// converted value, which is the value of the assignment.
+    fn translate_store(
+        &mut self,
+        ptr: ir::Operand,
+        value: ir::Operand,
+        context: &mut Context,
+    ) -> Result<ir::Operand, IrgenErrorMessage> {
+        let dtype = ptr
+            .dtype()
+            .get_pointer_inner()
+            .expect("an lvalue must be translated into a pointer")
+            .clone();
+        if dtype.is_immutable(self.structs) {
+            return Err(IrgenErrorMessage::RequireLvalue {
+                message: "the left operand of an assignment, which is not modifiable".to_string(),
+            });
+        }
+
+        let value = self.translate_typecast(value, dtype.unqualified(), context)?;
+        let _unused = context.insert_instruction(store_instruction(ptr, value.clone()))?;
+
+        Ok(value)
+    }
+
+    /// Computes `lhs op rhs` on values, converting them to their common type first. A comparison
+    /// results in an `int`.
+    fn translate_arithmetic(
+        &mut self,
+        op: &BinaryOperator,
+        lhs: ir::Operand,
+        rhs: ir::Operand,
+        context: &mut Context,
+    ) -> Result<ir::Operand, IrgenErrorMessage> {
+        let (lhs_dtype, rhs_dtype) = (lhs.dtype(), rhs.dtype());
+        let invalid = || IrgenErrorMessage::Misc {
+            message: format!("invalid operands `{lhs_dtype}` and `{rhs_dtype}` to `{op:?}`"),
+        };
+        let is_comparison = matches!(
+            op,
+            BinaryOperator::Less
+                | BinaryOperator::Greater
+                | BinaryOperator::LessOrEqual
+                | BinaryOperator::GreaterOrEqual
+                | BinaryOperator::Equals
+                | BinaryOperator::NotEquals
+        );
+
+        match (&lhs_dtype, &rhs_dtype) {
+            (ir::Dtype::Pointer { .. }, ir::Dtype::Int { .. })
+                if matches!(op, BinaryOperator::Plus | BinaryOperator::Minus) =>
+            {
+                return self.translate_pointer_offset(op, lhs, rhs, context);
+            }
+            (ir::Dtype::Int { .. }, ir::Dtype::Pointer { .. }) if *op == BinaryOperator::Plus => {
+                return self.translate_pointer_offset(op, rhs, lhs, context);
+            }
+            (ir::Dtype::Pointer { .. }, ir::Dtype::Pointer { .. }) if is_comparison => {
+                let result = context.insert_instruction(ir::Instruction::BinOp {
+                    op: op.clone(),
+                    lhs,
+                    rhs,
+                    dtype: ir::Dtype::BOOL,
+                    flags: ir::BinOpFlags::default(),
+                })?;
+                return self.translate_typecast(result, ir::Dtype::INT, context);
+            }
+            (ir::Dtype::Pointer { .. }, ir::Dtype::Int { .. })
+            | (ir::Dtype::Int { .. }, ir::Dtype::Pointer { .. })
+                if is_comparison =>
+            {
+                // Pointers are compared with the null pointer constant, i.e., `0`.
+                let (lhs, rhs) = if lhs_dtype.get_pointer_inner().is_some() {
+                    let rhs = self.translate_typecast(rhs, lhs_dtype.clone(), context)?;
+                    (lhs, rhs)
+                } else {
+                    let lhs = self.translate_typecast(lhs, rhs_dtype.clone(), context)?;
+                    (lhs, rhs)
+                };
+                let result = context.insert_instruction(ir::Instruction::BinOp {
+                    op: op.clone(),
+                    lhs,
+                    rhs,
+                    dtype: ir::Dtype::BOOL,
+                    flags: ir::BinOpFlags::default(),
+                })?;
+                return self.translate_typecast(result, ir::Dtype::INT, context);
+            }
+            _ => {}
+        }
+
+        let dtype = common_dtype(&lhs_dtype, &rhs_dtype).ok_or_else(invalid)?;
+        let is_integer_op = matches!(
+            op,
+            BinaryOperator::Modulo
+                | BinaryOperator::ShiftLeft
+                | BinaryOperator::ShiftRight
+                | BinaryOperator::BitwiseAnd
+                | BinaryOperator::BitwiseXor
+                | BinaryOperator::BitwiseOr
+        );
+        if is_integer_op && dtype.get_int_width().is_none() {
+            return Err(invalid());
+        }
+
+        if matches!(op, BinaryOperator::ShiftLeft | BinaryOperator::ShiftRight) {
+            // The result has the promoted type of `lhs`. The shift amount, which is promoted on its
+            // own in C, is converted to the type as well, as the operands of a `BinOp` agree.
+            let dtype = integer_promotion(&lhs_dtype);
+            let lhs = self.translate_typecast(lhs, dtype.clone(), context)?;
+            let rhs = self.translate_typecast(rhs, dtype.clone(), context)?;
+            return context.insert_instruction(ir::Instruction::BinOp {
+                op: op.clone(),
+                lhs,
+                rhs,
+                flags: ir::BinOpFlags::of_c(op, &dtype),
+                dtype,
+            });
+        }
+
+        let lhs = self.translate_typecast(lhs, dtype.clone(), context)?;
+        let rhs = self.translate_typecast(rhs, dtype.clone(), context)?;
+        if is_comparison {
+            let result = context.insert_instruction(ir::Instruction::BinOp {
+                op: op.clone(),
+                lhs,
+                rhs,
+                dtype: ir::Dtype::BOOL,
+                flags: ir::BinOpFlags::default(),
+            })?;
+            return self.translate_typecast(result, ir::Dtype::INT, context);
+        }
+
+        context.insert_instruction(ir::Instruction::BinOp {
+            op: op.clone(),
+            lhs,
+            rhs,
+            flags: ir::BinOpFlags::of_c(op, &dtype),
+            dtype,
+        })
+    }
+
+    /// Computes `ptr + offset` or `ptr - offset`, where `offset` is in the number of objects `ptr`
+    /// points to.
+    fn translate_pointer_offset(
+        &mut self,
+        op: &BinaryOperator,
+        ptr: ir::Operand,
+        offset: ir::Operand,
+        context: &mut Context,
+    ) -> Result<ir::Operand, IrgenErrorMessage> {
+        let dtype = ptr.dtype().unqualified();
+        let inner = dtype.get_pointer_inner().expect("`ptr` must be a pointer");
+        let (size_of, _) = self.size_align_of(inner)?;
+
+        let offset = self.translate_typecast(offset, ir::Dtype::LONG, context)?;
+        let offset = if *op == BinaryOperator::Minus {
+            context.insert_instruction(ir::Instruction::UnaryOp {
+                op: UnaryOperator::Minus,
+                operand: offset,
+                dtype: ir::Dtype::LONG,
+            })?
+        } else {
+            offset
+        };
+        let offset = context.insert_instruction(ir::Instruction::BinOp {
+            op: BinaryOperator::Multiply,
+            lhs: offset,
+            rhs: ir::Operand::constant(ir::Constant::int(size_of as u128, ir::Dtype::LONG)),
+            dtype: ir::Dtype::LONG,
+            flags: ir::BinOpFlags::of_c(&BinaryOperator::Multiply, &ir::Dtype::LONG),
+        })?;
+
+        context.insert_instruction(ir::Instruction::GetElementPtr { ptr, offset, dtype })
+    }
+
+    /// Translates the function call `call`. The arguments must match the prototype of the callee
+    /// in number, and be convertible to the types of the parameters. A function declared without
+    /// a prototype, e.g., `int f();`, takes no arguments, since its parameters are unknown.
+    fn translate_func_call(
+        &mut self,
+        call: &Node<CallExpression>,
+        context: &mut Context,
+    ) -> Result<ir::Operand, IrgenErrorMessage> {
+        let loc = self.loc(call.span);
+        let invalid_call = |message| IrgenErrorMessage::InvalidCall { message, loc };
+        let call = &call.node;
+
+        if let Expression::Identifier(identifier) = &call.callee.node {
+            let name = &identifier.node.name;
+            if self.lookup_symbol_table(name).is_err() {
+                return Err(invalid_call(format!(
+                    "implicit declaration of function `{name}`"
+                )));
+            }
+        }
+        let callee = self.translate_expr_rvalue(&call.callee.node, context)?;
+        let (ret, params) = some_or!(
+            callee
+                .dtype()
+                .get_pointer_inner()
+                .and_then(ir::Dtype::get_function_inner)
+                .map(|(ret, params)| (ret.clone(), params.clone())),
+            return Err(IrgenErrorMessage::NeedFunctionOrFunctionPointer { callee })
+        );
+        let name = callee
+            .get_constant()
+            .and_then(ir::Constant::get_global_variable_name);
+        if let Some(name) = name.filter(|name| self.unprototyped.contains(name)) {
+            if !call.arguments.is_empty() {
+                return Err(invalid_call(format!(
+                    "passing arguments to function `{name}` declared without a prototype"
+                )));
+            }
+        }
+        if call.arguments.len() != params.len() {
+            return Err(invalid_call(format!(
+                "too {} arguments to function call, expected {}, have {}",
+                if call.arguments.len() < params.len() {
+                    "few"
+                } else {
+                    "many"
+                },
+                params.len(),
+                call.arguments.len()
+            )));
+        }
+
+        let mut args = Vec::new();
+        for (i, (arg, param)) in izip!(&call.arguments, params).enumerate() {
+            let arg = self.translate_expr_rvalue(&arg.node, context)?;
+            let dtype = arg.dtype();
+            let arg = self
+                .translate_typecast(arg, param.clone(), context)
+                .map_err(|_| {
+                    invalid_call(format!(
+                        "passing `{dtype}` to parameter {} of incompatible type `{param}`",
+                        i + 1
+                    ))
+                })?;
+            args.push(arg);
+        }
+
+        context.insert_instruction(ir::Instruction::Call {
+            callee,
+            args,
+            return_type: ret.unqualified(),
+        })
+    }
+
+    /// Converts `value` to `target_dtype`.
+    fn translate_typecast(
+        &mut self,
+        value: ir::Operand,
+        target_dtype: ir::Dtype,
+        context: &mut Context,
+    ) -> Result<ir::Operand, IrgenErrorMessage> {
+        let dtype = value.dtype();
+        let target_dtype = target_dtype.unqualified();
+        if dtype.clone().unqualified() == target_dtype {
+            return Ok(value);
+        }
+
+        let is_scalar = |dtype: &ir::Dtype| {
+            matches!(
+                dtype,
+                ir::Dtype::Int { .. } | ir::Dtype::Float { .. } | ir::Dtype::Pointer { .. }
+            )
+        };
+        if !is_scalar(&dtype) || !is_scalar(&target_dtype) {
+            return Err(IrgenErrorMessage::Misc {
+                message: format!("cannot convert `{dtype}` to `{target_dtype}`"),
+            });
+        }
+
+        context.insert_instruction(ir::Instruction::TypeCast {
+            value,
+            target_dtype,
+        })
+    }
+
+    /// Checks that `value` is an integer or a floating-point number, as the operand of `op` must
+    /// be.
+    /// Converts `value` to `int` if it is an integer narrower than `int`, as C does to the operands
+    /// of most operators.
+    fn translate_integer_promotion(
+        &mut self,
+        value: ir::Operand,
+        context: &mut Context,
+    ) -> Result<ir::Operand, IrgenErrorMessage> {
+        let dtype = integer_promotion(&value.dtype());
+        self.translate_typecast(value, dtype, context)
+    }
+
+    fn expect_arithmetic(
+        &self,
+        op: &UnaryOperator,
+        value: &ir::Operand,
+    ) -> Result<(), IrgenErrorMessage> {
+        if matches!(
+            value.dtype(),
+            ir::Dtype::Int { .. } | ir::Dtype::Float { .. }
+        ) {
+            return Ok(());
+        }
+        Err(IrgenErrorMessage::Misc {
+            message: format!("invalid operand `{}` to `{op:?}`", value.dtype()),
+        })
+    }
+
+    /// Translate parameter declaration of the functions to IR.
+    ///
+    /// For example, given the following C function from [`foo.c`][foo]:
+    ///
+    /// ```C
+    /// int foo(int x, int y, int z) {
+    ///    if (x == y) { return y; }
+    ///    else { return z; }
+    /// }
+    /// ```
+    ///
+    /// The IR before this function looks roughly as follows:
+    ///
+    /// ```text
+    /// fun i32 @foo (i32, i32, i32) {
+    ///   init:
+    ///     bid: b0
+    ///     allocations:
+    ///
+    ///   block b0:
+    ///     %b0:p0:i32:x
+    ///     %b0:p1:i32:y
+    ///     %b0:p2:i32:z
+    /// ```
+    ///
+    /// With the following arguments :
+    /// ```ignore
+    /// signature = FunctionSignature { ret: ir::INT, params: vec![ir::INT, ir::INT, ir::INT] }
+    /// bid_init = 0
+    /// name_of_params = ["x", "y", "z"]
+    /// context = // omitted
+    ///  ```
+    ///
+    /// Resulting IR after this function should be roughly follows:
+    /// ```text
+    /// fun i32 @foo (i32, i32, i32) {
+    ///   init:
+    ///     bid: b0
+    ///     allocations:
+    ///       %l0:i32:x
+    ///       %l1:i32:y
+    ///       %l2:i32:z
+    ///
+    ///   block b0:
+    ///     %b0:p0:i32:x
+    ///     %b0:p1:i32:y
+    ///     %b0:p2:i32:z
+    ///     %b0:i0:unit = store %b0:p0:i32 %l0:i32*
+    ///     %b0:i1:unit = store %b0:p1:i32 %l1:i32*
+    ///     %b0:i2:unit = store %b0:p2:i32 %l2:i32*
+    /// ```
+    ///
+    /// In particular, note that it is added to the local allocation list and store them to the
+    /// initial phinodes.
+    ///
+    /// Note that the resulting IR is **a** solution. If you can think of a better way to
+    /// translate parameters, feel free to do so.
+    ///
+    /// [foo]: https://github.com/kaist-cp/kecc-public/blob/main/examples/c/foo.c
+    fn translate_parameter_decl(
+        &mut self,
+        signature: &ir::FunctionSignature,
+        bid_init: ir::BlockId,
+        name_of_params: &[String],
+        context: &mut Context,
+    ) -> Result<(), IrgenErrorMessage> {
+        if signature.params.len() != name_of_params.len() {
+            return Err(IrgenErrorMessage::Misc {
+                message: "parameter name omitted in function definition".to_string(),
+            });
+        }
+
+        for (index, (dtype, name)) in izip!(&signature.params, name_of_params).enumerate() {
+            let value = ir::Operand::register(ir::RegisterId::arg(bid_init, index), dtype.clone());
+            let _unused =
+                self.translate_alloc(name.clone(), dtype.clone(), Some(value), context)?;
+            self.builder.name_param(index, Some(name.clone()));
+        }
+
+        Ok(())
+    }
+}
+
+/// The lines of the main file of a preprocessed C source.
+///
+/// The preprocessor marks where the following lines come from with line markers, e.g.,
+/// `# 12 "foo.c"`. The first marker names the main file, and the lines of other files, e.g.,
+/// included headers, have no location. A source without markers is the main file itself.
+#[derive(Debug)]
+struct SourceMap {
+    /// The offset each line of the source starts at, and its line in the main file if any.
+    lines: Vec<(usize, Option<usize>)>,
+}
+
+impl SourceMap {
+    fn new(source: &str) -> Self {
+        let mut lines = Vec::new();
+        let mut main_file = None;
+        let mut in_main_file = true;
+        let mut next_line = 1;
+        let mut offset = 0;
+
+        for text in source.split_inclusive('\n') {
+            if let Some((line, file)) = parse_line_marker(text) {
+                let main_file = main_file.get_or_insert(file);
+                in_main_file = file == *main_file;
+                next_line = line;
+                lines.push((offset, None));
+            } else {
+                lines.push((offset, in_main_file.then_some(next_line)));
+                next_line += 1;
+            }
+            offset += text.len();
+        }
+
+        Self { lines }
+    }
+
+    /// The location of the byte at `offset` of the source.
+    fn loc(&self, offset: usize) -> Option<ir::SourceLoc> {
+        let index = self
+            .lines
+            .partition_point(|(start, _)| *start <= offset)
+            .checked_sub(1)?;
+        let (start, line) = self.lines[index];
+        Some(ir::SourceLoc {
+            line: line?,
+            column: offset - start + 1,
+        })
+    }
+}
+
+/// The line number and file name of a line marker, i.e., `# 12 "foo.c" 1` or `#line 12 "foo.c"`.
+fn parse_line_marker(text: &str) -> Option<(usize, &str)> {
+    let text = text.trim_start().strip_prefix('#')?.trim_start();
+    let text = text.strip_prefix("line").unwrap_or(text).trim_start();
+    let digits = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    let line = text[..digits].parse().ok()?;
+    let file = text[digits..].trim_start().strip_prefix('"')?;
+    let file = &file[..file.find('"')?];
+    Some((line, file))
+}
+
+/// The linkage of a name declared at file scope with `specifiers`, and whether the name is declared
+/// `extern`.
+#[inline]
+fn linkage_of_specifiers(
+    specifiers: &[Node<DeclarationSpecifier>],
+) -> Result<(ir::Linkage, bool), IrgenErrorMessage> {
+    let mut storage_classes = specifiers
+        .iter()
+        .filter_map(|specifier| {
+            if let DeclarationSpecifier::StorageClass(storage_class) = &specifier.node {
+                Some(&storage_class.node)
+            } else {
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+    // duplicate `typedef` is allowed
+    storage_classes.dedup();
+
+    match storage_classes.as_slice() {
+        [] | [StorageClassSpecifier::Typedef] => Ok((ir::Linkage::External, false)),
+        [StorageClassSpecifier::Static] => Ok((ir::Linkage::Internal, false)),
+        [StorageClassSpecifier::Extern] => Ok((ir::Linkage::External, true)),
+        [storage_class] => Err(IrgenErrorMessage::Misc {
+            message: format!("unsupported storage class specifier: {storage_class:?}"),
+        }),
+        _ => Err(IrgenErrorMessage::Misc {
+            message: "multiple storage classes in declaration specifiers".to_string(),
+        }),
+    }
+}
+
+#[inline]
+fn name_of_declarator(declarator: &Declarator) -> String {
+    let declarator_kind = &declarator.kind;
+    match &declarator_kind.node {
+        DeclaratorKind::Abstract => panic!("DeclaratorKind::Abstract is unsupported"),
+        DeclaratorKind::Identifier(identifier) => identifier.node.name.clone(),
+        DeclaratorKind::Declarator(declarator) => name_of_declarator(&declarator.node),
+    }
+}
+
+#[inline]
+fn name_of_params_from_function_declarator(declarator: &Declarator) -> Option<Vec<String>> {
+    let declarator_kind = &declarator.kind;
+    match &declarator_kind.node {
+        DeclaratorKind::Abstract => panic!("DeclaratorKind::Abstract is unsupported"),
+        DeclaratorKind::Identifier(_) => {
+            name_of_params_from_derived_declarators(&declarator.derived)
+        }
+        DeclaratorKind::Declarator(next_declarator) => {
+            name_of_params_from_function_declarator(&next_declarator.node)
+                .or_else(|| name_of_params_from_derived_declarators(&declarator.derived))
+        }
+    }
+}
+
+#[inline]
+fn name_of_params_from_derived_declarators(
+    derived_decls: &[Node<DerivedDeclarator>],
+) -> Option<Vec<String>> {
+    for derived_decl in derived_decls {
+        match &derived_decl.node {
+            DerivedDeclarator::Function(func_decl) => {
+                let name_of_params = func_decl
+                    .node
+                    .parameters
+                    .iter()
+                    .map(|p| name_of_parameter_declaration(&p.node))
+                    .collect::<Option<Vec<_>>>()
+                    .unwrap_or_default();
+                return Some(name_of_params);
+            }
+            DerivedDeclarator::KRFunction(_kr_func_decl) => {
+                // K&R function is allowed only when it has no parameter
+                return Some(Vec::new());
+            }
+            _ => (),
         };
     }
 
     None
 }
 
+/// Whether `declarator` declares a function without a prototype, e.g., `f()` in `int f();`.
+fn is_unprototyped(declarator: &Declarator) -> bool {
+    matches!(declarator.kind.node, DeclaratorKind::Identifier(_))
+        && matches!(
+            declarator.derived.first().map(|derived| &derived.node),
+            Some(DerivedDeclarator::KRFunction(_))
+        )
+}
+
 #[inline]
 fn name_of_parameter_declaration(parameter_declaration: &ParameterDeclaration) -> Option<String> {
     let declarator = some_or!(parameter_declaration.declarator.as_ref(), return None);
     Some(name_of_declarator(&declarator.node))
 }
 
-#[inline]
-fn is_valid_initializer(
+/// Returns `initializer` for an object of `dtype` with the braces of its subobjects made explicit,
+/// e.g., `{{1, 2}, {3, 0}}` for `int a[2][2] = {1, 2, 3}`. An aggregate initialized by a list has a
+/// list of the initializers of its first subobjects in order, and a scalar has an expression.
+///
+/// An expression for an aggregate subobject starts the initializer of its first scalar, so a
+/// struct member cannot be initialized by a struct value in a list.
+fn brace_initializer(
     initializer: &Initializer,
     dtype: &ir::Dtype,
     structs: &HashMap<String, Option<ir::Dtype>>,
-) -> bool {
-    match initializer {
-        Initializer::Expression(expr) => match dtype {
-            ir::Dtype::Int { .. } | ir::Dtype::Float { .. } | ir::Dtype::Pointer { .. } => {
-                match &expr.node {
-                    Expression::Constant(_) => true,
-                    Expression::UnaryOperator(unary) => matches!(
-                        &unary.node.operator.node,
-                        UnaryOperator::Minus | UnaryOperator::Plus
-                    ),
-                    _ => false,
-                }
+) -> Result<Initializer, IrgenErrorMessage> {
+    let items = match initializer {
+        Initializer::Expression(expr) => {
+            return Ok(some_or!(
+                string_initializer(&expr.node, dtype)?,
+                initializer.clone()
+            ))
+        }
+        Initializer::List(items) => items,
+    };
+
+    let mut items = items.iter().peekable();
+    let initializer = if subobjects_of(dtype, structs)?.is_some() {
+        Initializer::List(brace_items(&mut items, dtype, structs)?)
+    } else {
+        let item = items.next().ok_or_else(|| IrgenErrorMessage::Misc {
+            message: "empty scalar initializer".to_string(),
+        })?;
+        expect_no_designation(item)?;
+        brace_initializer(&item.node.initializer.node, dtype, structs)?
+    };
+    if items.next().is_some() {
+        return Err(IrgenErrorMessage::Misc {
+            message: "excess elements in initializer".to_string(),
+        });
+    }
+
+    Ok(initializer)
+}
+
+/// Takes the braced initializers of the subobjects of the aggregate `dtype` from `items`, as many
+/// as there are subobjects or items.
+fn brace_items<'a, I>(
+    items: &mut core::iter::Peekable<I>,
+    dtype: &ir::Dtype,
+    structs: &HashMap<String, Option<ir::Dtype>>,
+) -> Result<Vec<Node<InitializerListItem>>, IrgenErrorMessage>
+where
+    I: Iterator<Item = &'a Node<InitializerListItem>>,
+{
+    let dtypes = subobjects_of(dtype, structs)?.expect("`dtype` must be an aggregate");
+
+    let mut braced = Vec::new();
+    for dtype in dtypes {
+        let item = *some_or!(items.peek(), break);
+        expect_no_designation(item)?;
+        let initializer = match &item.node.initializer.node {
+            // The braces of the subobject are left out.
+            Initializer::Expression(expr)
+                if subobjects_of(&dtype, structs)?.is_some()
+                    && string_initializer(&expr.node, &dtype)?.is_none() =>
+            {
+                Initializer::List(brace_items(items, &dtype, structs)?)
             }
-            _ => false,
-        },
-        Initializer::List(items) => match dtype {
-            ir::Dtype::Array { inner, .. } => items
-                .iter()
-                .all(|i| is_valid_initializer(&i.node.initializer.node, inner, structs)),
-            ir::Dtype::Struct { name, .. } => {
-                let name = name.as_ref().expect("struct should have its name");
-                let struct_type = structs
-                    .get(name)
-                    .expect("struct type matched with `name` must exist")
-                    .as_ref()
-                    .expect("`struct_type` must have its definition");
-                let fields = struct_type
-                    .get_struct_fields()
-                    .expect("`struct_type` must be struct type")
-                    .as_ref()
-                    .expect("`fields` must be `Some`");
-
-                izip!(fields, items).all(|(f, i)| {
-                    is_valid_initializer(&i.node.initializer.node, f.deref(), structs)
-                })
+            initializer => {
+                let _unused = items.next();
+                brace_initializer(initializer, &dtype, structs)?
             }
-            _ => false,
+        };
+        braced.push(Node::new(
+            InitializerListItem {
+                designation: Vec::new(),
+                initializer: Box::new(Node::new(initializer, item.node.initializer.span)),
+            },
+            item.span,
+        ));
+    }
+
+    Ok(braced)
+}
+
+/// The dtypes of the subobjects of `dtype` in order, if it is an aggregate. Only the first member
+/// of a union is initialized by a brace-enclosed initializer.
+fn subobjects_of(
+    dtype: &ir::Dtype,
+    structs: &HashMap<String, Option<ir::Dtype>>,
+) -> Result<Option<Vec<ir::Dtype>>, IrgenErrorMessage> {
+    let dtypes = match dtype {
+        ir::Dtype::Array { inner, size } => vec![inner.deref().clone(); *size],
+        ir::Dtype::Struct { .. } => dtype
+            .resolve_struct_fields(structs)
+            .map_err(|e| IrgenErrorMessage::InvalidDtype { dtype_error: e })?
+            .iter()
+            .filter(|field| !is_unnamed_bit_field(field))
+            .take(if dtype.is_union() { 1 } else { usize::MAX })
+            .map(|field| field.deref().clone())
+            .collect(),
+        _ => return Ok(None),
+    };
+    Ok(Some(dtypes))
+}
+
+/// The initializer of the bytes of `expr` for an object of `dtype`, if `expr` is a string
+/// literal and `dtype` is a `char` array. The terminating null byte is left out if the array has
+/// no room for it.
+fn string_initializer(
+    expr: &Expression,
+    dtype: &ir::Dtype,
+) -> Result<Option<Initializer>, IrgenErrorMessage> {
+    let string_literal = some_or!(
+        if let Expression::StringLiteral(string_literal) = expr {
+            Some(&string_literal.node)
+        } else {
+            None
+        },
+        return Ok(None)
+    );
+    let (inner, size) = some_or!(
+        if let ir::Dtype::Array { inner, size } = dtype {
+            Some((inner, *size))
+        } else {
+            None
         },
+        return Ok(None)
+    );
+    if inner.get_int_width() != Some(ir::Dtype::SIZE_OF_CHAR * ir::Dtype::BITS_OF_BYTE) {
+        return Ok(None);
+    }
+
+    let mut bytes = bytes_of_string_literal(string_literal)?;
+    if bytes.len() > size + 1 {
+        return Err(IrgenErrorMessage::Misc {
+            message: "initializer-string for char array is too long".to_string(),
+        });
+    }
+    bytes.truncate(size);
+    Ok(Some(initializer_of_bytes(&bytes)))
+}
+
+/// The initializer list of `bytes` for a `char` array.
+fn initializer_of_bytes(bytes: &[u8]) -> Initializer {
+    let items = bytes
+        .iter()
+        .map(|byte| {
+            let value = ir::Constant::int(u128::from(*byte), ir::Dtype::INT);
+            let expr = literal_of_constant(&value).expect("an int has a literal");
+            let initializer = Initializer::Expression(Box::new(Node::new(expr, Span::none())));
+            let item = InitializerListItem {
+                designation: Vec::new(),
+                initializer: Box::new(Node::new(initializer, Span::none())),
+            };
+            Node::new(item, Span::none())
+        })
+        .collect();
+    Initializer::List(items)
+}
+
+/// The name of the global array of the string literal of `bytes`, which is shared by the string
+/// literals of the same bytes.
+fn name_of_string_literal(
+    string_literals: &mut HashMap<Vec<u8>, String>,
+    bytes: Vec<u8>,
+) -> String {
+    let count = string_literals.len();
+    string_literals
+        .entry(bytes)
+        .or_insert_with(|| format!("__string{count}"))
+        .clone()
+}
+
+/// The dtype of the global array of a string literal of `len` bytes including the terminating
+/// null byte. The array is read-only.
+fn string_literal_dtype(len: usize) -> ir::Dtype {
+    ir::Dtype::array(ir::Dtype::CHAR.set_const(true), len)
+}
+
+/// The bytes of `string_literal` including the terminating null byte, with its escape sequences
+/// replaced. The pieces of the literal, which are written next to each other in the C source,
+/// are concatenated.
+fn bytes_of_string_literal(string_literal: &StringLiteral) -> Result<Vec<u8>, IrgenErrorMessage> {
+    let invalid = |piece: &str| IrgenErrorMessage::Misc {
+        message: format!("unsupported string literal `{piece}`"),
+    };
+
+    let mut bytes = Vec::new();
+    for piece in string_literal {
+        let text = piece
+            .strip_prefix('"')
+            .and_then(|text| text.strip_suffix('"'))
+            .ok_or_else(|| invalid(piece))?;
+        bytes.extend(ir::unescape(text).ok_or_else(|| invalid(piece))?);
+    }
+    bytes.push(0);
+
+    Ok(bytes)
+}
+
+fn expect_no_designation(item: &Node<InitializerListItem>) -> Result<(), IrgenErrorMessage> {
+    if !item.node.designation.is_empty() {
+        return Err(IrgenErrorMessage::Misc {
+            message: "designated initializer is unsupported".to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// The literal of `constant`, which evaluates to `constant` once converted to its dtype.
+fn literal_of_constant(constant: &ir::Constant) -> Option<Expression> {
+    let (literal, is_negative) = match constant {
+        ir::Constant::Int {
+            value,
+            width,
+            is_signed,
+        } => {
+            // The magnitude of the minimum `long` is not a signed `long`, so it is written as its
+            // bits instead, which the conversion to its dtype reads back as a negative value.
+            let signed_value = *value as i128;
+            let (magnitude, is_negative) =
+                if *is_signed && signed_value < 0 && signed_value > i64::MIN.into() {
+                    (signed_value.unsigned_abs(), true)
+                } else {
+                    (ir::trim_unnecessary_bits(*value, *width as u128), false)
+                };
+            // Literals without a suffix are `int`s, which cannot hold larger magnitudes.
+            let size = if magnitude < 1 << (ir::Dtype::SIZE_OF_INT * ir::Dtype::BITS_OF_BYTE - 1) {
+                IntegerSize::Int
+            } else {
+                IntegerSize::Long
+            };
+            let integer = Integer {
+                base: IntegerBase::Decimal,
+                number: magnitude.to_string().into_boxed_str(),
+                suffix: IntegerSuffix {
+                    size,
+                    unsigned: false,
+                    imaginary: false,
+                },
+            };
+            (Constant::Integer(integer), is_negative)
+        }
+        ir::Constant::Float { value, width } => {
+            let value = value.into_inner();
+            if !value.is_finite() {
+                return None;
+            }
+            let (mut number, format) =
+                if *width == ir::Dtype::SIZE_OF_FLOAT * ir::Dtype::BITS_OF_BYTE {
+                    ((value.abs() as f32).to_string(), FloatFormat::Float)
+                } else {
+                    (value.abs().to_string(), FloatFormat::Double)
+                };
+            if !number.contains('.') {
+                number.push_str(".0");
+            }
+            let float = Float {
+                base: FloatBase::Decimal,
+                number: number.into_boxed_str(),
+                suffix: FloatSuffix {
+                    format,
+                    imaginary: false,
+                },
+            };
+            (Constant::Float(float), value.is_sign_negative())
+        }
+        _ => return None,
+    };
+
+    let literal = Expression::Constant(Box::new(Node::new(literal, Span::none())));
+    if !is_negative {
+        return Some(literal);
+    }
+
+    let minus = UnaryOperatorExpression {
+        operator: Node::new(UnaryOperator::Minus, Span::none()),
+        operand: Box::new(Node::new(literal, Span::none())),
+    };
+    Some(Expression::UnaryOperator(Box::new(Node::new(
+        minus,
+        Span::none(),
+    ))))
+}
+
+/// The type the operands of an arithmetic operator of `lhs` and `rhs` are converted to by the usual
+/// arithmetic conversions: the wider floating-point type if any, and otherwise the wider of the
+/// promoted integer types, unsigned if both are as wide.
+fn common_dtype(lhs: &ir::Dtype, rhs: &ir::Dtype) -> Option<ir::Dtype> {
+    let (lhs, rhs) = (&integer_promotion(lhs), &integer_promotion(rhs));
+    let dtype = match (lhs, rhs) {
+        (ir::Dtype::Float { width: lhs_w, .. }, ir::Dtype::Float { width: rhs_w, .. }) => {
+            ir::Dtype::float(*lhs_w.max(rhs_w))
+        }
+        (ir::Dtype::Float { .. }, ir::Dtype::Int { .. }) => lhs.clone(),
+        (ir::Dtype::Int { .. }, ir::Dtype::Float { .. }) => rhs.clone(),
+        (
+            ir::Dtype::Int {
+                width: lhs_w,
+                is_signed: lhs_s,
+                ..
+            },
+            ir::Dtype::Int {
+                width: rhs_w,
+                is_signed: rhs_s,
+                ..
+            },
+        ) => {
+            let is_signed = match lhs_w.cmp(rhs_w) {
+                core::cmp::Ordering::Less => *rhs_s,
+                core::cmp::Ordering::Equal => *lhs_s && *rhs_s,
+                core::cmp::Ordering::Greater => *lhs_s,
+            };
+            ir::Dtype::int(*lhs_w.max(rhs_w)).set_signed(is_signed)
+        }
+        _ => return None,
+    };
+    Some(dtype.unqualified())
+}
+
+/// The type `dtype` is promoted to, which is `int` for the integers narrower than `int`, and
+/// `dtype` itself for the others.
+fn integer_promotion(dtype: &ir::Dtype) -> ir::Dtype {
+    match dtype.get_int_width() {
+        Some(width) if width < ir::Dtype::SIZE_OF_INT * ir::Dtype::BITS_OF_BYTE => ir::Dtype::INT,
+        _ => dtype.clone().unqualified(),
+    }
+}
+
+/// The arithmetic operator of the compound assignment operator `op`, e.g., `+` of `+=`.
+fn arithmetic_of_compound_assignment(op: &BinaryOperator) -> Option<BinaryOperator> {
+    let op = match op {
+        BinaryOperator::AssignMultiply => BinaryOperator::Multiply,
+        BinaryOperator::AssignDivide => BinaryOperator::Divide,
+        BinaryOperator::AssignModulo => BinaryOperator::Modulo,
+        BinaryOperator::AssignPlus => BinaryOperator::Plus,
+        BinaryOperator::AssignMinus => BinaryOperator::Minus,
+        BinaryOperator::AssignShiftLeft => BinaryOperator::ShiftLeft,
+        BinaryOperator::AssignShiftRight => BinaryOperator::ShiftRight,
+        BinaryOperator::AssignBitwiseAnd => BinaryOperator::BitwiseAnd,
+        BinaryOperator::AssignBitwiseXor => BinaryOperator::BitwiseXor,
+        BinaryOperator::AssignBitwiseOr => BinaryOperator::BitwiseOr,
+        _ => return None,
+    };
+    Some(op)
+}
+
+/// The type of the result of a conditional operator whose arms are of `then_dtype` and
+/// `else_dtype`.
+///
+/// Arithmetic arms are converted to their common type. A pointer arm and an integer arm, which
+/// should be a null pointer constant, result in the pointer, and pointers to different types
+/// result in `void*` if either of them is.
+fn conditional_dtype(
+    then_dtype: &ir::Dtype,
+    else_dtype: &ir::Dtype,
+) -> Result<ir::Dtype, IrgenErrorMessage> {
+    if let Some(dtype) = common_dtype(then_dtype, else_dtype) {
+        return Ok(dtype);
+    }
+    let then_dtype = then_dtype.clone().unqualified();
+    let else_dtype = else_dtype.clone().unqualified();
+    if then_dtype == else_dtype {
+        return Ok(then_dtype);
+    }
+
+    let is_void_pointer = |dtype: &ir::Dtype| {
+        dtype
+            .get_pointer_inner()
+            .is_some_and(|inner| matches!(inner, ir::Dtype::Unit { .. }))
+    };
+    match (&then_dtype, &else_dtype) {
+        (ir::Dtype::Pointer { .. }, ir::Dtype::Int { .. }) => Ok(then_dtype),
+        (ir::Dtype::Int { .. }, ir::Dtype::Pointer { .. }) => Ok(else_dtype),
+        (ir::Dtype::Pointer { .. }, ir::Dtype::Pointer { .. }) if is_void_pointer(&then_dtype) => {
+            Ok(then_dtype)
+        }
+        (ir::Dtype::Pointer { .. }, ir::Dtype::Pointer { .. }) if is_void_pointer(&else_dtype) => {
+            Ok(else_dtype)
+        }
+        _ => Err(IrgenErrorMessage::Misc {
+            message: format!(
+                "type mismatch in conditional expression, `{then_dtype}` and `{else_dtype}`"
+            ),
+        }),
+    }
+}
+
+/// Whether `expr` is translated by [`IrgenFunc::translate_expr_lvalue`] into the pointer to the
+/// object it designates. A member of a struct that is not an object, as in `f().x`, is one as
+/// well, although it is not a modifiable lvalue in C.
+fn is_lvalue(expr: &Expression) -> bool {
+    match expr {
+        Expression::Identifier(_) | Expression::Member(_) | Expression::StringLiteral(_) => true,
+        Expression::UnaryOperator(unary) => unary.node.operator.node == UnaryOperator::Indirection,
+        Expression::BinaryOperator(binary) => binary.node.operator.node == BinaryOperator::Index,
+        _ => false,
+    }
+}
+
+/// The `load` from `ptr`, which is volatile if the object it points to is.
+fn load_instruction(ptr: ir::Operand) -> ir::Instruction {
+    let volatile = is_volatile_object(&ptr);
+    ir::Instruction::Load { ptr, volatile }
+}
+
+/// The `store` of `value` to `ptr`, which is volatile if the object it points to is.
+fn store_instruction(ptr: ir::Operand, value: ir::Operand) -> ir::Instruction {
+    let volatile = is_volatile_object(&ptr);
+    ir::Instruction::Store {
+        ptr,
+        value,
+        volatile,
+    }
+}
+
+/// Whether the object `ptr` points to is volatile, so that the accesses to it must be kept.
+fn is_volatile_object(ptr: &ir::Operand) -> bool {
+    ptr.dtype()
+        .get_pointer_inner()
+        .expect("`ptr` must be a pointer")
+        .is_volatile()
+}
+
+/// The error of `dtype_error` found at `loc` in the C source. An unsupported feature is reported as
+/// such, so that it is told apart from an invalid program.
+fn invalid_dtype(dtype_error: DtypeError, loc: Option<ir::SourceLoc>) -> IrgenErrorMessage {
+    match dtype_error {
+        DtypeError::Unsupported { feature } => IrgenErrorMessage::Unsupported { feature, loc },
+        dtype_error => IrgenErrorMessage::InvalidDtype { dtype_error },
     }
 }
 
+/// Whether `field` is an unnamed bit-field, which is not a member of its struct and only pads it.
+fn is_unnamed_bit_field(field: &Named<ir::Dtype>) -> bool {
+    field.name().is_none() && field.get_bit_field().is_some()
+}
+
+/// Computes `lhs op rhs` on the bits of a storage unit of bit-fields in the type of `lhs`,
+/// without the usual arithmetic conversions.
+fn bit_op(
+    op: BinaryOperator,
+    lhs: ir::Operand,
+    rhs: ir::Operand,
+    context: &mut Context,
+) -> Result<ir::Operand, IrgenErrorMessage> {
+    let dtype = lhs.dtype();
+    context.insert_instruction(ir::Instruction::BinOp {
+        op,
+        lhs,
+        rhs,
+        dtype,
+        flags: ir::BinOpFlags::default(),
+    })
+}
+
 #[inline]
 fn is_invalid_structure(dtype: &ir::Dtype, structs: &HashMap<String, Option<ir::Dtype>>) -> bool {
     // When `dtype` is `Dtype::Struct`, `structs` has real definition of `dtype`
@@ -62,13 +62,18 @@ pub use tests::*;
 pub use utils::*;
 pub use write_base::write;
 
-pub use c::Parse;
+pub use c::{Parse, ParseSource};
 pub use ir::Parse as IrParse;
 pub use ir::Visualizer as IrVisualizer;
 
 pub use asmgen::Asmgen;
 pub use irgen::Irgen;
 pub use opt::{
-    Deadcode, FunctionPass, Gvn, Mem2reg, Optimize, Repeat, SimplifyCfg, SimplifyCfgConstProp,
-    SimplifyCfgEmpty, SimplifyCfgMerge, SimplifyCfgReach, O0, O1,
+    pipeline, pipeline_with, BranchFold, Canonicalize, CompactNop, ConstFold, ConstProp, CopyProp,
+    DeadFunctionElimination, DeadGlobalElimination, DeadStoreElimination, Deadcode, DivByConst,
+    FunctionPass, Gvn, IndVarSimplify, Inline, InstCombine, Licm, LoopUnroll, LoopUnrollInner,
+    Mem2reg, Narrowing, OptLevel, Optimize, PassManager, PhiElimination, Pre, ProfileGuidedUnroll,
+    PruneBlockArgs, Reassociate, Repeat, Sccp, SimplifyCfg, SimplifyCfgConstProp, SimplifyCfgEmpty,
+    SimplifyCfgMerge, SimplifyCfgReach, Sroa, StoreForwarding, SwitchLowering,
+    UnreachableBlockElimination, O0, O1,
 };
@@ -0,0 +1,60 @@
+use crate::ir::*;
+use crate::opt::FunctionPass;
+use crate::*;
+
+/// Replaces the conditional jumps on constant conditions and the switches on constant values with
+/// the jumps they take.
+///
+/// A conditional jump takes its `then` jump if the condition is a nonzero integer and its `else`
+/// jump if it is zero. A switch takes the jump of the case equal to the value, or its default
+/// jump if there is none. Jumps on `undef` are left as they are. The other jumps are dropped
+/// together with their arguments, so that their targets may become unreachable. Unlike
+/// [`crate::opt::Sccp`], only the constant operands of the exits are considered, which makes the
+/// pass cheap enough to run right after the passes folding constants, e.g.,
+/// [`crate::opt::ConstProp`] or [`crate::opt::InstCombine`]. Removing the unreachable blocks is
+/// left to [`crate::opt::SimplifyCfg`] or [`crate::opt::UnreachableBlockElimination`].
+pub type BranchFold = FunctionPass<BranchFoldInner>;
+
+#[derive(Default, Clone, Copy, Debug)]
+pub struct BranchFoldInner {}
+
+impl Optimize<FunctionDefinition> for BranchFoldInner {
+    fn optimize(&mut self, code: &mut FunctionDefinition) -> bool {
+        let mut folded = false;
+        for block in code.blocks.values_mut() {
+            let arg = some_or!(taken_jump(&block.exit), continue);
+            block.exit = BlockExit::Jump { arg: arg.clone() };
+            folded = true;
+        }
+        folded
+    }
+}
+
+/// The only jump `exit` may take, if it is a conditional jump or a switch on a constant.
+fn taken_jump(exit: &BlockExit) -> Option<&JumpArg> {
+    match exit {
+        BlockExit::ConditionalJump {
+            condition,
+            arg_then,
+            arg_else,
+        } => {
+            let constant = condition.get_constant().filter(|c| !c.is_undef())?;
+            let (value, _, _) = constant.get_int()?;
+            Some(if value != 0 { arg_then } else { arg_else })
+        }
+        BlockExit::Switch {
+            value,
+            default,
+            cases,
+        } => {
+            let constant = value.get_constant().filter(|c| !c.is_undef())?;
+            Some(
+                cases
+                    .iter()
+                    .find(|(case, _)| case == constant)
+                    .map_or(default, |(_, arg)| arg),
+            )
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,77 @@
+use core::mem;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::ir::analysis::Cfg;
+use crate::ir::visit::walk_jump_arg_mut;
+use crate::ir::*;
+use crate::opt::compact_nop::CompactNopInner;
+use crate::opt::FunctionPass;
+use crate::*;
+
+/// Renumbers the blocks and instructions of each function into a canonical order.
+///
+/// The blocks reachable from the entry block are numbered from `b0` in reverse post-order, and the
+/// unreachable ones after them in the order of their old ids. The `nop` instructions are removed
+/// as [`CompactNop`](crate::CompactNop) does, so the instructions of each block are numbered
+/// densely as well. Two functions that differ only in the numbering of their blocks and in `nop`s
+/// are thus printed the same after this pass.
+pub type Canonicalize = FunctionPass<CanonicalizeInner>;
+
+#[derive(Default, Clone, Copy, Debug)]
+pub struct CanonicalizeInner {}
+
+impl Optimize<FunctionDefinition> for CanonicalizeInner {
+    fn optimize(&mut self, code: &mut FunctionDefinition) -> bool {
+        let compacted = CompactNopInner::default().optimize(code);
+
+        let cfg = Cfg::new(code);
+        let unreachable = code.blocks.keys().filter(|bid| !cfg.is_reachable(**bid));
+        let renames = cfg
+            .reverse_post_order()
+            .iter()
+            .chain(unreachable)
+            .enumerate()
+            .map(|(index, bid)| (*bid, BlockId(index)))
+            .collect::<HashMap<_, _>>();
+
+        if renames.iter().all(|(bid, bid_new)| bid == bid_new) {
+            return compacted;
+        }
+
+        let mut renamer = Renamer { renames };
+        renamer.rewrite_function(code);
+        code.bid_init = renamer.rename(code.bid_init);
+        code.blocks = mem::take(&mut code.blocks)
+            .into_iter()
+            .map(|(bid, block)| (renamer.rename(bid), block))
+            .collect::<BTreeMap<_, _>>();
+        true
+    }
+}
+
+/// Renames the blocks jumped to and the registers named after blocks.
+#[derive(Debug)]
+struct Renamer {
+    renames: HashMap<BlockId, BlockId>,
+}
+
+impl Renamer {
+    fn rename(&self, bid: BlockId) -> BlockId {
+        *self.renames.get(&bid).unwrap_or(&bid)
+    }
+}
+
+impl IrRewriter for Renamer {
+    fn rewrite_jump_arg(&mut self, arg: &mut JumpArg) {
+        arg.bid = self.rename(arg.bid);
+        walk_jump_arg_mut(self, arg);
+    }
+
+    fn rewrite_operand(&mut self, operand: &mut Operand) {
+        let (rid, _) = some_or!(operand.get_register_mut(), return);
+        match rid {
+            RegisterId::Arg { bid, .. } | RegisterId::Temp { bid, .. } => *bid = self.rename(*bid),
+            RegisterId::Local { .. } => {}
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use std::collections::HashMap;
+
+use crate::ir::*;
+use crate::opt::FunctionPass;
+use crate::*;
+
+/// Removes `nop` instructions and renumbers the remaining instructions of each block.
+///
+/// Passes may replace the instructions they delete with `nop` so that the ids of the other
+/// registers stay valid while they work, and leave the renumbering to this pass. The uses of
+/// a `nop`, whose value is the unit, are replaced with the unit constant.
+pub type CompactNop = FunctionPass<CompactNopInner>;
+
+#[derive(Default, Clone, Copy, Debug)]
+pub struct CompactNopInner {}
+
+impl Optimize<FunctionDefinition> for CompactNopInner {
+    fn optimize(&mut self, code: &mut FunctionDefinition) -> bool {
+        let mut renames = HashMap::new();
+
+        for (bid, block) in &mut code.blocks {
+            if !block
+                .instructions
+                .iter()
+                .any(|instr| matches!(**instr, Instruction::Nop))
+            {
+                continue;
+            }
+
+            let mut iid_new = 0;
+            for (iid, instr) in block.instructions.iter().enumerate() {
+                let rid = RegisterId::temp(*bid, iid);
+                if matches!(**instr, Instruction::Nop) {
+                    let _unused = renames.insert(rid, None);
+                } else {
+                    let _unused = renames.insert(rid, Some(RegisterId::temp(*bid, iid_new)));
+                    iid_new += 1;
+                }
+            }
+            block
+                .instructions
+                .retain(|instr| !matches!(**instr, Instruction::Nop));
+        }
+
+        if renames.is_empty() {
+            return false;
+        }
+
+        Renamer { renames }.rewrite_function(code);
+        true
+    }
+}
+
+/// Renames the uses of registers, where `None` stands for the register of a removed `nop`.
+#[derive(Debug)]
+struct Renamer {
+    renames: HashMap<RegisterId, Option<RegisterId>>,
+}
+
+impl IrRewriter for Renamer {
+    fn rewrite_operand(&mut self, operand: &mut Operand) {
+        let (rid, _) = some_or!(operand.get_register_mut(), return);
+        match self.renames.get(rid) {
+            Some(Some(rid_new)) => *rid = *rid_new,
+            Some(None) => *operand = Operand::constant(Constant::unit()),
+            None => {}
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use crate::ir::analysis::{Cfg, DefUse};
+use crate::ir::*;
+use crate::opt::FunctionPass;
+use crate::*;
+
+/// Folds the `BinOp`, `UnaryOp` and `TypeCast` instructions whose operands are constants,
+/// repeating until no more instruction is folded.
+///
+/// The value of a folded instruction is computed by [`const_eval`], which follows the semantics
+/// of the interpreter, and replaces its uses. The instruction itself is replaced with `nop`; see
+/// [`crate::opt::CompactNop`]. An instruction whose evaluation fails, e.g., a division by zero, is
+/// left as is, so that it still fails at run time.
+pub type ConstFold = FunctionPass<Repeat<ConstFoldInner>>;
+
+#[derive(Default, Clone, Copy, Debug)]
+pub struct ConstFoldInner {}
+
+impl Optimize<FunctionDefinition> for ConstFoldInner {
+    fn optimize(&mut self, code: &mut FunctionDefinition) -> bool {
+        let mut def_use = DefUse::new(code);
+        let mut changed = false;
+
+        // In reverse post-order, the operands folded in a block are mostly known before the block
+        // is visited, so that chains of folds take few repetitions.
+        let cfg = Cfg::new(code);
+        let unreachable = code.blocks.keys().filter(|bid| !cfg.is_reachable(**bid));
+        let order = cfg
+            .reverse_post_order()
+            .iter()
+            .chain(unreachable)
+            .copied()
+            .collect::<Vec<_>>();
+        for bid in order {
+            for iid in 0..code.blocks[&bid].instructions.len() {
+                let instr = &mut code
+                    .blocks
+                    .get_mut(&bid)
+                    .expect("block must exist")
+                    .instructions[iid];
+                let folded = some_or!(
+                    fold(instr, |operand| operand.get_constant().cloned()),
+                    continue
+                );
+                **instr = Instruction::Nop;
+                def_use.replace_all_uses(
+                    code,
+                    RegisterId::temp(bid, iid),
+                    &Operand::constant(folded),
+                );
+                changed = true;
+            }
+        }
+
+        changed
+    }
+}
+
+/// The value of `instr` if it is a `BinOp`, `UnaryOp` or `TypeCast` instruction whose operands
+/// have constant values, given by `constant_of`.
+pub(crate) fn fold<F>(instr: &Instruction, mut constant_of: F) -> Option<Constant>
+where
+    F: FnMut(&Operand) -> Option<Constant>,
+{
+    let folded = match instr {
+        Instruction::BinOp { op, lhs, rhs, .. } => {
+            const_eval::binary(op, &constant_of(lhs)?, &constant_of(rhs)?)
+        }
+        Instruction::UnaryOp { op, operand, .. } => const_eval::unary(op, &constant_of(operand)?),
+        Instruction::TypeCast {
+            value,
+            target_dtype,
+        } => const_eval::typecast(&constant_of(value)?, target_dtype),
+        _ => return None,
+    }
+    .ok()?;
+
+    // The uses of the instruction expect its dtype.
+    (folded.dtype() == instr.dtype().unqualified()).then_some(folded)
+}
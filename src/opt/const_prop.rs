@@ -0,0 +1,258 @@
+use std::collections::{BTreeSet, HashMap};
+
+use crate::ir::analysis::Cfg;
+use crate::ir::*;
+use crate::opt::const_fold::fold;
+use crate::opt::FunctionPass;
+use crate::*;
+
+/// Propagates the constant values of registers through instructions, blocks and jump arguments,
+/// replacing the uses of the registers with the constants.
+///
+/// This is the sparse conditional constant propagation of Wegman and Zadeck. A register is
+/// constant if it is a `BinOp`, `UnaryOp` or `TypeCast` instruction on constants, a `Select` whose
+/// chosen value is constant, or a phinode receiving the same constant from every jump that may be
+/// taken to its block. A conditional jump or a switch on a constant takes only the jump it
+/// chooses, so that the arguments of the other jumps do not spoil the phinodes of their targets.
+///
+/// Only operands are rewritten: the instructions whose uses are all replaced are left to
+/// [`crate::opt::Deadcode`], and the exits on constants to [`crate::opt::Sccp`] or
+/// [`crate::opt::SimplifyCfg`].
+pub type ConstProp = FunctionPass<ConstPropInner>;
+
+#[derive(Default, Clone, Copy, Debug)]
+pub struct ConstPropInner {}
+
+/// What is known of the value of a register.
+#[derive(Debug, Clone, PartialEq)]
+enum Lattice {
+    /// The register is not yet known to be defined with any value, e.g., because its block is not
+    /// yet known to be executed.
+    Unknown,
+    Constant(Constant),
+    /// The register may have different values.
+    Varying,
+}
+
+impl Lattice {
+    fn meet(self, other: Self) -> Self {
+        match (self, other) {
+            (Self::Unknown, other) | (other, Self::Unknown) => other,
+            (Self::Constant(lhs), Self::Constant(rhs)) if lhs == rhs => Self::Constant(lhs),
+            _ => Self::Varying,
+        }
+    }
+}
+
+impl Optimize<FunctionDefinition> for ConstPropInner {
+    fn optimize(&mut self, code: &mut FunctionDefinition) -> bool {
+        ConstantValues::new(code).replace_constants(code)
+    }
+}
+
+/// The values of the registers of a function and the jumps that may be taken, as found by sparse
+/// conditional constant propagation.
+#[derive(Debug)]
+pub(crate) struct ConstantValues {
+    values: HashMap<RegisterId, Lattice>,
+    /// The blocks known to be executed.
+    executed: BTreeSet<BlockId>,
+}
+
+impl ConstantValues {
+    pub(crate) fn new(code: &FunctionDefinition) -> Self {
+        let cfg = Cfg::new(code);
+        let mut analysis = Self {
+            values: HashMap::new(),
+            executed: BTreeSet::from([code.bid_init]),
+        };
+        for aid in 0..code.blocks[&code.bid_init].phinodes.len() {
+            let _unused = analysis
+                .values
+                .insert(RegisterId::arg(code.bid_init, aid), Lattice::Varying);
+        }
+
+        // The values only go down the lattice, so that they reach a fixpoint.
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for bid in cfg.reverse_post_order() {
+                if analysis.executed.contains(bid) {
+                    changed |= analysis.visit(code, &cfg, *bid);
+                }
+            }
+        }
+
+        analysis
+    }
+
+    /// Whether `bid` may be executed.
+    pub(crate) fn is_executed(&self, bid: BlockId) -> bool {
+        self.executed.contains(&bid)
+    }
+
+    /// Replaces the uses of the registers with constant values, returning whether any is replaced.
+    pub(crate) fn replace_constants(&self, code: &mut FunctionDefinition) -> bool {
+        let mut replacer = Replacer {
+            constants: self
+                .values
+                .iter()
+                .filter_map(|(rid, value)| match value {
+                    Lattice::Constant(constant) => Some((*rid, constant.clone())),
+                    _ => None,
+                })
+                .collect(),
+            replaced: false,
+        };
+        replacer.rewrite_function(code);
+        replacer.replaced
+    }
+
+    /// Updates the values of the phinodes and instructions of `bid`, and the blocks known to be
+    /// executed from it, returning whether anything changed.
+    fn visit(&mut self, code: &FunctionDefinition, cfg: &Cfg, bid: BlockId) -> bool {
+        let block = &code.blocks[&bid];
+        let mut changed = false;
+
+        if bid != code.bid_init {
+            for aid in 0..block.phinodes.len() {
+                let value = cfg
+                    .predecessors(bid)
+                    .iter()
+                    .filter(|pred| self.executed.contains(pred))
+                    .flat_map(|pred| self.taken_jumps(&code.blocks[pred].exit))
+                    .filter(|arg| arg.bid == bid)
+                    .fold(Lattice::Unknown, |value, arg| {
+                        value.meet(self.value_of(&arg.args[aid]))
+                    });
+                changed |= self.update(RegisterId::arg(bid, aid), value);
+            }
+        }
+
+        for (iid, instr) in block.instructions.iter().enumerate() {
+            let value = self.evaluate(instr);
+            changed |= self.update(RegisterId::temp(bid, iid), value);
+        }
+
+        for arg in self.taken_jumps(&block.exit) {
+            changed |= self.executed.insert(arg.bid);
+        }
+
+        changed
+    }
+
+    fn update(&mut self, rid: RegisterId, value: Lattice) -> bool {
+        let old = self.values.insert(rid, value.clone());
+        old.as_ref() != Some(&value)
+    }
+
+    fn value_of(&self, operand: &Operand) -> Lattice {
+        match operand {
+            Operand::Constant(constant) => Lattice::Constant(constant.clone()),
+            Operand::Register {
+                rid: RegisterId::Local { .. },
+                ..
+            } => Lattice::Varying,
+            Operand::Register { rid, .. } => {
+                self.values.get(rid).cloned().unwrap_or(Lattice::Unknown)
+            }
+        }
+    }
+
+    fn evaluate(&self, instr: &Instruction) -> Lattice {
+        match instr {
+            Instruction::BinOp { .. }
+            | Instruction::UnaryOp { .. }
+            | Instruction::TypeCast { .. } => {
+                let values = instr
+                    .operands()
+                    .into_iter()
+                    .map(|operand| self.value_of(operand))
+                    .collect::<Vec<_>>();
+                if values.contains(&Lattice::Varying) {
+                    return Lattice::Varying;
+                }
+                if values.contains(&Lattice::Unknown) {
+                    return Lattice::Unknown;
+                }
+                fold(instr, |operand| match self.value_of(operand) {
+                    Lattice::Constant(constant) => Some(constant),
+                    _ => None,
+                })
+                .map_or(Lattice::Varying, Lattice::Constant)
+            }
+            Instruction::Select {
+                condition,
+                then_value,
+                else_value,
+                ..
+            } => match self.truth_of(condition) {
+                Some(Some(true)) => self.value_of(then_value),
+                Some(Some(false)) => self.value_of(else_value),
+                Some(None) => self.value_of(then_value).meet(self.value_of(else_value)),
+                None => Lattice::Unknown,
+            },
+            _ => Lattice::Varying,
+        }
+    }
+
+    /// Whether `condition` is nonzero: `None` if its value is unknown, and `Some(None)` if it may
+    /// be either.
+    fn truth_of(&self, condition: &Operand) -> Option<Option<bool>> {
+        match self.value_of(condition) {
+            Lattice::Unknown => None,
+            Lattice::Constant(constant) => Some(
+                constant
+                    .get_int()
+                    .filter(|_| !constant.is_undef())
+                    .map(|(value, _, _)| value != 0),
+            ),
+            Lattice::Varying => Some(None),
+        }
+    }
+
+    /// The jumps of `exit` that may be taken if its block is executed.
+    pub(crate) fn taken_jumps<'a>(&self, exit: &'a BlockExit) -> Vec<&'a JumpArg> {
+        match exit {
+            BlockExit::ConditionalJump {
+                condition,
+                arg_then,
+                arg_else,
+            } => match self.truth_of(condition) {
+                Some(Some(true)) => vec![arg_then],
+                Some(Some(false)) => vec![arg_else],
+                Some(None) => vec![arg_then, arg_else],
+                None => Vec::new(),
+            },
+            BlockExit::Switch {
+                value,
+                default,
+                cases,
+            } => match self.value_of(value) {
+                Lattice::Unknown => Vec::new(),
+                Lattice::Constant(constant) if !constant.is_undef() => vec![cases
+                    .iter()
+                    .find(|(case, _)| *case == constant)
+                    .map_or(default, |(_, arg)| arg)],
+                _ => exit.jump_args(),
+            },
+            _ => exit.jump_args(),
+        }
+    }
+}
+
+/// Replaces the uses of the registers with constant values.
+#[derive(Debug)]
+struct Replacer {
+    constants: HashMap<RegisterId, Constant>,
+    replaced: bool,
+}
+
+impl IrRewriter for Replacer {
+    fn rewrite_operand(&mut self, operand: &mut Operand) {
+        let (rid, _) = some_or!(operand.get_register(), return);
+        let constant = some_or!(self.constants.get(rid), return);
+        *operand = Operand::constant(constant.clone());
+        self.replaced = true;
+    }
+}
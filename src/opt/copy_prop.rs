@@ -0,0 +1,57 @@
+use crate::ir::*;
+use crate::opt::compact_nop::CompactNopInner;
+use crate::opt::opt_utils::remove_phinodes;
+use crate::opt::prune_block_args::{trivial_phinodes, Copies};
+use crate::opt::FunctionPass;
+use crate::*;
+
+/// Replaces the uses of copies with their sources, and removes the copies.
+///
+/// A copy is a typecast to the dtype its value already has, or a trivial phinode as removed by
+/// [`crate::opt::PruneBlockArgs`], i.e., one that receives the same operand from every jump taken
+/// from a reachable block, ignoring the jumps passing the phinode itself. Every path to the
+/// phinode's block enters it first through a jump passing the source, so the source dominates the
+/// block and is available wherever the phinode is used.
+///
+/// The removed typecasts are compacted away as [`crate::opt::CompactNop`] does, and the trivial
+/// phinodes are removed together with the jump arguments passed to them.
+pub type CopyProp = FunctionPass<Repeat<CopyPropInner>>;
+
+#[derive(Default, Clone, Copy, Debug)]
+pub struct CopyPropInner {}
+
+impl Optimize<FunctionDefinition> for CopyPropInner {
+    fn optimize(&mut self, code: &mut FunctionDefinition) -> bool {
+        let mut copies = Copies::default();
+        let phinodes = trivial_phinodes(code, &mut copies);
+
+        let mut typecasts = false;
+        for (bid, block) in &mut code.blocks {
+            for (iid, instr) in block.instructions.iter_mut().enumerate() {
+                let Instruction::TypeCast {
+                    value,
+                    target_dtype,
+                } = &**instr
+                else {
+                    continue;
+                };
+                if value.dtype() != *target_dtype {
+                    continue;
+                }
+                let source = copies.resolve(value);
+                let _unused = copies.replaces.insert(RegisterId::temp(*bid, iid), source);
+                **instr = Instruction::Nop;
+                typecasts = true;
+            }
+        }
+
+        if phinodes.is_empty() && !typecasts {
+            return false;
+        }
+
+        copies.rewrite_function(code);
+        remove_phinodes(code, &phinodes);
+        let _unused = CompactNopInner::default().optimize(code);
+        true
+    }
+}
@@ -0,0 +1,33 @@
+use std::collections::HashSet;
+
+use crate::ir::analysis::GlobalRefs;
+use crate::ir::*;
+use crate::opt::opt_utils::remove_decls;
+use crate::*;
+
+/// Removes the functions that are unreachable from the entry of the program.
+///
+/// A function is reachable if it is a root, i.e., `main` or, in a unit without `main`, a function
+/// with external linkage, or if a reachable function calls it or otherwise refers to it, e.g.,
+/// takes its address. See [`GlobalRefs`]. The declarations of the other functions are removed,
+/// whether they are defined or not.
+#[derive(Default, Clone, Copy, Debug)]
+pub struct DeadFunctionElimination {}
+
+impl Optimize<TranslationUnit> for DeadFunctionElimination {
+    fn optimize(&mut self, code: &mut TranslationUnit) -> bool {
+        let reachable = GlobalRefs::new(code).reachable(&GlobalRefs::roots(code));
+        let dead = code
+            .decls
+            .iter()
+            .filter(|(name, decl)| decl.get_function().is_some() && !reachable.contains(*name))
+            .map(|(name, _)| name.clone())
+            .collect::<HashSet<_>>();
+        if dead.is_empty() {
+            return false;
+        }
+
+        remove_decls(code, &dead);
+        true
+    }
+}
@@ -0,0 +1,73 @@
+use std::collections::HashSet;
+
+use crate::ir::analysis::{GlobalRefs, GlobalUse};
+use crate::ir::*;
+use crate::opt::opt_utils::remove_decls;
+use crate::*;
+
+/// Removes the global variables whose values are never observed, repeating until no more
+/// variables are removed.
+///
+/// A variable is dead if it is not a root, i.e., it is declared in a unit with `main` or has
+/// internal linkage (see [`GlobalRefs::roots`]), and no function refers to it except as the
+/// address of non-volatile stores. Its declaration and the stores to it are removed, the stores
+/// being replaced with `nop`; see [`crate::opt::CompactNop`]. Variables whose values are stored
+/// only to removed variables are removed in the next repetition.
+///
+/// The functions referring to a variable are counted whether they are reachable or not, so
+/// running [`crate::opt::DeadFunctionElimination`] first removes more variables.
+pub type DeadGlobalElimination = Repeat<DeadGlobalEliminationInner>;
+
+#[derive(Default, Clone, Copy, Debug)]
+pub struct DeadGlobalEliminationInner {}
+
+impl Optimize<TranslationUnit> for DeadGlobalEliminationInner {
+    fn optimize(&mut self, code: &mut TranslationUnit) -> bool {
+        let refs = GlobalRefs::new(code);
+        let roots = GlobalRefs::roots(code);
+        let dead = code
+            .decls
+            .iter()
+            .filter(|(name, decl)| {
+                decl.get_variable().is_some()
+                    && !roots.contains(*name)
+                    && !refs.is_used(name, |kind| kind != GlobalUse::Store)
+            })
+            .map(|(name, _)| name.clone())
+            .collect::<HashSet<_>>();
+        if dead.is_empty() {
+            return false;
+        }
+
+        for definition in code
+            .decls
+            .values_mut()
+            .filter_map(|decl| decl.get_function_mut()?.1.as_mut())
+        {
+            for instr in definition
+                .blocks
+                .values_mut()
+                .flat_map(|block| &mut block.instructions)
+            {
+                let Instruction::Store {
+                    ptr,
+                    volatile: false,
+                    ..
+                } = &**instr
+                else {
+                    continue;
+                };
+                if ptr
+                    .get_constant()
+                    .and_then(Constant::get_global_variable_name)
+                    .is_some_and(|name| dead.contains(&name))
+                {
+                    **instr = Instruction::Nop;
+                }
+            }
+        }
+
+        remove_decls(code, &dead);
+        true
+    }
+}
@@ -0,0 +1,95 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::ir::analysis::{AliasAnalysis, MemoryBase};
+use crate::ir::*;
+use crate::*;
+
+/// Removes the stores whose values are never loaded.
+///
+/// A store is dead if it is followed in its block by another store to the same location of the
+/// same dtype, with no instruction in between that may read the location: a load that may alias it
+/// or, if the location is exposed, a call. A store to a local allocation that does not escape and
+/// is never loaded from is dead as well, since nothing else may read it. Volatile stores are
+/// kept.
+///
+/// The dead stores are replaced with `nop`; see [`crate::opt::CompactNop`]. Unlike the other
+/// passes, this one works on translation units, since the alias analysis needs the layouts of
+/// their structs.
+#[derive(Default, Clone, Copy, Debug)]
+pub struct DeadStoreElimination {}
+
+impl Optimize<TranslationUnit> for DeadStoreElimination {
+    fn optimize(&mut self, code: &mut TranslationUnit) -> bool {
+        let structs = &code.structs;
+        code.decls
+            .values_mut()
+            .filter_map(|decl| decl.get_function_mut()?.1.as_mut())
+            .map(|definition| eliminate_dead_stores(definition, structs))
+            .fold(false, |l, r| l | r)
+    }
+}
+
+fn eliminate_dead_stores(
+    code: &mut FunctionDefinition,
+    structs: &HashMap<String, Option<Dtype>>,
+) -> bool {
+    let alias = AliasAnalysis::new(code, structs);
+
+    // The local allocations that may be loaded from.
+    let loaded = code
+        .blocks
+        .values()
+        .flat_map(|block| &block.instructions)
+        .filter_map(|instr| match &**instr {
+            Instruction::Load { ptr, .. } => match alias.location(ptr).base {
+                MemoryBase::Local(aid) => Some(aid),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect::<HashSet<_>>();
+
+    let mut dead = Vec::new();
+    for (bid, block) in &code.blocks {
+        // The addresses stored to later in the block, and not read since.
+        let mut overwritten = Vec::<&Operand>::new();
+        for (iid, instr) in block.instructions.iter().enumerate().rev() {
+            match &**instr {
+                Instruction::Store {
+                    ptr,
+                    volatile: false,
+                    ..
+                } => {
+                    let location = alias.location(ptr);
+                    let is_unread = matches!(
+                        location.base,
+                        MemoryBase::Local(aid) if !alias.is_escaped(aid) && !loaded.contains(&aid)
+                    );
+                    let is_overwritten = location.base != MemoryBase::Unknown
+                        && location.offset.is_some()
+                        && overwritten.iter().any(|later| {
+                            alias.location(later) == location && later.dtype() == ptr.dtype()
+                        });
+                    if is_unread || is_overwritten {
+                        dead.push((*bid, iid));
+                    } else {
+                        overwritten.push(ptr);
+                    }
+                }
+                Instruction::Load { ptr, .. } => {
+                    overwritten.retain(|later| !alias.may_alias(later, ptr));
+                }
+                Instruction::Call { .. } => {
+                    overwritten.retain(|later| !alias.is_exposed(&alias.location(later)));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    for (bid, iid) in &dead {
+        let block = code.blocks.get_mut(bid).expect("block must exist");
+        *block.instructions[*iid] = Instruction::Nop;
+    }
+    !dead.is_empty()
+}
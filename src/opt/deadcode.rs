@@ -1,14 +1,91 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::ir::analysis::DefUse;
 use crate::ir::*;
+use crate::opt::compact_nop::CompactNopInner;
+use crate::opt::opt_utils::{remove_indices, remove_phinodes, Renamer};
 use crate::opt::FunctionPass;
 use crate::*;
 
+/// Removes the code whose results are unused and which has no side effects, repeating until no
+/// more code is removed.
+///
+/// The removed code is:
+///
+/// - the `BinOp`, `UnaryOp`, `TypeCast`, `GetElementPtr` and `Select` instructions and the
+///   non-volatile loads whose results are unused,
+/// - the phinodes that are unused, together with the jump arguments passed to them, except for
+///   those of the entry block, which are the parameters, and
+/// - the allocations that are unused.
+///
+/// The `nop` instructions are removed as well, as [`crate::opt::CompactNop`] does, and the
+/// remaining phinodes and allocations are renumbered. Code used only by other unused code is
+/// removed in the next repetition.
 pub type Deadcode = FunctionPass<Repeat<DeadcodeInner>>;
 
 #[derive(Default, Clone, Copy, Debug)]
 pub struct DeadcodeInner {}
 
 impl Optimize<FunctionDefinition> for DeadcodeInner {
-    fn optimize(&mut self, _code: &mut FunctionDefinition) -> bool {
-        todo!("Homework: Deadcode Elimination")
+    fn optimize(&mut self, code: &mut FunctionDefinition) -> bool {
+        let def_use = DefUse::new(code);
+
+        let mut removed_instructions = false;
+        for (bid, block) in &mut code.blocks {
+            for (iid, instr) in block.instructions.iter_mut().enumerate() {
+                if is_pure(instr) && !def_use.is_used(RegisterId::temp(*bid, iid)) {
+                    **instr = Instruction::Nop;
+                    removed_instructions = true;
+                }
+            }
+        }
+
+        // The unused phinodes of each block.
+        let mut phinodes = HashMap::new();
+        for (bid, block) in &code.blocks {
+            if *bid == code.bid_init {
+                continue;
+            }
+            let unused = (0..block.phinodes.len())
+                .filter(|aid| !def_use.is_used(RegisterId::arg(*bid, *aid)))
+                .collect::<HashSet<_>>();
+            if !unused.is_empty() {
+                let _unused = phinodes.insert(*bid, unused);
+            }
+        }
+        if !phinodes.is_empty() {
+            remove_phinodes(code, &phinodes);
+        }
+
+        let allocations = (0..code.allocations.len())
+            .filter(|aid| !def_use.is_used(RegisterId::local(*aid)))
+            .collect::<HashSet<_>>();
+        let mut renames = HashMap::new();
+        for (aid_new, aid) in (0..code.allocations.len())
+            .filter(|aid| !allocations.contains(aid))
+            .enumerate()
+        {
+            let _unused = renames.insert(RegisterId::local(aid), RegisterId::local(aid_new));
+        }
+        remove_indices(&mut code.allocations, &allocations);
+        if !allocations.is_empty() {
+            Renamer { renames }.rewrite_function(code);
+        }
+
+        let compacted = CompactNopInner::default().optimize(code);
+        removed_instructions || !phinodes.is_empty() || !allocations.is_empty() || compacted
+    }
+}
+
+/// Whether `instr` has no side effects, so that it may be removed if its result is unused.
+fn is_pure(instr: &Instruction) -> bool {
+    match instr {
+        Instruction::BinOp { .. }
+        | Instruction::UnaryOp { .. }
+        | Instruction::TypeCast { .. }
+        | Instruction::GetElementPtr { .. }
+        | Instruction::Select { .. } => true,
+        Instruction::Load { volatile, .. } => !volatile,
+        Instruction::Nop | Instruction::Store { .. } | Instruction::Call { .. } => false,
     }
 }
@@ -0,0 +1,257 @@
+use lang_c::ast;
+
+use crate::ir::*;
+use crate::opt::opt_utils::insert_instructions;
+use crate::opt::FunctionPass;
+use crate::*;
+
+/// Replaces integer divisions and remainders by constants with shifts and multiplications, which
+/// are much cheaper than `div` and `rem` and need no M extension on RISC-V.
+///
+/// A division by a power of two `2^k` becomes a right shift by `k`, where a negative signed
+/// dividend is first biased by `2^k - 1` so that the quotient rounds toward zero. A division of an
+/// integer of at most 32 bits by any other constant multiplies the dividend extended to 64 bits by
+/// a magic number approximating `2^p / d` from above and shifts the product right by `p`, as
+/// described in Granlund and Montgomery, "Division by Invariant Integers using Multiplication".
+/// Signed quotients are then incremented for negative dividends. The 32-bit unsigned divisors
+/// whose magic numbers would make the product overflow use a magic number one bit shorter, with a
+/// correction step adding back the missing multiple of the dividend.
+///
+/// A remainder is computed from the quotient as `x - (x / d) * d`, except that an unsigned
+/// remainder by a power of two masks the lower bits. Only positive divisors other than one are
+/// replaced, and the 64-bit divisions by constants other than powers of two are left as is, since
+/// the products would need 128 bits.
+pub type DivByConst = FunctionPass<DivByConstInner>;
+
+#[derive(Default, Clone, Copy, Debug)]
+pub struct DivByConstInner {}
+
+impl Optimize<FunctionDefinition> for DivByConstInner {
+    fn optimize(&mut self, code: &mut FunctionDefinition) -> bool {
+        let mut changed = false;
+
+        let bids = code.blocks.keys().copied().collect::<Vec<_>>();
+        for bid in bids {
+            // Expanding an instruction renumbers the instructions after it, so the block is
+            // expanded from its end.
+            for iid in (0..code.blocks[&bid].instructions.len()).rev() {
+                let mut emitter = Emitter {
+                    bid,
+                    iid,
+                    instructions: Vec::new(),
+                };
+                if !emitter.expand(&code.blocks[&bid].instructions[iid]) {
+                    continue;
+                }
+                let last = emitter
+                    .instructions
+                    .pop()
+                    .expect("expansion must emit instructions");
+                let count = emitter.instructions.len();
+                insert_instructions(code, bid, iid, emitter.instructions);
+                let block = code.blocks.get_mut(&bid).expect("block must exist");
+                *block.instructions[iid + count] = last.into_inner();
+                changed = true;
+            }
+        }
+
+        changed
+    }
+}
+
+/// How a division by a constant is computed with a multiplication.
+#[derive(Debug, Clone, Copy)]
+enum Magic {
+    /// `(x * multiplier) >> shift`.
+    Multiply { multiplier: u128, shift: usize },
+    /// `(t + ((x - t) >> 1)) >> (shift - 1)`, where `t` is `(x * multiplier) >> width`, i.e., the
+    /// upper half of the product of `x` and `multiplier + 2^width` shifted right by `shift`.
+    AddShift { multiplier: u128, shift: usize },
+}
+
+/// The magic number dividing unsigned `width`-bit integers by `divisor` with 64-bit products.
+fn unsigned_magic(divisor: u128, width: usize) -> Magic {
+    let log = ceil_log2(divisor);
+    // The quotient is exact for every dividend if the multiplier exceeds `2^shift / divisor` by
+    // at most `2^(shift - width) / divisor`, and the product fits in 64 bits if the multiplier has
+    // at most `64 - width` bits.
+    for shift in width..=width + log {
+        let multiplier = (1u128 << shift).div_ceil(divisor);
+        let error = multiplier * divisor - (1 << shift);
+        if error <= 1 << (shift - width) && multiplier < 1 << (64 - width) {
+            return Magic::Multiply { multiplier, shift };
+        }
+    }
+    Magic::AddShift {
+        multiplier: ((1 << width) * ((1 << log) - divisor)) / divisor + 1,
+        shift: log,
+    }
+}
+
+/// The magic number dividing signed `width`-bit integers by `divisor` with 64-bit products. The
+/// shifted product is the quotient for nonnegative dividends and one below it for negative ones.
+fn signed_magic(divisor: u128, width: usize) -> Magic {
+    let shift = width + ceil_log2(divisor) - 1;
+    Magic::Multiply {
+        multiplier: (1 << shift) / divisor + 1,
+        shift,
+    }
+}
+
+fn ceil_log2(value: u128) -> usize {
+    (u128::BITS - (value - 1).leading_zeros()) as usize
+}
+
+/// Emits the instructions replacing an instruction.
+#[derive(Debug)]
+struct Emitter {
+    bid: BlockId,
+    /// The index of the first emitted instruction.
+    iid: usize,
+    instructions: Vec<Named<Instruction>>,
+}
+
+impl Emitter {
+    /// Emits the instructions computing the division or remainder `instr`, returning whether it is
+    /// one by a constant that is replaced. The last of them replaces `instr`.
+    fn expand(&mut self, instr: &Named<Instruction>) -> bool {
+        let Instruction::BinOp {
+            op: op @ (ast::BinaryOperator::Divide | ast::BinaryOperator::Modulo),
+            lhs,
+            rhs,
+            dtype,
+            ..
+        } = &**instr
+        else {
+            return false;
+        };
+        let Some((divisor, width, is_signed)) = rhs.get_constant().and_then(Constant::get_int)
+        else {
+            return false;
+        };
+        let max = if is_signed {
+            1 << (width - 1)
+        } else {
+            1 << width
+        };
+        if divisor < 2 || divisor >= max {
+            return false;
+        }
+        let is_divide = *op == ast::BinaryOperator::Divide;
+        let int = |value: u128| Operand::constant(Constant::int(value, dtype.clone()));
+
+        if divisor.is_power_of_two() {
+            let log = divisor.trailing_zeros().into();
+            if !is_signed {
+                let _unused = if is_divide {
+                    self.binary(ast::BinaryOperator::ShiftRight, lhs.clone(), int(log))
+                } else {
+                    self.binary(
+                        ast::BinaryOperator::BitwiseAnd,
+                        lhs.clone(),
+                        int(divisor - 1),
+                    )
+                };
+                return true;
+            }
+            let sign = self.binary(
+                ast::BinaryOperator::ShiftRight,
+                lhs.clone(),
+                int(width as u128 - 1),
+            );
+            let bias = self.binary(ast::BinaryOperator::BitwiseAnd, sign, int(divisor - 1));
+            let biased = self.binary(ast::BinaryOperator::Plus, lhs.clone(), bias);
+            let quotient = self.binary(ast::BinaryOperator::ShiftRight, biased, int(log));
+            if !is_divide {
+                let product = self.binary(ast::BinaryOperator::ShiftLeft, quotient, int(log));
+                let _unused = self.binary(ast::BinaryOperator::Minus, lhs.clone(), product);
+            }
+            return true;
+        }
+
+        if width > 32 {
+            return false;
+        }
+        let wide = Dtype::int(64).set_signed(is_signed);
+        let wide_int = |value: u128| Operand::constant(Constant::int(value, wide.clone()));
+        let extended = self.typecast(lhs.clone(), wide.clone());
+        let magic = if is_signed {
+            signed_magic(divisor, width)
+        } else {
+            unsigned_magic(divisor, width)
+        };
+        let quotient = match magic {
+            Magic::Multiply { multiplier, shift } => {
+                let product = self.binary(
+                    ast::BinaryOperator::Multiply,
+                    extended.clone(),
+                    wide_int(multiplier),
+                );
+                self.binary(
+                    ast::BinaryOperator::ShiftRight,
+                    product,
+                    wide_int(shift as u128),
+                )
+            }
+            Magic::AddShift { multiplier, shift } => {
+                let product = self.binary(
+                    ast::BinaryOperator::Multiply,
+                    extended.clone(),
+                    wide_int(multiplier),
+                );
+                let high = self.binary(
+                    ast::BinaryOperator::ShiftRight,
+                    product,
+                    wide_int(width as u128),
+                );
+                let difference =
+                    self.binary(ast::BinaryOperator::Minus, extended.clone(), high.clone());
+                let half = self.binary(ast::BinaryOperator::ShiftRight, difference, wide_int(1));
+                let sum = self.binary(ast::BinaryOperator::Plus, high, half);
+                self.binary(
+                    ast::BinaryOperator::ShiftRight,
+                    sum,
+                    wide_int(shift as u128 - 1),
+                )
+            }
+        };
+        let quotient = if is_signed {
+            // Subtracting the sign, i.e., `-1` for negative dividends.
+            let sign = self.binary(ast::BinaryOperator::ShiftRight, extended, wide_int(63));
+            self.binary(ast::BinaryOperator::Minus, quotient, sign)
+        } else {
+            quotient
+        };
+        let quotient = self.typecast(quotient, dtype.clone());
+        if !is_divide {
+            let product = self.binary(ast::BinaryOperator::Multiply, quotient, int(divisor));
+            let _unused = self.binary(ast::BinaryOperator::Minus, lhs.clone(), product);
+        }
+        true
+    }
+
+    fn binary(&mut self, op: ast::BinaryOperator, lhs: Operand, rhs: Operand) -> Operand {
+        let dtype = lhs.dtype();
+        self.push(Instruction::BinOp {
+            op,
+            lhs,
+            rhs,
+            dtype,
+            flags: BinOpFlags::default(),
+        })
+    }
+
+    fn typecast(&mut self, value: Operand, target_dtype: Dtype) -> Operand {
+        self.push(Instruction::TypeCast {
+            value,
+            target_dtype,
+        })
+    }
+
+    fn push(&mut self, instr: Instruction) -> Operand {
+        let dtype = instr.dtype();
+        let rid = RegisterId::temp(self.bid, self.iid + self.instructions.len());
+        self.instructions.push(Named::new(None, instr));
+        Operand::register(rid, dtype)
+    }
+}
@@ -1,13 +1,307 @@
+use std::collections::HashMap;
+
+use itertools::Itertools;
+use lang_c::ast;
+
+use crate::ir::analysis::{Cfg, DefUse, Dominators};
+use crate::ir::*;
 use crate::opt::FunctionPass;
 use crate::*;
 
+/// Replaces the uses of redundant computations with the equal values computed before.
+///
+/// Registers are numbered so that registers with the same number have the same value. The
+/// `BinOp`, `UnaryOp`, `TypeCast` and `GetElementPtr` instructions get the same number if they
+/// compute the same operation on operands of the same numbers, where the operands of commutative
+/// operations are ordered first. A phinode receiving the same value from every jump has that
+/// value, and is replaced with the constant or the leader of the number. Every other register gets
+/// a number of its own.
+///
+/// The blocks are visited in reverse post-order, each knowing the leaders of the numbers, i.e.,
+/// the registers first computing them, at the end of its immediate dominator. The uses of an
+/// instruction whose number has a leader are replaced with the leader. Otherwise, if the
+/// instruction is used and every predecessor of its block has a leader for the number at its end,
+/// a phinode receiving the leaders is inserted and replaces the instruction. The redundant
+/// instructions themselves are left to [`crate::opt::Deadcode`].
 pub type Gvn = FunctionPass<GvnInner>;
 
 #[derive(Default, Clone, Copy, Debug)]
 pub struct GvnInner {}
 
-impl Optimize<ir::FunctionDefinition> for GvnInner {
-    fn optimize(&mut self, _code: &mut ir::FunctionDefinition) -> bool {
-        todo!("Homework: Global Variable Numbering")
+/// An operand, up to the values of registers.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Value {
+    Number(usize),
+    Constant(Constant),
+}
+
+/// The operation of an instruction on the values of its operands.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Expression {
+    BinOp {
+        op: ast::BinaryOperator,
+        lhs: Value,
+        rhs: Value,
+        dtype: Dtype,
+        flags: BinOpFlags,
+    },
+    UnaryOp {
+        op: ast::UnaryOperator,
+        operand: Value,
+        dtype: Dtype,
+    },
+    TypeCast {
+        value: Value,
+        target_dtype: Dtype,
+    },
+    GetElementPtr {
+        ptr: Value,
+        offset: Value,
+        dtype: Dtype,
+    },
+}
+
+#[derive(Debug, Default)]
+struct Numbering {
+    registers: HashMap<RegisterId, Value>,
+    expressions: HashMap<Expression, usize>,
+    next: usize,
+}
+
+impl Numbering {
+    fn fresh(&mut self) -> usize {
+        let number = self.next;
+        self.next += 1;
+        number
+    }
+
+    fn value_of(&mut self, operand: &Operand) -> Value {
+        match operand {
+            Operand::Constant(constant) => Value::Constant(constant.clone()),
+            Operand::Register { rid, .. } => {
+                // A register used before it is visited, e.g., through a back edge, or an
+                // allocation gets a number of its own.
+                if let Some(value) = self.registers.get(rid) {
+                    return value.clone();
+                }
+                let value = Value::Number(self.fresh());
+                let _unused = self.registers.insert(*rid, value.clone());
+                value
+            }
+        }
+    }
+
+    /// The number of `instr` if it computes an expression.
+    fn number_of(&mut self, instr: &Instruction) -> Option<usize> {
+        let expression = match instr {
+            Instruction::BinOp {
+                op,
+                lhs,
+                rhs,
+                dtype,
+                flags,
+            } => {
+                let mut lhs = self.value_of(lhs);
+                let mut rhs = self.value_of(rhs);
+                if is_commutative(op) && precedes(&rhs, &lhs) {
+                    std::mem::swap(&mut lhs, &mut rhs);
+                }
+                Expression::BinOp {
+                    op: op.clone(),
+                    lhs,
+                    rhs,
+                    dtype: dtype.clone(),
+                    flags: *flags,
+                }
+            }
+            Instruction::UnaryOp { op, operand, dtype } => Expression::UnaryOp {
+                op: op.clone(),
+                operand: self.value_of(operand),
+                dtype: dtype.clone(),
+            },
+            Instruction::TypeCast {
+                value,
+                target_dtype,
+            } => Expression::TypeCast {
+                value: self.value_of(value),
+                target_dtype: target_dtype.clone(),
+            },
+            Instruction::GetElementPtr { ptr, offset, dtype } => Expression::GetElementPtr {
+                ptr: self.value_of(ptr),
+                offset: self.value_of(offset),
+                dtype: dtype.clone(),
+            },
+            _ => return None,
+        };
+
+        if let Some(number) = self.expressions.get(&expression) {
+            return Some(*number);
+        }
+        let number = self.fresh();
+        let _unused = self.expressions.insert(expression, number);
+        Some(number)
+    }
+}
+
+pub(crate) fn is_commutative(op: &ast::BinaryOperator) -> bool {
+    matches!(
+        op,
+        ast::BinaryOperator::Plus
+            | ast::BinaryOperator::Multiply
+            | ast::BinaryOperator::BitwiseAnd
+            | ast::BinaryOperator::BitwiseOr
+            | ast::BinaryOperator::BitwiseXor
+            | ast::BinaryOperator::Equals
+            | ast::BinaryOperator::NotEquals
+    )
+}
+
+/// Whether `lhs` comes first among the operands of a commutative operation: registers come before
+/// constants, in the order of their numbers.
+fn precedes(lhs: &Value, rhs: &Value) -> bool {
+    match (lhs, rhs) {
+        (Value::Number(lhs), Value::Number(rhs)) => lhs < rhs,
+        (Value::Number(_), Value::Constant(_)) => true,
+        (Value::Constant(_), _) => false,
+    }
+}
+
+impl Optimize<FunctionDefinition> for GvnInner {
+    fn optimize(&mut self, code: &mut FunctionDefinition) -> bool {
+        let cfg = Cfg::new(code);
+        let dominators = Dominators::new(&cfg);
+        let def_use = DefUse::new(code);
+
+        let mut numbering = Numbering::default();
+        let mut replaces = HashMap::new();
+        let mut inserted = false;
+
+        // The leaders of the numbers at the end of each visited block.
+        let mut leaders_out = HashMap::<BlockId, HashMap<usize, Operand>>::new();
+        for bid in cfg.reverse_post_order() {
+            let mut leaders = dominators
+                .idom(*bid)
+                .map(|idom| leaders_out[&idom].clone())
+                .unwrap_or_default();
+            let preds = cfg
+                .predecessors(*bid)
+                .iter()
+                .filter(|pred| cfg.is_reachable(**pred))
+                .copied()
+                .collect::<Vec<_>>();
+
+            let block = &code.blocks[bid];
+            for (aid, phinode) in block.phinodes.iter().enumerate() {
+                let rid = RegisterId::arg(*bid, aid);
+                let incomings = preds
+                    .iter()
+                    .flat_map(|pred| code.blocks[pred].exit.jump_args())
+                    .filter(|arg| arg.bid == *bid)
+                    .map(|arg| numbering.value_of(&arg.args[aid]))
+                    .collect::<Vec<_>>();
+
+                // A phinode receiving the same value from every jump has that value.
+                let value = if *bid != code.bid_init && incomings.iter().all_equal() {
+                    incomings.into_iter().next()
+                } else {
+                    None
+                };
+                match value {
+                    Some(Value::Constant(constant)) => {
+                        let _unused = replaces.insert(rid, Operand::constant(constant.clone()));
+                        let _unused = numbering.registers.insert(rid, Value::Constant(constant));
+                    }
+                    Some(Value::Number(number)) if leaders.contains_key(&number) => {
+                        let _unused = replaces.insert(rid, leaders[&number].clone());
+                        let _unused = numbering.registers.insert(rid, Value::Number(number));
+                    }
+                    _ => {
+                        let number = numbering.fresh();
+                        let _unused = numbering.registers.insert(rid, Value::Number(number));
+                        let _unused =
+                            leaders.insert(number, Operand::register(rid, (**phinode).clone()));
+                    }
+                }
+            }
+
+            for iid in 0..code.blocks[bid].instructions.len() {
+                let rid = RegisterId::temp(*bid, iid);
+                let instr = &code.blocks[bid].instructions[iid];
+                let dtype = instr.dtype();
+                let number = numbering
+                    .number_of(instr)
+                    .unwrap_or_else(|| numbering.fresh());
+                let _unused = numbering.registers.insert(rid, Value::Number(number));
+
+                if let Some(leader) = leaders.get(&number) {
+                    let _unused = replaces.insert(rid, leader.clone());
+                    continue;
+                }
+
+                let incomings = preds
+                    .iter()
+                    .map(|pred| {
+                        leaders_out
+                            .get(pred)?
+                            .get(&number)
+                            .map(|leader| (*pred, leader))
+                    })
+                    .collect::<Option<Vec<_>>>();
+                let incomings = match incomings {
+                    Some(incomings)
+                        if preds.len() > 1 && *bid != code.bid_init && def_use.is_used(rid) =>
+                    {
+                        incomings
+                            .into_iter()
+                            .map(|(pred, leader)| (pred, leader.clone()))
+                            .collect::<HashMap<_, _>>()
+                    }
+                    _ => {
+                        let _unused = leaders.insert(number, Operand::register(rid, dtype));
+                        continue;
+                    }
+                };
+
+                let block = code.blocks.get_mut(bid).expect("visited block must exist");
+                let phinode = Operand::register(RegisterId::arg(*bid, block.phinodes.len()), dtype);
+                block.phinodes.push(Named::new(None, phinode.dtype()));
+                for (pred, leader) in incomings {
+                    let exit = &mut code.blocks.get_mut(&pred).expect("pred must exist").exit;
+                    exit.walk_jump_args(|arg| {
+                        if arg.bid == *bid {
+                            arg.args.push(leader.clone());
+                        }
+                    });
+                }
+                let _unused = replaces.insert(rid, phinode.clone());
+                let _unused = leaders.insert(number, phinode);
+                inserted = true;
+            }
+
+            let _unused = leaders_out.insert(*bid, leaders);
+        }
+
+        let mut replacer = Replacer {
+            replaces,
+            replaced: false,
+        };
+        replacer.rewrite_function(code);
+        inserted || replacer.replaced
+    }
+}
+
+/// Replaces the uses of the redundant registers with their leaders.
+#[derive(Debug)]
+struct Replacer {
+    replaces: HashMap<RegisterId, Operand>,
+    replaced: bool,
+}
+
+impl IrRewriter for Replacer {
+    fn rewrite_operand(&mut self, operand: &mut Operand) {
+        let (rid, _) = some_or!(operand.get_register(), return);
+        let leader = some_or!(self.replaces.get(rid), return);
+        *operand = leader.clone();
+        self.replaced = true;
     }
 }
@@ -0,0 +1,378 @@
+use std::collections::HashMap;
+
+use lang_c::ast;
+
+use crate::ir::analysis::{Cfg, Dominators, Loop, LoopInfo};
+use crate::ir::*;
+use crate::opt::compact_nop::CompactNopInner;
+use crate::opt::const_fold::fold;
+use crate::opt::opt_utils::{insert_preheader, Renamer};
+use crate::opt::FunctionPass;
+use crate::*;
+
+/// Simplifies the computations on the induction variables of loops.
+///
+/// A basic induction variable is an integer phinode of a loop header to which every back edge
+/// passes the phinode plus or minus the same constant step. Its values are affine in the number
+/// of iterations, and so are those of its products with loop-invariant factors:
+///
+/// - A product of a basic induction variable and an invariant factor is strength-reduced into a
+///   new induction variable of the header, which starts from the initial value times the factor
+///   and steps by the step times the factor. The products are computed once in the preheader,
+///   which is inserted if missing, and each latch adds the step instead of multiplying again.
+/// - The exit condition of a header comparing an induction variable stepping by one against a
+///   constant bound with `<` or `>`, which leaves the loop once the comparison fails, becomes a
+///   `!=` comparison if the variable starts at a constant on the right side of the bound. The
+///   variable then reaches the bound exactly and never passes it.
+///
+/// Integer arithmetic wraps around, so that the new induction variables have the values of the
+/// products they replace even if those overflow. The loops are simplified one at a time until no
+/// more simplification applies.
+pub type IndVarSimplify = FunctionPass<Repeat<IndVarSimplifyInner>>;
+
+#[derive(Default, Clone, Copy, Debug)]
+pub struct IndVarSimplifyInner {}
+
+impl Optimize<FunctionDefinition> for IndVarSimplifyInner {
+    fn optimize(&mut self, code: &mut FunctionDefinition) -> bool {
+        let cfg = Cfg::new(code);
+        let dominators = Dominators::new(&cfg);
+        let loops = LoopInfo::new(&cfg, &dominators);
+
+        for lp in loops.post_order() {
+            let ivs = induction_variables(code, lp, &cfg);
+            if ivs.is_empty() {
+                continue;
+            }
+            // Reducing multiplications may insert a preheader, which changes the loops.
+            let simplified = simplify_exit(code, lp, &ivs);
+            let reduced = reduce_multiplications(code, lp, &cfg, &ivs);
+            if simplified || reduced {
+                return true;
+            }
+        }
+
+        false
+    }
+}
+
+/// A basic induction variable of a loop.
+#[derive(Debug)]
+struct InductionVariable {
+    /// The index of the phinode in the header.
+    aid: usize,
+    /// The phinode as an operand.
+    phinode: Operand,
+    /// Either `+` or `-`.
+    op: ast::BinaryOperator,
+    step: Constant,
+    /// The value on entry to the loop, if it is a constant.
+    init: Option<Constant>,
+}
+
+/// The basic induction variables of `lp`.
+fn induction_variables(code: &FunctionDefinition, lp: &Loop, cfg: &Cfg) -> Vec<InductionVariable> {
+    let header = lp.header();
+    let back_edges = lp
+        .latches()
+        .iter()
+        .flat_map(|latch| code.blocks[latch].exit.jump_args())
+        .filter(|arg| arg.bid == header)
+        .collect::<Vec<_>>();
+    let entries = cfg
+        .predecessors(header)
+        .iter()
+        .filter(|pred| !lp.contains(**pred))
+        .flat_map(|pred| code.blocks[pred].exit.jump_args())
+        .filter(|arg| arg.bid == header)
+        .collect::<Vec<_>>();
+
+    let mut ivs = Vec::new();
+    for (aid, dtype) in code.blocks[&header].phinodes.iter().enumerate() {
+        if !matches!(**dtype, Dtype::Int { .. }) {
+            continue;
+        }
+        let phinode = Operand::register(RegisterId::arg(header, aid), (**dtype).clone());
+
+        // The step each back edge passes, as an operation and a constant.
+        let steps = back_edges
+            .iter()
+            .map(|arg| {
+                let (RegisterId::Temp { bid, iid }, _) = arg.args[aid].get_register()? else {
+                    return None;
+                };
+                let Instruction::BinOp { op, lhs, rhs, .. } =
+                    &*code.blocks.get(bid)?.instructions[*iid]
+                else {
+                    return None;
+                };
+                let step = match op {
+                    ast::BinaryOperator::Plus if *rhs == phinode => lhs,
+                    ast::BinaryOperator::Plus | ast::BinaryOperator::Minus if *lhs == phinode => {
+                        rhs
+                    }
+                    _ => return None,
+                };
+                Some((op.clone(), step.get_constant()?.clone()))
+            })
+            .collect::<Option<Vec<_>>>();
+        let Some(steps) = steps else {
+            continue;
+        };
+        let Some((op, step)) = steps.first().cloned() else {
+            continue;
+        };
+        if steps
+            .iter()
+            .any(|other| *other != (op.clone(), step.clone()))
+        {
+            continue;
+        }
+
+        let inits = entries
+            .iter()
+            .map(|arg| arg.args[aid].get_constant())
+            .collect::<Option<Vec<_>>>();
+        let init = match inits.as_deref() {
+            Some([init, others @ ..]) if others.iter().all(|other| other == init) => {
+                Some((*init).clone())
+            }
+            _ => None,
+        };
+
+        ivs.push(InductionVariable {
+            aid,
+            phinode,
+            op,
+            step,
+            init,
+        });
+    }
+
+    ivs
+}
+
+/// Replaces the `<` or `>` comparison deciding whether to leave `lp` at its header with `!=`, if
+/// an induction variable reaches its bound exactly. Returns whether the comparison is replaced.
+fn simplify_exit(code: &mut FunctionDefinition, lp: &Loop, ivs: &[InductionVariable]) -> bool {
+    let header = lp.header();
+    let block = code.blocks.get_mut(&header).expect("header must exist");
+    let BlockExit::ConditionalJump {
+        condition,
+        arg_then,
+        arg_else,
+    } = &block.exit
+    else {
+        return false;
+    };
+    if !lp.contains(arg_then.bid) || lp.contains(arg_else.bid) {
+        return false;
+    }
+    let Some((RegisterId::Temp { bid, iid }, _)) = condition.get_register() else {
+        return false;
+    };
+    if *bid != header {
+        return false;
+    }
+    let iid = *iid;
+    let Instruction::BinOp { op, lhs, rhs, .. } = &mut *block.instructions[iid] else {
+        return false;
+    };
+
+    for iv in ivs {
+        let init = some_or!(&iv.init, continue);
+        if iv.step.get_int().map(|(value, _, _)| value) != Some(1) {
+            continue;
+        }
+        let is_increasing = iv.op == ast::BinaryOperator::Plus;
+        // Whether the comparison holds while the variable is on the side of the bound it starts.
+        let bound = match op {
+            ast::BinaryOperator::Less if is_increasing && *lhs == iv.phinode => &*rhs,
+            ast::BinaryOperator::Greater if is_increasing && *rhs == iv.phinode => &*lhs,
+            ast::BinaryOperator::Greater if !is_increasing && *lhs == iv.phinode => &*rhs,
+            ast::BinaryOperator::Less if !is_increasing && *rhs == iv.phinode => &*lhs,
+            _ => continue,
+        };
+        let bound = some_or!(bound.get_constant(), continue);
+
+        let starts_before = Instruction::BinOp {
+            op: if is_increasing {
+                ast::BinaryOperator::LessOrEqual
+            } else {
+                ast::BinaryOperator::GreaterOrEqual
+            },
+            lhs: Operand::constant(init.clone()),
+            rhs: Operand::constant(bound.clone()),
+            dtype: Dtype::BOOL,
+            flags: BinOpFlags::default(),
+        };
+        let starts_before = fold(&starts_before, |operand| operand.get_constant().cloned());
+        if starts_before
+            .and_then(|value| value.get_int())
+            .map(|(value, _, _)| value)
+            != Some(1)
+        {
+            continue;
+        }
+
+        *op = ast::BinaryOperator::NotEquals;
+        return true;
+    }
+
+    false
+}
+
+/// Replaces the products of the induction variables of `lp` and invariant factors with new
+/// induction variables. Returns whether any product is replaced.
+fn reduce_multiplications(
+    code: &mut FunctionDefinition,
+    lp: &Loop,
+    cfg: &Cfg,
+    ivs: &[InductionVariable],
+) -> bool {
+    let is_invariant = |operand: &Operand| match operand {
+        Operand::Constant(_) => true,
+        Operand::Register { rid, .. } => match rid {
+            RegisterId::Local { .. } => true,
+            RegisterId::Arg { bid, .. } | RegisterId::Temp { bid, .. } => !lp.contains(*bid),
+        },
+    };
+
+    // The products of each induction variable and factor.
+    let mut products = Vec::<(&InductionVariable, Operand, Vec<(BlockId, usize)>)>::new();
+    for bid in lp.blocks() {
+        for (iid, instr) in code.blocks[bid].instructions.iter().enumerate() {
+            let Instruction::BinOp {
+                op: ast::BinaryOperator::Multiply,
+                lhs,
+                rhs,
+                dtype,
+                ..
+            } = &**instr
+            else {
+                continue;
+            };
+            for iv in ivs {
+                if *dtype != iv.phinode.dtype() {
+                    continue;
+                }
+                let factor = if *lhs == iv.phinode {
+                    rhs
+                } else if *rhs == iv.phinode {
+                    lhs
+                } else {
+                    continue;
+                };
+                if !is_invariant(factor) {
+                    continue;
+                }
+                match products
+                    .iter_mut()
+                    .find(|(other, other_factor, _)| other.aid == iv.aid && other_factor == factor)
+                {
+                    Some((_, _, instrs)) => instrs.push((*bid, iid)),
+                    None => products.push((iv, factor.clone(), vec![(*bid, iid)])),
+                }
+                break;
+            }
+        }
+    }
+    if products.is_empty() {
+        return false;
+    }
+
+    let header = lp.header();
+    let has_preheader = cfg
+        .predecessors(header)
+        .iter()
+        .filter(|pred| !lp.contains(**pred))
+        .count()
+        == 1;
+    let preheader = match lp.preheader(cfg) {
+        Some(preheader) if has_preheader => preheader,
+        _ => insert_preheader(code, lp),
+    };
+
+    let mut renames = HashMap::new();
+    for (iv, factor, instrs) in products {
+        let dtype = iv.phinode.dtype();
+        let init = code.blocks[&preheader]
+            .exit
+            .jump_args()
+            .into_iter()
+            .find(|arg| arg.bid == header)
+            .expect("preheader must jump to the header")
+            .args[iv.aid]
+            .clone();
+
+        let init = emit(
+            code,
+            preheader,
+            ast::BinaryOperator::Multiply,
+            init,
+            factor.clone(),
+        );
+        let step = emit(
+            code,
+            preheader,
+            ast::BinaryOperator::Multiply,
+            Operand::constant(iv.step.clone()),
+            factor,
+        );
+
+        let block = code.blocks.get_mut(&header).expect("header must exist");
+        let rid = RegisterId::arg(header, block.phinodes.len());
+        block.phinodes.push(Named::new(None, dtype.clone()));
+        let phinode = Operand::register(rid, dtype);
+        pass_to_header(code, preheader, header, init);
+        for latch in lp.latches() {
+            let next = emit(code, *latch, iv.op.clone(), phinode.clone(), step.clone());
+            pass_to_header(code, *latch, header, next);
+        }
+
+        for (bid, iid) in instrs {
+            let _unused = renames.insert(RegisterId::temp(bid, iid), rid);
+            let block = code.blocks.get_mut(&bid).expect("loop block must exist");
+            *block.instructions[iid] = Instruction::Nop;
+        }
+    }
+
+    Renamer { renames }.rewrite_function(code);
+    let _unused = CompactNopInner::default().optimize(code);
+    true
+}
+
+/// Computes `lhs op rhs` at the end of the block `bid`, or folds it if both are constants.
+fn emit(
+    code: &mut FunctionDefinition,
+    bid: BlockId,
+    op: ast::BinaryOperator,
+    lhs: Operand,
+    rhs: Operand,
+) -> Operand {
+    let dtype = lhs.dtype();
+    let instr = Instruction::BinOp {
+        op,
+        lhs,
+        rhs,
+        dtype: dtype.clone(),
+        flags: BinOpFlags::default(),
+    };
+    if let Some(value) = fold(&instr, |operand| operand.get_constant().cloned()) {
+        return Operand::constant(value);
+    }
+    let block = code.blocks.get_mut(&bid).expect("block must exist");
+    let rid = RegisterId::temp(bid, block.instructions.len());
+    block.instructions.push(Named::new(None, instr));
+    Operand::register(rid, dtype)
+}
+
+/// Appends `arg` to the arguments the block `bid` passes to `header`.
+fn pass_to_header(code: &mut FunctionDefinition, bid: BlockId, header: BlockId, arg: Operand) {
+    let block = code.blocks.get_mut(&bid).expect("block must exist");
+    block.exit.walk_jump_args(|jump| {
+        if jump.bid == header {
+            jump.args.push(arg.clone());
+        }
+    });
+}
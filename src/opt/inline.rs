@@ -0,0 +1,337 @@
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+
+use crate::ir::analysis::{GlobalRefs, GlobalUse};
+use crate::ir::visit::walk_jump_arg_mut;
+use crate::ir::*;
+use crate::opt::opt_utils::{new_bid, Renamer};
+use crate::*;
+
+/// Replaces the calls to functions defined in the translation unit with the bodies of the
+/// functions.
+///
+/// The cost of inlining a function at a call site is the size of the function, i.e., its number
+/// of instructions and block exits, less `constant_arg_bonus` for each constant argument of the
+/// call, since the code using the arguments may then be folded. A call is inlined if its cost is
+/// at most `threshold`, and the whole program, whose size is the sum of those of its functions,
+/// stays within `growth` percent more than its size before the pass. The functions of `always` are
+/// inlined whatever their costs and the growth, and those of `never` are not inlined at all.
+/// Recursive calls, i.e., those to functions that may call back the caller, are never inlined.
+///
+/// If a `profile` of a run of the code is given, e.g., with `--profile-use` of the driver, a call
+/// whose block is entered at least `hot_hits` times costs `hot_call_bonus` less, and a call whose
+/// block is never entered is not inlined, so that the code grows where the time is spent. The
+/// blocks are found by their ids, as [`crate::opt::ProfileGuidedUnroll`] finds them, and the block
+/// of the instructions after an inlined call is counted as the block of the call. The blocks with
+/// larger ids than any block the profile counts in the function, e.g., those added by unrolling
+/// after the run, are neither hot nor cold.
+///
+/// The functions are visited bottom-up, callees first, so that the calls inlined into a function
+/// are inlined together with it. The block of a call is split at the call: it jumps to the entry
+/// block of a copy of the callee, passing the arguments to the parameters, and the returns of the
+/// copy jump to the block of the instructions after the call, passing the returned value to its
+/// phinode replacing the call. The allocations of the callee become those of the caller. The
+/// callees no longer called are left to [`crate::opt::DeadFunctionElimination`]. Since the costs
+/// depend on the other functions, this pass works on translation units.
+#[derive(Debug, Clone)]
+pub struct Inline {
+    /// The largest cost of a function inlined at a call site.
+    pub threshold: usize,
+    /// The cost saved by each constant argument of a call site.
+    pub constant_arg_bonus: usize,
+    /// The functions inlined at every call site that is not recursive.
+    pub always: BTreeSet<String>,
+    /// The functions never inlined.
+    pub never: BTreeSet<String>,
+    /// How much the whole program may grow by inlining, in percent of its size.
+    pub growth: usize,
+    /// The execution counts of a run of the code, which is not profiled if empty.
+    pub profile: Profile,
+    /// The cost saved by a call whose block is hot.
+    pub hot_call_bonus: usize,
+    /// The number of times the block of a hot call is entered at least.
+    pub hot_hits: usize,
+}
+
+impl Default for Inline {
+    fn default() -> Self {
+        Self {
+            threshold: 40,
+            constant_arg_bonus: 10,
+            always: BTreeSet::new(),
+            never: BTreeSet::new(),
+            growth: 50,
+            profile: Profile::default(),
+            hot_call_bonus: 20,
+            hot_hits: 64,
+        }
+    }
+}
+
+impl Optimize<TranslationUnit> for Inline {
+    fn optimize(&mut self, code: &mut TranslationUnit) -> bool {
+        let refs = GlobalRefs::new(code);
+        let size = code
+            .decls
+            .values()
+            .filter_map(|decl| decl.get_function()?.1.as_ref())
+            .map(size_of)
+            .sum::<usize>();
+        let mut inliner = Inliner {
+            config: self,
+            refs: &refs,
+            budget: size * self.growth / 100,
+        };
+
+        let mut order = Vec::new();
+        let mut visited = HashSet::new();
+        for name in code.decls.keys() {
+            bottom_up(name, &refs, &mut visited, &mut order);
+        }
+
+        let mut inlined = false;
+        for name in order {
+            let decl = some_or!(code.decls.get(&name), continue);
+            let mut definition = some_or!(
+                decl.get_function()
+                    .and_then(|(_, definition)| definition.clone()),
+                continue
+            );
+            if !inliner.inline_calls(&name, &mut definition, code) {
+                continue;
+            }
+            let decl = code.decls.get_mut(&name).expect("function must exist");
+            *decl.get_function_mut().expect("function must exist").1 = Some(definition);
+            inlined = true;
+        }
+        inlined
+    }
+}
+
+/// Pushes the functions `name` calls to `order` before `name`, unless they are visited.
+fn bottom_up(
+    name: &str,
+    refs: &GlobalRefs,
+    visited: &mut HashSet<String>,
+    order: &mut Vec<String>,
+) {
+    if !visited.insert(name.to_string()) {
+        return;
+    }
+    for (callee, kind) in refs.refs(name) {
+        if kind == GlobalUse::Call {
+            bottom_up(callee, refs, visited, order);
+        }
+    }
+    order.push(name.to_string());
+}
+
+/// The number of instructions and block exits of `definition`.
+fn size_of(definition: &FunctionDefinition) -> usize {
+    definition
+        .blocks
+        .values()
+        .map(|block| block.instructions.len() + 1)
+        .sum()
+}
+
+#[derive(Debug)]
+struct Inliner<'a> {
+    config: &'a Inline,
+    refs: &'a GlobalRefs,
+    /// How much the program may still grow.
+    budget: usize,
+}
+
+impl Inliner<'_> {
+    /// Inlines the calls of `code`, the definition of `name`, to the functions of `unit` to be
+    /// inlined, returning whether any is.
+    fn inline_calls(
+        &mut self,
+        name: &str,
+        code: &mut FunctionDefinition,
+        unit: &TranslationUnit,
+    ) -> bool {
+        let mut inlined = false;
+
+        // The blocks of the calls, excluding those of the inlined callees, which are visited
+        // already, and the blocks of the profile they are split from.
+        let mut worklist = code.blocks.keys().copied().collect::<VecDeque<_>>();
+        let mut origins = code
+            .blocks
+            .keys()
+            .map(|bid| (*bid, *bid))
+            .collect::<HashMap<_, _>>();
+        while let Some(bid) = worklist.pop_front() {
+            for iid in 0..code.blocks[&bid].instructions.len() {
+                let call = &code.blocks[&bid].instructions[iid];
+                let callee = some_or!(self.callee(name, call, origins[&bid], unit), continue);
+                let cont = inline_call(code, bid, iid, callee);
+                let _unused = origins.insert(cont, origins[&bid]);
+                worklist.push_front(cont);
+                inlined = true;
+                break;
+            }
+        }
+        inlined
+    }
+
+    /// The definition of the function `call` of the function `name` calls, if the call is
+    /// inlined. The call is in the block `origin` of the profile.
+    fn callee<'u>(
+        &mut self,
+        name: &str,
+        call: &Instruction,
+        origin: BlockId,
+        unit: &'u TranslationUnit,
+    ) -> Option<&'u FunctionDefinition> {
+        let Instruction::Call {
+            callee: Operand::Constant(Constant::GlobalVariable { name: callee, .. }),
+            args,
+            return_type,
+        } = call
+        else {
+            return None;
+        };
+        if self.config.never.contains(callee)
+            || self
+                .refs
+                .reachable(&BTreeSet::from([callee.clone()]))
+                .contains(name)
+        {
+            return None;
+        }
+        let (signature, definition) = unit.decls.get(callee)?.get_function()?;
+        let definition = definition.as_ref()?;
+
+        // The call must match the definition, which it may not if it calls through a cast.
+        let params = &definition.blocks.get(&definition.bid_init)?.phinodes;
+        if args.len() != params.len()
+            || args
+                .iter()
+                .zip(params)
+                .any(|(arg, param)| arg.dtype().unqualified() != (**param).clone().unqualified())
+            || return_type.clone().unqualified() != signature.ret.clone().unqualified()
+        {
+            return None;
+        }
+
+        let size = size_of(definition);
+        if self.config.always.contains(callee) {
+            return Some(definition);
+        }
+        let profile = &self.config.profile;
+        let hits = profile.block(name, origin).hits;
+        if hits == 0 && !profile.is_empty() && profile.blocks(name).any(|(bid, _)| bid >= origin) {
+            return None;
+        }
+        let hot_bonus = if hits >= self.config.hot_hits {
+            self.config.hot_call_bonus
+        } else {
+            0
+        };
+        let bonus = args
+            .iter()
+            .filter(|arg| arg.get_constant().is_some())
+            .count()
+            * self.config.constant_arg_bonus
+            + hot_bonus;
+        if size.saturating_sub(bonus) > self.config.threshold || size > self.budget {
+            return None;
+        }
+        self.budget -= size;
+        Some(definition)
+    }
+}
+
+/// Inlines `callee` at the call that is the instruction `iid` of the block `bid` of `code`,
+/// returning the block of the instructions after the call.
+fn inline_call(
+    code: &mut FunctionDefinition,
+    bid: BlockId,
+    iid: usize,
+    callee: &FunctionDefinition,
+) -> BlockId {
+    let cont = new_bid(code);
+    let bids = callee
+        .blocks
+        .keys()
+        .enumerate()
+        .map(|(index, callee_bid)| (*callee_bid, BlockId(cont.0 + 1 + index)))
+        .collect::<HashMap<_, _>>();
+
+    let block = code.blocks.get_mut(&bid).expect("block must exist");
+    let rest = block.instructions.split_off(iid + 1);
+    let call = block.instructions.pop().expect("call must exist");
+    let Instruction::Call {
+        args, return_type, ..
+    } = call.clone().into_inner()
+    else {
+        unreachable!("only calls are inlined")
+    };
+    let exit = std::mem::replace(
+        &mut block.exit,
+        BlockExit::Jump {
+            arg: JumpArg::new(bids[&callee.bid_init], args),
+        },
+    );
+    let moved = rest.len();
+    let _unused = code.blocks.insert(
+        cont,
+        Block {
+            phinodes: vec![Named::new(call.name().cloned(), return_type)],
+            instructions: rest,
+            exit,
+        },
+    );
+
+    // The registers of the call and the instructions after it are now those of `cont`.
+    let mut renames = (0..moved)
+        .map(|index| {
+            (
+                RegisterId::temp(bid, iid + 1 + index),
+                RegisterId::temp(cont, index),
+            )
+        })
+        .collect::<HashMap<_, _>>();
+    let _unused = renames.insert(RegisterId::temp(bid, iid), RegisterId::arg(cont, 0));
+    Renamer { renames }.rewrite_function(code);
+
+    let mut copy = callee.clone();
+    Relocator {
+        bids: &bids,
+        aid_offset: code.allocations.len(),
+    }
+    .rewrite_function(&mut copy);
+    code.allocations.extend(copy.allocations);
+    for (callee_bid, mut block) in copy.blocks {
+        if let BlockExit::Return { value } = block.exit {
+            block.exit = BlockExit::Jump {
+                arg: JumpArg::new(cont, vec![value]),
+            };
+        }
+        let _unused = code.blocks.insert(bids[&callee_bid], block);
+    }
+    cont
+}
+
+/// Moves the blocks and allocations of a callee to those they have in the caller.
+#[derive(Debug)]
+struct Relocator<'a> {
+    bids: &'a HashMap<BlockId, BlockId>,
+    aid_offset: usize,
+}
+
+impl IrRewriter for Relocator<'_> {
+    fn rewrite_jump_arg(&mut self, arg: &mut JumpArg) {
+        arg.bid = self.bids[&arg.bid];
+        walk_jump_arg_mut(self, arg);
+    }
+
+    fn rewrite_operand(&mut self, operand: &mut Operand) {
+        let (rid, _) = some_or!(operand.get_register_mut(), return);
+        match rid {
+            RegisterId::Local { aid } => *aid += self.aid_offset,
+            RegisterId::Arg { bid, .. } | RegisterId::Temp { bid, .. } => *bid = self.bids[bid],
+        }
+    }
+}
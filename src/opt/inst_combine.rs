@@ -0,0 +1,218 @@
+use lang_c::ast;
+
+use crate::ir::analysis::{DefUse, Use};
+use crate::ir::*;
+use crate::opt::FunctionPass;
+use crate::*;
+
+/// Simplifies instructions by algebraic identities, repeating until no more instruction is
+/// simplified.
+///
+/// On integers, `x + 0`, `x - 0`, `x * 1` and their commuted forms are replaced with `x`, `x * 0`,
+/// `x - x` and `x ^ x` with `0`, and `x * 2^k` becomes `x << k`. Negating or complementing a value
+/// twice gives back the value. A typecast of a typecast is replaced with a single typecast if the
+/// intermediate integer dtype is at least as wide as both the others, so that no bits are lost in
+/// between. Comparisons with a constant on the left are mirrored to have it on the right.
+///
+/// The uses of an instruction replaced with a value are rewritten to the value, and the
+/// instruction itself is replaced with `nop`; see [`crate::opt::CompactNop`]. Floating-point
+/// arithmetic is left as is, since its identities do not hold for every value, e.g., `-0.0 + 0.0`
+/// is `0.0`.
+pub type InstCombine = FunctionPass<Repeat<InstCombineInner>>;
+
+#[derive(Default, Clone, Copy, Debug)]
+pub struct InstCombineInner {}
+
+impl Optimize<FunctionDefinition> for InstCombineInner {
+    fn optimize(&mut self, code: &mut FunctionDefinition) -> bool {
+        let mut def_use = DefUse::new(code);
+        let mut changed = false;
+
+        let bids = code.blocks.keys().copied().collect::<Vec<_>>();
+        for bid in bids {
+            for iid in 0..code.blocks[&bid].instructions.len() {
+                let simplified = some_or!(
+                    simplify(code, &code.blocks[&bid].instructions[iid]),
+                    continue
+                );
+                let at = Use::Instruction { bid, iid };
+                match simplified {
+                    Simplified::Operand(operand) => {
+                        def_use.rewrite_at(code, at, &mut SetInstruction(Some(Instruction::Nop)));
+                        def_use.replace_all_uses(code, RegisterId::temp(bid, iid), &operand);
+                    }
+                    Simplified::Instruction(instr) => {
+                        def_use.rewrite_at(code, at, &mut SetInstruction(Some(*instr)));
+                    }
+                }
+                changed = true;
+            }
+        }
+
+        changed
+    }
+}
+
+/// What an instruction simplifies to.
+#[derive(Debug)]
+enum Simplified {
+    /// A value the instruction always computes.
+    Operand(Operand),
+    /// A simpler instruction computing the same value.
+    Instruction(Box<Instruction>),
+}
+
+fn simplify(code: &FunctionDefinition, instr: &Instruction) -> Option<Simplified> {
+    match instr {
+        Instruction::BinOp {
+            op,
+            lhs,
+            rhs,
+            dtype,
+            ..
+        } => simplify_binary(op, lhs, rhs, dtype),
+        Instruction::UnaryOp { op, operand, dtype } => {
+            let Instruction::UnaryOp {
+                op: inner_op,
+                operand: inner,
+                ..
+            } = definition(code, operand)?
+            else {
+                return None;
+            };
+            let is_involution = matches!(
+                op,
+                ast::UnaryOperator::Minus | ast::UnaryOperator::Complement
+            );
+            (is_involution && op == inner_op && inner.dtype() == *dtype)
+                .then(|| Simplified::Operand(inner.clone()))
+        }
+        Instruction::TypeCast {
+            value,
+            target_dtype,
+        } => {
+            let Instruction::TypeCast {
+                value: inner,
+                target_dtype: middle_dtype,
+            } = definition(code, value)?
+            else {
+                return None;
+            };
+            let width = inner.dtype().get_int_width()?;
+            let middle_width = middle_dtype.get_int_width()?;
+            let target_width = target_dtype.get_int_width()?;
+            if middle_width < width || middle_width < target_width {
+                return None;
+            }
+            if inner.dtype() == *target_dtype {
+                return Some(Simplified::Operand(inner.clone()));
+            }
+            Some(Simplified::Instruction(Box::new(Instruction::TypeCast {
+                value: inner.clone(),
+                target_dtype: target_dtype.clone(),
+            })))
+        }
+        _ => None,
+    }
+}
+
+fn simplify_binary(
+    op: &ast::BinaryOperator,
+    lhs: &Operand,
+    rhs: &Operand,
+    dtype: &Dtype,
+) -> Option<Simplified> {
+    if let Some(mirrored) = mirror(op) {
+        if lhs.get_constant().is_none() || rhs.get_constant().is_some() {
+            return None;
+        }
+        return Some(Simplified::Instruction(Box::new(Instruction::BinOp {
+            op: mirrored,
+            lhs: rhs.clone(),
+            rhs: lhs.clone(),
+            dtype: dtype.clone(),
+            flags: BinOpFlags::default(),
+        })));
+    }
+
+    if !matches!(dtype, Dtype::Int { .. }) {
+        return None;
+    }
+    let zero = || Simplified::Operand(Operand::constant(Constant::int(0, dtype.clone())));
+    let is_same_register = lhs.get_register().is_some() && lhs == rhs;
+    let simplified = match op {
+        ast::BinaryOperator::Plus if is_int(rhs, 0) => Simplified::Operand(lhs.clone()),
+        ast::BinaryOperator::Plus if is_int(lhs, 0) => Simplified::Operand(rhs.clone()),
+        ast::BinaryOperator::Minus if is_int(rhs, 0) => Simplified::Operand(lhs.clone()),
+        ast::BinaryOperator::Minus | ast::BinaryOperator::BitwiseXor if is_same_register => zero(),
+        ast::BinaryOperator::Multiply if is_int(lhs, 0) || is_int(rhs, 0) => zero(),
+        ast::BinaryOperator::Multiply if is_int(rhs, 1) => Simplified::Operand(lhs.clone()),
+        ast::BinaryOperator::Multiply if is_int(lhs, 1) => Simplified::Operand(rhs.clone()),
+        ast::BinaryOperator::Multiply => {
+            let (value, factor) = if lhs.get_constant().is_some() {
+                (rhs, lhs)
+            } else {
+                (lhs, rhs)
+            };
+            let (factor, width, _) = factor.get_constant()?.get_int()?;
+            let shift = factor.trailing_zeros();
+            if !factor.is_power_of_two() || shift as usize >= width {
+                return None;
+            }
+            Simplified::Instruction(Box::new(Instruction::BinOp {
+                op: ast::BinaryOperator::ShiftLeft,
+                lhs: value.clone(),
+                rhs: Operand::constant(Constant::int(shift.into(), dtype.clone())),
+                dtype: dtype.clone(),
+                flags: BinOpFlags::default(),
+            }))
+        }
+        _ => return None,
+    };
+    Some(simplified)
+}
+
+/// The comparison that holds for swapped operands whenever `op` holds, if `op` is a comparison.
+fn mirror(op: &ast::BinaryOperator) -> Option<ast::BinaryOperator> {
+    let mirrored = match op {
+        ast::BinaryOperator::Less => ast::BinaryOperator::Greater,
+        ast::BinaryOperator::Greater => ast::BinaryOperator::Less,
+        ast::BinaryOperator::LessOrEqual => ast::BinaryOperator::GreaterOrEqual,
+        ast::BinaryOperator::GreaterOrEqual => ast::BinaryOperator::LessOrEqual,
+        ast::BinaryOperator::Equals => ast::BinaryOperator::Equals,
+        ast::BinaryOperator::NotEquals => ast::BinaryOperator::NotEquals,
+        _ => return None,
+    };
+    Some(mirrored)
+}
+
+/// Whether `operand` is the integer constant `value`.
+fn is_int(operand: &Operand, value: u128) -> bool {
+    operand
+        .get_constant()
+        .and_then(Constant::get_int)
+        .is_some_and(|(constant, _, _)| constant == value)
+}
+
+/// The instruction defining `operand`, if any.
+pub(crate) fn definition<'a>(
+    code: &'a FunctionDefinition,
+    operand: &Operand,
+) -> Option<&'a Instruction> {
+    let (RegisterId::Temp { bid, iid }, _) = operand.get_register()? else {
+        return None;
+    };
+    Some(code.blocks.get(bid)?.instructions.get(*iid)?)
+}
+
+/// Replaces an instruction with another.
+#[derive(Debug)]
+struct SetInstruction(Option<Instruction>);
+
+impl IrRewriter for SetInstruction {
+    fn rewrite_instruction(&mut self, _rid: RegisterId, instruction: &mut Named<Instruction>) {
+        if let Some(instr) = self.0.take() {
+            **instruction = instr;
+        }
+    }
+}
@@ -0,0 +1,200 @@
+use std::collections::{HashMap, HashSet};
+
+use lang_c::ast;
+
+use crate::ir::analysis::{AliasAnalysis, Cfg, Dominators, Loop, LoopInfo};
+use crate::ir::*;
+use crate::opt::compact_nop::CompactNopInner;
+use crate::opt::opt_utils::{insert_preheader, Renamer};
+use crate::*;
+
+/// Loop-invariant code motion: hoists the computations whose operands do not change in a loop out
+/// of it, into its preheader.
+///
+/// The `BinOp`, `UnaryOp`, `TypeCast`, `GetElementPtr` and `Select` instructions are hoisted if
+/// their operands are constants, allocations, or registers defined outside of the loop or hoisted
+/// themselves. Divisions, remainders and shifts are hoisted only if their right operands are
+/// constants that cannot make them fail, since they may not run at all in the loop. For the same
+/// reason, a `BinOp` loses its `nuw`, `nsw` and `exact` flags unless its block runs whenever the
+/// loop exits. A non-volatile load with such an operand is hoisted as well if its block runs
+/// whenever the loop exits, and no store or call in the loop may write to the memory it reads.
+///
+/// If the header of a loop has no preheader, i.e., a single predecessor outside of the loop that
+/// jumps only to the header, a new block is inserted to become one. It receives the arguments
+/// passed to the header from outside of the loop and passes them on. The inner loops are visited
+/// first, so that code is hoisted as far out as possible. The hoisted instructions leave `nop`s
+/// behind, which are removed as [`crate::opt::CompactNop`] does. Like
+/// [`crate::opt::DeadStoreElimination`], this pass works on translation units, since the alias
+/// analysis needs the layouts of their structs.
+#[derive(Default, Clone, Copy, Debug)]
+pub struct Licm {}
+
+impl Optimize<TranslationUnit> for Licm {
+    fn optimize(&mut self, code: &mut TranslationUnit) -> bool {
+        let structs = &code.structs;
+        code.decls
+            .values_mut()
+            .filter_map(|decl| decl.get_function_mut()?.1.as_mut())
+            .map(|definition| {
+                let mut changed = false;
+                while hoist(definition, structs) {
+                    changed = true;
+                }
+                changed
+            })
+            .fold(false, |l, r| l | r)
+    }
+}
+
+/// Hoists the invariant instructions of the first loop that has any, returning whether any
+/// instruction is hoisted.
+fn hoist(code: &mut FunctionDefinition, structs: &HashMap<String, Option<Dtype>>) -> bool {
+    let cfg = Cfg::new(code);
+    let dominators = Dominators::new(&cfg);
+    let loops = LoopInfo::new(&cfg, &dominators);
+    let alias = AliasAnalysis::new(code, structs);
+
+    for lp in loops.post_order() {
+        let invariants = invariants(code, lp, &cfg, &dominators, &alias);
+        if invariants.is_empty() {
+            continue;
+        }
+
+        let preheader = lp
+            .preheader(&cfg)
+            .unwrap_or_else(|| insert_preheader(code, lp));
+
+        let mut renames = HashMap::new();
+        let base = code.blocks[&preheader].instructions.len();
+        let mut hoisted = Vec::new();
+        for (k, (bid, iid, always_runs)) in invariants.into_iter().enumerate() {
+            let block = code.blocks.get_mut(&bid).expect("loop block must exist");
+            let mut instr = std::mem::replace(
+                &mut block.instructions[iid],
+                Named::new(None, Instruction::Nop),
+            );
+            if !always_runs {
+                if let Instruction::BinOp { flags, .. } = &mut *instr {
+                    *flags = BinOpFlags::default();
+                }
+            }
+            hoisted.push(instr);
+            let _unused = renames.insert(
+                RegisterId::temp(bid, iid),
+                RegisterId::temp(preheader, base + k),
+            );
+        }
+        code.blocks
+            .get_mut(&preheader)
+            .expect("preheader must exist")
+            .instructions
+            .extend(hoisted);
+
+        Renamer { renames }.rewrite_function(code);
+        let _unused = CompactNopInner::default().optimize(code);
+        return true;
+    }
+
+    false
+}
+
+/// The invariant instructions of `lp` that may be hoisted, in an order where each comes after the
+/// invariant instructions it uses, and whether their blocks run whenever the loop exits.
+fn invariants(
+    code: &FunctionDefinition,
+    lp: &Loop,
+    cfg: &Cfg,
+    dominators: &Dominators,
+    alias: &AliasAnalysis<'_>,
+) -> Vec<(BlockId, usize, bool)> {
+    // The instructions that may write to memory read in the loop.
+    let writes = lp
+        .blocks()
+        .iter()
+        .flat_map(|bid| &code.blocks[bid].instructions)
+        .filter(|instr| {
+            matches!(
+                &***instr,
+                Instruction::Store { .. } | Instruction::Call { .. }
+            )
+        })
+        .collect::<Vec<_>>();
+    let exiting = lp
+        .blocks()
+        .iter()
+        .filter(|bid| cfg.successors(**bid).iter().any(|succ| !lp.contains(*succ)))
+        .copied()
+        .collect::<Vec<_>>();
+
+    let mut invariants = Vec::new();
+    let mut hoisted = HashSet::new();
+    for bid in cfg.reverse_post_order() {
+        if !lp.contains(*bid) {
+            continue;
+        }
+        let always_runs =
+            !exiting.is_empty() && exiting.iter().all(|exit| dominators.dominates(*bid, *exit));
+        for (iid, instr) in code.blocks[bid].instructions.iter().enumerate() {
+            let is_invariant = |operand: &Operand| match operand {
+                Operand::Constant(_) => true,
+                Operand::Register { rid, .. } => match rid {
+                    RegisterId::Local { .. } => true,
+                    RegisterId::Arg { bid, .. } | RegisterId::Temp { bid, .. } => {
+                        !lp.contains(*bid) || hoisted.contains(rid)
+                    }
+                },
+            };
+            let may_hoist = match &**instr {
+                Instruction::BinOp { op, lhs, rhs, .. } => {
+                    !may_fail(op, rhs) && is_invariant(lhs) && is_invariant(rhs)
+                }
+                Instruction::UnaryOp { operand, .. } => is_invariant(operand),
+                Instruction::TypeCast { value, .. } => is_invariant(value),
+                Instruction::GetElementPtr { ptr, offset, .. } => {
+                    is_invariant(ptr) && is_invariant(offset)
+                }
+                Instruction::Select {
+                    condition,
+                    then_value,
+                    else_value,
+                    ..
+                } => {
+                    is_invariant(condition) && is_invariant(then_value) && is_invariant(else_value)
+                }
+                Instruction::Load {
+                    ptr,
+                    volatile: false,
+                } => {
+                    is_invariant(ptr)
+                        && always_runs
+                        && writes.iter().all(|write| !alias.may_conflict(instr, write))
+                }
+                _ => false,
+            };
+            if may_hoist {
+                invariants.push((*bid, iid, always_runs));
+                let _unused = hoisted.insert(RegisterId::temp(*bid, iid));
+            }
+        }
+    }
+
+    invariants
+}
+
+/// Whether the binary operation `op` with the right operand `rhs` may fail at run time, as integer
+/// divisions by zero and shifts by the width or more do.
+pub(crate) fn may_fail(op: &ast::BinaryOperator, rhs: &Operand) -> bool {
+    if rhs.dtype().get_float_width().is_some() {
+        return false;
+    }
+    let rhs = rhs.get_constant().and_then(Constant::get_int);
+    match op {
+        ast::BinaryOperator::Divide | ast::BinaryOperator::Modulo => {
+            !matches!(rhs, Some((value, _, _)) if value != 0)
+        }
+        ast::BinaryOperator::ShiftLeft | ast::BinaryOperator::ShiftRight => {
+            !matches!(rhs, Some((value, width, _)) if value < width as u128)
+        }
+        _ => false,
+    }
+}
@@ -0,0 +1,399 @@
+use std::collections::HashMap;
+
+use crate::ir::analysis::{Cfg, DefUse, Dominators, Loop, LoopInfo};
+use crate::ir::visit::walk_jump_arg_mut;
+use crate::ir::*;
+use crate::opt::const_fold::fold;
+use crate::opt::opt_utils::{new_bid, Renamer};
+use crate::opt::FunctionPass;
+use crate::*;
+
+/// Unrolls the innermost loops that have a single latch and leave to a single block.
+///
+/// A loop whose header is the only block leaving it, deciding whether to leave with a conditional
+/// jump, is fully unrolled if the number of iterations follows from the constant arguments passed
+/// to the header on entry, evaluated as [`crate::opt::ConstFold`] does. The body is copied once per
+/// iteration, followed by a copy of the header jumping out of the loop, and each copy of the
+/// header jumps directly to the block its condition chooses. Any other loop is partially unrolled:
+/// its body is copied `factor` times, each copy jumping back to the header of the next one, and
+/// each keeping its jumps out of the loop, so that the number of iterations need not be known.
+///
+/// No loop grows beyond `budget` phinodes and instructions; a loop whose full unrolling would
+/// exceed it is partially unrolled instead, with as many copies as fit. Before unrolling, the
+/// jumps out of the loop are redirected to a new block passing their arguments on, and the
+/// registers of the loop used outside of it are passed to new phinodes of that block, so that the
+/// copies of the registers reach their uses. Partial unrolling keeps the loops, so this pass
+/// changes the code every time it runs.
+pub type LoopUnroll = FunctionPass<LoopUnrollInner>;
+
+#[derive(Clone, Copy, Debug)]
+pub struct LoopUnrollInner {
+    /// The number of copies of the body of a partially unrolled loop.
+    pub factor: usize,
+    /// The largest number of phinodes and instructions of an unrolled loop.
+    pub budget: usize,
+}
+
+impl Default for LoopUnrollInner {
+    fn default() -> Self {
+        Self {
+            factor: 4,
+            budget: 64,
+        }
+    }
+}
+
+impl Optimize<FunctionDefinition> for LoopUnrollInner {
+    fn optimize(&mut self, code: &mut FunctionDefinition) -> bool {
+        let cfg = Cfg::new(code);
+        let dominators = Dominators::new(&cfg);
+        let headers = LoopInfo::new(&cfg, &dominators)
+            .post_order()
+            .into_iter()
+            .filter(|lp| lp.children().is_empty())
+            .map(Loop::header)
+            .collect::<Vec<_>>();
+
+        let mut unrolled = false;
+        for header in headers {
+            // Unrolling an innermost loop leaves the other innermost loops as they are, but changes
+            // the control-flow graph around them.
+            let cfg = Cfg::new(code);
+            let dominators = Dominators::new(&cfg);
+            let loops = LoopInfo::new(&cfg, &dominators);
+            let lp = some_or!(loops.get(header), continue);
+            unrolled |= self.unroll(code, lp, &cfg);
+        }
+        unrolled
+    }
+}
+
+impl LoopUnrollInner {
+    /// Unrolls `lp`, returning whether it is unrolled.
+    pub(crate) fn unroll(&self, code: &mut FunctionDefinition, lp: &Loop, cfg: &Cfg) -> bool {
+        let [latch] = lp.latches() else {
+            return false;
+        };
+        let exits = lp.exits(cfg);
+        let exit = match exits.iter().collect::<Vec<_>>().as_slice() {
+            [exit] => **exit,
+            _ => return false,
+        };
+
+        let size_of = |bid: &BlockId| {
+            let block = &code.blocks[bid];
+            block.phinodes.len() + block.instructions.len()
+        };
+        let size = lp.blocks().iter().map(size_of).sum::<usize>().max(1);
+        let header_size = size_of(&lp.header());
+        let max_trip_count = self.budget.saturating_sub(header_size) / size;
+
+        let copies = match trip_count(code, lp, cfg, *latch, max_trip_count) {
+            Some(trip_count) => Copies::Full(trip_count),
+            None => {
+                let factor = self.factor.min(self.budget / size);
+                if factor < 2 {
+                    return false;
+                }
+                Copies::Partial(factor)
+            }
+        };
+
+        dedicate_exit(code, lp, exit);
+        copy_body(code, lp, copies);
+        true
+    }
+}
+
+/// How many times a loop is copied.
+#[derive(Debug, Clone, Copy)]
+enum Copies {
+    /// Fully unrolled with the number of iterations.
+    Full(usize),
+    /// Partially unrolled with the number of copies of the body.
+    Partial(usize),
+}
+
+/// The number of iterations of `lp`, if it is at most `max` and known from the constant arguments
+/// passed to the header on entry.
+fn trip_count(
+    code: &FunctionDefinition,
+    lp: &Loop,
+    cfg: &Cfg,
+    latch: BlockId,
+    max: usize,
+) -> Option<usize> {
+    let header = lp.header();
+    let is_exiting = |bid: &BlockId| cfg.successors(*bid).iter().any(|succ| !lp.contains(*succ));
+    if lp
+        .blocks()
+        .iter()
+        .any(|bid| *bid != header && is_exiting(bid))
+    {
+        return None;
+    }
+    let BlockExit::ConditionalJump {
+        condition,
+        arg_then,
+        arg_else,
+        ..
+    } = &code.blocks[&header].exit
+    else {
+        return None;
+    };
+    if lp.contains(arg_then.bid) == lp.contains(arg_else.bid) {
+        return None;
+    }
+
+    let [entry] = cfg
+        .predecessors(header)
+        .iter()
+        .filter(|pred| !lp.contains(**pred))
+        .flat_map(|pred| code.blocks[pred].exit.jump_args())
+        .filter(|arg| arg.bid == header)
+        .collect::<Vec<_>>()[..]
+    else {
+        return None;
+    };
+    let [back_edge] = code.blocks[&latch]
+        .exit
+        .jump_args()
+        .into_iter()
+        .filter(|arg| arg.bid == header)
+        .collect::<Vec<_>>()[..]
+    else {
+        return None;
+    };
+
+    let blocks = cfg
+        .reverse_post_order()
+        .iter()
+        .filter(|bid| lp.contains(**bid))
+        .collect::<Vec<_>>();
+    let constant_of = |values: &HashMap<RegisterId, Constant>, operand: &Operand| match operand {
+        Operand::Constant(constant) => Some(constant.clone()),
+        Operand::Register { rid, .. } => values.get(rid).cloned(),
+    };
+
+    // The values of the phinodes of the header in each iteration, if constant.
+    let mut phinodes = entry
+        .args
+        .iter()
+        .map(|arg| arg.get_constant().cloned())
+        .collect::<Vec<_>>();
+    for trip_count in 0..=max {
+        let mut values = phinodes
+            .iter()
+            .enumerate()
+            .filter_map(|(aid, value)| Some((RegisterId::arg(header, aid), value.clone()?)))
+            .collect::<HashMap<_, _>>();
+        for bid in &blocks {
+            for (iid, instr) in code.blocks[bid].instructions.iter().enumerate() {
+                let value = fold(instr, |operand| constant_of(&values, operand));
+                if let Some(value) = value {
+                    let _unused = values.insert(RegisterId::temp(**bid, iid), value);
+                }
+            }
+        }
+
+        let (condition, _, _) = constant_of(&values, condition)?.get_int()?;
+        let taken = if condition != 0 { arg_then } else { arg_else };
+        if !lp.contains(taken.bid) {
+            return Some(trip_count);
+        }
+        phinodes = back_edge
+            .args
+            .iter()
+            .map(|arg| constant_of(&values, arg))
+            .collect();
+    }
+
+    None
+}
+
+/// Redirects the jumps of `lp` to `exit` to a new block.
+///
+/// The new block has the phinodes of `exit` and passes them to it. The registers of the loop used
+/// outside of it are passed to new phinodes of the block as well, which replace them in their uses
+/// outside of the loop. The block dominates those uses, since leaving the loop always passes it.
+fn dedicate_exit(code: &mut FunctionDefinition, lp: &Loop, exit: BlockId) {
+    let dedicated = new_bid(code);
+    for bid in lp.blocks() {
+        let block = code.blocks.get_mut(bid).expect("loop block must exist");
+        block.exit.walk_jump_args(|arg| {
+            if arg.bid == exit {
+                arg.bid = dedicated;
+            }
+        });
+    }
+    let phinodes = code.blocks[&exit].phinodes.clone();
+    let args = phinodes
+        .iter()
+        .enumerate()
+        .map(|(aid, dtype)| Operand::register(RegisterId::arg(dedicated, aid), (**dtype).clone()))
+        .collect();
+    let block = Block {
+        phinodes,
+        instructions: Vec::new(),
+        exit: BlockExit::Jump {
+            arg: JumpArg::new(exit, args),
+        },
+    };
+    let _unused = code.blocks.insert(dedicated, block);
+
+    let def_use = DefUse::new(code);
+    let mut defs = Vec::new();
+    for bid in lp.blocks() {
+        let block = &code.blocks[bid];
+        defs.extend(block.phinodes.iter().enumerate().map(|(aid, phinode)| {
+            (
+                RegisterId::arg(*bid, aid),
+                Named::new(phinode.name().cloned(), (**phinode).clone()),
+            )
+        }));
+        defs.extend(block.instructions.iter().enumerate().map(|(iid, instr)| {
+            (
+                RegisterId::temp(*bid, iid),
+                Named::new(instr.name().cloned(), instr.dtype()),
+            )
+        }));
+    }
+
+    let mut renames = HashMap::new();
+    for (rid, phinode) in defs {
+        if def_use.uses(rid).iter().all(|u| lp.contains(u.bid())) {
+            continue;
+        }
+        let operand = Operand::register(rid, (*phinode).clone());
+        let block = code.blocks.get_mut(&dedicated).expect("block must exist");
+        let _unused = renames.insert(rid, RegisterId::arg(dedicated, block.phinodes.len()));
+        block.phinodes.push(phinode);
+        for bid in lp.blocks() {
+            let block = code.blocks.get_mut(bid).expect("loop block must exist");
+            block.exit.walk_jump_args(|arg| {
+                if arg.bid == dedicated {
+                    arg.args.push(operand.clone());
+                }
+            });
+        }
+    }
+
+    let mut renamer = Renamer { renames };
+    for (bid, block) in &mut code.blocks {
+        if !lp.contains(*bid) {
+            renamer.rewrite_block(*bid, block);
+        }
+    }
+}
+
+/// Replaces the blocks of `lp` with their copies.
+fn copy_body(code: &mut FunctionDefinition, lp: &Loop, copies: Copies) {
+    let header = lp.header();
+    let body = lp
+        .blocks()
+        .iter()
+        .map(|bid| (*bid, code.blocks[bid].clone()))
+        .collect::<Vec<_>>();
+    // The blocks of each copy, where a fully unrolled loop ends with a copy of the header alone.
+    let copies_of = |index: usize| match copies {
+        Copies::Full(trip_count) if index == trip_count => vec![header],
+        _ => lp.blocks().iter().copied().collect(),
+    };
+    let count = match copies {
+        Copies::Full(trip_count) => trip_count + 1,
+        Copies::Partial(factor) => factor,
+    };
+
+    // The ids of the blocks of each copy, where the first copy keeps the ids of the loop.
+    let mut next_bid = new_bid(code);
+    let mut bids = Vec::<HashMap<_, _>>::new();
+    for index in 0..count {
+        bids.push(
+            copies_of(index)
+                .into_iter()
+                .map(|bid| {
+                    if index == 0 {
+                        return (bid, bid);
+                    }
+                    let copy = next_bid;
+                    next_bid = BlockId(next_bid.0 + 1);
+                    (bid, copy)
+                })
+                .collect(),
+        );
+    }
+
+    for bid in lp.blocks() {
+        let _unused = code.blocks.remove(bid);
+    }
+    for (index, copy) in bids.iter().enumerate() {
+        let next_header = match copies {
+            Copies::Full(_) => bids.get(index + 1).map_or(header, |next| next[&header]),
+            Copies::Partial(factor) => bids[(index + 1) % factor][&header],
+        };
+        let mut copier = Copier {
+            bids: copy,
+            header,
+            next_header,
+        };
+        for (bid, block) in &body {
+            let copied = some_or!(copy.get(bid), continue);
+            let mut block = block.clone();
+            if let (Copies::Full(trip_count), true) = (copies, *bid == header) {
+                // The copies of the header jump directly to the block their conditions choose.
+                let BlockExit::ConditionalJump {
+                    arg_then, arg_else, ..
+                } = block.exit
+                else {
+                    panic!("the header of a fully unrolled loop must branch")
+                };
+                let (body_arg, exit_arg) = if lp.contains(arg_then.bid) {
+                    (arg_then, arg_else)
+                } else {
+                    (arg_else, arg_then)
+                };
+                let arg = if index < trip_count {
+                    body_arg
+                } else {
+                    exit_arg
+                };
+                block.exit = BlockExit::Jump { arg };
+            }
+            copier.rewrite_block(*copied, &mut block);
+            let _unused = code.blocks.insert(*copied, block);
+        }
+    }
+}
+
+/// Renames the registers and jumps of a block of the loop into those of a copy.
+#[derive(Debug)]
+struct Copier<'a> {
+    /// The ids of the copies of the blocks of the loop.
+    bids: &'a HashMap<BlockId, BlockId>,
+    header: BlockId,
+    /// The header the copy jumps back to.
+    next_header: BlockId,
+}
+
+impl IrRewriter for Copier<'_> {
+    fn rewrite_jump_arg(&mut self, arg: &mut JumpArg) {
+        walk_jump_arg_mut(self, arg);
+        if arg.bid == self.header {
+            arg.bid = self.next_header;
+        } else if let Some(bid) = self.bids.get(&arg.bid) {
+            arg.bid = *bid;
+        }
+    }
+
+    fn rewrite_operand(&mut self, operand: &mut Operand) {
+        let (rid, _) = some_or!(operand.get_register_mut(), return);
+        match rid {
+            RegisterId::Arg { bid, .. } | RegisterId::Temp { bid, .. } => {
+                if let Some(copy) = self.bids.get(bid) {
+                    *bid = *copy;
+                }
+            }
+            RegisterId::Local { .. } => {}
+        }
+    }
+}
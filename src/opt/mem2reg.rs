@@ -1,14 +1,235 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::ir::analysis::{Cfg, DominanceFrontiers, Dominators};
 use crate::ir::*;
 use crate::opt::FunctionPass;
 use crate::*;
 
+/// Promotes the allocations that are only loaded from and stored to into registers, constructing
+/// SSA.
+///
+/// An allocation is promotable if its address is used only as the address of non-volatile loads
+/// and stores. Each load of a promotable allocation is replaced with the value last stored to it,
+/// which is `undef` if nothing is stored before. Where stores on different paths meet, i.e., in
+/// the iterated dominance frontier of the blocks storing to the allocation, a phinode named after
+/// the allocation receives the values of the paths as jump arguments. Phinodes are placed only in
+/// blocks where the allocation is live, so that no phinode is dead from the start.
+///
+/// The promoted loads and stores are replaced with `nop`, and the allocations are kept, so that
+/// the ids of the other registers stay the same. See [`crate::opt::CompactNop`] and
+/// [`crate::opt::Deadcode`] for cleaning them up. An allocation needing a phinode in the entry
+/// block, which is jumped to, is not promoted, since the phinodes of the entry block are the
+/// parameters.
 pub type Mem2reg = FunctionPass<Mem2regInner>;
 
 #[derive(Default, Clone, Copy, Debug)]
 pub struct Mem2regInner {}
 
 impl Optimize<FunctionDefinition> for Mem2regInner {
-    fn optimize(&mut self, _code: &mut FunctionDefinition) -> bool {
-        todo!("Homework: Register Promotion")
+    fn optimize(&mut self, code: &mut FunctionDefinition) -> bool {
+        let mut promotables = promotable_allocations(code);
+        if promotables.is_empty() {
+            return false;
+        }
+
+        let cfg = Cfg::new(code);
+        let dominators = Dominators::new(&cfg);
+        let frontiers = DominanceFrontiers::new(&cfg, &dominators);
+
+        // The promotable allocations needing a phinode in each block, in the order of their ids.
+        let mut joins = BTreeMap::<BlockId, Vec<usize>>::new();
+        for aid in promotables.clone() {
+            let (stores, live_ins) = stores_and_live_ins(code, &cfg, aid);
+            let phinodes = frontiers
+                .iterated_frontier(stores)
+                .intersection(&live_ins)
+                .copied()
+                .collect::<Vec<_>>();
+            if phinodes.contains(&code.bid_init) {
+                let _unused = promotables.remove(&aid);
+                continue;
+            }
+            for bid in phinodes {
+                joins.entry(bid).or_default().push(aid);
+            }
+        }
+        if promotables.is_empty() {
+            return false;
+        }
+
+        let allocations = &code.allocations;
+        let undef = |aid: usize| {
+            Operand::constant(Constant::undef((*allocations[aid]).clone().unqualified()))
+        };
+
+        // The phinodes inserted for the promotable allocations.
+        let mut phinodes = HashMap::new();
+        for (bid, aids) in &joins {
+            let block = code.blocks.get_mut(bid).expect("join must be a block");
+            for aid in aids {
+                let allocation = &allocations[*aid];
+                let dtype = (**allocation).clone().unqualified();
+                let phinode = Operand::register(RegisterId::arg(*bid, block.phinodes.len()), dtype);
+                block
+                    .phinodes
+                    .push(Named::new(allocation.name().cloned(), phinode.dtype()));
+                let _unused = phinodes.insert((*bid, *aid), phinode);
+            }
+        }
+
+        // Walks the dominator tree, so that the value last stored to an allocation on the way to
+        // a block is known when the block is visited. Unreachable blocks are visited on their own,
+        // knowing no values.
+        let mut replaces = HashMap::new();
+        let mut worklist = code
+            .blocks
+            .keys()
+            .filter(|bid| !dominators.is_reachable(**bid))
+            .map(|bid| (*bid, HashMap::new()))
+            .collect::<Vec<_>>();
+        worklist.push((code.bid_init, HashMap::new()));
+        while let Some((bid, mut values)) = worklist.pop() {
+            for aid in joins.get(&bid).into_iter().flatten() {
+                let _unused = values.insert(*aid, phinodes[&(bid, *aid)].clone());
+            }
+
+            let block = code.blocks.get_mut(&bid).expect("visited block must exist");
+            for (iid, instr) in block.instructions.iter_mut().enumerate() {
+                let aid = some_or!(accessed_allocation(instr), continue);
+                if !promotables.contains(&aid) {
+                    continue;
+                }
+                match &**instr {
+                    Instruction::Store { value, .. } => {
+                        let _unused = values.insert(aid, value.clone());
+                    }
+                    Instruction::Load { .. } => {
+                        let value = values.get(&aid).cloned().unwrap_or_else(|| undef(aid));
+                        let _unused = replaces.insert(RegisterId::temp(bid, iid), value);
+                    }
+                    _ => unreachable!("only loads and stores access an allocation"),
+                }
+                **instr = Instruction::Nop;
+            }
+
+            block.exit.walk_jump_args(|arg| {
+                for aid in joins.get(&arg.bid).into_iter().flatten() {
+                    arg.args
+                        .push(values.get(aid).cloned().unwrap_or_else(|| undef(*aid)));
+                }
+            });
+
+            for child in dominators.children(bid) {
+                worklist.push((*child, values.clone()));
+            }
+        }
+
+        Replacer { replaces }.rewrite_function(code);
+        true
+    }
+}
+
+/// The allocations accessed by a load or store whose address is used only as the address of
+/// non-volatile loads and stores.
+fn promotable_allocations(code: &FunctionDefinition) -> BTreeSet<usize> {
+    let mut accessed = BTreeSet::new();
+    let mut escaped = BTreeSet::new();
+    let mut escape = |operands: Vec<&Operand>| {
+        for operand in operands {
+            if let Some((RegisterId::Local { aid }, _)) = operand.get_register() {
+                let _unused = escaped.insert(*aid);
+            }
+        }
+    };
+
+    for block in code.blocks.values() {
+        for instr in &block.instructions {
+            match &**instr {
+                Instruction::Load {
+                    volatile: false, ..
+                } => {}
+                Instruction::Store {
+                    value,
+                    volatile: false,
+                    ..
+                } => escape(vec![value]),
+                _ => {
+                    escape(instr.operands());
+                    continue;
+                }
+            }
+            if let Some(aid) = accessed_allocation(instr) {
+                let _unused = accessed.insert(aid);
+            }
+        }
+        escape(block.exit.operands());
+    }
+
+    accessed.difference(&escaped).copied().collect()
+}
+
+/// The allocation a load or store accesses, if its address is one.
+fn accessed_allocation(instr: &Instruction) -> Option<usize> {
+    let ptr = match instr {
+        Instruction::Load { ptr, .. } | Instruction::Store { ptr, .. } => ptr,
+        _ => return None,
+    };
+    if let Some((RegisterId::Local { aid }, _)) = ptr.get_register() {
+        Some(*aid)
+    } else {
+        None
+    }
+}
+
+/// The blocks storing to the allocation `aid`, and the blocks where it is live, i.e., from whose
+/// beginning a load of it may be reached before any store to it.
+fn stores_and_live_ins(
+    code: &FunctionDefinition,
+    cfg: &Cfg,
+    aid: usize,
+) -> (BTreeSet<BlockId>, BTreeSet<BlockId>) {
+    let mut stores = BTreeSet::new();
+    let mut live_ins = BTreeSet::new();
+    for (bid, block) in &code.blocks {
+        let mut accesses = block
+            .instructions
+            .iter()
+            .filter(|instr| accessed_allocation(instr) == Some(aid))
+            .peekable();
+        if matches!(
+            accesses.peek().map(|instr| &***instr),
+            Some(Instruction::Load { .. })
+        ) {
+            let _unused = live_ins.insert(*bid);
+        }
+        if accesses.any(|instr| matches!(**instr, Instruction::Store { .. })) {
+            let _unused = stores.insert(*bid);
+        }
+    }
+
+    let mut worklist = live_ins.iter().copied().collect::<Vec<_>>();
+    while let Some(bid) = worklist.pop() {
+        for pred in cfg.predecessors(bid) {
+            if !stores.contains(pred) && live_ins.insert(*pred) {
+                worklist.push(*pred);
+            }
+        }
+    }
+
+    (stores, live_ins)
+}
+
+/// Replaces the uses of the promoted loads with the values they load.
+#[derive(Debug)]
+struct Replacer {
+    replaces: HashMap<RegisterId, Operand>,
+}
+
+impl IrRewriter for Replacer {
+    fn rewrite_operand(&mut self, operand: &mut Operand) {
+        // A value stored in an unreachable block may be a load visited after the store.
+        while let Some((rid, _)) = operand.get_register() {
+            *operand = some_or!(self.replaces.get(rid), return).clone();
+        }
     }
 }
@@ -1,42 +1,110 @@
 use crate::*;
 
+mod branch_fold;
+mod canonicalize;
+mod compact_nop;
+mod const_fold;
+mod const_prop;
+mod copy_prop;
+mod dead_function;
+mod dead_global;
+mod dead_store;
 mod deadcode;
+mod div_by_const;
 mod gvn;
+mod indvars;
+mod inline;
+mod inst_combine;
+mod licm;
+mod loop_unroll;
 mod mem2reg;
+mod narrow;
 pub mod opt_utils;
+mod pass_manager;
+mod phi_elim;
+mod pipeline;
+mod pre;
+mod profile_unroll;
+mod prune_block_args;
+mod reassociate;
+mod sccp;
 mod simplify_cfg;
+mod sroa;
+mod store_forward;
+mod switch_lowering;
+mod unreachable_block;
 
+pub use branch_fold::BranchFold;
+pub use canonicalize::Canonicalize;
+pub use compact_nop::CompactNop;
+pub use const_fold::ConstFold;
+pub use const_prop::ConstProp;
+pub use copy_prop::CopyProp;
+pub use dead_function::DeadFunctionElimination;
+pub use dead_global::DeadGlobalElimination;
+pub use dead_store::DeadStoreElimination;
 pub use deadcode::Deadcode;
+pub use div_by_const::DivByConst;
 pub use gvn::Gvn;
+pub use indvars::IndVarSimplify;
+pub use inline::Inline;
+pub use inst_combine::InstCombine;
+pub use licm::Licm;
+pub use loop_unroll::{LoopUnroll, LoopUnrollInner};
 pub use mem2reg::Mem2reg;
+pub use narrow::Narrowing;
+pub use pass_manager::PassManager;
+pub use phi_elim::PhiElimination;
+pub use pipeline::{pipeline, pipeline_with, OptLevel};
+pub use pre::Pre;
+pub use profile_unroll::ProfileGuidedUnroll;
+pub use prune_block_args::PruneBlockArgs;
+pub use reassociate::Reassociate;
+pub use sccp::Sccp;
 pub use simplify_cfg::{
     SimplifyCfg, SimplifyCfgConstProp, SimplifyCfgEmpty, SimplifyCfgMerge, SimplifyCfgReach,
 };
+pub use sroa::Sroa;
+pub use store_forward::StoreForwarding;
+pub use switch_lowering::SwitchLowering;
+pub use unreachable_block::UnreachableBlockElimination;
 
 use crate::ir;
 
+/// An optimization pass over code of type `T`, typically a translation unit or a function.
+///
+/// Passes compose as pairs, which run both passes in order, and with [`Repeat`], which reruns a
+/// pass until it reaches a fixpoint.
 pub trait Optimize<T> {
+    /// Optimizes `code` in place, returning whether it changed.
     fn optimize(&mut self, code: &mut T) -> bool;
 }
 
 pub type O0 = Null;
-pub type O1 = Repeat<(SimplifyCfg, (Mem2reg, (Gvn, Deadcode)))>;
+pub type O1 = (
+    Repeat<(SimplifyCfg, (Mem2reg, (Gvn, Deadcode)))>,
+    Canonicalize,
+);
 
+/// The pass that does nothing.
 #[derive(Default, Clone, Copy, Debug)]
 pub struct Null;
 
+/// Runs the inner pass until it no longer changes the code.
 #[derive(Default, Debug)]
 pub struct Repeat<O> {
     inner: O,
 }
 
+/// Lifts a pass over function definitions to declarations and translation units, running it on
+/// every function with a body.
 #[derive(Default, Debug)]
 pub struct FunctionPass<T: Optimize<ir::FunctionDefinition>> {
     inner: T,
 }
 
-impl Optimize<ir::TranslationUnit> for Null {
-    fn optimize(&mut self, _code: &mut ir::TranslationUnit) -> bool {
+impl<T> Optimize<T> for Null {
+    fn optimize(&mut self, _code: &mut T) -> bool {
         false
     }
 }
@@ -60,6 +128,13 @@ impl<T, O: Optimize<T>> Optimize<T> for Repeat<O> {
     }
 }
 
+impl<T: Optimize<ir::FunctionDefinition>> FunctionPass<T> {
+    /// Lifts `inner`, e.g., a pass configured other than by default.
+    pub fn new(inner: T) -> Self {
+        Self { inner }
+    }
+}
+
 impl<T> Optimize<ir::TranslationUnit> for FunctionPass<T>
 where
     T: Optimize<ir::FunctionDefinition>,
@@ -0,0 +1,179 @@
+use lang_c::ast;
+
+use crate::ir::analysis::DefUse;
+use crate::ir::*;
+use crate::opt::compact_nop::CompactNopInner;
+use crate::opt::inst_combine::definition;
+use crate::opt::opt_utils::insert_instructions;
+use crate::opt::FunctionPass;
+use crate::*;
+
+/// Narrows integer computations whose results are only truncated to the narrower dtype of the
+/// truncation, repeating until no more computation is narrowed.
+///
+/// The lower bits of the result of `+`, `-`, `*`, `&`, `|`, `^`, negation and complement depend
+/// only on the lower bits of the operands. A truncating typecast of such a computation, i.e., one
+/// to a narrower integer dtype other than a boolean, is thus replaced with the computation on the
+/// operands truncated to that dtype, if the truncation is the only use of the computation. The
+/// operands extended from the narrow dtype are used as they are and the constants are truncated,
+/// e.g., `(char)((int)a + (int)b)` becomes the addition of the `char`s `a` and `b`. At most one
+/// other operand, possibly used on both sides, is truncated by a new typecast, which may then be
+/// narrowed in turn, so that no more typecasts are computed than before. The narrowed
+/// computations wrap around, so their flags are dropped.
+///
+/// The wide computations are replaced with `nop` and removed as [`crate::opt::CompactNop`] does,
+/// and the extensions made unused are left to [`crate::opt::Deadcode`].
+pub type Narrowing = FunctionPass<Repeat<NarrowingInner>>;
+
+#[derive(Default, Clone, Copy, Debug)]
+pub struct NarrowingInner {}
+
+/// How a truncation is narrowed.
+#[derive(Debug)]
+struct Narrowed {
+    /// The computation on the narrow dtype replacing the truncation.
+    instr: Instruction,
+    /// The typecast truncating an operand, inserted right before the computation, which refers to
+    /// it by the register of the truncation being narrowed.
+    truncation: Option<Instruction>,
+}
+
+impl Optimize<FunctionDefinition> for NarrowingInner {
+    fn optimize(&mut self, code: &mut FunctionDefinition) -> bool {
+        let def_use = DefUse::new(code);
+        let mut changed = false;
+
+        let bids = code.blocks.keys().copied().collect::<Vec<_>>();
+        'blocks: for bid in bids {
+            for iid in 0..code.blocks[&bid].instructions.len() {
+                let (narrowed, wide) = some_or!(
+                    narrow(
+                        code,
+                        &def_use,
+                        bid,
+                        iid,
+                        &code.blocks[&bid].instructions[iid]
+                    ),
+                    continue
+                );
+                let (wide_bid, wide_iid) = wide;
+                let wide_block = code.blocks.get_mut(&wide_bid).expect("block must exist");
+                *wide_block.instructions[wide_iid] = Instruction::Nop;
+                changed = true;
+
+                let Some(truncation) = narrowed.truncation else {
+                    let block = code.blocks.get_mut(&bid).expect("block must exist");
+                    *block.instructions[iid] = narrowed.instr;
+                    continue;
+                };
+                // Inserting the truncation renumbers the instructions after it, so the other
+                // truncations are narrowed in the next repetition.
+                insert_instructions(code, bid, iid, vec![Named::new(None, truncation)]);
+                let block = code.blocks.get_mut(&bid).expect("block must exist");
+                *block.instructions[iid + 1] = narrowed.instr;
+                break 'blocks;
+            }
+        }
+
+        if changed {
+            let _unused = CompactNopInner::default().optimize(code);
+        }
+        changed
+    }
+}
+
+/// How `instr`, the instruction `iid` of the block `bid`, is narrowed if it truncates a
+/// computation used only by it, together with the location of the computation.
+fn narrow(
+    code: &FunctionDefinition,
+    def_use: &DefUse,
+    bid: BlockId,
+    iid: usize,
+    instr: &Instruction,
+) -> Option<(Narrowed, (BlockId, usize))> {
+    let Instruction::TypeCast {
+        value,
+        target_dtype,
+    } = instr
+    else {
+        return None;
+    };
+    let width = value.dtype().get_int_width()?;
+    let target_width = target_dtype.get_int_width()?;
+    if target_width >= width || target_width == 1 {
+        return None;
+    }
+
+    let (rid, _) = value.get_register()?;
+    let RegisterId::Temp {
+        bid: wide_bid,
+        iid: wide_iid,
+    } = *rid
+    else {
+        return None;
+    };
+    if def_use.uses(*rid).len() != 1 {
+        return None;
+    }
+
+    let mut truncation = None;
+    let mut truncate = |operand: &Operand| -> Option<Operand> {
+        if let Some(constant) = operand.get_constant() {
+            let truncated = const_eval::typecast(constant, target_dtype).ok()?;
+            return Some(Operand::constant(truncated));
+        }
+        if let Some(Instruction::TypeCast { value: inner, .. }) = definition(code, operand) {
+            if inner.dtype() == *target_dtype {
+                return Some(inner.clone());
+            }
+        }
+        let truncated = Operand::register(RegisterId::temp(bid, iid), target_dtype.clone());
+        match &truncation {
+            Some(Instruction::TypeCast { value, .. }) if value == operand => Some(truncated),
+            Some(_) => None,
+            None => {
+                truncation = Some(Instruction::TypeCast {
+                    value: operand.clone(),
+                    target_dtype: target_dtype.clone(),
+                });
+                Some(truncated)
+            }
+        }
+    };
+
+    let instr = match &*code.blocks[&wide_bid].instructions[wide_iid] {
+        Instruction::BinOp { op, lhs, rhs, .. }
+            if matches!(
+                op,
+                ast::BinaryOperator::Plus
+                    | ast::BinaryOperator::Minus
+                    | ast::BinaryOperator::Multiply
+                    | ast::BinaryOperator::BitwiseAnd
+                    | ast::BinaryOperator::BitwiseOr
+                    | ast::BinaryOperator::BitwiseXor
+            ) =>
+        {
+            Instruction::BinOp {
+                op: op.clone(),
+                lhs: truncate(lhs)?,
+                rhs: truncate(rhs)?,
+                dtype: target_dtype.clone(),
+                flags: BinOpFlags::default(),
+            }
+        }
+        Instruction::UnaryOp { op, operand, .. }
+            if matches!(
+                op,
+                ast::UnaryOperator::Minus | ast::UnaryOperator::Complement
+            ) =>
+        {
+            Instruction::UnaryOp {
+                op: op.clone(),
+                operand: truncate(operand)?,
+                dtype: target_dtype.clone(),
+            }
+        }
+        _ => return None,
+    };
+    Some((Narrowed { instr, truncation }, (wide_bid, wide_iid)))
+}
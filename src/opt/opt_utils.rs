@@ -3,3 +3,189 @@
 //! You can add here utilities commonly used in the implementation of multiple optimizations.
 
 #![allow(dead_code)]
+
+use std::collections::{HashMap, HashSet};
+
+use crate::ir::analysis::Loop;
+use crate::ir::*;
+use crate::*;
+
+/// The id after those of the blocks of `code`.
+pub fn new_bid(code: &FunctionDefinition) -> BlockId {
+    code.blocks
+        .keys()
+        .next_back()
+        .map_or(BlockId(0), |bid| BlockId(bid.0 + 1))
+}
+
+/// Inserts a preheader for `lp`, i.e., a block that is the only predecessor of the header outside
+/// of the loop and jumps only to the header, returning its id.
+///
+/// The jumps to the header from outside of the loop are redirected to the preheader, which has
+/// the phinodes of the header and passes them to it. If the header is the entry block, the
+/// preheader becomes the entry block instead.
+pub fn insert_preheader(code: &mut FunctionDefinition, lp: &Loop) -> BlockId {
+    let header = lp.header();
+    let preheader = new_bid(code);
+
+    for (bid, block) in &mut code.blocks {
+        if lp.contains(*bid) {
+            continue;
+        }
+        block.exit.walk_jump_args(|arg| {
+            if arg.bid == header {
+                arg.bid = preheader;
+            }
+        });
+    }
+
+    let phinodes = code.blocks[&header].phinodes.clone();
+    let args = phinodes
+        .iter()
+        .enumerate()
+        .map(|(aid, dtype)| Operand::register(RegisterId::arg(preheader, aid), (**dtype).clone()))
+        .collect();
+    let block = Block {
+        phinodes,
+        instructions: Vec::new(),
+        exit: BlockExit::Jump {
+            arg: JumpArg::new(header, args),
+        },
+    };
+    let _unused = code.blocks.insert(preheader, block);
+    if code.bid_init == header {
+        code.bid_init = preheader;
+    }
+
+    preheader
+}
+
+/// Splits the critical edges of `code`, returning whether any edge is split.
+///
+/// An edge is critical if its source may jump to other blocks as well and its target may be
+/// jumped to from other blocks as well, so that code placed at either end would run on other
+/// edges. Conditional jumps and switches jumping to the same block more than once count as
+/// several edges. Each critical edge is redirected to a new block with no phinodes or
+/// instructions that jumps to the target with the arguments of the edge, which are still
+/// available there since the source dominates the new block.
+pub fn split_critical_edges(code: &mut FunctionDefinition) -> bool {
+    let mut jumps_to = HashMap::<BlockId, usize>::new();
+    for block in code.blocks.values() {
+        for arg in block.exit.jump_args() {
+            *jumps_to.entry(arg.bid).or_default() += 1;
+        }
+    }
+
+    let mut next_bid = new_bid(code);
+    let mut forwarders = Vec::new();
+    for block in code.blocks.values_mut() {
+        if block.exit.jump_args().len() < 2 {
+            continue;
+        }
+        block.exit.walk_jump_args(|arg| {
+            if jumps_to[&arg.bid] < 2 {
+                return;
+            }
+            let bid = next_bid;
+            next_bid = BlockId(next_bid.0 + 1);
+            let forwarded = std::mem::replace(arg, JumpArg::new(bid, Vec::new()));
+            forwarders.push((
+                bid,
+                Block {
+                    phinodes: Vec::new(),
+                    instructions: Vec::new(),
+                    exit: BlockExit::Jump { arg: forwarded },
+                },
+            ));
+        });
+    }
+
+    let split = !forwarders.is_empty();
+    code.blocks.extend(forwarders);
+    split
+}
+
+/// Removes the phinodes of each block at the indices in `phinodes`, together with the jump
+/// arguments passed to them, and renumbers the remaining phinodes. The removed phinodes must be
+/// unused.
+pub fn remove_phinodes(code: &mut FunctionDefinition, phinodes: &HashMap<BlockId, HashSet<usize>>) {
+    let mut renames = HashMap::new();
+    for (bid, aids) in phinodes {
+        let block = code.blocks.get_mut(bid).expect("block must exist");
+        for (aid_new, aid) in (0..block.phinodes.len())
+            .filter(|aid| !aids.contains(aid))
+            .enumerate()
+        {
+            let _unused =
+                renames.insert(RegisterId::arg(*bid, aid), RegisterId::arg(*bid, aid_new));
+        }
+        remove_indices(&mut block.phinodes, aids);
+    }
+    for block in code.blocks.values_mut() {
+        block.exit.walk_jump_args(|arg| {
+            if let Some(aids) = phinodes.get(&arg.bid) {
+                remove_indices(&mut arg.args, aids);
+            }
+        });
+    }
+    Renamer { renames }.rewrite_function(code);
+}
+
+/// Inserts `instructions` before the instruction `iid` of the block `bid`, renumbering the
+/// instructions from `iid` on together with their uses. The inserted instructions refer to each
+/// other by the registers they have after the insertion.
+pub fn insert_instructions(
+    code: &mut FunctionDefinition,
+    bid: BlockId,
+    iid: usize,
+    instructions: Vec<Named<Instruction>>,
+) {
+    let count = instructions.len();
+    let len = code.blocks[&bid].instructions.len();
+    let renames = (iid..len)
+        .map(|moved| {
+            (
+                RegisterId::temp(bid, moved),
+                RegisterId::temp(bid, moved + count),
+            )
+        })
+        .collect();
+    Renamer { renames }.rewrite_function(code);
+
+    let block = code.blocks.get_mut(&bid).expect("block must exist");
+    let rest = block.instructions.split_off(iid);
+    block.instructions.extend(instructions);
+    block.instructions.extend(rest);
+}
+
+/// Removes the elements of `vec` at `indices`.
+pub fn remove_indices<T>(vec: &mut Vec<T>, indices: &HashSet<usize>) {
+    let mut index = 0;
+    vec.retain(|_| {
+        let keep = !indices.contains(&index);
+        index += 1;
+        keep
+    });
+}
+
+/// Removes the declarations of `names` from `unit`, together with their order and locations.
+pub fn remove_decls(unit: &mut TranslationUnit, names: &HashSet<String>) {
+    unit.decls.retain(|name, _| !names.contains(name));
+    unit.decl_order.retain(|name| !names.contains(name));
+    unit.decl_locs.retain(|name, _| !names.contains(name));
+}
+
+/// Renames the uses of registers.
+#[derive(Debug)]
+pub struct Renamer {
+    pub renames: HashMap<RegisterId, RegisterId>,
+}
+
+impl IrRewriter for Renamer {
+    fn rewrite_operand(&mut self, operand: &mut Operand) {
+        let (rid, _) = some_or!(operand.get_register_mut(), return);
+        if let Some(rid_new) = self.renames.get(rid) {
+            *rid = *rid_new;
+        }
+    }
+}
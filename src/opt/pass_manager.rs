@@ -0,0 +1,154 @@
+use core::fmt;
+
+use crate::ir::*;
+use crate::*;
+
+/// A sequence of named passes over translation units, built at run time and run in order.
+///
+/// Unlike the passes composed as types, e.g., [`crate::opt::O1`], a pass manager can report what
+/// each of its passes does, which helps to find the pass introducing a regression in the middle of
+/// a pipeline. The reports are written to stderr, each headed by the name of its pass:
+///
+/// - with [`PassManager::print_changes`], the diff of the IR before and after each pass, and
+/// - with [`PassManager::print_after_all`], the IR of each function a pass changes, printed after
+///   the pass like `-print-after-all` of LLVM. The functions a pass removes are reported as well.
+///
+/// The passes of a group added with [`PassManager::add_repeat`] are reported as the other passes
+/// are, whatever the options of the group itself.
+#[derive(Default)]
+pub struct PassManager {
+    passes: Vec<Entry>,
+    print_changes: bool,
+    print_after_all: bool,
+}
+
+enum Entry {
+    Pass {
+        name: String,
+        pass: Box<dyn Optimize<TranslationUnit>>,
+    },
+    Repeat(PassManager),
+}
+
+impl fmt::Debug for PassManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PassManager")
+            .field("passes", &self.passes)
+            .field("print_changes", &self.print_changes)
+            .field("print_after_all", &self.print_after_all)
+            .finish()
+    }
+}
+
+impl fmt::Debug for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Pass { name, .. } => write!(f, "{name}"),
+            Self::Repeat(passes) => f.debug_tuple("Repeat").field(&passes.passes).finish(),
+        }
+    }
+}
+
+impl PassManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends `pass`, reported as `name`.
+    pub fn add<O>(&mut self, name: &str, pass: O) -> &mut Self
+    where
+        O: Optimize<TranslationUnit> + 'static,
+    {
+        self.passes.push(Entry::Pass {
+            name: name.to_string(),
+            pass: Box::new(pass),
+        });
+        self
+    }
+
+    /// Appends the group of `passes`, which is run until none of its passes changes the code as
+    /// [`crate::opt::Repeat`] does.
+    pub fn add_repeat(&mut self, passes: PassManager) -> &mut Self {
+        self.passes.push(Entry::Repeat(passes));
+        self
+    }
+
+    /// Appends the passes of `passes`, which are run once in order as the other passes are.
+    pub fn append(&mut self, passes: PassManager) -> &mut Self {
+        self.passes.extend(passes.passes);
+        self
+    }
+
+    /// Whether no pass is added.
+    pub fn is_empty(&self) -> bool {
+        self.passes.is_empty()
+    }
+
+    /// Makes the next runs print the diff of the IR before and after each pass.
+    pub fn print_changes(&mut self, enable: bool) -> &mut Self {
+        self.print_changes = enable;
+        self
+    }
+
+    /// Makes the next runs print the functions each pass changes after the pass.
+    pub fn print_after_all(&mut self, enable: bool) -> &mut Self {
+        self.print_after_all = enable;
+        self
+    }
+
+    fn run(
+        &mut self,
+        code: &mut TranslationUnit,
+        print_changes: bool,
+        print_after_all: bool,
+    ) -> bool {
+        let mut changed = false;
+        for entry in &mut self.passes {
+            match entry {
+                Entry::Pass { name, pass } => {
+                    let before = (print_changes || print_after_all).then(|| code.clone());
+                    let pass_changed = pass.optimize(code);
+                    if let Some(before) = before {
+                        if print_changes {
+                            eprint!("[{name}]\n{}", diff(&before, code));
+                        }
+                        if print_after_all && pass_changed {
+                            print_changed_functions(name, &before, code);
+                        }
+                    }
+                    changed |= pass_changed;
+                }
+                Entry::Repeat(passes) => {
+                    while passes.run(code, print_changes, print_after_all) {
+                        changed = true;
+                    }
+                }
+            }
+        }
+        changed
+    }
+}
+
+impl Optimize<TranslationUnit> for PassManager {
+    fn optimize(&mut self, code: &mut TranslationUnit) -> bool {
+        let (print_changes, print_after_all) = (self.print_changes, self.print_after_all);
+        self.run(code, print_changes, print_after_all)
+    }
+}
+
+/// Prints the functions of `after` that differ from those of `before`, and names the removed ones.
+fn print_changed_functions(pass: &str, before: &TranslationUnit, after: &TranslationUnit) {
+    let mut stderr = std::io::stderr();
+    for (name, decl) in after.decls_in_order() {
+        if decl.get_function().is_none() || before.decls.get(name) == Some(decl) {
+            continue;
+        }
+        eprintln!("; *** IR Dump After {pass} on @{name} ***");
+        write(&(name, decl), &mut stderr).unwrap();
+    }
+    for (name, decl) in before.decls_in_order() {
+        if decl.get_function().is_some() && !after.decls.contains_key(name) {
+            eprintln!("; *** IR Dump After {pass} on @{name}: removed ***");
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use core::ops::Deref;
+use std::collections::HashMap;
+
+use crate::ir::*;
+use crate::opt::opt_utils::{new_bid, split_critical_edges};
+use crate::opt::FunctionPass;
+use crate::*;
+
+/// Lowers the phinodes and jump arguments of each function into copies through memory, as is
+/// needed before register allocation.
+///
+/// Each phinode gets an allocation of its own. Every jump passing arguments stores them into the
+/// allocations of the phinodes of its target, and the target loads them at its beginning into
+/// instructions replacing the phinodes. Since the loads happen before anything else in the
+/// block, a value copied into a phinode is never overwritten while it is still used, which is the
+/// lost-copy problem, and a jump swapping the phinodes of its block stores the values loaded
+/// before, not the ones being stored, which is the swap problem.
+///
+/// The critical edges are split first, so that the copies happen only on the edge they belong to.
+/// If the entry block is jumped to, a new entry block taking the parameters is inserted before it.
+/// The phinodes of the entry block, which are the parameters, are kept.
+pub type PhiElimination = FunctionPass<PhiEliminationInner>;
+
+#[derive(Default, Clone, Copy, Debug)]
+pub struct PhiEliminationInner {}
+
+impl Optimize<FunctionDefinition> for PhiEliminationInner {
+    fn optimize(&mut self, code: &mut FunctionDefinition) -> bool {
+        let has_phinodes = |code: &FunctionDefinition| {
+            code.blocks.iter().any(|(bid, block)| {
+                *bid != code.bid_init && !block.phinodes.is_empty()
+                    || block
+                        .exit
+                        .jump_args()
+                        .iter()
+                        .any(|arg| !arg.args.is_empty())
+            })
+        };
+        if !has_phinodes(code) {
+            return false;
+        }
+
+        insert_entry(code);
+        let _unused = split_critical_edges(code);
+
+        // The allocations of the phinodes.
+        let mut slots = HashMap::new();
+        for (bid, block) in &code.blocks {
+            if *bid == code.bid_init {
+                continue;
+            }
+            for (aid, phinode) in block.phinodes.iter().enumerate() {
+                let ptr = Operand::register(
+                    RegisterId::local(code.allocations.len()),
+                    Dtype::pointer(phinode.deref().clone()),
+                );
+                code.allocations
+                    .push(Named::new(phinode.name().cloned(), phinode.deref().clone()));
+                let _unused = slots.insert(RegisterId::arg(*bid, aid), ptr);
+            }
+        }
+
+        let shifts = code
+            .blocks
+            .iter()
+            .filter(|(bid, _)| **bid != code.bid_init)
+            .map(|(bid, block)| (*bid, block.phinodes.len()))
+            .filter(|(_, shift)| *shift != 0)
+            .collect();
+        Renamer { shifts }.rewrite_function(code);
+
+        for (bid, block) in &mut code.blocks {
+            if *bid == code.bid_init {
+                continue;
+            }
+            let loads = block
+                .phinodes
+                .drain(..)
+                .enumerate()
+                .map(|(aid, phinode)| {
+                    Named::new(
+                        phinode.name().cloned(),
+                        Instruction::Load {
+                            ptr: slots[&RegisterId::arg(*bid, aid)].clone(),
+                            volatile: false,
+                        },
+                    )
+                })
+                .collect::<Vec<_>>();
+            let _unused = block.instructions.splice(0..0, loads);
+        }
+
+        for block in code.blocks.values_mut() {
+            let mut stores = Vec::new();
+            block.exit.walk_jump_args(|arg| {
+                for (aid, value) in arg.args.drain(..).enumerate() {
+                    stores.push(Named::new(
+                        None,
+                        Instruction::Store {
+                            ptr: slots[&RegisterId::arg(arg.bid, aid)].clone(),
+                            value,
+                            volatile: false,
+                        },
+                    ));
+                }
+            });
+            block.instructions.extend(stores);
+        }
+
+        true
+    }
+}
+
+/// Inserts a new entry block taking the parameters and jumping to the entry block with them, if
+/// the entry block is jumped to.
+fn insert_entry(code: &mut FunctionDefinition) {
+    let is_jumped_to = code.blocks.values().any(|block| {
+        block
+            .exit
+            .jump_args()
+            .iter()
+            .any(|arg| arg.bid == code.bid_init)
+    });
+    if !is_jumped_to {
+        return;
+    }
+
+    let bid = new_bid(code);
+    let phinodes = code.blocks[&code.bid_init].phinodes.clone();
+    let args = phinodes
+        .iter()
+        .enumerate()
+        .map(|(aid, phinode)| Operand::register(RegisterId::arg(bid, aid), phinode.deref().clone()))
+        .collect();
+    let block = Block {
+        phinodes,
+        instructions: Vec::new(),
+        exit: BlockExit::Jump {
+            arg: JumpArg::new(code.bid_init, args),
+        },
+    };
+    let _unused = code.blocks.insert(bid, block);
+    code.bid_init = bid;
+}
+
+/// Renames the phinodes of a block to the loads replacing them, and shifts its instructions after
+/// the loads.
+#[derive(Debug)]
+struct Renamer {
+    /// The number of phinodes of each block.
+    shifts: HashMap<BlockId, usize>,
+}
+
+impl IrRewriter for Renamer {
+    fn rewrite_operand(&mut self, operand: &mut Operand) {
+        let (rid, _) = some_or!(operand.get_register_mut(), return);
+        match *rid {
+            RegisterId::Arg { bid, aid } if self.shifts.contains_key(&bid) => {
+                *rid = RegisterId::temp(bid, aid);
+            }
+            RegisterId::Temp { bid, iid } => {
+                if let Some(shift) = self.shifts.get(&bid) {
+                    *rid = RegisterId::temp(bid, iid + shift);
+                }
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use core::fmt;
+use core::str::FromStr;
+
+use crate::opt::*;
+
+/// An optimization level, as selected by `--opt-level` of the driver.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum OptLevel {
+    /// No optimization.
+    #[default]
+    O0,
+    /// Promotion of locals to registers and the cleanups around it.
+    O1,
+    /// The passes of `-O1`, together with constant propagation, algebraic simplification, loop
+    /// optimizations and the removal of dead globals.
+    O2,
+}
+
+impl fmt::Display for OptLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::O0 => write!(f, "O0"),
+            Self::O1 => write!(f, "O1"),
+            Self::O2 => write!(f, "O2"),
+        }
+    }
+}
+
+impl FromStr for OptLevel {
+    type Err = String;
+
+    /// Parses the level of `-O{level}`, i.e., `0`, `1` or `2`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "0" => Ok(Self::O0),
+            "1" => Ok(Self::O1),
+            "2" => Ok(Self::O2),
+            _ => Err(format!(
+                "unknown optimization level `{s}`, expected 0, 1 or 2"
+            )),
+        }
+    }
+}
+
+/// The passes run at `level`, each named as its command line option of the driver. The pipeline
+/// may be extended with more passes, or set to print the IR after each pass, before it is run.
+///
+/// - `-O0` runs no pass.
+/// - `-O1` runs `remove-unreachable`, `mem2reg`, `gvn` and `deadcode` repeatedly until none of
+///   them changes the code, and then `canonicalize`. Unlike [`O1`], it does not depend on
+///   `simplify-cfg`, so the blocks left empty or mergeable are kept.
+/// - `-O2` first removes the functions unreachable from `main`. The repeated passes of `-O1` are
+///   interleaved with `sroa` before `mem2reg`, `sccp`, `instcombine`, `branch-fold`, `copy-prop`
+///   and `pre`, and followed by `inline`, after which `dead-function` runs again, `reassociate`,
+///   the loop optimizations `licm`, `indvars` and `loop-unroll`, `div-by-const`, `narrow`,
+///   `store-forward`, `dse` and `dead-global`. The code they expose is cleaned up by repeating the
+///   scalar passes again, before `lower-switch` and `canonicalize`.
+pub fn pipeline(level: OptLevel) -> PassManager {
+    pipeline_with(level, Inline::default())
+}
+
+/// The passes of [`pipeline`], where `-O2` inlines functions as `inline` decides.
+pub fn pipeline_with(level: OptLevel, inline: Inline) -> PassManager {
+    let mut passes = PassManager::new();
+    match level {
+        OptLevel::O0 => {}
+        OptLevel::O1 => {
+            let mut cleanup = PassManager::new();
+            let _ = cleanup
+                .add("remove-unreachable", UnreachableBlockElimination::default())
+                .add("mem2reg", Mem2reg::default())
+                .add("gvn", Gvn::default())
+                .add("deadcode", Deadcode::default());
+            let _ = passes
+                .add_repeat(cleanup)
+                .add("canonicalize", Canonicalize::default());
+        }
+        OptLevel::O2 => {
+            let _ = passes
+                .add("dead-function", DeadFunctionElimination::default())
+                .add_repeat(scalar_passes(true))
+                .add("inline", inline)
+                .add("dead-function", DeadFunctionElimination::default())
+                .add("reassociate", Reassociate::default())
+                .add("licm", Licm::default())
+                .add("indvars", IndVarSimplify::default())
+                .add("loop-unroll", LoopUnroll::default())
+                .add("div-by-const", DivByConst::default())
+                .add("narrow", Narrowing::default())
+                .add("store-forward", StoreForwarding::default())
+                .add("dse", DeadStoreElimination::default())
+                .add("dead-global", DeadGlobalElimination::default())
+                .add_repeat(scalar_passes(false))
+                .add("lower-switch", SwitchLowering::default())
+                .add("canonicalize", Canonicalize::default());
+        }
+    }
+    passes
+}
+
+/// The scalar passes of `-O2`, with `sroa` and `mem2reg` if `promote`.
+fn scalar_passes(promote: bool) -> PassManager {
+    let mut passes = PassManager::new();
+    let _ = passes.add("remove-unreachable", UnreachableBlockElimination::default());
+    if promote {
+        let _ = passes
+            .add("sroa", Sroa::default())
+            .add("mem2reg", Mem2reg::default());
+    }
+    let _ = passes
+        .add("sccp", Sccp::default())
+        .add("instcombine", InstCombine::default())
+        .add("branch-fold", BranchFold::default())
+        .add("copy-prop", CopyProp::default())
+        .add("gvn", Gvn::default())
+        .add("pre", Pre::default())
+        .add("deadcode", Deadcode::default());
+    passes
+}
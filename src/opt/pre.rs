@@ -0,0 +1,259 @@
+use std::collections::HashMap;
+
+use crate::ir::analysis::{Cfg, DefUse, Dominators};
+use crate::ir::*;
+use crate::opt::gvn::is_commutative;
+use crate::opt::licm::may_fail;
+use crate::opt::FunctionPass;
+use crate::*;
+
+/// Partial redundancy elimination: makes the computations that are already computed on some but
+/// not all paths to them fully redundant, repeating until no more computation is.
+///
+/// A `BinOp`, `UnaryOp`, `TypeCast` or `GetElementPtr` instruction of a block with several
+/// predecessors is partially redundant if its operands are available at the end of every
+/// predecessor, i.e., they are constants, allocations, phinodes of its block or registers defined
+/// in blocks strictly dominating it, and the same instruction, with the phinodes replaced by the
+/// arguments of the jump, is computed in all but one predecessor or their dominators. The
+/// instruction is then inserted at the end of the remaining predecessor, which must jump only to
+/// the block so that no other path computes it, and a phinode receiving the computed values
+/// replaces the uses of the instruction. This is the scalar PRE of LLVM's GVN, which hoists, e.g.,
+/// computations after an `if` without `else` computing them as well, or computations of a loop
+/// header using the values computed in the previous iteration.
+///
+/// Only one predecessor may miss the computation, so that no path computes more than before.
+/// Divisions, remainders and shifts are inserted only if they cannot fail, as with
+/// [`crate::opt::Licm`]. If a call before the instruction in its block may not return, the
+/// inserted instruction may run where the original one would not have, and loses its `nuw`, `nsw`
+/// and `exact` flags. The instructions made unused are left to [`crate::opt::Deadcode`], and
+/// the fully redundant ones to [`crate::opt::Gvn`].
+pub type Pre = FunctionPass<Repeat<PreInner>>;
+
+#[derive(Default, Clone, Copy, Debug)]
+pub struct PreInner {}
+
+/// How a partially redundant instruction is made fully redundant.
+#[derive(Debug)]
+struct Insertion {
+    bid: BlockId,
+    iid: usize,
+    /// The predecessor missing the computation, and the instruction inserted at its end.
+    pred: BlockId,
+    instr: Instruction,
+    /// The registers computing the instruction for the other predecessors.
+    leaders: HashMap<BlockId, Operand>,
+}
+
+impl Optimize<FunctionDefinition> for PreInner {
+    fn optimize(&mut self, code: &mut FunctionDefinition) -> bool {
+        let cfg = Cfg::new(code);
+        let dominators = Dominators::new(&cfg);
+        let def_use = DefUse::new(code);
+
+        let mut insertions = Vec::new();
+        for bid in cfg.reverse_post_order() {
+            if *bid == code.bid_init {
+                continue;
+            }
+            // The arguments of the only jump of each reachable predecessor to the block.
+            let preds = cfg
+                .predecessors(*bid)
+                .iter()
+                .filter(|pred| cfg.is_reachable(**pred))
+                .map(|pred| {
+                    let [arg] = code.blocks[pred]
+                        .exit
+                        .jump_args()
+                        .into_iter()
+                        .filter(|arg| arg.bid == *bid)
+                        .collect::<Vec<_>>()[..]
+                    else {
+                        return None;
+                    };
+                    Some((*pred, &arg.args))
+                })
+                .collect::<Option<Vec<_>>>();
+            let preds = some_or!(preds, continue);
+            if preds.len() < 2 {
+                continue;
+            }
+
+            for (iid, instr) in code.blocks[bid].instructions.iter().enumerate() {
+                if !def_use.is_used(RegisterId::temp(*bid, iid))
+                    || !is_candidate(instr, *bid, &dominators)
+                {
+                    continue;
+                }
+
+                let mut missing = Vec::new();
+                let mut leaders = HashMap::new();
+                for (pred, args) in &preds {
+                    let translated = translate(instr, *bid, args);
+                    match find(code, &translated, *pred, *bid, &dominators) {
+                        Some(leader) => {
+                            let _unused = leaders.insert(*pred, leader);
+                        }
+                        None => missing.push((*pred, translated)),
+                    }
+                }
+
+                let [(pred, translated)] = &missing[..] else {
+                    continue;
+                };
+                if !matches!(code.blocks[pred].exit, BlockExit::Jump { .. }) {
+                    continue;
+                }
+                let mut instr = translated.clone();
+                if code.blocks[bid].instructions[..iid]
+                    .iter()
+                    .any(|other| matches!(**other, Instruction::Call { .. }))
+                {
+                    if let Instruction::BinOp { flags, .. } = &mut instr {
+                        *flags = BinOpFlags::default();
+                    }
+                }
+                insertions.push(Insertion {
+                    bid: *bid,
+                    iid,
+                    pred: *pred,
+                    instr,
+                    leaders,
+                });
+            }
+        }
+
+        let mut replaces = HashMap::new();
+        for insertion in &insertions {
+            let dtype = code.blocks[&insertion.bid].instructions[insertion.iid].dtype();
+            let pred = code
+                .blocks
+                .get_mut(&insertion.pred)
+                .expect("predecessor must exist");
+            let inserted = Operand::register(
+                RegisterId::temp(insertion.pred, pred.instructions.len()),
+                dtype.clone(),
+            );
+            pred.instructions
+                .push(Named::new(None, insertion.instr.clone()));
+
+            let block = code
+                .blocks
+                .get_mut(&insertion.bid)
+                .expect("block must exist");
+            let phinode = Operand::register(
+                RegisterId::arg(insertion.bid, block.phinodes.len()),
+                dtype.clone(),
+            );
+            block.phinodes.push(Named::new(None, dtype.clone()));
+            for (bid, block) in &mut code.blocks {
+                let incoming = if *bid == insertion.pred {
+                    inserted.clone()
+                } else {
+                    insertion
+                        .leaders
+                        .get(bid)
+                        .cloned()
+                        .unwrap_or_else(|| Operand::constant(Constant::undef(dtype.clone())))
+                };
+                block.exit.walk_jump_args(|arg| {
+                    if arg.bid == insertion.bid {
+                        arg.args.push(incoming.clone());
+                    }
+                });
+            }
+            let _unused = replaces.insert(RegisterId::temp(insertion.bid, insertion.iid), phinode);
+        }
+
+        Replacer { replaces }.rewrite_function(code);
+        !insertions.is_empty()
+    }
+}
+
+/// Whether `instr` of the block `bid` may be inserted at the end of the predecessors of the block.
+fn is_candidate(instr: &Instruction, bid: BlockId, dominators: &Dominators) -> bool {
+    let is_available = |operand: &Operand| match operand {
+        Operand::Constant(_) => true,
+        Operand::Register { rid, .. } => match rid {
+            RegisterId::Local { .. } => true,
+            RegisterId::Arg { bid: def, .. } => {
+                *def == bid || dominators.strictly_dominates(*def, bid)
+            }
+            RegisterId::Temp { bid: def, .. } => dominators.strictly_dominates(*def, bid),
+        },
+    };
+    let may_insert = match instr {
+        Instruction::BinOp { op, rhs, .. } => !may_fail(op, rhs),
+        Instruction::UnaryOp { .. }
+        | Instruction::TypeCast { .. }
+        | Instruction::GetElementPtr { .. } => true,
+        _ => false,
+    };
+    may_insert && instr.operands().into_iter().all(is_available)
+}
+
+/// `instr` of the block `bid` as computed at the end of a predecessor jumping to the block with
+/// `args`, i.e., with the phinodes of the block replaced by the arguments.
+fn translate(instr: &Instruction, bid: BlockId, args: &[Operand]) -> Instruction {
+    let mut translated = instr.clone();
+    for operand in translated.operands_mut() {
+        if let Some((RegisterId::Arg { bid: def, aid }, _)) = operand.get_register() {
+            if *def == bid {
+                *operand = args[*aid].clone();
+            }
+        }
+    }
+    translated
+}
+
+/// The register computing `instr` at the end of `pred`, i.e., an instruction of `pred` or its
+/// dominators other than `bid` computing the same, if any.
+fn find(
+    code: &FunctionDefinition,
+    instr: &Instruction,
+    pred: BlockId,
+    bid: BlockId,
+    dominators: &Dominators,
+) -> Option<Operand> {
+    let swapped = match instr {
+        Instruction::BinOp {
+            op,
+            lhs,
+            rhs,
+            dtype,
+            flags,
+        } if is_commutative(op) => Some(Instruction::BinOp {
+            op: op.clone(),
+            lhs: rhs.clone(),
+            rhs: lhs.clone(),
+            dtype: dtype.clone(),
+            flags: *flags,
+        }),
+        _ => None,
+    };
+
+    dominators
+        .dominators(pred)
+        .filter(|dom| *dom != bid)
+        .find_map(|dom| {
+            let iid = code.blocks[&dom]
+                .instructions
+                .iter()
+                .position(|other| **other == *instr || Some(&**other) == swapped.as_ref())?;
+            Some(Operand::register(RegisterId::temp(dom, iid), instr.dtype()))
+        })
+}
+
+/// Replaces the uses of the partially redundant instructions with their phinodes.
+#[derive(Debug)]
+struct Replacer {
+    replaces: HashMap<RegisterId, Operand>,
+}
+
+impl IrRewriter for Replacer {
+    fn rewrite_operand(&mut self, operand: &mut Operand) {
+        let (rid, _) = some_or!(operand.get_register(), return);
+        if let Some(phinode) = self.replaces.get(rid) {
+            *operand = phinode.clone();
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use crate::ir::analysis::{Cfg, Dominators, Loop, LoopInfo};
+use crate::ir::*;
+use crate::opt::LoopUnrollInner;
+use crate::*;
+
+/// Unrolls the innermost loops that a profile finds hot, as many times as they iterate on
+/// average, as [`crate::opt::LoopUnroll`] does with a fixed factor.
+///
+/// The profile must be collected by running the code this pass optimizes, e.g., with
+/// `--profile-output` of the driver, since the loops are found by the ids of their blocks. A loop
+/// is hot if its header is entered at least `min_hits` times. Each time its latch is entered, it
+/// jumps back to the header, so the loop is entered about `header - latch` times and iterates
+/// about `header / (header - latch)` times on average, where `header` and `latch` are the numbers
+/// of times the blocks are entered. The factor of partial unrolling is the largest power of two not
+/// above the average number of iterations and `max_factor`, and the loop is left as is if it is
+/// less than two. The loops whose numbers of iterations are known are fully unrolled as
+/// [`crate::opt::LoopUnroll`] does, if they are hot.
+///
+/// The cold loops, including those never run by the profile, are left as they are, so that the
+/// code grows only where the time is spent. Since the profile counts the blocks of each function
+/// by its name, this pass works on translation units.
+#[derive(Debug, Clone)]
+pub struct ProfileGuidedUnroll {
+    pub profile: Profile,
+    /// The number of times the header of a hot loop is entered at least.
+    pub min_hits: usize,
+    /// The largest number of copies of the body of a partially unrolled loop.
+    pub max_factor: usize,
+    /// The largest number of phinodes and instructions of an unrolled loop.
+    pub budget: usize,
+}
+
+impl ProfileGuidedUnroll {
+    pub fn new(profile: Profile) -> Self {
+        Self {
+            profile,
+            min_hits: 64,
+            max_factor: 8,
+            budget: LoopUnrollInner::default().budget,
+        }
+    }
+
+    /// The factor of unrolling `lp` of `func_name`, if it is hot.
+    fn factor(&self, func_name: &str, lp: &Loop) -> Option<usize> {
+        let [latch] = lp.latches() else {
+            return None;
+        };
+        let header = self.profile.block(func_name, lp.header()).hits;
+        if header < self.min_hits {
+            return None;
+        }
+        let latch = self.profile.block(func_name, *latch).hits;
+        let entries = header.saturating_sub(latch).max(1);
+        let iterations = (header / entries).min(self.max_factor);
+        (iterations >= 2).then(|| 1 << iterations.ilog2())
+    }
+
+    fn unroll_function(&self, func_name: &str, code: &mut FunctionDefinition) -> bool {
+        // The factors are decided before any loop is unrolled, while the blocks still have the ids
+        // of the profile.
+        let cfg = Cfg::new(code);
+        let dominators = Dominators::new(&cfg);
+        let factors = LoopInfo::new(&cfg, &dominators)
+            .post_order()
+            .into_iter()
+            .filter(|lp| lp.children().is_empty())
+            .filter_map(|lp| Some((lp.header(), self.factor(func_name, lp)?)))
+            .collect::<Vec<_>>();
+
+        let mut unrolled = false;
+        for (header, factor) in factors {
+            let cfg = Cfg::new(code);
+            let dominators = Dominators::new(&cfg);
+            let loops = LoopInfo::new(&cfg, &dominators);
+            let lp = some_or!(loops.get(header), continue);
+            let inner = LoopUnrollInner {
+                factor,
+                budget: self.budget,
+            };
+            unrolled |= inner.unroll(code, lp, &cfg);
+        }
+        unrolled
+    }
+}
+
+impl Optimize<TranslationUnit> for ProfileGuidedUnroll {
+    fn optimize(&mut self, code: &mut TranslationUnit) -> bool {
+        code.decls
+            .iter_mut()
+            .filter_map(|(name, decl)| Some((name, decl.get_function_mut()?.1.as_mut()?)))
+            .map(|(name, definition)| self.unroll_function(name, definition))
+            .fold(false, |l, r| l | r)
+    }
+}
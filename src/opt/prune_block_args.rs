@@ -0,0 +1,92 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::ir::analysis::Cfg;
+use crate::ir::*;
+use crate::opt::opt_utils::remove_phinodes;
+use crate::opt::FunctionPass;
+use crate::*;
+
+/// Removes the redundant block arguments, i.e., the trivial phinodes, together with the jump
+/// arguments passed to them, repeating until no more phinodes are removed.
+///
+/// A phinode is trivial if it receives the same operand from every jump taken from a reachable
+/// block, ignoring the jumps passing the phinode itself, as the phinodes placed by
+/// [`crate::opt::Mem2reg`] for variables not changed in a loop do. Its uses are replaced with the
+/// operand, which is available wherever the phinode is used: every path to the phinode's block
+/// enters it first through a jump passing the operand, so the operand dominates the block. The
+/// remaining phinodes are renumbered. [`crate::opt::CopyProp`] removes the trivial phinodes as
+/// well, together with the other copies.
+pub type PruneBlockArgs = FunctionPass<Repeat<PruneBlockArgsInner>>;
+
+#[derive(Default, Clone, Copy, Debug)]
+pub struct PruneBlockArgsInner {}
+
+impl Optimize<FunctionDefinition> for PruneBlockArgsInner {
+    fn optimize(&mut self, code: &mut FunctionDefinition) -> bool {
+        let mut copies = Copies::default();
+        let phinodes = trivial_phinodes(code, &mut copies);
+        if phinodes.is_empty() {
+            return false;
+        }
+
+        copies.rewrite_function(code);
+        remove_phinodes(code, &phinodes);
+        true
+    }
+}
+
+/// The trivial phinodes of each block of `code`, whose sources are added to `copies`.
+pub(crate) fn trivial_phinodes(
+    code: &FunctionDefinition,
+    copies: &mut Copies,
+) -> HashMap<BlockId, HashSet<usize>> {
+    let cfg = Cfg::new(code);
+    let mut phinodes = HashMap::<BlockId, HashSet<usize>>::new();
+    for bid in cfg.reverse_post_order() {
+        if *bid == code.bid_init {
+            continue;
+        }
+        let block = &code.blocks[bid];
+        for aid in 0..block.phinodes.len() {
+            let rid = RegisterId::arg(*bid, aid);
+            let mut sources = cfg
+                .predecessors(*bid)
+                .iter()
+                .filter(|pred| cfg.is_reachable(**pred))
+                .flat_map(|pred| code.blocks[pred].exit.jump_args())
+                .filter(|arg| arg.bid == *bid)
+                .map(|arg| copies.resolve(&arg.args[aid]))
+                .filter(|source| source.get_register().map(|(rid, _)| *rid) != Some(rid));
+            let source = some_or!(sources.next(), continue);
+            if sources.any(|other| other != source) {
+                continue;
+            }
+            let _unused = copies.replaces.insert(rid, source);
+            let _unused = phinodes.entry(*bid).or_default().insert(aid);
+        }
+    }
+    phinodes
+}
+
+/// Replaces the uses of copies with their sources.
+#[derive(Default, Debug)]
+pub(crate) struct Copies {
+    pub(crate) replaces: HashMap<RegisterId, Operand>,
+}
+
+impl Copies {
+    /// The source of `operand`, following the copies found so far.
+    pub(crate) fn resolve(&self, operand: &Operand) -> Operand {
+        let mut operand = operand.clone();
+        while let Some((rid, _)) = operand.get_register() {
+            operand = some_or!(self.replaces.get(rid), break).clone();
+        }
+        operand
+    }
+}
+
+impl IrRewriter for Copies {
+    fn rewrite_operand(&mut self, operand: &mut Operand) {
+        *operand = self.resolve(operand);
+    }
+}
@@ -0,0 +1,270 @@
+use std::collections::{HashMap, HashSet};
+
+use lang_c::ast;
+
+use crate::ir::analysis::{Cfg, DefUse};
+use crate::ir::*;
+use crate::opt::compact_nop::CompactNopInner;
+use crate::opt::opt_utils::Renamer;
+use crate::opt::FunctionPass;
+use crate::*;
+
+/// Reassociates chains of associative and commutative integer operations into a canonical order.
+///
+/// The `+`, `*`, `&`, `|` and `^` instructions on integers of a block form trees, where an
+/// instruction is an inner node of the tree of the instruction using it if that is its only use
+/// and computes the same operation in the same block. The leaves of each tree are sorted by where
+/// they are defined, earlier ones first, and their constants are folded into one, which comes
+/// last. The tree is then rebuilt as a chain combining the leaves in that order, placed just
+/// before the instruction at its root.
+///
+/// Operations on the same leaves in any order thus become the same chain, whose common prefixes
+/// [`crate::opt::Gvn`] numbers the same, and the values defined outside of a loop are combined
+/// before those defined in it, so that [`crate::opt::Licm`] can hoist them. Integer arithmetic
+/// wraps around, so that any order computes the same value. The rebuilt instructions have no
+/// flags, since the intermediate values may differ. The inner nodes of the rebuilt trees are
+/// removed as [`crate::opt::CompactNop`] does.
+pub type Reassociate = FunctionPass<ReassociateInner>;
+
+#[derive(Default, Clone, Copy, Debug)]
+pub struct ReassociateInner {}
+
+impl Optimize<FunctionDefinition> for ReassociateInner {
+    fn optimize(&mut self, code: &mut FunctionDefinition) -> bool {
+        let cfg = Cfg::new(code);
+        let positions = cfg
+            .reverse_post_order()
+            .iter()
+            .enumerate()
+            .map(|(index, bid)| (*bid, index))
+            .collect::<HashMap<_, _>>();
+
+        let mut changed = false;
+        for bid in cfg.reverse_post_order() {
+            let def_use = DefUse::new(code);
+            let chains = chains(code, *bid, &def_use, &positions);
+            if chains.is_empty() {
+                continue;
+            }
+            rebuild(code, *bid, chains);
+            changed = true;
+        }
+
+        if changed {
+            let _unused = CompactNopInner::default().optimize(code);
+        }
+        changed
+    }
+}
+
+/// A tree rebuilt as a chain.
+#[derive(Debug)]
+struct Chain {
+    op: ast::BinaryOperator,
+    dtype: Dtype,
+    /// The leaves in the order they are combined.
+    leaves: Vec<Operand>,
+    /// The inner nodes of the tree other than the root.
+    inner: Vec<usize>,
+}
+
+fn is_reassociable(op: &ast::BinaryOperator) -> bool {
+    matches!(
+        op,
+        ast::BinaryOperator::Plus
+            | ast::BinaryOperator::Multiply
+            | ast::BinaryOperator::BitwiseAnd
+            | ast::BinaryOperator::BitwiseOr
+            | ast::BinaryOperator::BitwiseXor
+    )
+}
+
+/// The trees of the block `bid` not in canonical order, by their roots.
+fn chains(
+    code: &FunctionDefinition,
+    bid: BlockId,
+    def_use: &DefUse,
+    positions: &HashMap<BlockId, usize>,
+) -> HashMap<usize, Chain> {
+    let block = &code.blocks[&bid];
+    let operation = |iid: usize| match &*block.instructions[iid] {
+        Instruction::BinOp {
+            op,
+            lhs,
+            rhs,
+            dtype,
+            ..
+        } if is_reassociable(op) && matches!(dtype, Dtype::Int { .. }) => {
+            Some((op, lhs, rhs, dtype))
+        }
+        _ => None,
+    };
+
+    // The instructions that are inner nodes of the trees of their only uses.
+    let mut inner = HashSet::new();
+    for iid in 0..block.instructions.len() {
+        let (op, lhs, rhs, dtype) = some_or!(operation(iid), continue);
+        for operand in [lhs, rhs] {
+            let Some((
+                RegisterId::Temp {
+                    bid: def_bid,
+                    iid: def_iid,
+                },
+                _,
+            )) = operand.get_register()
+            else {
+                continue;
+            };
+            if *def_bid != bid || def_use.uses(RegisterId::temp(bid, *def_iid)).len() != 1 {
+                continue;
+            }
+            if operation(*def_iid)
+                .is_some_and(|(def_op, _, _, def_dtype)| def_op == op && def_dtype == dtype)
+            {
+                let _unused = inner.insert(*def_iid);
+            }
+        }
+    }
+
+    // Registers defined earlier come first, and constants last.
+    let rank = |operand: &Operand| match operand {
+        Operand::Constant(_) => None,
+        Operand::Register { rid, .. } => Some(match rid {
+            RegisterId::Local { aid } => (0, 0, *aid),
+            RegisterId::Arg { bid, aid } => (positions.get(bid).map_or(0, |p| p + 1), 0, *aid),
+            RegisterId::Temp { bid, iid } => (positions.get(bid).map_or(0, |p| p + 1), 1, *iid),
+        }),
+    };
+
+    let mut chains = HashMap::new();
+    for root in 0..block.instructions.len() {
+        if inner.contains(&root) {
+            continue;
+        }
+        let (op, lhs, rhs, dtype) = some_or!(operation(root), continue);
+
+        // The leaves in the order of the tree, and whether the tree is a chain already.
+        let mut leaves = Vec::new();
+        let mut nodes = Vec::new();
+        let mut is_chain = true;
+        let mut stack = vec![(rhs, true), (lhs, false)];
+        while let Some((operand, is_rhs)) = stack.pop() {
+            let def_iid = match operand.get_register() {
+                Some((RegisterId::Temp { bid: def_bid, iid }, _))
+                    if *def_bid == bid && inner.contains(iid) =>
+                {
+                    *iid
+                }
+                _ => {
+                    leaves.push(operand.clone());
+                    continue;
+                }
+            };
+            let (_, lhs, rhs, _) = operation(def_iid).expect("inner node must be an operation");
+            nodes.push(def_iid);
+            is_chain &= !is_rhs;
+            stack.push((rhs, true));
+            stack.push((lhs, false));
+        }
+
+        let (mut sorted, constants) = leaves
+            .iter()
+            .cloned()
+            .partition::<Vec<_>, _>(|leaf| rank(leaf).is_some());
+        if sorted.is_empty() {
+            continue;
+        }
+        sorted.sort_by_key(rank);
+        if let Some((first, rest)) = constants.split_first() {
+            let constant = rest.iter().try_fold(
+                first
+                    .get_constant()
+                    .expect("leaf must be a constant")
+                    .clone(),
+                |acc, constant| const_eval::binary(op, &acc, constant.get_constant()?).ok(),
+            );
+            sorted.push(Operand::constant(some_or!(constant, continue)));
+        }
+        if sorted.len() < 2 || (is_chain && sorted == leaves) {
+            continue;
+        }
+
+        let _unused = chains.insert(
+            root,
+            Chain {
+                op: op.clone(),
+                dtype: dtype.clone(),
+                leaves: sorted,
+                inner: nodes,
+            },
+        );
+    }
+
+    chains
+}
+
+/// Rebuilds the trees of the block `bid` at their roots as `chains`.
+fn rebuild(code: &mut FunctionDefinition, bid: BlockId, mut chains: HashMap<usize, Chain>) {
+    // Each chain takes as many instructions as it has leaves but one, the last of which replaces
+    // its root, so that the instructions after it move.
+    let mut renames = HashMap::new();
+    let mut shift = 0;
+    for iid in 0..code.blocks[&bid].instructions.len() {
+        if let Some(chain) = chains.get(&iid) {
+            shift += chain.leaves.len() - 2;
+        }
+        let _unused = renames.insert(
+            RegisterId::temp(bid, iid),
+            RegisterId::temp(bid, iid + shift),
+        );
+    }
+    let mut renamer = Renamer { renames };
+    renamer.rewrite_function(code);
+    for chain in chains.values_mut() {
+        for leaf in &mut chain.leaves {
+            renamer.rewrite_operand(leaf);
+        }
+    }
+
+    let block = code.blocks.get_mut(&bid).expect("block must exist");
+    for chain in chains.values() {
+        for iid in &chain.inner {
+            *block.instructions[*iid] = Instruction::Nop;
+        }
+    }
+
+    let mut instructions = Vec::new();
+    for (iid, instr) in std::mem::take(&mut block.instructions)
+        .into_iter()
+        .enumerate()
+    {
+        let chain = some_or!(chains.remove(&iid), {
+            instructions.push(instr);
+            continue;
+        });
+        let name = instr.name().cloned();
+        let mut leaves = chain.leaves.into_iter();
+        let mut acc = leaves.next().expect("chain must have leaves");
+        let count = leaves.len();
+        for (index, leaf) in leaves.enumerate() {
+            let instr = Instruction::BinOp {
+                op: chain.op.clone(),
+                lhs: acc,
+                rhs: leaf,
+                dtype: chain.dtype.clone(),
+                flags: BinOpFlags::default(),
+            };
+            let name = if index + 1 == count {
+                name.clone()
+            } else {
+                None
+            };
+            acc = Operand::register(
+                RegisterId::temp(bid, instructions.len()),
+                chain.dtype.clone(),
+            );
+            instructions.push(Named::new(name, instr));
+        }
+    }
+    block.instructions = instructions;
+}
@@ -0,0 +1,44 @@
+use crate::ir::*;
+use crate::opt::const_prop::ConstantValues;
+use crate::opt::FunctionPass;
+use crate::*;
+
+/// Sparse conditional constant propagation, resolving the branches it decides.
+///
+/// Constants are found and propagated as [`crate::opt::ConstProp`] does, tracking which jumps may
+/// be taken. A conditional jump or a switch of an executed block that may take only one of its
+/// jumps is then replaced with that jump, so that the blocks only reachable through the other
+/// jumps become unreachable. Removing them is left to [`crate::opt::SimplifyCfg`].
+pub type Sccp = FunctionPass<SccpInner>;
+
+#[derive(Default, Clone, Copy, Debug)]
+pub struct SccpInner {}
+
+impl Optimize<FunctionDefinition> for SccpInner {
+    fn optimize(&mut self, code: &mut FunctionDefinition) -> bool {
+        let values = ConstantValues::new(code);
+
+        let mut resolved = false;
+        for (bid, block) in &mut code.blocks {
+            if !values.is_executed(*bid)
+                || !matches!(
+                    block.exit,
+                    BlockExit::ConditionalJump { .. } | BlockExit::Switch { .. }
+                )
+            {
+                continue;
+            }
+            let taken = values.taken_jumps(&block.exit);
+            let [arg] = taken.as_slice() else {
+                continue;
+            };
+            block.exit = BlockExit::Jump {
+                arg: (*arg).clone(),
+            };
+            resolved = true;
+        }
+
+        let replaced = values.replace_constants(code);
+        resolved || replaced
+    }
+}
@@ -0,0 +1,151 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::ir::analysis::{AliasAnalysis, MemoryBase};
+use crate::ir::*;
+use crate::*;
+
+/// Scalar replacement of aggregates: splits the local structs and arrays whose elements are
+/// accessed only at constant offsets into allocations of their own, which
+/// [`crate::opt::Mem2reg`] may then promote into registers.
+///
+/// A struct or array allocation is split if it does not escape, and every load and store through
+/// a pointer derived from it is non-volatile, accesses an integer, float or pointer, and has a
+/// known offset, e.g., after [`crate::opt::Sccp`] folds the offsets of array indices. The
+/// accesses at each offset must be of the same dtype, and must not overlap the accesses at other
+/// offsets or lie outside of the allocation. Each offset accessed then gets a new allocation of
+/// that dtype, named after the offset, and the loads and stores access it directly.
+///
+/// The aggregate and the pointers derived from it are left to [`crate::opt::Deadcode`]. Like
+/// [`crate::opt::DeadStoreElimination`], this pass works on translation units, since the alias
+/// analysis needs the layouts of their structs.
+#[derive(Default, Clone, Copy, Debug)]
+pub struct Sroa {}
+
+impl Optimize<TranslationUnit> for Sroa {
+    fn optimize(&mut self, code: &mut TranslationUnit) -> bool {
+        let structs = &code.structs;
+        code.decls
+            .values_mut()
+            .filter_map(|decl| decl.get_function_mut()?.1.as_mut())
+            .map(|definition| split_aggregates(definition, structs))
+            .fold(false, |l, r| l | r)
+    }
+}
+
+fn split_aggregates(
+    code: &mut FunctionDefinition,
+    structs: &HashMap<String, Option<Dtype>>,
+) -> bool {
+    let alias = AliasAnalysis::new(code, structs);
+
+    // The dtypes accessed at each offset of the aggregates, and the accesses of each.
+    let mut slots = BTreeMap::<usize, BTreeMap<isize, Dtype>>::new();
+    let mut accesses = Vec::new();
+    let mut unsplittable = HashSet::new();
+    for (bid, block) in &code.blocks {
+        for (iid, instr) in block.instructions.iter().enumerate() {
+            let (ptr, volatile) = match &**instr {
+                Instruction::Load { ptr, volatile } | Instruction::Store { ptr, volatile, .. } => {
+                    (ptr, *volatile)
+                }
+                _ => continue,
+            };
+            let location = alias.location(ptr);
+            let MemoryBase::Local(aid) = location.base else {
+                continue;
+            };
+            let dtype = ptr
+                .dtype()
+                .get_pointer_inner()
+                .expect("address must be a pointer")
+                .clone()
+                .unqualified();
+            let offset = match location.offset {
+                Some(offset)
+                    if !volatile
+                        && matches!(
+                            dtype,
+                            Dtype::Int { .. } | Dtype::Float { .. } | Dtype::Pointer { .. }
+                        ) =>
+                {
+                    offset
+                }
+                _ => {
+                    let _unused = unsplittable.insert(aid);
+                    continue;
+                }
+            };
+            let accessed = slots
+                .entry(aid)
+                .or_default()
+                .entry(offset)
+                .or_insert_with(|| dtype.clone());
+            if *accessed != dtype {
+                let _unused = unsplittable.insert(aid);
+            }
+            accesses.push((*bid, iid, aid, offset));
+        }
+    }
+
+    let allocations = &code.allocations;
+    slots.retain(|aid, slots| {
+        !unsplittable.contains(aid)
+            && !alias.is_escaped(*aid)
+            && matches!(
+                *allocations[*aid],
+                Dtype::Struct { .. } | Dtype::Array { .. }
+            )
+            && fits(&allocations[*aid], slots, structs)
+    });
+    if slots.is_empty() {
+        return false;
+    }
+
+    // The pointers to the new allocation of each offset accessed.
+    let mut replaces = HashMap::new();
+    for (aid, slots) in &slots {
+        let name = code.allocations[*aid].name().cloned();
+        for (offset, dtype) in slots {
+            let ptr = Operand::register(
+                RegisterId::local(code.allocations.len()),
+                Dtype::pointer(dtype.clone()),
+            );
+            code.allocations.push(Named::new(
+                name.as_ref().map(|name| format!("{name}_{offset}")),
+                dtype.clone(),
+            ));
+            let _unused = replaces.insert((*aid, *offset), ptr);
+        }
+    }
+
+    for (bid, iid, aid, offset) in accesses {
+        let replace = some_or!(replaces.get(&(aid, offset)), continue);
+        let block = code.blocks.get_mut(&bid).expect("block must exist");
+        match &mut *block.instructions[iid] {
+            Instruction::Load { ptr, .. } | Instruction::Store { ptr, .. } => {
+                *ptr = replace.clone();
+            }
+            _ => unreachable!("only loads and stores access the aggregates"),
+        }
+    }
+    true
+}
+
+/// Whether the accesses of `slots`, each at its offset with its dtype, lie within `aggregate`
+/// without overlapping each other.
+fn fits(
+    aggregate: &Dtype,
+    slots: &BTreeMap<isize, Dtype>,
+    structs: &HashMap<String, Option<Dtype>>,
+) -> bool {
+    let (size, _) = ok_or!(aggregate.size_align_of(structs), return false);
+    let mut end = 0;
+    for (offset, dtype) in slots {
+        let (slot_size, _) = ok_or!(dtype.size_align_of(structs), return false);
+        if *offset < end {
+            return false;
+        }
+        end = offset + slot_size as isize;
+    }
+    end <= size as isize
+}
@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+
+use crate::ir::analysis::{AliasAnalysis, Cfg, Dominators, MemoryBase};
+use crate::ir::*;
+use crate::opt::compact_nop::CompactNopInner;
+use crate::opt::prune_block_args::Copies;
+use crate::*;
+
+/// Forwards the values stored to memory to the loads reading them back, removing the loads.
+///
+/// A non-volatile load reads the value of a store if the store writes to the same address with a
+/// value of the same dtype, i.e., the address is the same operand or the alias analysis finds the
+/// same offset into the same object, and no instruction that may write to the memory read, i.e.,
+/// an aliasing store or a call to which the memory is exposed, may run in between. The store is
+/// searched backwards from the load in its block and then in its dominators, in which case every
+/// block on a path from the dominator to the load must not write to the memory either. These are
+/// the blocks strictly dominated by the dominator from which the load's block is reachable, and
+/// the whole block of the load if it is in a loop.
+///
+/// The uses of the loads are replaced with the stored values, and the loads are removed as
+/// [`crate::opt::CompactNop`] does. The stores are left to
+/// [`crate::opt::DeadStoreElimination`]. Like it, this pass works on translation units, since the
+/// alias analysis needs the layouts of their structs.
+#[derive(Default, Clone, Copy, Debug)]
+pub struct StoreForwarding {}
+
+impl Optimize<TranslationUnit> for StoreForwarding {
+    fn optimize(&mut self, code: &mut TranslationUnit) -> bool {
+        let structs = &code.structs;
+        code.decls
+            .values_mut()
+            .filter_map(|decl| decl.get_function_mut()?.1.as_mut())
+            .map(|definition| forward_stores(definition, structs))
+            .fold(false, |l, r| l | r)
+    }
+}
+
+fn forward_stores(code: &mut FunctionDefinition, structs: &HashMap<String, Option<Dtype>>) -> bool {
+    let cfg = Cfg::new(code);
+    let dominators = Dominators::new(&cfg);
+    let alias = AliasAnalysis::new(code, structs);
+    let forwarder = Forwarder {
+        code,
+        cfg: &cfg,
+        dominators: &dominators,
+        alias: &alias,
+    };
+
+    let mut copies = Copies::default();
+    for bid in cfg.reverse_post_order() {
+        // Whether the block may run again after a load in it before reaching the load again.
+        let in_loop = cfg
+            .successors(*bid)
+            .iter()
+            .any(|succ| cfg.reachable_from(*succ).contains(bid));
+        for (iid, instr) in code.blocks[bid].instructions.iter().enumerate() {
+            if !matches!(
+                &**instr,
+                Instruction::Load {
+                    volatile: false,
+                    ..
+                }
+            ) {
+                continue;
+            }
+            let value = some_or!(forwarder.stored_value(instr, *bid, iid, in_loop), continue);
+            let _unused = copies.replaces.insert(RegisterId::temp(*bid, iid), value);
+        }
+    }
+    if copies.replaces.is_empty() {
+        return false;
+    }
+
+    for rid in copies.replaces.keys() {
+        let RegisterId::Temp { bid, iid } = rid else {
+            unreachable!("only loads are forwarded")
+        };
+        let block = code.blocks.get_mut(bid).expect("block must exist");
+        *block.instructions[*iid] = Instruction::Nop;
+    }
+    copies.rewrite_function(code);
+    let _unused = CompactNopInner::default().optimize(code);
+    true
+}
+
+/// Finds the stores whose values loads read.
+#[derive(Debug)]
+struct Forwarder<'a> {
+    code: &'a FunctionDefinition,
+    cfg: &'a Cfg,
+    dominators: &'a Dominators,
+    alias: &'a AliasAnalysis<'a>,
+}
+
+impl Forwarder<'_> {
+    /// The value of the store that `load`, the instruction `iid` of the block `bid`, reads, if
+    /// any. The block is `in_loop` if it may run again after the load before reaching the load.
+    fn stored_value(
+        &self,
+        load: &Instruction,
+        bid: BlockId,
+        iid: usize,
+        in_loop: bool,
+    ) -> Option<Operand> {
+        let Instruction::Load { ptr, .. } = load else {
+            unreachable!("only loads are forwarded")
+        };
+        let location = self.alias.location(ptr);
+        let must_alias = |other: &Operand| {
+            other == ptr
+                || (location.base != MemoryBase::Unknown
+                    && location.offset.is_some()
+                    && self.alias.location(other) == location)
+        };
+
+        let mut instructions = &self.code.blocks[&bid].instructions[..iid];
+        let mut current = bid;
+        loop {
+            for instr in instructions.iter().rev() {
+                if let Instruction::Store {
+                    ptr: other,
+                    value,
+                    volatile: false,
+                } = &**instr
+                {
+                    if must_alias(other) && value.dtype() == load.dtype() {
+                        return Some(value.clone());
+                    }
+                }
+                if self.alias.may_conflict(instr, load) {
+                    return None;
+                }
+            }
+
+            let idom = self.dominators.idom(current)?;
+            // The blocks that may run between the end of `idom` and the load.
+            let writes = self
+                .cfg
+                .blocks()
+                .filter(|other| {
+                    self.dominators.strictly_dominates(idom, *other)
+                        && (*other != bid || in_loop)
+                        && self.cfg.reachable_from(*other).contains(&bid)
+                })
+                .flat_map(|other| &self.code.blocks[&other].instructions)
+                .any(|instr| self.alias.may_conflict(instr, load));
+            if writes {
+                return None;
+            }
+
+            instructions = &self.code.blocks[&idom].instructions;
+            current = idom;
+        }
+    }
+}
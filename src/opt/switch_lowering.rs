@@ -0,0 +1,193 @@
+use lang_c::ast;
+
+use crate::ir::*;
+use crate::opt::opt_utils::new_bid;
+use crate::opt::FunctionPass;
+use crate::*;
+
+/// Lowers the switches into jump tables, balanced comparison trees or linear comparison chains,
+/// depending on the number of their cases and how densely the cases cover their range.
+///
+/// A switch with at least `min_table_cases` cases, covering at least `min_table_density` percent
+/// of the values between its smallest and largest cases, is kept as a switch, which the backend
+/// lowers into a jump table, since the IR has no indirect jumps. A switch with at most
+/// `max_linear` cases becomes a chain of blocks, each comparing the value to a case and jumping
+/// to it if they are equal, or to the next block otherwise; the last one jumps to the default
+/// instead. Any other switch becomes a balanced tree: the cases are sorted, as signed or unsigned
+/// integers following the dtype of the value, and split at the middle case, the value less than
+/// which goes to the lower half. Each half is lowered in turn, so that the dense halves of a
+/// sparse switch are kept as switches.
+///
+/// The first comparison is appended to the block of the switch, and the others get new blocks.
+/// A switch without cases becomes a jump to its default, and switches on constants are left to
+/// [`crate::opt::BranchFold`].
+pub type SwitchLowering = FunctionPass<SwitchLoweringInner>;
+
+#[derive(Clone, Copy, Debug)]
+pub struct SwitchLoweringInner {
+    /// The largest number of cases lowered into a linear chain.
+    pub max_linear: usize,
+    /// The smallest number of cases kept as a jump table.
+    pub min_table_cases: usize,
+    /// The smallest percentage of the range of the cases they cover to be kept as a jump table.
+    pub min_table_density: usize,
+}
+
+impl Default for SwitchLoweringInner {
+    fn default() -> Self {
+        Self {
+            max_linear: 3,
+            min_table_cases: 4,
+            min_table_density: 40,
+        }
+    }
+}
+
+impl Optimize<FunctionDefinition> for SwitchLoweringInner {
+    fn optimize(&mut self, code: &mut FunctionDefinition) -> bool {
+        let mut lowered = false;
+        let bids = code.blocks.keys().copied().collect::<Vec<_>>();
+        for bid in bids {
+            let BlockExit::Switch {
+                value,
+                default,
+                cases,
+            } = &code.blocks[&bid].exit
+            else {
+                continue;
+            };
+            if value.get_constant().is_some() {
+                continue;
+            }
+            let dtype = value.dtype();
+            if dtype.get_int_width().is_none() {
+                continue;
+            }
+            let is_signed = dtype.is_int_signed();
+            let mut keyed = Vec::new();
+            for (case, arg) in cases {
+                let (case_value, width, _) = some_or!(case.get_int(), break);
+                let key = if is_signed {
+                    sign_extension(case_value, width as u128) as i128
+                } else {
+                    case_value as i128
+                };
+                keyed.push((key, case.clone(), arg.clone()));
+            }
+            if keyed.len() != cases.len() || (!keyed.is_empty() && self.is_dense(&keyed)) {
+                continue;
+            }
+            keyed.sort_by_key(|(key, _, _)| *key);
+
+            let (value, default) = (value.clone(), default.clone());
+            self.lower(code, bid, &value, &default, &keyed);
+            lowered = true;
+        }
+        lowered
+    }
+}
+
+impl SwitchLoweringInner {
+    /// Whether `cases`, sorted by their keys, are to be kept as a jump table.
+    fn is_dense(&self, cases: &[(i128, Constant, JumpArg)]) -> bool {
+        let (Some((min, _, _)), Some((max, _, _))) = (cases.first(), cases.last()) else {
+            return false;
+        };
+        let range = max.abs_diff(*min).saturating_add(1);
+        cases.len() >= self.min_table_cases
+            && (cases.len() as u128).saturating_mul(100)
+                >= range.saturating_mul(self.min_table_density as u128)
+    }
+
+    /// Replaces the exit of the block `bid` with the lowering of a switch on `value` over
+    /// `cases`, sorted by their keys, appending its first comparison to the block.
+    fn lower(
+        &self,
+        code: &mut FunctionDefinition,
+        bid: BlockId,
+        value: &Operand,
+        default: &JumpArg,
+        cases: &[(i128, Constant, JumpArg)],
+    ) {
+        let exit = if cases.is_empty() {
+            BlockExit::Jump {
+                arg: default.clone(),
+            }
+        } else if cases.len() >= self.min_table_cases && self.is_dense(cases) {
+            BlockExit::Switch {
+                value: value.clone(),
+                default: default.clone(),
+                cases: cases
+                    .iter()
+                    .map(|(_, case, arg)| (case.clone(), arg.clone()))
+                    .collect(),
+            }
+        } else if cases.len() <= self.max_linear {
+            let ((_, case, arg), rest) = cases.split_first().expect("cases must exist");
+            let condition = compare(code, bid, ast::BinaryOperator::Equals, value, case);
+            let arg_else = if rest.is_empty() {
+                default.clone()
+            } else {
+                let next = new_block(code);
+                self.lower(code, next, value, default, rest);
+                JumpArg::new(next, Vec::new())
+            };
+            BlockExit::ConditionalJump {
+                condition,
+                arg_then: arg.clone(),
+                arg_else,
+            }
+        } else {
+            let (lower, upper) = cases.split_at(cases.len() / 2);
+            let (_, pivot, _) = &upper[0];
+            let condition = compare(code, bid, ast::BinaryOperator::Less, value, pivot);
+            let then = new_block(code);
+            self.lower(code, then, value, default, lower);
+            let r#else = new_block(code);
+            self.lower(code, r#else, value, default, upper);
+            BlockExit::ConditionalJump {
+                condition,
+                arg_then: JumpArg::new(then, Vec::new()),
+                arg_else: JumpArg::new(r#else, Vec::new()),
+            }
+        };
+        code.blocks.get_mut(&bid).expect("block must exist").exit = exit;
+    }
+}
+
+/// Appends the comparison `op` of `value` with `case` to the block `bid`, returning its result.
+fn compare(
+    code: &mut FunctionDefinition,
+    bid: BlockId,
+    op: ast::BinaryOperator,
+    value: &Operand,
+    case: &Constant,
+) -> Operand {
+    let block = code.blocks.get_mut(&bid).expect("block must exist");
+    let rid = RegisterId::temp(bid, block.instructions.len());
+    block.instructions.push(Named::new(
+        None,
+        Instruction::BinOp {
+            op,
+            lhs: value.clone(),
+            rhs: Operand::constant(case.clone()),
+            dtype: Dtype::BOOL,
+            flags: BinOpFlags::default(),
+        },
+    ));
+    Operand::register(rid, Dtype::BOOL)
+}
+
+/// Adds an empty block to `code`, whose exit is set when it is lowered, returning its id.
+fn new_block(code: &mut FunctionDefinition) -> BlockId {
+    let bid = new_bid(code);
+    let _unused = code.blocks.insert(
+        bid,
+        Block {
+            phinodes: Vec::new(),
+            instructions: Vec::new(),
+            exit: BlockExit::Unreachable,
+        },
+    );
+    bid
+}
@@ -0,0 +1,18 @@
+use crate::ir::*;
+use crate::opt::FunctionPass;
+use crate::*;
+
+/// Removes the blocks unreachable from the entry block, as [`crate::ir::remove_unreachable_blocks`]
+/// does. Unlike [`crate::opt::SimplifyCfg`], the remaining blocks are left as they are, so the
+/// pass may clean up the output of irgen before the interpreter or the verifier without changing
+/// anything else.
+pub type UnreachableBlockElimination = FunctionPass<UnreachableBlockEliminationInner>;
+
+#[derive(Default, Clone, Copy, Debug)]
+pub struct UnreachableBlockEliminationInner {}
+
+impl Optimize<FunctionDefinition> for UnreachableBlockEliminationInner {
+    fn optimize(&mut self, code: &mut FunctionDefinition) -> bool {
+        !remove_unreachable_blocks(code).is_empty()
+    }
+}
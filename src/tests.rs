@@ -44,14 +44,10 @@ fn ast_initializer(number: i32) -> ast::Initializer {
 fn modify_ir(unit: &mut ir::TranslationUnit, rand_num: i32) {
     for (name, decl) in &mut unit.decls {
         if name == NONCE_NAME {
-            let (dtype, _) = decl.get_variable().expect("`decl` must be variable");
-            let initializer = ast_initializer(rand_num);
-            let new_decl = ir::Declaration::Variable {
-                dtype: dtype.clone(),
-                initializer: Some(initializer),
+            let ir::Declaration::Variable { initializer, .. } = decl else {
+                panic!("`decl` must be variable");
             };
-
-            *decl = new_decl;
+            *initializer = Some(ast_initializer(rand_num));
         }
     }
 }
@@ -105,6 +101,7 @@ pub fn test_irgen(path: &Path) {
     let mut ir = Irgen::default()
         .translate(&unit)
         .unwrap_or_else(|irgen_error| panic!("{}", irgen_error));
+    ir::verify(&ir).unwrap_or_else(|verify_error| panic!("{}", verify_error));
 
     let rand_num = rand::thread_rng().gen_range(1..100);
     let new_c = modify_c(path, rand_num);
@@ -251,6 +248,22 @@ fn test_irparse_for_optimized_ir<O: Optimize<ir::TranslationUnit>>(
     optimized_ir
 }
 
+/// Tests that printing the IR and parsing it back yields the same IR.
+pub fn test_ir_roundtrip(path: &Path) {
+    // Check if the file has .ir extension
+    assert_eq!(path.extension(), Some(std::ffi::OsStr::new("ir")));
+    let ir = ir::Parse::default()
+        .translate(&path)
+        .unwrap_or_else(|_| panic!("parse failed {}", path.display()));
+
+    let printed = ir.to_string();
+    let reparsed = printed
+        .parse::<ir::TranslationUnit>()
+        .expect("parse failed while parsing the output from the printer");
+    assert_eq!(ir, reparsed);
+    assert_eq!(printed, reparsed.to_string());
+}
+
 /// Tests optimizations.
 pub fn test_opt<P1: AsRef<Path>, P2: AsRef<Path>, O: Optimize<ir::TranslationUnit>>(
     from: &P1,
@@ -284,10 +297,35 @@ pub fn test_opt<P1: AsRef<Path>, P2: AsRef<Path>, O: Optimize<ir::TranslationUni
             .write_fmt(format_args!("\n[after opt (expected)]"))
             .unwrap();
         write(&to, &mut stderr()).unwrap();
+        stderr()
+            .lock()
+            .write_fmt(format_args!(
+                "\n[after opt (diff from expected)]\n{}",
+                ir::diff(&to, &ir)
+            ))
+            .unwrap();
         panic!("[test_opt]");
     }
 }
 
+/// Tests the canonicalization, which must produce exactly the expected IR, not just an
+/// equivalent one, and leave it as it is when run again.
+pub fn test_canonicalize<P1: AsRef<Path>, P2: AsRef<Path>>(from: &P1, to: &P2) {
+    let mut ir = ir::Parse::default()
+        .translate(from)
+        .expect("parse failed while parsing the output from implemented printer");
+    let to = ir::Parse::default()
+        .translate(to)
+        .expect("parse failed while parsing the output from implemented printer");
+
+    let _ = Canonicalize::default().optimize(&mut ir);
+    assert_eq!(ir.to_string(), to.to_string(), "[test_canonicalize]");
+    assert!(
+        !Canonicalize::default().optimize(&mut ir),
+        "[test_canonicalize] canonical IR is changed again"
+    );
+}
+
 /// Tests asmgen.
 pub fn test_asmgen(path: &Path) {
     // Check if the file has .ir extension
@@ -453,7 +491,7 @@ pub fn test_end_to_end(path: &Path) {
     let mut ir = Irgen::default()
         .translate(&unit)
         .unwrap_or_else(|irgen_error| panic!("{}", irgen_error));
-    let _ = O1::default().optimize(&mut ir);
+    let _ = pipeline(OptLevel::O1).optimize(&mut ir);
     let args = Vec::new();
     let result = ir::interp(&ir, args).unwrap_or_else(|interp_error| panic!("{}", interp_error));
     // We only allow a main function whose return type is `int`
@@ -1,3 +1,4 @@
+use std::collections::{BTreeSet, HashSet};
 use std::ffi::OsStr;
 use std::path::Path;
 
@@ -46,6 +47,79 @@ fn test_opt_between_dirs<O: Optimize<ir::TranslationUnit>>(from: &Path, to: &Pat
     }
 }
 
+fn parse_ir(path: &str) -> ir::TranslationUnit {
+    ir::Parse::default()
+        .translate(&Path::new(path))
+        .unwrap_or_else(|_| panic!("parse failed {path}"))
+}
+
+fn irgen(path: &str) -> ir::TranslationUnit {
+    let unit = ParseSource
+        .translate(&Path::new(path))
+        .unwrap_or_else(|_| panic!("parse failed {path}"));
+    Irgen::default()
+        .translate(&unit)
+        .unwrap_or_else(|irgen_error| panic!("{irgen_error}"))
+}
+
+/// The message of the error translating the C source `source` into IR.
+fn irgen_error(source: &str) -> String {
+    let temp_dir = tempfile::tempdir().expect("temp dir creation failed");
+    let path = temp_dir.path().join("source.c");
+    std::fs::write(&path, source).expect("writing the source failed");
+    let unit = ParseSource
+        .translate(&path.as_path())
+        .unwrap_or_else(|_| panic!("parse failed {source}"));
+    Irgen::default()
+        .translate(&unit)
+        .expect_err("irgen must fail")
+        .message
+        .to_string()
+}
+
+fn assert_returns(result: Result<ir::Value, ir::InterpreterError>, expected: i32) {
+    let result = result.unwrap_or_else(|interp_error| panic!("{}", interp_error));
+    let (value, width, is_signed) = result.get_int().expect("non-integer value occurs");
+    assert_eq!(width, 32);
+    assert!(is_signed);
+    assert_eq!(value as i32, expected);
+}
+
+/// Runs the `main` of `ir` by `interpreter`, passing it the `int` argument `arg`.
+fn run_i32(
+    interpreter: &mut ir::Interpreter,
+    ir: &ir::TranslationUnit,
+    arg: i32,
+) -> Result<ir::Value, ir::InterpreterError> {
+    let arg = ir::Value::Int {
+        value: arg as u128,
+        width: 32,
+        is_signed: true,
+    };
+    interpreter.run(ir, vec![arg])
+}
+
+fn function_of<'a>(ir: &'a ir::TranslationUnit, name: &str) -> &'a ir::FunctionDefinition {
+    ir.decls
+        .get(name)
+        .and_then(|decl| decl.get_function()?.1.as_ref())
+        .unwrap_or_else(|| panic!("no definition of {name}"))
+}
+
+/// Replaces the uses of a register with a constant.
+struct Replace {
+    rid: ir::RegisterId,
+    value: ir::Constant,
+}
+
+impl ir::IrRewriter for Replace {
+    fn rewrite_operand(&mut self, operand: &mut ir::Operand) {
+        if operand.get_register().map(|(rid, _)| rid) == Some(&self.rid) {
+            *operand = ir::Operand::constant(self.value.clone());
+        }
+    }
+}
+
 const IRGEN_SMALL_TEST_IGNORE_LIST: [&str; 12] = [
     "examples/c/array.c",
     "examples/c/array2.c",
@@ -114,6 +188,204 @@ fn test_examples_irparse() {
     test_dir(Path::new("examples/c"), OsStr::new("c"), test_irparse);
 }
 
+#[test]
+fn test_examples_irparse_duplicate() {
+    // A name declared twice is a parse error rather than a panic.
+    let add = std::fs::read_to_string("examples/link/add.ir").expect("reading the IR failed");
+    assert!(format!("{add}\n{add}")
+        .parse::<ir::TranslationUnit>()
+        .is_err());
+
+    let structs = "struct %t0 : { a:i32 }\nstruct %t0 : { b:i64 }\n";
+    assert!(format!("{structs}\n{add}")
+        .parse::<ir::TranslationUnit>()
+        .is_err());
+}
+
+#[test]
+fn test_examples_irgen_cond_speculate() {
+    // Neither the overflowing `x + 1` nor the volatile read of `v` are evaluated if their arm is
+    // not taken, so no `select` is used.
+    let ir = irgen("examples/c/cond_speculate.c");
+    assert!(!ir.to_string().contains("select"));
+    assert_returns(
+        ir::Interpreter::new().flag_check(true).run(&ir, Vec::new()),
+        7,
+    );
+}
+
+#[test]
+fn test_examples_irgen_address_constant() {
+    let ir = irgen("examples/c/address_constant.c");
+    assert_returns(ir::Interpreter::new().run(&ir, Vec::new()), 1);
+
+    // `&a[2]`, `a + 1` and `&pts[1].y` are the addresses of the globals offset by bytes, and the
+    // `sizeof` expressions are folded.
+    let text = ir.to_string();
+    for line in [
+        "var i32* @pa = (&(a)) + 8l",
+        "var i32* @p = (&(a)) + 4l",
+        "var i32* @q = (&(a)) + 8l",
+        "var i64 @n = 16",
+        "var i64 @m = 4",
+        "var i32* @py = (&(pts)) + 12l",
+        "var [2 x i32*] @ps = {&(a), (&(a)) + 12l}",
+        "var [ret:i32 params:()]* @fp = &(f)",
+    ] {
+        assert!(text.lines().any(|printed| printed == line), "{line}");
+    }
+
+    // The addresses are parsed back, and keep the globals they point to alive.
+    let mut reparsed = text.parse::<ir::TranslationUnit>().unwrap();
+    assert_eq!(reparsed, ir);
+    let _ = pipeline(OptLevel::O2).optimize(&mut reparsed);
+    assert!(reparsed.decls.contains_key("a"));
+    assert_returns(ir::Interpreter::new().run(&reparsed, Vec::new()), 1);
+
+    // The value of a variable is not a constant, even where a pointer is expected.
+    for source in ["int x = 1;\nint y = x;\n", "int x = 1;\nint *p = x;\n"] {
+        assert!(irgen_error(source).contains("not a compile-time constant"));
+    }
+}
+
+#[test]
+fn test_examples_irgen_string_pointer() {
+    let ir = irgen("examples/c/string_pointer.c");
+    assert_returns(ir::Interpreter::new().run(&ir, Vec::new()), 1);
+
+    // A pointer initialized by a string literal points at the read-only array it is lowered into,
+    // which is shared by the literals of the same bytes.
+    let text = ir.to_string();
+    for line in [
+        "var i8* @str = &(__string0)",
+        "static var [3 x const i8] @__string0 = {104, 105, 0}",
+        "var [3 x i8*] @strs = {&(__string1), &(__string0), (&(__string0)) + 1l}",
+        "var [3 x i8] @buf = {97, 98, 0}",
+    ] {
+        assert!(text.lines().any(|printed| printed == line), "{line}");
+    }
+
+    let reparsed = text.parse::<ir::TranslationUnit>().unwrap();
+    assert_eq!(reparsed, ir);
+    assert_returns(ir::Interpreter::new().run(&reparsed, Vec::new()), 1);
+}
+
+#[test]
+fn test_examples_irgen_switch_fallthrough() {
+    // The cases fall through to the next ones, also across the labels stacked on one statement.
+    let ir = irgen("examples/c/switch_fallthrough.c");
+    assert_returns(ir::Interpreter::new().run(&ir, Vec::new()), 1);
+}
+
+#[test]
+fn test_examples_irgen_unsupported() {
+    // The parser passes these constructs through, and irgen reports where they are.
+    for (source, message) in [
+        ("enum color { RED };\n", "1:1: enumeration is unsupported"),
+        (
+            "int f() {\n  enum color c;\n  return 0;\n}\n",
+            "2:3: enumeration is unsupported",
+        ),
+        ("_Atomic int x;\n", "1:1: `_Atomic` is unsupported"),
+        ("_Atomic(int) x;\n", "1:1: `_Atomic` is unsupported"),
+        ("typeof(1) x;\n", "1:1: `typeof` is unsupported"),
+        (
+            "int f() {\n  goto end;\nend:\n  return 0;\n}\n",
+            "2:3: `goto` statement is unsupported",
+        ),
+    ] {
+        assert_eq!(irgen_error(source), message, "{source}");
+    }
+}
+
+#[test]
+fn test_examples_irgen_unprototyped() {
+    // The parameters of a function declared without a prototype are unknown, so it takes no
+    // arguments, unless it is declared with a prototype.
+    for (source, message) in [
+        (
+            "int h();\nint main() {\n  return h(1, 2.0);\n}\n",
+            "3:10: passing arguments to function `h` declared without a prototype",
+        ),
+        (
+            "int h() {\n  return 1;\n}\nint main() {\n  return h(1);\n}\n",
+            "5:10: passing arguments to function `h` declared without a prototype",
+        ),
+        (
+            "int h();\nint h(void);\nint main() {\n  return h(1);\n}\n",
+            "4:10: too many arguments to function call, expected 0, have 1",
+        ),
+    ] {
+        assert_eq!(irgen_error(source), message, "{source}");
+    }
+}
+
+#[test]
+fn test_examples_irgen_struct_table() {
+    let ir = irgen("examples/c/linked_list.c");
+    assert_returns(ir::Interpreter::new().run(&ir, Vec::new()), 1);
+
+    // `struct node` refers to itself, and `struct tree` and `struct forest` to each other, by
+    // name through the struct table.
+    let node = ir::Dtype::lookup_struct("node", &ir.structs).unwrap();
+    let fields = node.resolve_struct_fields(&ir.structs).unwrap();
+    let next = fields[1].get_pointer_inner().unwrap();
+    assert_eq!(next.get_struct_name(), Some(&Some("node".to_string())));
+    assert_eq!(next.get_struct_fields(), Some(&None));
+    assert_eq!(
+        next.resolve_struct_layout(&ir.structs).unwrap(),
+        (16, 8, &[0, 8][..])
+    );
+    let forest = ir::Dtype::lookup_struct("forest", &ir.structs).unwrap();
+    assert_eq!(forest.size_align_of(&ir.structs).unwrap(), (8, 8));
+    assert!(ir::Dtype::lookup_struct("leaf", &ir.structs).is_err());
+
+    // The table is printed and parsed back.
+    let reparsed = ir.to_string().parse::<ir::TranslationUnit>().unwrap();
+    assert_eq!(reparsed.structs, ir.structs);
+}
+
+#[test]
+fn test_examples_data_layout() {
+    let ir = irgen("examples/c/linked_list.c");
+    let node = ir::Dtype::lookup_struct("node", &ir.structs).unwrap();
+    let fields = node.resolve_struct_fields(&ir.structs).unwrap();
+    let long = ir::Dtype::LONG;
+
+    // The sizes used by irgen and the interpreter are those of the default target.
+    let rv64 = ir::DataLayout::default();
+    assert_eq!(rv64, ir::DataLayout::RV64);
+    assert_eq!(
+        node.size_align_of_in(&rv64, &ir.structs).unwrap(),
+        node.size_align_of(&ir.structs).unwrap()
+    );
+
+    // On a 32-bit target, `next` follows `value` right away and `long` is aligned to 4 bytes.
+    let ilp32 = ir::DataLayout {
+        pointer_size: 4,
+        max_align: 4,
+    };
+    assert_eq!(node.size_align_of_in(&ilp32, &ir.structs).unwrap(), (8, 4));
+    assert_eq!(
+        ir::Dtype::struct_layout_in(fields, &ilp32, &ir.structs).unwrap(),
+        (8, 4, vec![0, 4])
+    );
+    assert_eq!(long.size_align_of_in(&ilp32, &ir.structs).unwrap(), (8, 4));
+    assert_eq!(
+        ir::Dtype::array(long, 3)
+            .size_align_of_in(&ilp32, &ir.structs)
+            .unwrap(),
+        (24, 4)
+    );
+}
+
+#[test]
+fn test_examples_ir_roundtrip() {
+    for dir in ASMGEN_TEST_DIR_LIST.iter() {
+        test_dir(Path::new(dir), OsStr::new("ir"), test_ir_roundtrip);
+    }
+}
+
 #[test]
 fn test_examples_simplify_cfg() {
     test_opt(
@@ -177,6 +449,17 @@ fn test_examples_deadcode() {
     );
 }
 
+#[test]
+fn test_examples_deadcode_chain() {
+    // Unused chains of instructions, non-entry phinodes and allocations are removed along with
+    // `nop`s, unlike the parameters, stores and volatile loads, and the rest are renumbered.
+    test_opt(
+        &Path::new("examples/deadcode/chain.input.ir"),
+        &Path::new("examples/deadcode/chain.output.ir"),
+        &mut Deadcode::default(),
+    );
+}
+
 #[test]
 fn test_examples_gvn() {
     test_opt(
@@ -192,6 +475,1562 @@ fn test_examples_gvn() {
     );
 }
 
+#[test]
+fn test_examples_gvn_scoped() {
+    // Commuted operands get the same number, the leaders of dominators replace later uses, and a
+    // value computed by every predecessor is merged by a new phinode, unlike the `sub` computed
+    // by one predecessor only.
+    test_opt(
+        &Path::new("examples/gvn/scoped.input.ir"),
+        &Path::new("examples/gvn/scoped.output.ir"),
+        &mut Gvn::default(),
+    );
+
+    let args = [3, 4].map(|value| ir::Value::Int {
+        value,
+        width: 32,
+        is_signed: true,
+    });
+    for path in [
+        "examples/gvn/scoped.input.ir",
+        "examples/gvn/scoped.output.ir",
+    ] {
+        let result = ir::Interpreter::new().run(&parse_ir(path), args.to_vec());
+        assert_returns(result, 18);
+    }
+}
+
+#[test]
+fn test_examples_compact_nop() {
+    test_opt(
+        &Path::new("examples/compact_nop/compact_nop.input.ir"),
+        &Path::new("examples/compact_nop/compact_nop.output.ir"),
+        &mut CompactNop::default(),
+    );
+}
+
+#[test]
+fn test_examples_canonicalize() {
+    test_canonicalize(
+        &Path::new("examples/canonicalize/canonicalize.input.ir"),
+        &Path::new("examples/canonicalize/canonicalize.output.ir"),
+    );
+}
+
+#[test]
+fn test_examples_phi_elim() {
+    test_opt(
+        &Path::new("examples/phi_elim/phi_elim.input.ir"),
+        &Path::new("examples/phi_elim/phi_elim.output.ir"),
+        &mut PhiElimination::default(),
+    );
+}
+
+#[test]
+fn test_examples_phi_elim_critical_edge() {
+    // The copies into `r` are placed on new blocks splitting the critical edges `b0 -> b2` and
+    // both `b1 -> b2`, where they run on those edges only.
+    test_opt(
+        &Path::new("examples/phi_elim/critical_edge.input.ir"),
+        &Path::new("examples/phi_elim/critical_edge.output.ir"),
+        &mut PhiElimination::default(),
+    );
+
+    let ir = parse_ir("examples/phi_elim/critical_edge.output.ir");
+    let run = |n| run_i32(&mut ir::Interpreter::new(), &ir, n);
+    assert_returns(run(-1), 0);
+    assert_returns(run(5), 1);
+    assert_returns(run(20), 20);
+}
+
+#[test]
+fn test_examples_instcombine() {
+    test_opt(
+        &Path::new("examples/instcombine/instcombine.input.ir"),
+        &Path::new("examples/instcombine/instcombine.output.ir"),
+        &mut InstCombine::default(),
+    );
+}
+
+#[test]
+fn test_examples_reassociate() {
+    test_opt(
+        &Path::new("examples/reassociate/reassociate.input.ir"),
+        &Path::new("examples/reassociate/reassociate.output.ir"),
+        &mut Reassociate::default(),
+    );
+}
+
+#[test]
+fn test_examples_store_forward() {
+    test_opt(
+        &Path::new("examples/store_forward/store_forward.input.ir"),
+        &Path::new("examples/store_forward/store_forward.output.ir"),
+        &mut StoreForwarding::default(),
+    );
+}
+
+#[test]
+fn test_examples_dse() {
+    test_opt(
+        &Path::new("examples/dse/dse.input.ir"),
+        &Path::new("examples/dse/dse.output.ir"),
+        &mut DeadStoreElimination::default(),
+    );
+}
+
+#[test]
+fn test_examples_sroa() {
+    test_opt(
+        &Path::new("examples/sroa/sroa.input.ir"),
+        &Path::new("examples/sroa/sroa.output.ir"),
+        &mut Sroa::default(),
+    );
+}
+
+#[test]
+fn test_examples_narrow() {
+    test_opt(
+        &Path::new("examples/narrow/narrow.input.ir"),
+        &Path::new("examples/narrow/narrow.output.ir"),
+        &mut Narrowing::default(),
+    );
+}
+
+#[test]
+fn test_examples_switch_lowering() {
+    test_opt(
+        &Path::new("examples/switch_lowering/switch_lowering.input.ir"),
+        &Path::new("examples/switch_lowering/switch_lowering.output.ir"),
+        &mut SwitchLowering::default(),
+    );
+}
+
+#[test]
+fn test_examples_const_fold() {
+    // Chains of folds across blocks, but not the division by zero.
+    test_opt(
+        &Path::new("examples/const_fold/const_fold.input.ir"),
+        &Path::new("examples/const_fold/const_fold.output.ir"),
+        &mut ConstFold::default(),
+    );
+
+    // `-20` truncated to `u8` is `236`, which is doubled as an `i32`.
+    for path in [
+        "examples/const_fold/const_fold.input.ir",
+        "examples/const_fold/const_fold.output.ir",
+    ] {
+        assert_returns(ir::Interpreter::new().run(&parse_ir(path), Vec::new()), 472);
+    }
+}
+
+#[test]
+fn test_examples_const_prop() {
+    test_opt(
+        &Path::new("examples/const_prop/const_prop.input.ir"),
+        &Path::new("examples/const_prop/const_prop.output.ir"),
+        &mut ConstProp::default(),
+    );
+}
+
+#[test]
+fn test_examples_copy_prop() {
+    test_opt(
+        &Path::new("examples/copy_prop/copy_prop.input.ir"),
+        &Path::new("examples/copy_prop/copy_prop.output.ir"),
+        &mut CopyProp::default(),
+    );
+}
+
+#[test]
+fn test_examples_prune_block_args() {
+    test_opt(
+        &Path::new("examples/prune_block_args/prune_block_args.input.ir"),
+        &Path::new("examples/prune_block_args/prune_block_args.output.ir"),
+        &mut PruneBlockArgs::default(),
+    );
+}
+
+#[test]
+fn test_examples_unreachable_block() {
+    test_opt(
+        &Path::new("examples/unreachable_block/unreachable_block.input.ir"),
+        &Path::new("examples/unreachable_block/unreachable_block.output.ir"),
+        &mut UnreachableBlockElimination::default(),
+    );
+}
+
+#[test]
+fn test_examples_branch_fold() {
+    test_opt(
+        &Path::new("examples/branch_fold/branch_fold.input.ir"),
+        &Path::new("examples/branch_fold/branch_fold.output.ir"),
+        &mut BranchFold::default(),
+    );
+}
+
+#[test]
+fn test_examples_dead_function() {
+    test_opt(
+        &Path::new("examples/dead_function/dead_function.input.ir"),
+        &Path::new("examples/dead_function/dead_function.output.ir"),
+        &mut DeadFunctionElimination::default(),
+    );
+    test_opt(
+        &Path::new("examples/dead_function/library.input.ir"),
+        &Path::new("examples/dead_function/library.output.ir"),
+        &mut DeadFunctionElimination::default(),
+    );
+}
+
+#[test]
+fn test_examples_dead_global() {
+    test_opt(
+        &Path::new("examples/dead_global/dead_global.input.ir"),
+        &Path::new("examples/dead_global/dead_global.output.ir"),
+        &mut DeadGlobalElimination::default(),
+    );
+    test_opt(
+        &Path::new("examples/dead_global/library.input.ir"),
+        &Path::new("examples/dead_global/library.output.ir"),
+        &mut DeadGlobalElimination::default(),
+    );
+}
+
+#[test]
+fn test_examples_volatile() {
+    // Volatile accesses are neither promoted, removed nor forwarded, unlike those of `y`.
+    test_opt(
+        &Path::new("examples/volatile/volatile.input.ir"),
+        &Path::new("examples/volatile/volatile.output.ir"),
+        &mut (
+            Mem2reg::default(),
+            (
+                Deadcode::default(),
+                (DeadStoreElimination::default(), StoreForwarding::default()),
+            ),
+        ),
+    );
+
+    let ir = parse_ir("examples/volatile/volatile.input.ir");
+    let definition = function_of(&ir, "main");
+    let instructions = &definition.blocks[&ir::BlockId(0)].instructions;
+    assert!(instructions[1].is_volatile() && !instructions[1].has_no_side_effects());
+    assert!(instructions[3].is_volatile() && !instructions[3].has_no_side_effects());
+    assert!(!instructions[7].is_volatile() && instructions[7].has_no_side_effects());
+
+    // Volatile accesses conflict with each other even if they access distinct objects.
+    let alias = ir::analysis::AliasAnalysis::new(definition, &ir.structs);
+    let volatile_y = ir::Instruction::Load {
+        ptr: ir::Operand::register(ir::RegisterId::local(1), ir::Dtype::pointer(ir::Dtype::INT)),
+        volatile: true,
+    };
+    assert!(alias.may_conflict(&instructions[3], &volatile_y));
+    assert!(!alias.may_conflict(&instructions[1], &instructions[7]));
+}
+
+#[test]
+fn test_examples_div_by_const() {
+    test_opt(
+        &Path::new("examples/div_by_const/div_by_const.input.ir"),
+        &Path::new("examples/div_by_const/div_by_const.output.ir"),
+        &mut DivByConst::default(),
+    );
+}
+
+#[test]
+fn test_examples_inline() {
+    test_opt(
+        &Path::new("examples/inline/inline.input.ir"),
+        &Path::new("examples/inline/inline.output.ir"),
+        &mut Inline::default(),
+    );
+}
+
+#[test]
+fn test_examples_licm() {
+    test_opt(
+        &Path::new("examples/licm/licm.input.ir"),
+        &Path::new("examples/licm/licm.output.ir"),
+        &mut Licm::default(),
+    );
+
+    // The addition hoisted out of the loop that runs zero times loses its `nsw` flag.
+    let mut ir = parse_ir("examples/licm/licm.input.ir");
+    let _ = Licm::default().optimize(&mut ir);
+    assert_returns(
+        ir::Interpreter::new().flag_check(true).run(&ir, Vec::new()),
+        48,
+    );
+}
+
+#[test]
+fn test_examples_indvars() {
+    test_opt(
+        &Path::new("examples/indvars/indvars.input.ir"),
+        &Path::new("examples/indvars/indvars.output.ir"),
+        &mut IndVarSimplify::default(),
+    );
+}
+
+#[test]
+fn test_examples_loop_unroll() {
+    test_opt(
+        &Path::new("examples/loop_unroll/loop_unroll.input.ir"),
+        &Path::new("examples/loop_unroll/loop_unroll.output.ir"),
+        &mut LoopUnroll::default(),
+    );
+}
+
+#[test]
+fn test_examples_pre() {
+    test_opt(
+        &Path::new("examples/pre/pre.input.ir"),
+        &Path::new("examples/pre/pre.output.ir"),
+        &mut Pre::default(),
+    );
+}
+
+#[test]
+fn test_examples_sccp() {
+    test_opt(
+        &Path::new("examples/sccp/sccp.input.ir"),
+        &Path::new("examples/sccp/sccp.output.ir"),
+        &mut Sccp::default(),
+    );
+}
+
+#[test]
+fn test_examples_interp_void() {
+    // The results of the calls to `bump`, including the one passed to `b1`, are unit values.
+    let ir = parse_ir("examples/interp/void.ir");
+    ir::verify(&ir).unwrap();
+    assert_returns(
+        ir::Interpreter::new()
+            .undef_check(true)
+            .run(&ir, Vec::new()),
+        7,
+    );
+
+    let ir = irgen("examples/c/return_void.c");
+    assert_returns(ir::Interpreter::new().run(&ir, Vec::new()), 1);
+}
+
+#[test]
+fn test_examples_interp_extern() {
+    let ir = parse_ir("examples/interp/extern.ir");
+
+    // `square` is only declared, and the definition of `twice` takes precedence.
+    let mut interpreter = ir::Interpreter::new();
+    let _ = interpreter
+        .register_extern("square", |args| {
+            let (x, _, _) = args[0].get_int().expect("`square` takes an integer");
+            let x = x as i32;
+            ir::Value::Int {
+                value: (x * x) as u128,
+                width: 32,
+                is_signed: true,
+            }
+        })
+        .register_extern("twice", |_| unreachable!("`twice` is defined in the IR"));
+    assert_returns(interpreter.run(&ir, Vec::new()), 36);
+
+    // Without the host function, the call to `square` fails.
+    assert!(ir::interp(&ir, Vec::new()).is_err());
+}
+
+#[test]
+fn test_examples_interp_rand() {
+    let ir = parse_ir("examples/interp/rand.ir");
+
+    // `rand` starts as if seeded with 1, so reseeding with 1 repeats the first number.
+    assert_returns(ir::interp(&ir, Vec::new()), 377401575);
+    assert_returns(ir::interp(&ir, Vec::new()), 377401575);
+
+    // Another initial seed gives another first number.
+    assert_returns(ir::Interpreter::new().seed(42).run(&ir, Vec::new()), -1);
+}
+
+#[test]
+fn test_examples_interp_replay() {
+    let ir = parse_ir("examples/interp/replay.ir");
+    let temp_dir = tempfile::tempdir().expect("temp dir creation failed");
+    let log = temp_dir.path().join("replay.log");
+
+    let mut readings = 3..;
+    assert_returns(
+        ir::Interpreter::new()
+            .register_extern("sensor", move |_| ir::Value::Int {
+                value: readings.next().expect("`readings` is infinite"),
+                width: 32,
+                is_signed: true,
+            })
+            .record(&log)
+            .run(&ir, Vec::new()),
+        9034,
+    );
+    let text = std::fs::read_to_string(&log).expect("reading the log failed");
+    assert!(text.starts_with("sensor i32 3\nsensor i32 4\nrand i32 "));
+
+    // The replayed run neither calls the host nor uses its own seed.
+    assert_returns(
+        ir::Interpreter::new()
+            .register_extern("sensor", |_| unreachable!("`sensor` is replayed"))
+            .seed(5)
+            .replay(&log)
+            .run(&ir, Vec::new()),
+        9034,
+    );
+
+    // Signed integers are logged as signed numbers.
+    std::fs::write(&log, "sensor i32 -1\nsensor i32 -2\nrand i32 0\n")
+        .expect("writing the log failed");
+    let replay = |log: &Path| {
+        ir::Interpreter::new()
+            .register_extern("sensor", |_| unreachable!("`sensor` is replayed"))
+            .replay(log)
+            .run(&ir, Vec::new())
+    };
+    assert_returns(replay(&log), -12);
+
+    // A log that runs out before the execution does, or that the execution does not use up, is an
+    // error.
+    for text in [
+        "sensor i32 3\n",
+        "sensor i32 3\nsensor i32 4\nrand i32 0\nrand i32 0\n",
+    ] {
+        std::fs::write(&log, text).expect("writing the log failed");
+        assert!(matches!(
+            replay(&log),
+            Err(ir::InterpreterError::Replay { .. })
+        ));
+    }
+
+    // A replayed `EOF` compares equal to -1 and less than 0, ending the loop of `getchar`.
+    let ir = parse_ir("examples/interp/getchar.ir");
+    std::fs::write(&log, "getchar i32 104\ngetchar i32 105\ngetchar i32 -1\n")
+        .expect("writing the log failed");
+    assert_returns(ir::Interpreter::new().replay(&log).run(&ir, Vec::new()), 21);
+}
+
+#[test]
+fn test_examples_interp_profile() {
+    let ir = parse_ir("examples/interp/profile.ir");
+    let mut interpreter = ir::Interpreter::new();
+    assert_returns(interpreter.profiling(true).run(&ir, Vec::new()), 6);
+    let profile = interpreter.profile().clone();
+
+    // The loop header of `sum` runs once more than its body, which has three instructions.
+    let b = ir::BlockId;
+    let count = |hits, instructions| ir::BlockCount { hits, instructions };
+    assert_eq!(profile.block("sum", b(1)), count(5, 10));
+    assert_eq!(profile.block("sum", b(2)), count(4, 12));
+    assert_eq!(profile.function_instructions("sum"), 24);
+    assert_eq!(profile.total_instructions(), 26);
+    assert_eq!(
+        ir::Profile::parse(&profile.to_string()),
+        Ok(profile.clone())
+    );
+
+    // The report ranks the loop body first and annotates the blocks with their counts.
+    let mut report = Vec::new();
+    profile.write_report(&ir, &mut report).unwrap();
+    let report = String::from_utf8(report).unwrap();
+    let ranking = report
+        .lines()
+        .skip_while(|line| !line.starts_with("blocks"))
+        .nth(1)
+        .unwrap();
+    assert!(ranking.ends_with("@sum:b2 (4 hits)"));
+    assert!(report.contains("block b1:\t\t\t\t; 5 hits, 10 instructions"));
+
+    assert_returns(interpreter.profiling(false).run(&ir, Vec::new()), 6);
+    assert!(interpreter.profile().is_empty());
+}
+
+#[test]
+fn test_examples_profile_guided() {
+    let ir = parse_ir("examples/profile/profile.ir");
+    let mut interpreter = ir::Interpreter::new();
+    assert_returns(interpreter.profiling(true).run(&ir, Vec::new()), 488);
+    let profile = interpreter.profile().clone();
+
+    let callees = |ir: &ir::TranslationUnit| {
+        function_of(ir, "main")
+            .blocks
+            .values()
+            .flat_map(|block| block.instructions.iter())
+            .filter_map(|instr| match &**instr {
+                ir::Instruction::Call { callee, .. } => {
+                    callee.get_constant()?.get_global_variable_name()
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+    };
+    let inline = |threshold, profile: &ir::Profile| {
+        let mut ir = ir.clone();
+        let _ = Inline {
+            threshold,
+            profile: profile.clone(),
+            ..Default::default()
+        }
+        .optimize(&mut ir);
+        assert_returns(ir::Interpreter::new().run(&ir, Vec::new()), 488);
+        callees(&ir)
+    };
+
+    // Without a profile both calls are inlined under the threshold and neither above it. The call
+    // to `step` in the loop is hot enough to be inlined above the threshold, and the call to
+    // `cold` is never reached.
+    assert!(inline(40, &ir::Profile::default()).is_empty());
+    assert_eq!(inline(0, &ir::Profile::default()), ["step", "cold"]);
+    assert_eq!(inline(40, &profile), ["cold"]);
+    assert_eq!(inline(0, &profile), ["cold"]);
+
+    // The loop header is entered 101 times per entry, so the loop is unrolled by the largest
+    // factor, unless it is not hot enough.
+    let mut unrolled = ir.clone();
+    assert!(ProfileGuidedUnroll::new(profile.clone()).optimize(&mut unrolled));
+    assert!(function_of(&unrolled, "main").blocks.len() > function_of(&ir, "main").blocks.len());
+    assert_returns(ir::Interpreter::new().run(&unrolled, Vec::new()), 488);
+
+    let mut cold = ir.clone();
+    let mut pass = ProfileGuidedUnroll::new(profile);
+    pass.min_hits = 102;
+    assert!(!pass.optimize(&mut cold));
+    assert_eq!(cold, ir);
+}
+
+#[test]
+fn test_examples_interp_leak_check() {
+    let ir = parse_ir("examples/interp/leak.ir");
+
+    // Only the block allocated by `calloc` in `make` is not freed.
+    let mut interpreter = ir::Interpreter::new();
+    assert_returns(interpreter.leak_check(true).run(&ir, Vec::new()), 7);
+    let leaks = interpreter
+        .leaks()
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+    assert_eq!(leaks, ["8 bytes allocated at make:b0:0 are never freed"]);
+
+    assert_returns(interpreter.leak_check(false).run(&ir, Vec::new()), 7);
+    assert!(interpreter.leaks().is_empty());
+}
+
+#[test]
+fn test_examples_interp_calloc() {
+    let ir = parse_ir("examples/interp/calloc.ir");
+
+    // The overflowing `calloc` and the `malloc` too large for the host return null, and the other
+    // `calloc` returns zeroed memory.
+    assert_returns(ir::Interpreter::new().run(&ir, Vec::new()), 110);
+
+    // Under a memory limit, the `malloc` exceeding it fails instead.
+    assert_eq!(
+        ir::Interpreter::new()
+            .memory_limit(100)
+            .run(&ir, Vec::new()),
+        Err(ir::InterpreterError::OutOfMemory {
+            requested: usize::MAX,
+            limit: 100
+        })
+    );
+}
+
+#[test]
+fn test_examples_interp_memory_limit() {
+    let ir = parse_ir("examples/interp/memory_limit.ir");
+
+    // The global and the 51 frames of the deepest recursion take 208 bytes, and the frames of the
+    // first recursion are freed before the second one.
+    assert_returns(
+        ir::Interpreter::new()
+            .memory_limit(208)
+            .run(&ir, Vec::new()),
+        2550,
+    );
+    assert_eq!(
+        ir::Interpreter::new()
+            .memory_limit(207)
+            .run(&ir, Vec::new()),
+        Err(ir::InterpreterError::OutOfMemory {
+            requested: 4,
+            limit: 207
+        })
+    );
+}
+
+#[test]
+fn test_examples_interp_float() {
+    // Both additions are equal to the constants only if rounded to single precision, and
+    // 16777217 is not representable in single precision.
+    assert_returns(
+        ir::interp(&parse_ir("examples/interp/float.ir"), Vec::new()),
+        2,
+    );
+}
+
+#[test]
+fn test_examples_interp_wide() {
+    // 128-bit products and quotients, 64-bit wrap-arounds and shifts, and `long double`, which is
+    // stored as 16 bytes: (-3000 + 100 + 10 + 6) * -4.
+    let ir = parse_ir("examples/interp/wide.ir");
+    assert_returns(ir::Interpreter::new().run(&ir, Vec::new()), 11536);
+
+    // The 128-bit constants are printed and parsed back.
+    let ir = ir.to_string().parse::<ir::TranslationUnit>().unwrap();
+    assert_returns(ir::Interpreter::new().run(&ir, Vec::new()), 11536);
+}
+
+#[test]
+fn test_examples_interp_bool() {
+    // Casting a nonzero integer or float, or loading a nonzero byte, to a boolean gives 1, and
+    // a nonzero byte is a true branch condition.
+    assert_returns(
+        ir::interp(&parse_ir("examples/interp/bool.ir"), Vec::new()),
+        3,
+    );
+}
+
+#[test]
+fn test_examples_interp_select() {
+    let ir = parse_ir("examples/interp/select.ir");
+    ir::verify(&ir).unwrap();
+    assert_eq!(ir.to_string().parse::<ir::TranslationUnit>().unwrap(), ir);
+    let run = |n| run_i32(ir::Interpreter::new().undef_check(true), &ir, n);
+
+    // Only the selected operand is read, so the undefined one is harmless unless chosen.
+    assert_returns(run(-5), 5);
+    assert_returns(run(7), 7);
+    assert!(matches!(run(0), Ok(ir::Value::Undef { .. })));
+
+    // The condition must be a boolean and the operands of the dtype of the result.
+    for (from, to) in [
+        ("select %b0:i0:u1", "select %b0:p0:i32"),
+        ("undef:i32, %b0:i2:i32", "undef:i64, %b0:i2:i32"),
+    ] {
+        let text =
+            std::fs::read_to_string("examples/interp/select.ir").expect("reading the IR failed");
+        let ir = text
+            .replacen(from, to, 1)
+            .parse::<ir::TranslationUnit>()
+            .unwrap();
+        assert!(ir::verify(&ir).is_err());
+    }
+}
+
+#[test]
+fn test_examples_interp_undef() {
+    let ir = parse_ir("examples/interp/undef.ir");
+    let run =
+        |mode, undef_check| run_i32(ir::Interpreter::new().undef_check(undef_check), &ir, mode);
+    let undefined_use = |result: Result<ir::Value, ir::InterpreterError>| match result {
+        Err(ir::InterpreterError::UndefinedValue { operand, .. }) => operand.to_string(),
+        result => panic!("unexpected result {result:?}"),
+    };
+
+    // Computing with the uninitialized `x` is fine unless checked, and so is returning the result.
+    assert_returns(run(0, false), 7);
+    assert_eq!(undefined_use(run(0, true)), "%b0:i0:i32");
+    assert!(matches!(run(2, false), Ok(ir::Value::Undef { .. })));
+
+    // Branching on an undefined value always fails.
+    assert_eq!(undefined_use(run(1, false)), "%b3:i0:u1");
+}
+
+#[test]
+fn test_examples_interp_punning() {
+    let ir = parse_ir("examples/interp/punning.ir");
+    let run = |mode| run_i32(ir::Interpreter::new().undef_check(true), &ir, mode);
+
+    // The bits of `1.0f` read as an int, the low bytes of `0x01020304`, and zero bytes read as a
+    // null pointer.
+    assert_returns(run(0), 1431);
+
+    // The bytes of a pointer into a memory block have no concrete value.
+    assert!(matches!(
+        run(1),
+        Err(ir::InterpreterError::UndefinedValue { .. })
+    ));
+
+    let ir = irgen("examples/c/union.c");
+    assert_returns(ir::Interpreter::new().run(&ir, Vec::new()), 1);
+}
+
+#[test]
+fn test_examples_interp_pointer_cmp() {
+    let ir = parse_ir("examples/interp/pointer_cmp.ir");
+    let cross = |cross| {
+        vec![ir::Value::Int {
+            value: cross,
+            width: 1,
+            is_signed: false,
+        }]
+    };
+
+    // Pointers into the same array compare by their offsets.
+    assert_returns(ir::interp(&ir, cross(0)), 0b10101);
+
+    // Pointers into different blocks may only be compared for equality.
+    let result = ir::interp(&ir, cross(1));
+    assert!(
+        matches!(
+            &result,
+            Err(ir::InterpreterError::Misc { pc, msg, .. })
+                if pc.iid == 1 && msg == "`cmp lt` compares pointers into different memory blocks"
+        ),
+        "{result:?}"
+    );
+}
+
+#[test]
+fn test_examples_interp_abort() {
+    let ir = parse_ir("examples/interp/abort.ir");
+    let run = |mode| run_i32(&mut ir::Interpreter::new(), &ir, mode);
+    let aborted = |result| match result {
+        Err(ir::InterpreterError::Aborted { msg, .. }) => msg,
+        result => panic!("{result:?} is not aborted"),
+    };
+
+    assert_returns(run(0), 5);
+    assert_eq!(aborted(run(1)), "assertion failed in `main`");
+    assert_eq!(
+        aborted(run(2)),
+        "assertion `x > 0` failed in `main` at a.c:3"
+    );
+    assert_eq!(aborted(run(3)), "`abort` is called in `main`");
+}
+
+#[test]
+fn test_examples_interp_flags() {
+    let ir = parse_ir("examples/interp/flags.ir");
+    let run = |mode, flag_check| run_i32(ir::Interpreter::new().flag_check(flag_check), &ir, mode);
+    let violated = |result| match result {
+        Err(ir::InterpreterError::FlagViolation { flag, .. }) => flag,
+        result => panic!("{result:?} violates no flag"),
+    };
+
+    assert_returns(run(0, true), 306783278);
+    assert_eq!(violated(run(1, true)), "nsw");
+    assert_eq!(violated(run(2, true)), "nuw");
+    assert_eq!(violated(run(3, true)), "exact");
+
+    // Without the check, the instructions compute as if they had no flag.
+    assert_returns(run(1, false), i32::MIN);
+    assert_returns(run(2, false), 156);
+    assert_returns(run(3, false), 3);
+}
+
+#[test]
+fn test_examples_link() {
+    let main = parse_ir("examples/link/main.ir");
+    let add = parse_ir("examples/link/add.ir");
+    let linked = ir::link([main.clone(), add.clone()]).unwrap();
+
+    // `@add` is declared by `main.ir` and defined by `add.ir`, and the `static` `@helper` of
+    // `main.ir` is renamed apart from that of `add.ir`.
+    assert!(linked.decls["add"].is_definition());
+    assert_eq!(linked.decls["helper_0"].linkage(), ir::Linkage::Internal);
+    assert_eq!(linked.decls["helper"].linkage(), ir::Linkage::Internal);
+    assert_returns(ir::Interpreter::new().run(&linked, Vec::new()), 134);
+
+    assert_eq!(
+        ir::link([add.clone(), add]),
+        Err(ir::LinkError::MultipleDefinitions {
+            name: "add".to_string()
+        })
+    );
+}
+
+#[test]
+fn test_examples_decl_order() {
+    let names = |ir: &ir::TranslationUnit| {
+        ir.decls_in_order()
+            .map(|(name, _)| name.clone())
+            .collect::<Vec<_>>()
+    };
+
+    // Declarations keep the order of the C source through printing and parsing.
+    let mut ir = irgen("examples/linkage/linkage.c");
+    let declared = ["counter", "shared", "elsewhere", "external", "bump", "main"];
+    assert_eq!(names(&ir), declared);
+    let text = ir.to_string();
+    let positions = declared
+        .iter()
+        .map(|name| {
+            let name = format!("@{name}");
+            text.lines()
+                .position(|line| line.split_whitespace().any(|word| word == name))
+                .unwrap()
+        })
+        .collect::<Vec<_>>();
+    assert!(positions.windows(2).all(|pair| pair[0] < pair[1]));
+    assert_eq!(names(&text.parse().unwrap()), declared);
+
+    // Declarations added later follow in the order of their names.
+    for name in ["zeta", "alpha"] {
+        let decl = ir::Declaration::try_from(ir::Dtype::INT).unwrap();
+        let _unused = ir.decls.insert(name.to_string(), decl);
+    }
+    assert_eq!(names(&ir)[declared.len()..], ["alpha", "zeta"]);
+
+    // Linking keeps the order of the first unit declaring each name, renaming along.
+    let main = parse_ir("examples/link/main.ir");
+    let add = parse_ir("examples/link/add.ir");
+    let linked = ir::link([main, add]).unwrap();
+    assert_eq!(names(&linked), ["add", "helper_0", "main", "helper"]);
+}
+
+#[test]
+fn test_examples_cfg() {
+    let ir = parse_ir("examples/analysis/cfg.ir");
+    let cfg = ir::analysis::Cfg::new(function_of(&ir, "cfg"));
+    let b = ir::BlockId;
+
+    assert_eq!(cfg.entry(), b(0));
+    assert_eq!(cfg.successors(b(0)), [b(1), b(6)]);
+    assert_eq!(cfg.successors(b(9)), [b(9)]);
+    assert!(cfg.successors(b(7)).is_empty());
+    assert_eq!(cfg.predecessors(b(2)), [b(1), b(3)]);
+    assert_eq!(cfg.predecessors(b(7)), [b(5), b(6)]);
+    assert!(cfg.predecessors(b(8)).is_empty());
+
+    // Every block but the unreachable `b8` comes before its successors except along back edges.
+    assert_eq!(
+        cfg.reverse_post_order(),
+        [b(0), b(6), b(9), b(1), b(5), b(7), b(2), b(4), b(3)]
+    );
+    assert_eq!(cfg.post_order().next(), Some(b(3)));
+    assert_eq!(cfg.blocks().count(), 10);
+    assert!(cfg.is_reachable(b(9)));
+    assert!(!cfg.is_reachable(b(8)));
+    assert_eq!(
+        cfg.reachable_from(b(2)).into_iter().collect::<Vec<_>>(),
+        [b(1), b(2), b(3), b(4), b(5), b(7)]
+    );
+    assert_eq!(
+        cfg.reachable_from(b(8)).into_iter().collect::<Vec<_>>(),
+        [b(8)]
+    );
+}
+
+#[test]
+fn test_examples_liveness() {
+    let ir = parse_ir("examples/analysis/cfg.ir");
+    let definition = function_of(&ir, "cfg");
+    let cfg = ir::analysis::Cfg::new(definition);
+    let liveness = ir::analysis::Liveness::new(definition, &cfg);
+    let b = ir::BlockId;
+    let arg = ir::RegisterId::arg;
+    let temp = ir::RegisterId::temp;
+    let set = |rids: &[ir::RegisterId]| rids.iter().copied().collect::<HashSet<_>>();
+
+    // `n` is live throughout the loops, and `i` throughout the inner one, but the phinodes of a
+    // block are not live at its beginning.
+    assert!(liveness.live_in(b(0)).is_empty());
+    assert_eq!(liveness.live_out(b(0)), &set(&[arg(b(0), 0)]));
+    assert_eq!(liveness.live_in(b(1)), &set(&[arg(b(0), 0)]));
+    assert_eq!(liveness.live_in(b(2)), &set(&[arg(b(0), 0), arg(b(1), 0)]));
+    assert_eq!(liveness.live_out(b(3)), &set(&[arg(b(0), 0), arg(b(1), 0)]));
+    assert!(liveness.live_in(b(7)).is_empty());
+    assert!(liveness.live_in(b(8)).is_empty());
+
+    // The comparison is live from its definition until the exit using it.
+    assert_eq!(liveness.live_before(b(0), 0), &set(&[arg(b(0), 0)]));
+    assert_eq!(
+        liveness.live_before(b(0), 1),
+        &set(&[arg(b(0), 0), temp(b(0), 0)])
+    );
+    assert_eq!(liveness.live_after(b(0), 0), liveness.live_before(b(0), 1));
+    assert!(liveness.is_used(b(3), 0));
+}
+
+#[test]
+fn test_examples_alias() {
+    let ir = parse_ir("examples/analysis/alias.ir");
+    let definition = function_of(&ir, "alias");
+    let alias = ir::analysis::AliasAnalysis::new(definition, &ir.structs);
+    let instructions = &definition.blocks[&ir::BlockId(0)].instructions;
+    let temp = |iid: usize| {
+        ir::Operand::register(
+            ir::RegisterId::temp(ir::BlockId(0), iid),
+            ir::HasDtype::dtype(&*instructions[iid]),
+        )
+    };
+    let local = |aid: usize| {
+        ir::Operand::register(
+            ir::RegisterId::local(aid),
+            ir::Dtype::pointer((*definition.allocations[aid]).clone()),
+        )
+    };
+    let param = ir::Operand::register(
+        ir::RegisterId::arg(ir::BlockId(0), 0),
+        ir::Dtype::pointer(ir::Dtype::INT),
+    );
+    let global = ir::Operand::constant(ir::Constant::global_variable(
+        "g".to_string(),
+        ir::Dtype::INT,
+    ));
+
+    // Pointers are traced through `getelementptr` and typecasts, summing the constant offsets.
+    let location = |base, offset| ir::analysis::MemoryLocation { base, offset };
+    assert_eq!(
+        alias.location(&temp(2)),
+        location(ir::analysis::MemoryBase::Local(0), Some(8))
+    );
+    assert_eq!(
+        alias.location(&temp(3)),
+        location(ir::analysis::MemoryBase::Local(0), None)
+    );
+    assert_eq!(
+        alias.location(&temp(4)),
+        location(ir::analysis::MemoryBase::Local(0), Some(4))
+    );
+    assert_eq!(
+        alias.location(&global),
+        location(ir::analysis::MemoryBase::Global("g".to_string()), Some(0))
+    );
+    assert_eq!(
+        alias.location(&param).base,
+        ir::analysis::MemoryBase::Unknown
+    );
+
+    // Only `y` is passed to a call.
+    assert!(!alias.is_escaped(0));
+    assert!(!alias.is_escaped(1));
+    assert!(alias.is_escaped(2));
+    assert!(alias.is_exposed(&alias.location(&local(2))));
+    assert!(!alias.is_exposed(&alias.location(&temp(0))));
+    assert!(alias.is_exposed(&alias.location(&global)));
+
+    // Accesses of the same object alias only if their bytes overlap.
+    assert!(!alias.may_alias(&temp(0), &temp(1)));
+    assert!(!alias.may_alias(&temp(1), &temp(2)));
+    assert!(alias.may_alias(&temp(1), &temp(4)));
+    assert!(alias.may_alias(&temp(0), &temp(3)));
+    assert!(!alias.may_alias(&temp(0), &local(1)));
+    assert!(!alias.may_alias(&local(1), &global));
+
+    // Unknown pointers alias only exposed objects.
+    assert!(!alias.may_alias(&param, &local(1)));
+    assert!(alias.may_alias(&param, &local(2)));
+    assert!(alias.may_alias(&param, &global));
+
+    // Callees access exposed memory only.
+    assert!(!alias.may_conflict(&instructions[8], &instructions[6]));
+    assert!(alias.may_conflict(&instructions[8], &instructions[7]));
+    assert!(alias.may_conflict(&instructions[8], &instructions[10]));
+    assert!(!alias.may_conflict(&instructions[5], &instructions[6]));
+    assert!(alias.may_conflict(&instructions[9], &instructions[10]));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_examples_serde() {
+    fn assert_serde<T: serde::Serialize + serde::de::DeserializeOwned>() {}
+
+    // Everything reachable from a translation unit serializes, including the operators and
+    // initializers borrowed from the C AST.
+    assert_serde::<ir::TranslationUnit>();
+    assert_serde::<ir::Declaration>();
+    assert_serde::<ir::FunctionDefinition>();
+    assert_serde::<ir::Instruction>();
+    assert_serde::<ir::BlockExit>();
+    assert_serde::<ir::Operand>();
+    assert_serde::<ir::Dtype>();
+}
+
+#[test]
+fn test_examples_equiv() {
+    let base = parse_ir("examples/equiv/base.ir");
+    let renumbered = parse_ir("examples/equiv/renumbered.ir");
+    let swapped = parse_ir("examples/equiv/swapped.ir");
+
+    // Numbering the blocks differently, even the entry block, keeps the function the same.
+    assert!(ir::equiv(&base, &base));
+    assert!(ir::equiv(&base, &renumbered));
+    assert!(ir::equiv(&renumbered, &base));
+
+    // Swapping the targets of a branch changes what the function computes.
+    assert!(!ir::equiv(&base, &swapped));
+    assert!(!ir::equiv(&renumbered, &swapped));
+}
+
+#[test]
+fn test_examples_builder() {
+    use lang_c::ast;
+
+    let mut ir = parse_ir("examples/builder/sum.ir");
+    let int = |value| ir::Operand::constant(ir::Constant::int(value, ir::Dtype::INT));
+    let bin_op = |op, lhs, rhs, dtype| ir::Instruction::BinOp {
+        op,
+        lhs,
+        rhs,
+        dtype,
+        flags: ir::BinOpFlags::default(),
+    };
+    let load = |ptr| ir::Instruction::Load {
+        ptr,
+        volatile: false,
+    };
+    let store = |value, ptr| ir::Instruction::Store {
+        ptr,
+        value,
+        volatile: false,
+    };
+
+    // Build `@sum` again: `s = 0; for (i = 0; i < n; i++) s += i; return s;`
+    let signature =
+        ir::FunctionSignature::new(ir::Dtype::function(ir::Dtype::INT, vec![ir::Dtype::INT]));
+    let mut builder = ir::FunctionBuilder::new(signature);
+    builder.name_param(0, Some("n".to_string()));
+    let s = builder.push_alloc(ir::Named::new(Some("s".to_string()), ir::Dtype::INT));
+    let entry = builder.entry();
+    let cond = builder.append_block();
+    let body = builder.append_block();
+    let exit = builder.append_block();
+
+    let _unused = builder.push_instr(entry, store(int(0), s.clone()));
+    builder.terminate(
+        entry,
+        ir::BlockExit::Jump {
+            arg: ir::JumpArg::new(cond, vec![int(0)]),
+        },
+    );
+
+    let i = builder.push_phinode(cond, ir::Named::new(Some("i".to_string()), ir::Dtype::INT));
+    let is_less = builder.push_instr(
+        cond,
+        bin_op(
+            ast::BinaryOperator::Less,
+            i.clone(),
+            builder.param(0),
+            ir::Dtype::BOOL,
+        ),
+    );
+    builder.terminate(
+        cond,
+        ir::BlockExit::ConditionalJump {
+            condition: is_less,
+            arg_then: ir::JumpArg::new(body, Vec::new()),
+            arg_else: ir::JumpArg::new(exit, Vec::new()),
+        },
+    );
+
+    let loaded = builder.push_instr(body, load(s.clone()));
+    let sum = builder.push_instr(
+        body,
+        bin_op(ast::BinaryOperator::Plus, loaded, i.clone(), ir::Dtype::INT),
+    );
+    let _unused = builder.push_instr(body, store(sum, s.clone()));
+    let next = builder.push_instr(
+        body,
+        bin_op(ast::BinaryOperator::Plus, i, int(1), ir::Dtype::INT),
+    );
+    assert!(!builder.is_terminated(body));
+    builder.terminate(
+        body,
+        ir::BlockExit::Jump {
+            arg: ir::JumpArg::new(cond, vec![next]),
+        },
+    );
+    assert!(builder.is_terminated(body));
+
+    let result = builder.push_instr(exit, load(s));
+    builder.terminate(exit, ir::BlockExit::Return { value: result });
+
+    // The built function is the same as the parsed one, and computes the same.
+    let built = builder.finish();
+    let expected = ir.clone();
+    let (_, definition) = ir.decls.get_mut("sum").unwrap().get_function_mut().unwrap();
+    *definition = Some(built);
+    assert!(ir::equiv(&ir, &expected));
+    assert_eq!(ir.to_string(), expected.to_string());
+    assert_returns(ir::Interpreter::new().run(&ir, Vec::new()), 6);
+}
+
+#[test]
+fn test_examples_visit() {
+    #[derive(Default)]
+    struct Trace {
+        events: Vec<String>,
+    }
+
+    impl ir::IrVisitor for Trace {
+        fn visit_allocation(&mut self, aid: usize, _allocation: &ir::Named<ir::Dtype>) {
+            self.events.push(format!("%l{aid}"));
+        }
+
+        fn visit_phinode(&mut self, rid: ir::RegisterId, _phinode: &ir::Named<ir::Dtype>) {
+            self.events.push(rid.to_string());
+        }
+
+        fn visit_instruction(
+            &mut self,
+            rid: ir::RegisterId,
+            instruction: &ir::Named<ir::Instruction>,
+        ) {
+            self.events.push(rid.to_string());
+            ir::visit::walk_instruction(self, rid, instruction);
+        }
+
+        fn visit_block_exit(&mut self, bid: ir::BlockId, exit: &ir::BlockExit) {
+            self.events.push(format!("exit {bid}"));
+            ir::visit::walk_block_exit(self, bid, exit);
+        }
+
+        fn visit_operand(&mut self, operand: &ir::Operand) {
+            self.events.push(format!("  {operand}"));
+        }
+    }
+
+    let mut ir = parse_ir("examples/builder/sum.ir");
+
+    // Allocations come first, then each block in order: its phinodes, its instructions with
+    // their operands, and its exit with the arguments of its jumps.
+    let mut trace = Trace::default();
+    ir::IrVisitor::visit_function(&mut trace, function_of(&ir, "sum"));
+    assert_eq!(
+        trace.events[..9],
+        [
+            "%l0",
+            "%b0:p0",
+            "%b0:i0",
+            "  %l0:i32*",
+            "  0:i32",
+            "exit b0",
+            "  0:i32",
+            "%b1:p0",
+            "%b1:i0",
+        ]
+    );
+    assert_eq!(trace.events.last().unwrap(), "  %b3:i0:i32");
+    assert_eq!(trace.events.len(), 30);
+
+    // Bounding the loop by 3 instead of the argument sums 0, 1 and 2 only.
+    let (_, definition) = ir.decls.get_mut("sum").unwrap().get_function_mut().unwrap();
+    ir::IrRewriter::rewrite_function(
+        &mut Replace {
+            rid: ir::RegisterId::arg(ir::BlockId(0), 0),
+            value: ir::Constant::int(3, ir::Dtype::INT),
+        },
+        definition.as_mut().unwrap(),
+    );
+    assert_returns(ir::Interpreter::new().run(&ir, Vec::new()), 3);
+}
+
+#[test]
+fn test_examples_linkage() {
+    let ir = irgen("examples/linkage/linkage.c");
+    let linkage = |name: &str| ir.decls[name].linkage();
+    let is_definition = |name: &str| match &ir.decls[name] {
+        ir::Declaration::Variable { is_definition, .. } => *is_definition,
+        ir::Declaration::Function { definition, .. } => definition.is_some(),
+    };
+
+    // `static` names are internal, and `extern` variables without initializers are not defined.
+    assert_eq!(linkage("counter"), ir::Linkage::Internal);
+    assert_eq!(linkage("bump"), ir::Linkage::Internal);
+    assert_eq!(linkage("shared"), ir::Linkage::External);
+    assert_eq!(linkage("elsewhere"), ir::Linkage::External);
+    assert_eq!(linkage("external"), ir::Linkage::External);
+    assert!(is_definition("counter"));
+    assert!(is_definition("shared"));
+    assert!(!is_definition("elsewhere"));
+    assert!(!is_definition("external"));
+
+    // Linkage survives printing and parsing.
+    let text = ir.to_string();
+    assert_eq!(text.parse::<ir::TranslationUnit>().unwrap(), ir);
+    assert_returns(ir::Interpreter::new().run(&ir, Vec::new()), 9);
+
+    // A variable defined elsewhere has no memory to read.
+    let ir = text
+        .replacen("load @counter:i32*", "load @elsewhere:i32*", 1)
+        .parse::<ir::TranslationUnit>()
+        .unwrap();
+    assert!(matches!(
+        ir::Interpreter::new().run(&ir, Vec::new()),
+        Err(ir::InterpreterError::NoVariableDefinition { var_name }) if var_name == "elsewhere"
+    ));
+}
+
+#[test]
+fn test_examples_stats() {
+    let ir = parse_ir("examples/builder/sum.ir");
+    let stats = ir::stats(&ir);
+
+    let sum = &stats.functions["sum"];
+    assert_eq!(sum.blocks, 4);
+    assert_eq!(sum.phinodes, 2);
+    assert_eq!(sum.allocations, 1);
+    assert_eq!(sum.instructions["cmp lt"], 1);
+    assert_eq!(sum.instructions["add"], 2);
+    assert_eq!(sum.total_instructions(), 7);
+    assert!(!sum.instructions.contains_key("call"));
+
+    let total = stats.total();
+    assert_eq!(total.blocks, 5);
+    assert_eq!(total.instructions["call"], 1);
+    assert_eq!(total.total_instructions(), 8);
+
+    assert_eq!(
+        stats.to_string(),
+        "\
+function   blocks phinodes allocations instructions
+@main           1        0           0            1
+@sum            4        2           1            7
+total           5        2           1            8
+
+opcode      count
+add             2
+call            1
+cmp lt          1
+load            2
+store           2
+"
+    );
+}
+
+#[test]
+fn test_examples_cfg_dot() {
+    let ir = parse_ir("examples/builder/sum.ir");
+    let dot = ir::cfg_dot("sum", function_of(&ir, "sum"));
+    let lines = dot.lines().collect::<Vec<_>>();
+
+    // A node per block listing its code, with the entry block doubly bordered.
+    assert_eq!(lines[0], "digraph \"sum\" {");
+    assert_eq!(lines.last(), Some(&"}"));
+    assert!(lines[2].starts_with("\"b0\" [label=\"block b0:\\l"));
+    assert!(lines[2].ends_with("j b1(0:i32)\\l\", peripheries=2];"));
+    assert!(lines[3].contains("%b1:i0:u1 = cmp lt %b1:p0:i32 %b0:p0:i32\\l"));
+    assert!(!lines[3].contains("peripheries"));
+
+    // An edge per jump, labeled with its condition and its arguments.
+    assert_eq!(
+        lines[6..lines.len() - 1],
+        [
+            "\"b0\" -> \"b1\" [label=\"(0:i32)\"];",
+            "\"b1\" -> \"b2\" [label=\"true\"];",
+            "\"b1\" -> \"b3\" [label=\"false\"];",
+            "\"b2\" -> \"b1\" [label=\"(%b2:i3:i32)\"];",
+        ]
+    );
+
+    let ir = parse_ir("examples/switch_lowering/switch_lowering.input.ir");
+    let dot = ir::cfg_dot("linear", function_of(&ir, "linear"));
+    assert!(dot.ends_with(
+        "\
+\"b0\" -> \"b3\" [label=\"5\\n(1:i32)\"];
+\"b0\" -> \"b3\" [label=\"-7\\n(2:i32)\"];
+\"b0\" -> \"b3\" [label=\"default\\n(0:i32)\"];
+}"
+    ));
+}
+
+#[test]
+fn test_examples_def_use() {
+    let mut ir = parse_ir("examples/builder/sum.ir");
+    let (_, definition) = ir.decls.get_mut("sum").unwrap().get_function_mut().unwrap();
+    let definition = definition.as_mut().unwrap();
+    let mut def_use = ir::analysis::DefUse::new(definition);
+    let b = ir::BlockId;
+    let arg = ir::RegisterId::arg;
+    let temp = ir::RegisterId::temp;
+    let instr = |bid, iid| ir::analysis::Use::Instruction { bid: b(bid), iid };
+    let exit = |bid| ir::analysis::Use::Exit { bid: b(bid) };
+    let uses =
+        |def_use: &ir::analysis::DefUse, rid| def_use.uses(rid).iter().copied().collect::<Vec<_>>();
+
+    assert!(def_use.is_defined(ir::RegisterId::local(0)));
+    assert!(def_use.is_defined(arg(b(1), 0)));
+    assert!(def_use.is_defined(temp(b(2), 3)));
+    assert!(!def_use.is_defined(temp(b(2), 4)));
+    assert!(!def_use.is_defined(arg(b(0), 1)));
+
+    // Uses are ordered by block and then by instruction, with the exit last.
+    assert_eq!(
+        uses(&def_use, ir::RegisterId::local(0)),
+        [instr(0, 0), instr(2, 0), instr(2, 2), instr(3, 0)]
+    );
+    assert_eq!(
+        uses(&def_use, arg(b(1), 0)),
+        [instr(1, 0), instr(2, 1), instr(2, 3)]
+    );
+    assert_eq!(uses(&def_use, temp(b(1), 0)), [exit(1)]);
+    assert_eq!(uses(&def_use, temp(b(2), 3)), [exit(2)]);
+    assert!(!def_use.is_used(temp(b(2), 2)));
+
+    // Rewriting a single instruction updates its uses only.
+    let mut step_by_one = Replace {
+        rid: arg(b(1), 0),
+        value: ir::Constant::int(1, ir::Dtype::INT),
+    };
+    def_use.rewrite_at(definition, instr(2, 3), &mut step_by_one);
+    assert_eq!(uses(&def_use, arg(b(1), 0)), [instr(1, 0), instr(2, 1)]);
+
+    // Replacing every use of `n` leaves it unused, and the other uses as a new index finds them.
+    let bound = ir::Operand::constant(ir::Constant::int(3, ir::Dtype::INT));
+    def_use.replace_all_uses(definition, arg(b(0), 0), &bound);
+    assert!(!def_use.is_used(arg(b(0), 0)));
+    assert_eq!(
+        def_use.uses(arg(b(1), 0)),
+        ir::analysis::DefUse::new(definition).uses(arg(b(1), 0))
+    );
+    assert_eq!(
+        definition.blocks[&b(1)].instructions[0].to_string(),
+        "cmp lt %b1:p0:i32 3:i32"
+    );
+}
+
+#[test]
+fn test_examples_diff() {
+    let before = parse_ir("examples/volatile/volatile.input.ir");
+    let after = parse_ir("examples/volatile/volatile.output.ir");
+    assert!(ir::diff(&before, &before).is_empty());
+
+    // Only the changed parts are reported, each line of them removed or added as a whole.
+    let diff = ir::diff(&before, &after);
+    assert_eq!(
+        diff.to_string(),
+        "\
+@main init
+  - %l1:i32:y
+@main b0
+  - %b0:i4:unit = store 3:i32 %l1:i32*
+  - %b0:i5:unit = store 4:i32 %l1:i32*
+  - %b0:i6:i32 = load %l1:i32*
+  - %b0:i7:i32 = load %l1:i32*
+  - %b0:i8:i32 = add %b0:i2:i32 %b0:i6:i32
+  - ret %b0:i8:i32
+  + %b0:i4:i32 = add %b0:i2:i32 4:i32
+  + ret %b0:i4:i32
+"
+    );
+
+    // Removed declarations, and changed lines in the middle of a block.
+    let before = parse_ir("examples/dead_global/dead_global.input.ir");
+    let after = parse_ir("examples/dead_global/dead_global.output.ir");
+    let diff = ir::diff(&before, &after);
+    let locations = diff
+        .hunks
+        .iter()
+        .map(|hunk| hunk.location.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(locations, ["@escaped", "@main b0", "@pointee", "@written"]);
+    assert_eq!(
+        diff.hunks[0].changes,
+        [ir::Change::Removed("var i32* @escaped = 0".to_string())]
+    );
+    assert_eq!(
+        diff.hunks[1].changes[..2],
+        [
+            ir::Change::Removed("%b0:i1:unit = store %b0:i0:i32 @written:i32*".to_string()),
+            ir::Change::Added("%b0:i1:unit = nop".to_string()),
+        ]
+    );
+    assert_eq!(diff.hunks[1].changes.len(), 6);
+}
+
+#[test]
+fn test_examples_source_loc() {
+    let ir = irgen("examples/loc/loc.c");
+    let at = |line, column| Some(ir::SourceLoc { line, column });
+
+    // Declarations are at their declarators, and instructions at the code they are translated
+    // from, e.g., the parameters stored at the function's declarator.
+    assert_eq!(ir.decl_loc("g"), at(1, 5));
+    assert_eq!(ir.decl_loc("add_uninit"), at(3, 5));
+    assert_eq!(ir.decl_loc("main"), at(8, 5));
+    assert_eq!(ir.decl_loc("x"), None);
+    let instructions = &function_of(&ir, "add_uninit").blocks[&ir::BlockId(0)].instructions;
+    assert_eq!(instructions[0].loc(), at(3, 5));
+    assert_eq!(instructions[3].loc(), at(5, 10));
+
+    // Errors of the interpreter point to the source.
+    let error = ir::Interpreter::new()
+        .undef_check(true)
+        .run(&ir, Vec::new())
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "add_uninit:b0:3 (line 5:10) / use of the undefined value of `%b0:i1:i32`"
+    );
+
+    // Locations are neither printed nor compared.
+    let parsed = ir.to_string().parse::<ir::TranslationUnit>().unwrap();
+    assert_eq!(parsed.decl_loc("main"), None);
+    assert_eq!(parsed, ir);
+}
+
+#[test]
+fn test_examples_dominators() {
+    let ir = parse_ir("examples/analysis/cfg.ir");
+    let cfg = ir::analysis::Cfg::new(function_of(&ir, "cfg"));
+    let dominators = ir::analysis::Dominators::new(&cfg);
+    let b = ir::BlockId;
+
+    let idoms = (0..10).map(|i| dominators.idom(b(i))).collect::<Vec<_>>();
+    assert_eq!(
+        idoms,
+        [
+            None,
+            Some(b(0)),
+            Some(b(1)),
+            Some(b(2)),
+            Some(b(2)),
+            Some(b(1)),
+            Some(b(0)),
+            Some(b(0)),
+            None,
+            Some(b(6)),
+        ]
+    );
+    assert_eq!(
+        dominators.dominators(b(3)).collect::<Vec<_>>(),
+        [b(3), b(2), b(1), b(0)]
+    );
+    assert!(dominators.dominates(b(1), b(4)));
+    assert!(dominators.dominates(b(7), b(7)));
+    assert!(!dominators.strictly_dominates(b(7), b(7)));
+    assert!(!dominators.dominates(b(5), b(7)));
+
+    // The unreachable block neither dominates nor is dominated.
+    assert!(!dominators.is_reachable(b(8)));
+    assert!(!dominators.dominates(b(0), b(8)));
+    assert!(!dominators.dominates(b(8), b(8)));
+
+    // The verifier rejects a use of a register whose definition does not dominate it.
+    assert!(ir::verify(&ir).is_ok());
+    let ir = std::fs::read_to_string("examples/analysis/cfg.ir")
+        .expect("reading the example failed")
+        .replace("ret %b7:p0:i32", "ret %b1:p0:i32")
+        .parse::<ir::TranslationUnit>()
+        .expect("parse failed");
+    assert!(matches!(
+        ir::verify(&ir),
+        Err(ir::VerifyError::Instruction { msg, .. })
+            if msg == "the definition of `%b1:p0` does not dominate its use"
+    ));
+}
+
+#[test]
+fn test_examples_dominance_frontiers() {
+    let ir = parse_ir("examples/analysis/cfg.ir");
+    let cfg = ir::analysis::Cfg::new(function_of(&ir, "cfg"));
+    let dominators = ir::analysis::Dominators::new(&cfg);
+    let frontiers = ir::analysis::DominanceFrontiers::new(&cfg, &dominators);
+    let b = ir::BlockId;
+    let set = |bids: &[usize]| bids.iter().copied().map(b).collect::<BTreeSet<_>>();
+
+    let expected: [&[usize]; 10] = [
+        &[],
+        &[1, 7],
+        &[1, 2],
+        &[2],
+        &[1],
+        &[7],
+        &[7],
+        &[],
+        &[],
+        &[9],
+    ];
+    for (i, expected) in expected.iter().enumerate() {
+        assert_eq!(frontiers.frontier(b(i)), &set(expected), "frontier of b{i}");
+    }
+
+    // A variable defined in the inner loop needs phinodes in both loop headers and the merge.
+    assert_eq!(frontiers.iterated_frontier([b(3)]), set(&[1, 2, 7]));
+    assert_eq!(frontiers.iterated_frontier([b(5), b(6)]), set(&[7]));
+}
+
+#[test]
+fn test_examples_post_dominators() {
+    let ir = parse_ir("examples/analysis/cfg.ir");
+    let cfg = ir::analysis::Cfg::new(function_of(&ir, "cfg"));
+    let post_dominators = ir::analysis::PostDominators::new(&cfg);
+    let b = ir::BlockId;
+
+    let ipdoms = (0..8)
+        .map(|i| post_dominators.ipdom(b(i)))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        ipdoms,
+        [
+            Some(b(7)),
+            Some(b(5)),
+            Some(b(4)),
+            Some(b(2)),
+            Some(b(1)),
+            Some(b(7)),
+            Some(b(7)),
+            None,
+        ]
+    );
+    assert_eq!(post_dominators.roots(), [b(7)]);
+    assert_eq!(
+        post_dominators.post_dominators(b(3)).collect::<Vec<_>>(),
+        [b(3), b(2), b(4), b(1), b(5), b(7)]
+    );
+    assert!(post_dominators.post_dominates(b(1), b(4)));
+    assert!(!post_dominators.post_dominates(b(5), b(0)));
+    assert!(!post_dominators.strictly_post_dominates(b(7), b(7)));
+
+    // Neither the infinite loop nor the unreachable block are in the tree.
+    for i in [8, 9] {
+        assert!(!post_dominators.reaches_exit(b(i)));
+        assert!(!post_dominators.post_dominates(b(7), b(i)));
+        assert_eq!(post_dominators.post_dominators(b(i)).count(), 0);
+    }
+}
+
+#[test]
+fn test_examples_loop_info() {
+    let ir = parse_ir("examples/analysis/cfg.ir");
+    let cfg = ir::analysis::Cfg::new(function_of(&ir, "cfg"));
+    let dominators = ir::analysis::Dominators::new(&cfg);
+    let loops = ir::analysis::LoopInfo::new(&cfg, &dominators);
+    let b = ir::BlockId;
+    let set = |bids: &[usize]| bids.iter().copied().map(b).collect::<BTreeSet<_>>();
+
+    assert_eq!(loops.roots(), [b(9), b(1)]);
+    let post_order = loops
+        .post_order()
+        .into_iter()
+        .map(ir::analysis::Loop::header)
+        .collect::<Vec<_>>();
+    assert_eq!(post_order, [b(9), b(2), b(1)]);
+
+    let outer = loops.get(b(1)).expect("b1 must be a header");
+    assert_eq!(outer.blocks(), &set(&[1, 2, 3, 4]));
+    assert_eq!(outer.latches(), [b(4)]);
+    assert_eq!(outer.children(), [b(2)]);
+    assert_eq!(outer.parent(), None);
+    assert_eq!(outer.exits(&cfg), set(&[5]));
+    // The block entering the loop also jumps elsewhere.
+    assert_eq!(outer.preheader(&cfg), None);
+
+    let inner = loops.get(b(2)).expect("b2 must be a header");
+    assert_eq!(inner.blocks(), &set(&[2, 3]));
+    assert_eq!(inner.latches(), [b(3)]);
+    assert_eq!(inner.parent(), Some(b(1)));
+    assert_eq!(inner.depth(), 2);
+    assert_eq!(inner.exits(&cfg), set(&[4]));
+
+    let infinite = loops.get(b(9)).expect("b9 must be a header");
+    assert_eq!(infinite.latches(), [b(9)]);
+    assert!(infinite.exits(&cfg).is_empty());
+
+    let depths = (0..10).map(|i| loops.depth(b(i))).collect::<Vec<_>>();
+    assert_eq!(depths, [0, 1, 2, 2, 1, 0, 0, 0, 0, 1]);
+    assert!(loops.is_back_edge(b(3), b(2)));
+    assert!(!loops.is_back_edge(b(2), b(3)));
+    assert!(!loops.is_header(b(3)));
+}
+
 #[test]
 fn test_examples_optimize() {
     test_opt_between_dirs(
@@ -201,6 +2040,21 @@ fn test_examples_optimize() {
     )
 }
 
+#[test]
+fn test_examples_pipeline() {
+    test_dir(Path::new("examples/ir0"), OsStr::new("ir"), |path| {
+        let ir = parse_ir(path.to_str().expect("must be transformable to `&str`"));
+        let expected = ir::interp(&ir, Vec::new());
+
+        // Each level runs to completion and keeps the result of the program.
+        for level in [OptLevel::O1, OptLevel::O2] {
+            let mut optimized = ir.clone();
+            let _ = pipeline(level).optimize(&mut optimized);
+            assert_eq!(ir::interp(&optimized, Vec::new()), expected, "{level}");
+        }
+    });
+}
+
 #[test]
 fn test_examples_asmgen_small() {
     for dir in ASMGEN_TEST_DIR_LIST.iter() {